@@ -0,0 +1,80 @@
+use crate::bybit::{BoxError, BybitClient};
+use crate::config::Category;
+
+/// How the account holds its balance, which decides where the wallet
+/// balance lives and which field says what's free to trade with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountType {
+    /// Separate derivatives and spot wallets, margin tracked per coin.
+    Classic,
+    /// Unified Trading Account, margin is portfolio-wide.
+    Unified,
+}
+
+impl AccountType {
+    pub async fn detect(client: &BybitClient) -> Result<AccountType, BoxError> {
+        let info = client.get_account_info().await?;
+        match info.unified_margin_status {
+            1 => Ok(AccountType::Classic),
+            3..=6 => Ok(AccountType::Unified),
+            status => {
+                Err(format!("unsupported account type (unifiedMarginStatus {})", status).into())
+            }
+        }
+    }
+
+    /// Wallet `category` trades from.
+    pub fn wallet(&self, category: Category) -> &'static str {
+        match (self, category) {
+            (AccountType::Unified, _) => "UNIFIED",
+            (AccountType::Classic, Category::Linear) => "CONTRACT",
+            (AccountType::Classic, Category::Spot) => "SPOT",
+        }
+    }
+
+    /// Account equity in USD. Classic accounts add up the USD value of
+    /// every coin in the derivatives and spot wallets.
+    pub async fn equity(&self, client: &BybitClient) -> Result<f64, BoxError> {
+        if *self == AccountType::Unified {
+            let accounts = client.get_wallet_balance("UNIFIED", "").await?;
+            let account = accounts.first().ok_or("no UNIFIED wallet returned")?;
+            return Ok(account.total_equity.parse()?);
+        }
+        let mut equity = 0.0;
+        for wallet in ["CONTRACT", "SPOT"] {
+            for account in client.get_wallet_balance(wallet, "").await? {
+                for coin in &account.coin {
+                    equity += coin.usd_value.parse::<f64>().unwrap_or(0.0);
+                }
+            }
+        }
+        Ok(equity)
+    }
+
+    /// Balance free to trade `category` in `coin` with. Unified accounts
+    /// report it portfolio-wide in USD, classic ones per wallet and coin.
+    pub async fn available_balance(
+        &self,
+        client: &BybitClient,
+        category: Category,
+        coin: &str,
+    ) -> Result<f64, BoxError> {
+        let wallet = self.wallet(category);
+        let accounts = client.get_wallet_balance(wallet, coin).await?;
+        let account = accounts
+            .first()
+            .ok_or_else(|| format!("no {} wallet returned", wallet))?;
+        let available = match self {
+            AccountType::Unified => &account.total_available_balance,
+            AccountType::Classic => {
+                &account
+                    .coin
+                    .iter()
+                    .find(|balance| balance.coin == coin)
+                    .ok_or_else(|| format!("no {} balance in {} wallet", coin, wallet))?
+                    .available_to_withdraw
+            }
+        };
+        Ok(available.parse()?)
+    }
+}
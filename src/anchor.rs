@@ -0,0 +1,154 @@
+//! Picks the candle a ladder is anchored on: the one for the period being
+//! placed, not an old or not yet opened one. The anchor can also be reduced
+//! from that candle and the ones before it.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fmt;
+
+/// How a symbol's anchor price is taken from its latest candles.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Reducer {
+    /// The open of the current candle.
+    #[default]
+    Open,
+    /// The lowest low, so a gap day doesn't lift the ladder.
+    MinLow,
+    /// The mean of the opens.
+    AvgOpen,
+    /// The highest high, for ladders laid out above the market.
+    MaxHigh,
+}
+
+impl fmt::Display for Reducer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Reducer::Open => "open",
+            Reducer::MinLow => "min_low",
+            Reducer::AvgOpen => "avg_open",
+            Reducer::MaxHigh => "max_high",
+        })
+    }
+}
+
+/// The prices of a candle a reducer reads, as the exchange formats them.
+#[derive(Debug, Clone, Copy)]
+pub struct Candle<'a> {
+    pub open: &'a str,
+    pub high: &'a str,
+    pub low: &'a str,
+}
+
+/// Why a candle can't anchor the period being placed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleCandle {
+    /// The start time isn't a millisecond timestamp.
+    Unreadable(String),
+    /// From an earlier period, e.g. yesterday's candle.
+    Old {
+        start: DateTime<Utc>,
+        expected: DateTime<Utc>,
+    },
+    /// From a later period, so our clock is behind the exchange's.
+    Future {
+        start: DateTime<Utc>,
+        expected: DateTime<Utc>,
+    },
+}
+
+impl fmt::Display for StaleCandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaleCandle::Unreadable(start) => {
+                write!(f, "kline start time {:?} is unreadable", start)
+            }
+            StaleCandle::Old { start, expected } => write!(
+                f,
+                "stale kline from {}, expected the candle opening {}",
+                start, expected
+            ),
+            StaleCandle::Future { start, expected } => write!(
+                f,
+                "kline from {} is ahead of the candle opening {}, check the clock",
+                start, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StaleCandle {}
+
+/// When a kline's `startTime`, in epoch milliseconds, falls.
+pub fn start_of(start_time: &str) -> Result<DateTime<Utc>, StaleCandle> {
+    start_time
+        .parse::<i64>()
+        .ok()
+        .and_then(DateTime::from_timestamp_millis)
+        .ok_or_else(|| StaleCandle::Unreadable(start_time.to_string()))
+}
+
+/// Whether a kline starting at `start_time` is the candle opening at
+/// `expected`.
+pub fn check(start_time: &str, expected: DateTime<Utc>) -> Result<(), StaleCandle> {
+    let start = start_of(start_time)?;
+    if start < expected {
+        Err(StaleCandle::Old { start, expected })
+    } else if start > expected {
+        Err(StaleCandle::Future { start, expected })
+    } else {
+        Ok(())
+    }
+}
+
+/// Which of the candles starting at `start_times` opens at `expected`.
+/// With none of them, the newest's mismatch is the error.
+pub fn select(start_times: &[&str], expected: DateTime<Utc>) -> Result<usize, StaleCandle> {
+    let mut newest = None;
+    for (index, start_time) in start_times.iter().enumerate() {
+        match check(start_time, expected) {
+            Ok(()) => return Ok(index),
+            Err(e) => {
+                newest.get_or_insert(e);
+            }
+        }
+    }
+    Err(newest.unwrap_or_else(|| StaleCandle::Unreadable(String::new())))
+}
+
+/// The anchor price `reducer` takes from `candles`, newest first. It keeps
+/// the exchange's formatting, an average taking the newest open's decimals.
+/// None when there are no candles or a price is unreadable.
+pub fn reduce(reducer: Reducer, candles: &[Candle]) -> Option<String> {
+    let newest = candles.first()?;
+    let parsed = |price: &str| price.parse::<f64>().ok();
+    let extreme = |prices: Vec<&str>, lowest: bool| {
+        let mut best: Option<(&str, f64)> = None;
+        for price in prices {
+            let value = parsed(price)?;
+            let better = best.is_none_or(|(_, current)| {
+                if lowest {
+                    value < current
+                } else {
+                    value > current
+                }
+            });
+            if better {
+                best = Some((price, value));
+            }
+        }
+        best.map(|(price, _)| price.to_string())
+    };
+    match reducer {
+        Reducer::Open => parsed(newest.open).map(|_| newest.open.to_string()),
+        Reducer::MinLow => extreme(candles.iter().map(|c| c.low).collect(), true),
+        Reducer::MaxHigh => extreme(candles.iter().map(|c| c.high).collect(), false),
+        Reducer::AvgOpen => {
+            let opens: Option<Vec<f64>> = candles.iter().map(|c| parsed(c.open)).collect();
+            let opens = opens?;
+            let decimals = newest.open.split_once('.').map_or(0, |(_, d)| d.len());
+            let average = opens.iter().sum::<f64>() / opens.len() as f64;
+            Some(format!("{:.*}", decimals, average))
+        }
+    }
+}
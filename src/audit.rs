@@ -0,0 +1,237 @@
+use crate::bybit::BoxError;
+use crate::notes::Note;
+use chrono::{NaiveDate, SecondsFormat};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Replays the journal in `dir` as a readable timeline: every record of
+/// `day`, or of `order` (an orderLinkId) across all days, or both. `notes`
+/// are woven in by time. Ends with how each order ended up.
+pub fn timeline(
+    dir: &str,
+    day: Option<NaiveDate>,
+    order: Option<&str>,
+    notes: &[Note],
+) -> Result<String, BoxError> {
+    let prefix = match day {
+        Some(day) => format!("journal-{}.", day),
+        None => "journal-".to_string(),
+    };
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("can't read journal {}: {}", dir, e))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| file_name(path).starts_with(&prefix))
+        .collect();
+    if files.is_empty() {
+        return Err(format!("no journal files matching {}* in {}", prefix, dir).into());
+    }
+    files.sort();
+
+    let mut records = Vec::new();
+    for path in files {
+        for line in fs::read_to_string(&path)?.lines() {
+            match serde_json::from_str::<Value>(line) {
+                Ok(record) => records.push(record),
+                Err(e) => println!("skipping bad line in {}: {}", path.display(), e),
+            }
+        }
+    }
+    records.sort_by_key(|record| record["seq"].as_u64());
+    records.extend(
+        notes
+            .iter()
+            .filter(|note| day.is_none_or(|day| note.at.date_naive() == day))
+            .map(|note| {
+                json!({
+                    "at": note.at.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    "kind": "note",
+                    "data": note,
+                })
+            }),
+    );
+    // stable, so records within the same millisecond keep their sequence
+    records.sort_by_key(|record| text(&record["at"]));
+
+    let mut lines = Vec::new();
+    let mut dispositions: BTreeMap<String, String> = BTreeMap::new();
+    for record in &records {
+        if order.is_some_and(|order| !mentions(record, order)) {
+            continue;
+        }
+        let data = &record["data"];
+        if record["kind"] == "order" {
+            dispositions.insert(text(&data["order_link_id"]), disposition(data));
+        }
+        // within a day the time is enough
+        let at = text(&record["at"]);
+        let time = match day {
+            Some(_) => at.split('T').nth(1).unwrap_or(&at).trim_end_matches('Z'),
+            None => &at,
+        };
+        lines.push(format!("{} {}", time, describe(record)));
+    }
+    if lines.is_empty() {
+        return Ok("no matching records".to_string());
+    }
+    if !dispositions.is_empty() {
+        lines.push(String::new());
+        lines.push("final state of each order:".to_string());
+        for (link_id, disposition) in dispositions {
+            lines.push(format!("  {:<36} {}", link_id, disposition));
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// One record as a line of the timeline.
+fn describe(record: &Value) -> String {
+    let data = &record["data"];
+    match record["kind"].as_str().unwrap_or_default() {
+        "cycle_started" => format!(
+            "cycle started: {} for the {} open{}",
+            text(&data["strategy"]),
+            text(&data["open"]),
+            if data["late"] == true { " (late)" } else { "" }
+        ),
+        "anchor" => format!(
+            "anchor {} {}: open {}, low {}",
+            text(&data["strategy"]),
+            text(&data["symbol"]),
+            text(&data["open"]),
+            text(&data["low"])
+        ),
+        "order" => {
+            let mut line = format!(
+                "order {} {} {} {}: {} -> {}",
+                text(&data["strategy"]),
+                text(&data["symbol"]),
+                text(&data["side"]),
+                text(&data["order_link_id"]),
+                if data["from"].is_null() {
+                    "new".to_string()
+                } else {
+                    text(&data["from"])
+                },
+                text(&data["to"])
+            );
+            if !text(&data["price"]).is_empty() {
+                line.push_str(&format!(
+                    ", {} @ {}",
+                    text(&data["qty"]),
+                    text(&data["price"])
+                ));
+            }
+            if data["executed"].as_f64().unwrap_or(0.0) > 0.0 {
+                line.push_str(&format!(
+                    ", executed {} @ {}",
+                    data["executed"], data["average"]
+                ));
+            }
+            line
+        }
+        "request" => {
+            let mut line = format!("-> {} {}", text(&data["method"]), text(&data["path"]));
+            if let Some(query) = data["query"].as_str() {
+                line.push_str(&format!("?{}", query));
+            }
+            if let Some(body) = data["body"].as_str() {
+                line.push_str(&format!(" {}", body));
+            }
+            line
+        }
+        "response" => format!(
+            "<- {} HTTP {} retCode {} {}",
+            text(&data["path"]),
+            data["status"],
+            data["ret_code"],
+            text(&data["ret_msg"])
+        ),
+        "notification" => match data["error"].as_str() {
+            Some(error) => format!(
+                "notification via {} failed: {} ({})",
+                text(&data["channel"]),
+                text(&data["summary"]),
+                error
+            ),
+            None => format!(
+                "notified via {}: {}",
+                text(&data["channel"]),
+                text(&data["summary"])
+            ),
+        },
+        "note" => format!(
+            "note on {}: {}",
+            text(&data["subject"]),
+            text(&data["text"])
+        ),
+        "cycle_finished" => {
+            let symbols: Vec<String> = data["symbols"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|entry| {
+                    let outcome = &entry["outcome"];
+                    format!(
+                        "{} {}{}",
+                        text(&entry["symbol"]),
+                        text(&outcome["outcome"]),
+                        match &outcome["detail"] {
+                            Value::Null => String::new(),
+                            detail => format!(" ({})", text(detail)),
+                        }
+                    )
+                })
+                .collect();
+            format!(
+                "cycle finished: {}, {}; cancelled {}",
+                text(&data["strategy"]),
+                symbols.join(", "),
+                data["cancelled"]
+            )
+        }
+        kind => format!("{} {}", kind, data),
+    }
+}
+
+fn disposition(data: &Value) -> String {
+    let mut state = text(&data["to"]);
+    if data["executed"].as_f64().unwrap_or(0.0) > 0.0 {
+        state.push_str(&format!(
+            ", executed {} @ {}",
+            data["executed"], data["average"]
+        ));
+    }
+    state
+}
+
+/// Whether the record is about `order`: its lifecycle, or a request that
+/// carried its orderLinkId.
+fn mentions(record: &Value, order: &str) -> bool {
+    let data = &record["data"];
+    match record["kind"].as_str().unwrap_or_default() {
+        "order" => data["order_link_id"] == order,
+        "note" => data["subject"] == order,
+        "request" => [&data["body"], &data["query"]]
+            .iter()
+            .any(|field| field.as_str().is_some_and(|text| text.contains(order))),
+        _ => false,
+    }
+}
+
+/// A string field as-is, anything else in its JSON form.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
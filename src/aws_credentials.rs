@@ -0,0 +1,309 @@
+//! The AWS access key Secrets Manager is called with, found the way the
+//! AWS SDKs' default chain finds one: the environment, the shared
+//! credentials file, a web identity, the container endpoint, then the
+//! instance metadata service. So an instance, task or pod role works
+//! without a key in the environment.
+
+use crate::bybit::BoxError;
+use crate::scrub;
+use crate::sigv4::AccessKey;
+use reqwest::{Client, Response, Url};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Where ECS serves a task role's credentials, under
+/// `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI`.
+const ECS_ENDPOINT: &str = "http://169.254.170.2";
+/// Hosts a full container credentials URI may reach over plain http
+/// besides loopback: ECS and EKS Pod Identity.
+const CONTAINER_HOSTS: [&str; 2] = ["169.254.170.2", "169.254.170.23"];
+const IMDS_ENDPOINT: &str = "http://169.254.169.254";
+/// How long the instance metadata service gets, short since nothing
+/// answers off EC2 and it's the last source tried.
+const IMDS_TIMEOUT: Duration = Duration::from_secs(1);
+const IMDS_TOKEN_TTL_SECS: &str = "21600";
+/// Session name for an assumed web identity role, unless
+/// `AWS_ROLE_SESSION_NAME` gives one.
+const SESSION_NAME: &str = "stink-bid";
+
+/// An AWS access key, with the session token temporary ones come with.
+/// The secret and the token are registered for scrubbing when read.
+pub struct AwsKey {
+    id: String,
+    secret: String,
+    token: Option<String>,
+}
+
+impl AwsKey {
+    fn new(id: String, secret: String, token: Option<String>) -> AwsKey {
+        scrub::register(&secret);
+        if let Some(token) = &token {
+            scrub::register(token);
+        }
+        AwsKey { id, secret, token }
+    }
+
+    pub fn access_key(&self) -> AccessKey<'_> {
+        AccessKey {
+            id: &self.id,
+            secret: &self.secret,
+        }
+    }
+
+    /// Sent as `x-amz-security-token` with a temporary key.
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+/// Credentials as the container endpoint and the instance metadata
+/// service serve them.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Issued {
+    access_key_id: String,
+    secret_access_key: String,
+    token: Option<String>,
+}
+
+impl From<Issued> for AwsKey {
+    fn from(issued: Issued) -> AwsKey {
+        AwsKey::new(issued.access_key_id, issued.secret_access_key, issued.token)
+    }
+}
+
+/// The first key the chain finds, and where it came from. A source that
+/// is set up but fails is an error rather than a step to the next one,
+/// as with the SDKs, so a broken role isn't masked by a stale key.
+pub async fn find(client: &Client, region: &str) -> Result<(AwsKey, &'static str), BoxError> {
+    if let Some(key) = from_env()? {
+        return Ok((key, "the environment"));
+    }
+    if let Some(key) = from_shared_file()? {
+        return Ok((key, "the shared credentials file"));
+    }
+    if let Some(key) = from_web_identity(client, region).await? {
+        return Ok((key, "a web identity"));
+    }
+    if let Some(key) = from_container(client).await? {
+        return Ok((key, "the container credentials endpoint"));
+    }
+    let searched = "no AWS credentials in the environment, the shared credentials file, \
+                    a web identity or the container endpoint";
+    if set("AWS_EC2_METADATA_DISABLED").is_some_and(|value| value.eq_ignore_ascii_case("true")) {
+        return Err(format!("{}, and instance metadata is disabled", searched).into());
+    }
+    let key = from_instance_metadata()
+        .await
+        .map_err(|e| format!("{}, nor from instance metadata: {}", searched, e))?;
+    Ok((key, "the instance metadata service"))
+}
+
+/// `name`, when it's set to something.
+fn set(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// The body of a successful reply, else its status and body as the error.
+async fn text(response: Response) -> Result<String, BoxError> {
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, text).into());
+    }
+    Ok(text)
+}
+
+/// `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and `AWS_SESSION_TOKEN`.
+fn from_env() -> Result<Option<AwsKey>, BoxError> {
+    let Some(id) = set("AWS_ACCESS_KEY_ID") else {
+        return Ok(None);
+    };
+    let secret = set("AWS_SECRET_ACCESS_KEY")
+        .ok_or("AWS_ACCESS_KEY_ID is set without AWS_SECRET_ACCESS_KEY")?;
+    Ok(Some(AwsKey::new(id, secret, set("AWS_SESSION_TOKEN"))))
+}
+
+/// The `AWS_PROFILE` section, or `default`, of `~/.aws/credentials` or
+/// `AWS_SHARED_CREDENTIALS_FILE`. Only static keys are read; a profile
+/// that assumes a role isn't followed.
+fn from_shared_file() -> Result<Option<AwsKey>, BoxError> {
+    let path = match (set("AWS_SHARED_CREDENTIALS_FILE"), set("HOME")) {
+        (Some(path), _) => path,
+        (None, Some(home)) => format!("{}/.aws/credentials", home),
+        (None, None) => return Ok(None),
+    };
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    let named = set("AWS_PROFILE");
+    let profile = named.as_deref().unwrap_or("default");
+    let mut section = None;
+    let (mut id, mut secret, mut token) = (None, None, None);
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(name.trim().to_string());
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if section.as_deref() != Some(profile) {
+            continue;
+        }
+        let value = Some(value.trim().to_string());
+        match key.trim() {
+            "aws_access_key_id" => id = value,
+            "aws_secret_access_key" => secret = value,
+            "aws_session_token" => token = value,
+            _ => {}
+        }
+    }
+    match (id, secret) {
+        (Some(id), Some(secret)) => Ok(Some(AwsKey::new(id, secret, token))),
+        // the default profile is only looked for; a named one has to be there
+        _ if named.is_none() => Ok(None),
+        _ => Err(format!(
+            "profile {} in {} has no access key and secret",
+            profile, path
+        )
+        .into()),
+    }
+}
+
+/// A role assumed with the token in `AWS_WEB_IDENTITY_TOKEN_FILE`, as
+/// EKS IRSA sets up, through STS in `region` or at `AWS_ENDPOINT_URL_STS`.
+async fn from_web_identity(client: &Client, region: &str) -> Result<Option<AwsKey>, BoxError> {
+    let (Some(token_file), Some(role)) = (set("AWS_WEB_IDENTITY_TOKEN_FILE"), set("AWS_ROLE_ARN"))
+    else {
+        return Ok(None);
+    };
+    let identity = fs::read_to_string(&token_file)
+        .map_err(|e| format!("failed reading {}: {}", token_file, e))?;
+    let identity = identity.trim();
+    scrub::register(identity);
+    let endpoint = set("AWS_ENDPOINT_URL_STS")
+        .unwrap_or_else(|| format!("https://sts.{}.amazonaws.com", region));
+    let session = set("AWS_ROLE_SESSION_NAME").unwrap_or_else(|| SESSION_NAME.to_string());
+    let reply = client
+        .post(endpoint)
+        .form(&[
+            ("Action", "AssumeRoleWithWebIdentity"),
+            ("Version", "2011-06-15"),
+            ("RoleArn", role.as_str()),
+            ("RoleSessionName", session.as_str()),
+            ("WebIdentityToken", identity),
+        ])
+        .send()
+        .await?;
+    let reply = text(reply)
+        .await
+        .map_err(|e| format!("failed assuming {}: {}", role, e))?;
+    // the reply is XML, and these three elements are all that's needed of it
+    let element = |name: &str| {
+        let open = format!("<{}>", name);
+        let close = format!("</{}>", name);
+        let start = reply.find(&open)? + open.len();
+        let end = start + reply[start..].find(&close)?;
+        Some(reply[start..end].trim().to_string())
+    };
+    match (
+        element("AccessKeyId"),
+        element("SecretAccessKey"),
+        element("SessionToken"),
+    ) {
+        (Some(id), Some(secret), Some(token)) => Ok(Some(AwsKey::new(id, secret, Some(token)))),
+        _ => Err(format!("assuming {} returned no credentials", role).into()),
+    }
+}
+
+/// A task or pod role from `AWS_CONTAINER_CREDENTIALS_RELATIVE_URI` on
+/// ECS, or `AWS_CONTAINER_CREDENTIALS_FULL_URI` with the authorization
+/// token beside it.
+async fn from_container(client: &Client) -> Result<Option<AwsKey>, BoxError> {
+    let uri = match (
+        set("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI"),
+        set("AWS_CONTAINER_CREDENTIALS_FULL_URI"),
+    ) {
+        (Some(relative), _) => format!("{}{}", ECS_ENDPOINT, relative),
+        (None, Some(full)) => {
+            let url = Url::parse(&full)?;
+            let host = url.host_str().unwrap_or_default();
+            let local = host == "localhost"
+                || CONTAINER_HOSTS.contains(&host)
+                || host
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .parse::<IpAddr>()
+                    .is_ok_and(|ip| ip.is_loopback());
+            // the token would go out in the clear to anywhere else
+            if url.scheme() != "https" && !local {
+                return Err(format!(
+                    "AWS_CONTAINER_CREDENTIALS_FULL_URI {} needs https or a local host",
+                    full
+                )
+                .into());
+            }
+            full
+        }
+        (None, None) => return Ok(None),
+    };
+    let authorization = match set("AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE") {
+        Some(path) => Some(
+            fs::read_to_string(&path)
+                .map_err(|e| format!("failed reading {}: {}", path, e))?
+                .trim()
+                .to_string(),
+        ),
+        None => set("AWS_CONTAINER_AUTHORIZATION_TOKEN"),
+    };
+    let mut get = client.get(&uri);
+    if let Some(authorization) = authorization {
+        scrub::register(&authorization);
+        get = get.header("Authorization", authorization);
+    }
+    let reply = text(get.send().await?)
+        .await
+        .map_err(|e| format!("failed fetching container credentials: {}", e))?;
+    let issued: Issued = serde_json::from_str(&reply)?;
+    Ok(Some(issued.into()))
+}
+
+/// The instance role, from IMDSv2 or `AWS_EC2_METADATA_SERVICE_ENDPOINT`.
+async fn from_instance_metadata() -> Result<AwsKey, BoxError> {
+    let endpoint =
+        set("AWS_EC2_METADATA_SERVICE_ENDPOINT").unwrap_or_else(|| IMDS_ENDPOINT.to_string());
+    let endpoint = endpoint.trim_end_matches('/');
+    let client = Client::builder()
+        .timeout(IMDS_TIMEOUT)
+        .build()
+        .expect("failed building http client");
+    let token = client
+        .put(format!("{}/latest/api/token", endpoint))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", IMDS_TOKEN_TTL_SECS)
+        .send()
+        .await?;
+    let token = text(token).await?;
+    let roles = format!("{}/latest/meta-data/iam/security-credentials/", endpoint);
+    let get = |url: String| {
+        client
+            .get(url)
+            .header("X-aws-ec2-metadata-token", token.as_str())
+            .send()
+    };
+    let role = text(get(roles.clone()).await?).await?;
+    let role = role
+        .lines()
+        .next()
+        .map(str::trim)
+        .filter(|role| !role.is_empty())
+        .ok_or("the instance has no role")?;
+    let reply = text(get(format!("{}{}", roles, role)).await?).await?;
+    let issued: Issued = serde_json::from_str(&reply)?;
+    Ok(issued.into())
+}
@@ -0,0 +1,1767 @@
+use crate::cancels;
+pub use crate::cancels::{CancelOrderData, CancelOutcome, Settled};
+use crate::clock::{self, Measurement};
+use crate::config::{AnchorPrice, Category, Config, Interval, RetryConfig};
+use crate::cooldown::Cooldown;
+use crate::credentials::Credentials;
+use crate::journal;
+use crate::latency::{LatencyTracker, Outcome};
+use crate::notify;
+pub use crate::order_request::OrderRequest;
+pub use crate::placements::{BatchOrderResponse, BatchOrderResult};
+use crate::profile;
+use crate::ratelimit::{LimiterStats, RateLimiter};
+use crate::response::{self, ApiResponse};
+use crate::retry::{self, ErrorClass, Failure, RetryPolicy};
+use crate::scrub;
+use crate::trace;
+use crate::verbosity::{self, Level};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{
+    collections::BTreeMap,
+    env, fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, Ordering},
+        Mutex, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Most orders Bybit accepts in one batch request.
+const BATCH_LIMIT: usize = 10;
+/// Most candles one kline request returns.
+const KLINE_PAGE: usize = 1000;
+/// retCode for a trading-stop call that matches what's already set.
+const NOT_MODIFIED: i32 = 34040;
+/// retCode for switching to the margin mode a symbol is already in.
+const MARGIN_MODE_NOT_MODIFIED: i32 = 110026;
+
+/// retCode for request parameters Bybit rejects, which is what an unknown
+/// symbol comes back as.
+const PARAMS_ERROR: i32 = 10001;
+
+/// Per-order retCodes for prices or quantities that don't fit the
+/// instrument's tick size or qty step. Linear reports these as params
+/// errors.
+const PRECISION_CODES: [i32; 3] = [PARAMS_ERROR, 170134, 170137];
+
+/// Per-order retCodes for an orderLinkId that is already taken (linear,
+/// spot). Entry ids are deterministic, so this means the order exists.
+const DUPLICATE_LINK_ID_CODES: [i32; 2] = [110072, 170141];
+
+/// retCode for going over the per-IP limit, which comes before a 403 ban.
+const IP_LIMITED: i32 = 10018;
+/// retCode for a timestamp outside the recv window, i.e. a drifted clock.
+const TIMESTAMP_ERROR: i32 = 10002;
+/// retCode for an expired API key.
+const EXPIRED_KEY: i32 = 33004;
+/// retCode for a key bound to IPs that don't include ours.
+pub const UNMATCHED_IP: i32 = 10010;
+/// retCodes for a key that is wrong, expired, lacks permissions or is bound
+/// to other IPs, or a bad signature. None of these fix themselves.
+const AUTH_CODES: [i32; 5] = [10003, 10004, 10005, UNMATCHED_IP, EXPIRED_KEY];
+/// How long after its timestamp the exchange accepts a signed request.
+pub const RECV_WINDOW_MS: i64 = 10000;
+/// Longest an HTTP request may take before its outcome counts as unknown.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A symbol the exchange doesn't know or isn't trading.
+#[derive(Debug)]
+pub struct SymbolUnavailable {
+    pub symbol: String,
+    pub reason: String,
+}
+
+impl fmt::Display for SymbolUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is unavailable: {}", self.symbol, self.reason)
+    }
+}
+
+impl std::error::Error for SymbolUnavailable {}
+
+/// The exchange is down for maintenance.
+#[derive(Debug)]
+pub struct Maintenance {
+    pub reason: String,
+}
+
+impl fmt::Display for Maintenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exchange under maintenance: {}", self.reason)
+    }
+}
+
+impl std::error::Error for Maintenance {}
+
+impl Maintenance {
+    pub fn is(error: &BoxError) -> bool {
+        error.downcast_ref::<Maintenance>().is_some()
+    }
+}
+
+fn maintenance(reason: String) -> BoxError {
+    Box::new(Maintenance { reason })
+}
+
+fn unavailable(symbol: &str, reason: String) -> BoxError {
+    Box::new(SymbolUnavailable {
+        symbol: symbol.to_string(),
+        reason,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KlineData {
+    pub symbol: String,
+    pub category: String,
+    pub list: Vec<Kline>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Kline {
+    pub start_time: String,
+    pub open_price: String,
+    pub high_price: String,
+    pub low_price: String,
+    pub close_price: String,
+    /// Mark and index klines have no volume or turnover.
+    #[serde(default)]
+    pub volume: String,
+    #[serde(default)]
+    pub turnover: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchOrderRequest {
+    pub category: String,
+    pub request: Vec<OrderRequest>,
+}
+
+impl BatchOrderRequest {
+    pub fn new(category: Category, request: Vec<OrderRequest>) -> Self {
+        BatchOrderRequest {
+            category: category.as_param().to_string(),
+            request,
+        }
+    }
+}
+
+/// An order the batch endpoint refused, from `retExtInfo.list`.
+#[derive(Debug)]
+pub struct Rejection {
+    pub order_link_id: String,
+    pub code: i32,
+    pub msg: String,
+}
+
+impl Rejection {
+    /// Whether the exchange refused the order's price or qty format, which
+    /// means the cached instrument rules are out of date.
+    pub fn is_precision(&self) -> bool {
+        PRECISION_CODES.contains(&self.code)
+    }
+
+    pub fn is_duplicate(&self) -> bool {
+        DUPLICATE_LINK_ID_CODES.contains(&self.code)
+    }
+}
+
+/// What came back from placing a batch: the accepted orders and the
+/// rejected ones.
+#[derive(Debug, Default)]
+pub struct Placement {
+    pub placed: Vec<BatchOrderResponse>,
+    pub rejected: Vec<Rejection>,
+}
+
+impl From<&BatchOrderResponse> for CancelOrderData {
+    fn from(order_response: &BatchOrderResponse) -> Self {
+        CancelOrderData {
+            symbol: order_response.symbol.clone(),
+            order_id: order_response.order_id.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchCancelRequest {
+    pub category: String,
+    pub request: Vec<CancelOrderData>,
+}
+
+impl BatchCancelRequest {
+    pub fn new(category: Category, request: Vec<CancelOrderData>) -> Self {
+        BatchCancelRequest {
+            category: category.as_param().to_string(),
+            request,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CancelAllRequest {
+    pub category: String,
+    pub symbol: String,
+    #[serde(rename = "orderFilter", skip_serializing_if = "Option::is_none")]
+    pub order_filter: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CancelAllResult {
+    pub list: Vec<CancelledOrder>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CancelledOrder {
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+    #[serde(rename = "orderLinkId")]
+    pub order_link_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CancelOrderRequest {
+    pub category: String,
+    pub symbol: String,
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+    #[serde(rename = "orderFilter", skip_serializing_if = "Option::is_none")]
+    pub order_filter: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TradingStopRequest {
+    pub category: String,
+    pub symbol: String,
+    #[serde(rename = "tpslMode")]
+    pub tpsl_mode: String,
+    #[serde(rename = "trailingStop", skip_serializing_if = "Option::is_none")]
+    pub trailing_stop: Option<String>,
+    #[serde(rename = "stopLoss", skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<String>,
+    #[serde(rename = "positionIdx")]
+    pub position_idx: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SwitchIsolatedRequest {
+    pub category: String,
+    pub symbol: String,
+    /// 0 for cross margin, 1 for isolated.
+    #[serde(rename = "tradeMode")]
+    pub trade_mode: u8,
+    #[serde(rename = "buyLeverage")]
+    pub buy_leverage: String,
+    #[serde(rename = "sellLeverage")]
+    pub sell_leverage: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderListResult {
+    pub list: Vec<Order>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Order {
+    pub symbol: String,
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+    #[serde(rename = "orderLinkId")]
+    pub order_link_id: String,
+    #[serde(rename = "orderStatus")]
+    pub order_status: String,
+    #[serde(default)]
+    pub side: String,
+    pub price: String,
+    pub qty: String,
+    #[serde(rename = "avgPrice", default)]
+    pub avg_price: String,
+    #[serde(rename = "cumExecQty", default)]
+    pub cum_exec_qty: String,
+    #[serde(rename = "reduceOnly", default)]
+    pub reduce_only: bool,
+    /// Last change in milliseconds, the fill time of a filled order.
+    #[serde(rename = "updatedTime", default)]
+    pub updated_time: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstrumentList {
+    pub list: Vec<InstrumentInfo>,
+    #[serde(rename = "nextPageCursor", default)]
+    pub next_page_cursor: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionLog {
+    pub list: Vec<Transaction>,
+    #[serde(rename = "nextPageCursor", default)]
+    pub next_page_cursor: String,
+}
+
+/// One entry of the unified account's transaction log.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Transaction {
+    pub id: String,
+    pub symbol: String,
+    /// Position size the funding was settled on.
+    #[serde(default)]
+    pub size: String,
+    /// Funding fee, positive when paid and negative when received.
+    #[serde(default)]
+    pub funding: String,
+    #[serde(rename = "transactionTime")]
+    pub transaction_time: String,
+}
+
+/// Trading rules for a symbol. Linear and spot fill in different fields of
+/// the lot size filter.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InstrumentInfo {
+    pub symbol: String,
+    /// "Trading" while the symbol can be traded.
+    pub status: String,
+    #[serde(rename = "quoteCoin")]
+    pub quote_coin: String,
+    #[serde(rename = "priceFilter")]
+    pub price_filter: PriceFilter,
+    #[serde(rename = "lotSizeFilter")]
+    pub lot_size_filter: LotSizeFilter,
+    /// Linear only.
+    #[serde(rename = "leverageFilter")]
+    pub leverage_filter: Option<LeverageFilter>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LeverageFilter {
+    #[serde(rename = "maxLeverage")]
+    pub max_leverage: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PriceFilter {
+    #[serde(rename = "tickSize")]
+    pub tick_size: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LotSizeFilter {
+    /// Linear qty increment.
+    #[serde(rename = "qtyStep")]
+    pub qty_step: Option<String>,
+    /// Spot qty increment.
+    #[serde(rename = "basePrecision")]
+    pub base_precision: Option<String>,
+    /// Linear minimum order value.
+    #[serde(rename = "minNotionalValue")]
+    pub min_notional_value: Option<String>,
+    /// Spot minimum order value.
+    #[serde(rename = "minOrderAmt")]
+    pub min_order_amt: Option<String>,
+    #[serde(rename = "minOrderQty")]
+    pub min_order_qty: Option<String>,
+    /// Largest qty of a limit order.
+    #[serde(rename = "maxOrderQty")]
+    pub max_order_qty: Option<String>,
+    /// Spot maximum order value.
+    #[serde(rename = "maxOrderAmt")]
+    pub max_order_amt: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpotMarginState {
+    /// "1" when spot margin trading is on.
+    #[serde(rename = "spotMarginMode")]
+    pub spot_margin_mode: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpotBorrowCheck {
+    /// Most quote coin a buy can spend, borrowing included.
+    #[serde(rename = "spotMaxTradeAmount")]
+    pub spot_max_trade_amount: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ServerTime {
+    #[serde(rename = "timeNano")]
+    pub time_nano: String,
+}
+
+/// What the API key in use may do, from `/v5/user/query-api`.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiKeyInfo {
+    /// 1 for a read-only key.
+    #[serde(rename = "readOnly")]
+    pub read_only: i32,
+    /// Permission names by group, e.g. "ContractTrade": ["Order", "Position"].
+    #[serde(default)]
+    pub permissions: BTreeMap<String, Vec<String>>,
+    /// IPs the key is bound to, "*" when unbound.
+    #[serde(default)]
+    pub ips: Vec<String>,
+    /// Empty for a key that doesn't expire.
+    #[serde(rename = "expiredAt", default)]
+    pub expired_at: String,
+    /// 1 for a Unified Trading Account key.
+    #[serde(default)]
+    pub uta: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AccountInfo {
+    /// 1 classic, 3/4 UTA 1.0, 5/6 UTA 2.0.
+    #[serde(rename = "unifiedMarginStatus")]
+    pub unified_margin_status: i32,
+    /// A unified account's margin mode: REGULAR_MARGIN (cross),
+    /// ISOLATED_MARGIN or PORTFOLIO_MARGIN.
+    #[serde(rename = "marginMode", default)]
+    pub margin_mode: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WalletBalanceResult {
+    pub list: Vec<WalletAccount>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WalletAccount {
+    #[serde(rename = "accountType")]
+    pub account_type: String,
+    #[serde(rename = "totalAvailableBalance", default)]
+    pub total_available_balance: String,
+    /// USD equity, only filled in for unified accounts.
+    #[serde(rename = "totalEquity", default)]
+    pub total_equity: String,
+    #[serde(default)]
+    pub coin: Vec<WalletCoin>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WalletCoin {
+    pub coin: String,
+    #[serde(rename = "walletBalance", default)]
+    pub wallet_balance: String,
+    #[serde(rename = "availableToWithdraw", default)]
+    pub available_to_withdraw: String,
+    #[serde(rename = "usdValue", default)]
+    pub usd_value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TickerList {
+    pub list: Vec<Ticker>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OpenInterestList {
+    pub list: Vec<OpenInterest>,
+}
+
+/// Open interest at the start of one interval.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OpenInterest {
+    #[serde(rename = "openInterest")]
+    pub open_interest: String,
+    pub timestamp: String,
+}
+
+/// Last price and 24h stats of a symbol, lighter to fetch than a kline.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Ticker {
+    pub symbol: String,
+    #[serde(rename = "lastPrice")]
+    pub last_price: String,
+    /// Only derivatives have one.
+    #[serde(rename = "markPrice", default)]
+    pub mark_price: String,
+    #[serde(rename = "prevPrice24h", default)]
+    pub prev_price_24h: String,
+    #[serde(rename = "highPrice24h", default)]
+    pub high_price_24h: String,
+    #[serde(rename = "lowPrice24h", default)]
+    pub low_price_24h: String,
+    /// Base volume over the last 24h.
+    #[serde(rename = "volume24h", default)]
+    pub volume_24h: String,
+    /// Quote volume over the last 24h.
+    #[serde(rename = "turnover24h", default)]
+    pub turnover_24h: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PositionList {
+    pub list: Vec<PositionInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PositionInfo {
+    pub symbol: String,
+    pub side: String,
+    pub size: String,
+    #[serde(rename = "avgPrice")]
+    pub avg_price: String,
+    #[serde(rename = "liqPrice", default)]
+    pub liq_price: String,
+    #[serde(rename = "unrealisedPnl", default)]
+    pub unrealised_pnl: String,
+    #[serde(rename = "takeProfit", default)]
+    pub take_profit: String,
+    #[serde(rename = "stopLoss", default)]
+    pub stop_loss: String,
+    #[serde(rename = "trailingStop", default)]
+    pub trailing_stop: String,
+    /// 0 in one-way mode; 1 (buy side) or 2 (sell side) in hedge mode.
+    #[serde(rename = "positionIdx", default)]
+    pub position_idx: u8,
+}
+
+impl PositionInfo {
+    pub fn is_open(&self) -> bool {
+        self.size.parse::<f64>().is_ok_and(|size| size > 0.0)
+    }
+
+    /// Whether a take profit, stop loss or trailing stop is set. Bybit
+    /// reports unset ones as "0" or leaves them empty.
+    pub fn protected(&self) -> bool {
+        [&self.take_profit, &self.stop_loss, &self.trailing_stop]
+            .iter()
+            .any(|value| value.parse::<f64>().is_ok_and(|value| value > 0.0))
+    }
+}
+
+impl fmt::Display for PositionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} @ {}, liq {}, unrealized {}",
+            self.symbol,
+            self.side,
+            self.size,
+            self.avg_price,
+            if self.liq_price.is_empty() {
+                "-"
+            } else {
+                &self.liq_price
+            },
+            self.unrealised_pnl
+        )?;
+        if self.protected() {
+            write!(
+                f,
+                ", TP {} SL {} trailing {}",
+                self.take_profit, self.stop_loss, self.trailing_stop
+            )
+        } else {
+            write!(f, ", no TP/SL")
+        }
+    }
+}
+
+pub struct BybitClient {
+    client: Client,
+    base_url: String,
+    /// Swapped for rotated ones by `set_credentials`.
+    credentials: RwLock<Credentials>,
+    recv_window: String,
+    kline_url: String,
+    batch_order_url: String,
+    batch_cancel_order_url: String,
+    /// Paces every signed (account and order) request.
+    signed_limiter: RateLimiter,
+    /// Paces market data requests.
+    public_limiter: RateLimiter,
+    /// Holds requests back while the IP is banned.
+    cooldown: Cooldown,
+    latency: LatencyTracker,
+    /// Log every request and the body of failed replies.
+    trace: bool,
+    /// Set once an auth failure has been alerted, until a call succeeds.
+    auth_alerted: AtomicBool,
+    /// Milliseconds the exchange's clock is ahead of ours, added to every
+    /// signed timestamp.
+    clock_offset_ms: AtomicI64,
+    /// The latest clock measurement.
+    clock: Mutex<Option<Measurement>>,
+    /// Both our clocks as of the latest measurement, to tell when the wall
+    /// clock jumps against the monotonic one.
+    clock_anchor: Mutex<Option<(Instant, DateTime<Utc>)>>,
+    /// Wall clock jump that has the clock remeasured.
+    clock_jump_ms: i64,
+    retry: RetryConfig,
+}
+
+/// Splits a batch order reply into accepted orders and per-order
+/// rejections, using the codes in `retExtInfo.list`.
+fn parse_placement(
+    response_data: ApiResponse<Value>,
+    orders: &[OrderRequest],
+) -> Result<Placement, BoxError> {
+    // retExtInfo.list holds a code per order, in request order
+    let codes = response_data
+        .ret_ext_info
+        .get("list")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let result: BatchOrderResult =
+        serde_json::from_value(response_data.into_result("batch order")?)?;
+    let mut placement = Placement::default();
+    for (index, order) in result.list.into_iter().enumerate() {
+        let code = codes
+            .get(index)
+            .and_then(|info| info.get("code"))
+            .and_then(Value::as_i64)
+            .unwrap_or(0) as i32;
+        if code == 0 && !order.order_id.is_empty() {
+            placement.placed.push(order);
+            continue;
+        }
+        let msg = codes
+            .get(index)
+            .and_then(|info| info.get("msg"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        placement.rejected.push(Rejection {
+            order_link_id: orders
+                .get(index)
+                .map(|request| request.order_link_id.clone())
+                .unwrap_or(order.order_link_id),
+            code,
+            msg: msg.to_string(),
+        });
+    }
+    Ok(placement)
+}
+
+fn generate_signature(
+    timestamp: &str,
+    api_key: &str,
+    recv_window: &str,
+    payload: &str,
+    api_secret: &str,
+) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(api_secret.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(api_key.as_bytes());
+    mac.update(recv_window.as_bytes());
+    mac.update(payload.as_bytes());
+
+    let result = mac.finalize();
+    let code_bytes = result.into_bytes();
+    hex::encode(code_bytes)
+}
+
+impl BybitClient {
+    /// A client for market data only, with no API key to sign with.
+    pub fn public(config: &Config) -> BybitClient {
+        BybitClient::new(config, Credentials::new(String::new(), String::new()))
+    }
+
+    /// A client signing with `credentials`, as fetched by
+    /// `credentials::fetch`.
+    pub fn new(config: &Config, credentials: Credentials) -> BybitClient {
+        let limits = &config.rate_limits;
+        // a configured base_url takes every endpoint with it
+        let endpoint = |var: &str, missing: &str| {
+            let url = env::var(var).expect(missing);
+            match &config.base_url {
+                Some(base) => profile::rebase(&url, base).unwrap_or(url),
+                None => url,
+            }
+        };
+        let batch_order_url = endpoint("BATCH_ORDER_URL", "batch order url is missing");
+        let order_path = Url::parse(&batch_order_url)
+            .map(|url| url.path().to_string())
+            .unwrap_or_default();
+        BybitClient {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("failed building http client"),
+            base_url: config.base_url.clone().unwrap_or_else(|| {
+                env::var("BYBIT_BASE_URL").unwrap_or_else(|_| "https://api.bybit.com".to_string())
+            }),
+            credentials: RwLock::new(credentials),
+            recv_window: RECV_WINDOW_MS.to_string(),
+            kline_url: endpoint("KLINE_URL", "KLINE_URL env var is missing"),
+            batch_order_url,
+            batch_cancel_order_url: endpoint(
+                "BATCH_CANCEL_ORDER_URL",
+                "batch cancel order url is missing",
+            ),
+            signed_limiter: RateLimiter::new(limits.signed_per_second),
+            public_limiter: RateLimiter::new(limits.public_per_second),
+            cooldown: Cooldown::new(),
+            latency: LatencyTracker::new(order_path, Duration::from_millis(config.latency_warn_ms)),
+            trace: config.trace_requests,
+            auth_alerted: AtomicBool::new(false),
+            clock_offset_ms: AtomicI64::new(0),
+            clock: Mutex::new(None),
+            clock_anchor: Mutex::new(None),
+            clock_jump_ms: config.clock_jump_ms as i64,
+            retry: config.retry.clone(),
+        }
+    }
+
+    /// Time spent waiting in the (signed, public) rate limiters.
+    pub fn limiter_stats(&self) -> (LimiterStats, LimiterStats) {
+        (self.signed_limiter.stats(), self.public_limiter.stats())
+    }
+
+    pub fn cooldown(&self) -> &Cooldown {
+        &self.cooldown
+    }
+
+    /// Sends `request`, timing it for the latency window of its path.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, reqwest::Error> {
+        let request = request.build()?;
+        let endpoint = request.url().path().to_string();
+        if self.trace || verbosity::enabled(Level::Trace) {
+            trace::request(&request);
+        }
+        journal::record(
+            "request",
+            json!({
+                "method": request.method().as_str(),
+                "path": endpoint,
+                "query": request.url().query(),
+                "body": request
+                    .body()
+                    .and_then(|body| body.as_bytes())
+                    .map(String::from_utf8_lossy),
+            }),
+        );
+        let started = Instant::now();
+        let sent = self.client.execute(request).await;
+        let outcome = match &sent {
+            Ok(response) if response.status().is_success() => Outcome::Success,
+            _ => Outcome::TransportError,
+        };
+        self.latency.record(&endpoint, started.elapsed(), outcome);
+        sent
+    }
+
+    /// Signs and sends a private API request. `body` is sent as JSON; the
+    /// query string is signed instead when there's none. The timestamp is
+    /// taken here, after any rate limiter wait, so it's never stale. If the
+    /// wall clock jumped since the last measurement, it's remeasured first.
+    async fn send_signed(
+        &self,
+        method: Method,
+        url: Url,
+        body: Option<String>,
+    ) -> Result<Response, reqwest::Error> {
+        if let Some(drift) = self.clock_jumped() {
+            println!(
+                "WARNING: system clock jumped {}ms since the last clock check, remeasuring",
+                drift
+            );
+            if let Err(e) = self.measure_clock().await {
+                println!("warning: clock check failed: {}", e);
+            }
+        }
+        let timestamp = (Utc::now().timestamp_millis()
+            + self.clock_offset_ms.load(Ordering::Relaxed))
+        .to_string();
+        let payload = match &body {
+            Some(body) => body.as_str(),
+            None => url.query().unwrap_or_default(),
+        };
+        let credentials = self.credentials();
+        let signature = generate_signature(
+            &timestamp,
+            credentials.api_key(),
+            &self.recv_window,
+            payload,
+            credentials.api_secret(),
+        );
+        scrub::register_signature(&signature);
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("X-BAPI-API-KEY", credentials.api_key())
+            .header("X-BAPI-SIGN", &signature)
+            .header("X-BAPI-SIGN-TYPE", "2")
+            .header("X-BAPI-TIMESTAMP", &timestamp)
+            .header("X-BAPI-RECV-WINDOW", &self.recv_window);
+        if let Some(body) = body {
+            request = request
+                .body(body)
+                .header("Content-Type", "application/json");
+        }
+        self.send(request).await
+    }
+
+    /// One try of a request, `sent`, its outcome classified for
+    /// `retry::with_retry`. Replies with a retCode resending won't change
+    /// are returned for the caller to read.
+    async fn attempt<T: DeserializeOwned>(
+        &self,
+        sent: impl Future<Output = Result<Response, BoxError>>,
+    ) -> Result<ApiResponse<T>, Failure> {
+        let response = match sent.await {
+            Ok(response) => response,
+            Err(e) => {
+                let class = match e.downcast_ref::<reqwest::Error>() {
+                    Some(e) => retry::classify_send(e.is_connect()),
+                    // held back by the cooldown
+                    None => ErrorClass::Rejected,
+                };
+                return Err(Failure::new(class, e));
+            }
+        };
+        let status = response.status();
+        if let Some(class) = retry::classify_status(status.as_u16()) {
+            let error = if status == StatusCode::SERVICE_UNAVAILABLE {
+                maintenance(format!("HTTP {}", status))
+            } else {
+                format!("HTTP {}", status).into()
+            };
+            return Err(Failure::new(class, error));
+        }
+        let response_data: ApiResponse<T> = self.read(response).await.map_err(|e| {
+            // a body that couldn't be read may still have been acted on
+            let class = match e.is::<reqwest::Error>() {
+                true => ErrorClass::Ambiguous,
+                false => ErrorClass::Rejected,
+            };
+            Failure::new(class, e)
+        })?;
+        if let Some(class) = retry::classify_code(response_data.ret_code) {
+            let error = format!("{} {}", response_data.ret_code, response_data.ret_msg);
+            return Err(Failure::new(class, error));
+        }
+        Ok(response_data)
+    }
+
+    /// What `send` sends, under `policy`, named `what` in the log.
+    async fn call<T: DeserializeOwned, F>(
+        &self,
+        policy: &RetryPolicy,
+        what: &str,
+        send: impl Fn() -> F,
+    ) -> Result<ApiResponse<T>, BoxError>
+    where
+        F: Future<Output = Result<Response, BoxError>>,
+    {
+        retry::with_retry(policy, what, || self.attempt(send()))
+            .await
+            .map_err(Failure::into_error)
+    }
+
+    /// Market data GET, retried under the reads policy.
+    async fn public_get<T: DeserializeOwned>(
+        &self,
+        what: &str,
+        url: Url,
+    ) -> Result<ApiResponse<T>, BoxError> {
+        let url = &url;
+        self.call(&self.retry.reads, what, || async move {
+            self.cooldown.admit(false).await?;
+            self.public_limiter.acquire().await;
+            Ok(self.send(self.client.get(url.clone())).await?)
+        })
+        .await
+    }
+
+    /// One try of a signed POST of `body` to `url`. `critical` calls wait
+    /// out a cooldown instead of failing.
+    async fn post_once(
+        &self,
+        url: &str,
+        body: &str,
+        critical: bool,
+    ) -> Result<ApiResponse<Value>, Failure> {
+        self.attempt(async {
+            let url = Url::parse(url)?;
+            self.cooldown.admit(critical).await?;
+            self.signed_limiter.acquire().await;
+            Ok(self
+                .send_signed(Method::POST, url, Some(body.to_string()))
+                .await?)
+        })
+        .await
+    }
+
+    /// Follows the exchange's clock after a timestamp rejection, so the
+    /// next signed request lands inside the recv window.
+    fn correct_clock(&self, ret_code: i32, server_time: u64) {
+        if ret_code != TIMESTAMP_ERROR || server_time == 0 {
+            return;
+        }
+        let offset = server_time as i64 - Utc::now().timestamp_millis();
+        self.clock_offset_ms.store(offset, Ordering::Relaxed);
+        println!(
+            "WARNING: clock is {}ms behind the exchange, correcting",
+            offset
+        );
+    }
+
+    /// Measures our clock against the exchange's and signs with the offset
+    /// from then on. Warns when the offset is over half the recv window or
+    /// moved by more than `clock_jump_ms` since the last measurement.
+    pub async fn measure_clock(&self) -> Result<Measurement, BoxError> {
+        let sent = Utc::now();
+        let server = self.get_server_time().await?;
+        let measurement = Measurement::new(sent, Utc::now(), server);
+        *self.clock_anchor.lock().expect("clock lock poisoned") =
+            Some((Instant::now(), measurement.at));
+        let previous = self
+            .clock
+            .lock()
+            .expect("clock lock poisoned")
+            .replace(measurement);
+        self.clock_offset_ms
+            .store(measurement.offset_ms, Ordering::Relaxed);
+        if let Some(previous) = previous {
+            if (measurement.offset_ms - previous.offset_ms).abs() > self.clock_jump_ms {
+                println!(
+                    "WARNING: clock offset jumped from {}ms to {}ms",
+                    previous.offset_ms, measurement.offset_ms
+                );
+            }
+        }
+        if measurement.is_risky(RECV_WINDOW_MS) {
+            println!(
+                "WARNING: {}, over half the {}ms recv window. Sync the system clock",
+                measurement, RECV_WINDOW_MS
+            );
+        } else {
+            info!("{}", measurement);
+        }
+        Ok(measurement)
+    }
+
+    /// The latest clock measurement, none before the first.
+    pub fn clock(&self) -> Option<Measurement> {
+        *self.clock.lock().expect("clock lock poisoned")
+    }
+
+    /// How far the wall clock jumped since the last measurement, when it's
+    /// more than `clock_jump_ms`. Rebases on the jump, so it's reported once.
+    fn clock_jumped(&self) -> Option<i64> {
+        let mut anchor = self.clock_anchor.lock().expect("clock lock poisoned");
+        let (instant, wall) = (*anchor)?;
+        let elapsed = ChronoDuration::from_std(instant.elapsed()).unwrap_or_default();
+        let drift = clock::drift(wall, elapsed, Utc::now());
+        if drift.abs() <= self.clock_jump_ms {
+            return None;
+        }
+        *anchor = Some((Instant::now(), Utc::now()));
+        Some(drift)
+    }
+
+    /// The credentials requests are signed with now.
+    pub fn credentials(&self) -> Credentials {
+        self.credentials
+            .read()
+            .expect("credentials lock poisoned")
+            .clone()
+    }
+
+    /// Signs every request from now on with `credentials`, e.g. after the
+    /// secret was rotated.
+    pub fn set_credentials(&self, credentials: Credentials) {
+        *self.credentials.write().expect("credentials lock poisoned") = credentials;
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn latency(&self) -> &LatencyTracker {
+        &self.latency
+    }
+
+    /// Trips the cooldown on a 403.
+    fn screen_status(&self, status: StatusCode) -> Result<(), BoxError> {
+        if status == StatusCode::FORBIDDEN {
+            return Err(self.cooldown.trip(format!("HTTP {}", status)));
+        }
+        Ok(())
+    }
+
+    /// Trips the cooldown on the IP rate-limit retCode. Any other answer
+    /// means the API is reachable again.
+    fn screen_code(&self, endpoint: &str, ret_code: i32, ret_msg: &str) -> Result<(), BoxError> {
+        if ret_code != 0 {
+            self.latency.mark_bybit_error(endpoint);
+        }
+        if ret_code == IP_LIMITED {
+            return Err(self.cooldown.trip(format!("{} {}", ret_code, ret_msg)));
+        }
+        if AUTH_CODES.contains(&ret_code) {
+            if !self.auth_alerted.swap(true, Ordering::Relaxed) {
+                let mut message = format!(
+                    "{} rejected the API key: {} {}",
+                    endpoint, ret_code, ret_msg
+                );
+                if ret_code == EXPIRED_KEY {
+                    message.push_str(
+                        ". It has expired, create a new key in Bybit under API Management and update API_KEY and API_SECRET",
+                    );
+                }
+                notify::critical(&message);
+            }
+        } else if ret_code == 0 {
+            self.auth_alerted.store(false, Ordering::Relaxed);
+        }
+        self.cooldown.clear();
+        Ok(())
+    }
+
+    /// Parses a reply after screening it for an IP ban or maintenance.
+    async fn read<T: DeserializeOwned>(
+        &self,
+        response: Response,
+    ) -> Result<ApiResponse<T>, BoxError> {
+        let endpoint = response.url().path().to_string();
+        let status = response.status();
+        self.screen_status(status)?;
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            return Err(maintenance(format!("HTTP {}", status)));
+        }
+        // checked before parsing `result`, which maintenance replies leave out
+        let body: Value = response.json().await?;
+        let ret_code = body["retCode"].as_i64().unwrap_or_default() as i32;
+        let ret_msg = body["retMsg"].as_str().unwrap_or_default();
+        journal::record(
+            "response",
+            json!({
+                "path": endpoint,
+                "status": status.as_u16(),
+                "ret_code": ret_code,
+                "ret_msg": ret_msg,
+            }),
+        );
+        // -vv logs every reply, the trace option only the failed ones
+        if (self.trace && ret_code != 0) || verbosity::enabled(Level::Trace) {
+            trace::reply(&endpoint, ret_code, &body.to_string());
+        }
+        self.correct_clock(ret_code, body["time"].as_u64().unwrap_or_default());
+        self.screen_code(&endpoint, ret_code, ret_msg)?;
+        if ret_code != 0 && ret_msg.to_lowercase().contains("maintenance") {
+            return Err(maintenance(format!("{} {}", ret_code, ret_msg)));
+        }
+        Ok(response::parse(body)?)
+    }
+
+    /// Fetches the newest candle of `interval` for `symbol`, built from the
+    /// `price` kind.
+    /// The newest candle of `symbol`, which may still be forming.
+    pub async fn get_kline(
+        &self,
+        category: Category,
+        symbol: &str,
+        interval: Interval,
+        price: AnchorPrice,
+    ) -> Result<(String, Kline), BoxError> {
+        let first_kline = self
+            .get_latest_klines(category, symbol, interval, price, 1)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| unavailable(symbol, "no klines returned".to_string()))?;
+        Ok((symbol.to_string(), first_kline))
+    }
+
+    /// The latest `limit` candles of `symbol`, newest first as Bybit lists
+    /// them. Never empty.
+    pub async fn get_latest_klines(
+        &self,
+        category: Category,
+        symbol: &str,
+        interval: Interval,
+        price: AnchorPrice,
+        limit: usize,
+    ) -> Result<Vec<Kline>, BoxError> {
+        let mut url = self.kline_url(category, symbol, interval, price)?;
+        url.query_pairs_mut()
+            .append_pair("limit", &limit.to_string());
+        let api_response: ApiResponse<Value> = self.public_get("kline", url).await?;
+        if api_response.ret_code == PARAMS_ERROR {
+            return Err(unavailable(symbol, api_response.ret_msg));
+        }
+        let data: KlineData = serde_json::from_value(api_response.into_result("kline")?)?;
+        if data.list.is_empty() {
+            return Err(unavailable(symbol, "no klines returned".to_string()));
+        }
+        Ok(data.list)
+    }
+
+    /// Last-price candles of `interval` for `symbol` that started in
+    /// `[start, end]`, oldest first, read a page at a time going back from
+    /// `end`.
+    pub async fn get_klines(
+        &self,
+        category: Category,
+        symbol: &str,
+        interval: Interval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Kline>, BoxError> {
+        let mut klines = Vec::new();
+        let mut until = end.timestamp_millis();
+        loop {
+            let mut url = self.kline_url(category, symbol, interval, AnchorPrice::Last)?;
+            url.query_pairs_mut()
+                .append_pair("start", &start.timestamp_millis().to_string())
+                .append_pair("end", &until.to_string())
+                .append_pair("limit", &KLINE_PAGE.to_string());
+            let api_response: ApiResponse<Value> = self.public_get("kline", url).await?;
+            if api_response.ret_code == PARAMS_ERROR {
+                return Err(unavailable(symbol, api_response.ret_msg));
+            }
+            let data: KlineData = serde_json::from_value(api_response.into_result("kline")?)?;
+            let page = data.list.len();
+            // newest first, so the last candle is the oldest on the page
+            let oldest = data
+                .list
+                .last()
+                .and_then(|kline| kline.start_time.parse::<i64>().ok());
+            klines.extend(data.list.into_iter().filter(|kline| {
+                kline
+                    .start_time
+                    .parse::<i64>()
+                    .is_ok_and(|at| at >= start.timestamp_millis() && at <= until)
+            }));
+            match oldest {
+                Some(oldest)
+                    if page >= KLINE_PAGE
+                        && oldest > start.timestamp_millis()
+                        && oldest <= until =>
+                {
+                    until = oldest - 1
+                }
+                _ => break,
+            }
+        }
+        klines.sort_by_key(|kline| kline.start_time.parse::<i64>().unwrap_or_default());
+        klines.dedup_by(|a, b| a.start_time == b.start_time);
+        Ok(klines)
+    }
+
+    /// `KLINE_URL` for `symbol`, with any `category`, `symbol` or `interval`
+    /// already in it replaced, and its last path segment too for mark and
+    /// index klines.
+    fn kline_url(
+        &self,
+        category: Category,
+        symbol: &str,
+        interval: Interval,
+        price: AnchorPrice,
+    ) -> Result<Url, BoxError> {
+        let mut url = Url::parse(&self.kline_url)?;
+        if price != AnchorPrice::Last {
+            let path = url.path().to_string();
+            let dir = path.rsplit_once('/').map_or("", |(dir, _)| dir);
+            url.set_path(&format!("{}/{}", dir, price.kline_endpoint()));
+        }
+        let query: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| {
+                !["category", "symbol", "interval", "start", "end", "limit"].contains(&key.as_ref())
+            })
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(query)
+            .append_pair("category", category.as_param())
+            .append_pair("interval", interval.as_param())
+            .append_pair("symbol", symbol);
+        Ok(url)
+    }
+
+    /// Signed GET against `path` on the base url. Non-zero retCodes are
+    /// returned as errors.
+    async fn signed_get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, BoxError> {
+        let url = Url::parse_with_params(&format!("{}{}", self.base_url, path), query)?;
+        let url = &url;
+        let response_data: ApiResponse<T> = self
+            .call(&self.retry.reads, path, || async move {
+                self.cooldown.admit(false).await?;
+                self.signed_limiter.acquire().await;
+                Ok(self.send_signed(Method::GET, url.clone(), None).await?)
+            })
+            .await?;
+        Ok(response_data.into_result(path)?)
+    }
+
+    /// Every instrument listed in `category`, following the page cursor.
+    pub async fn list_instruments(
+        &self,
+        category: Category,
+    ) -> Result<Vec<InstrumentInfo>, BoxError> {
+        let mut instruments = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let mut query = vec![("category", category.as_param()), ("limit", "1000")];
+            if !cursor.is_empty() {
+                query.push(("cursor", &cursor));
+            }
+            let url = Url::parse_with_params(
+                &format!("{}/v5/market/instruments-info", self.base_url),
+                &query,
+            )?;
+            let response: ApiResponse<InstrumentList> =
+                self.public_get("instruments-info", url).await?;
+            let page = response.into_result("instruments-info")?;
+            instruments.extend(page.list);
+            if page.next_page_cursor.is_empty() {
+                return Ok(instruments);
+            }
+            cursor = page.next_page_cursor;
+        }
+    }
+
+    /// Last price and 24h stats of `symbol`. A params error means the
+    /// symbol is unavailable, as with klines.
+    pub async fn get_ticker(&self, category: Category, symbol: &str) -> Result<Ticker, BoxError> {
+        let url = Url::parse_with_params(
+            &format!("{}/v5/market/tickers", self.base_url),
+            &[("category", category.as_param()), ("symbol", symbol)],
+        )?;
+        let response: ApiResponse<TickerList> = self.public_get("tickers", url).await?;
+        if response.ret_code == PARAMS_ERROR {
+            return Err(unavailable(symbol, response.ret_msg));
+        }
+        response
+            .into_result("tickers")?
+            .list
+            .into_iter()
+            .next()
+            .ok_or_else(|| unavailable(symbol, "no ticker returned".to_string()))
+    }
+
+    /// Hourly open interest of `symbol` over the last `hours`, newest
+    /// first.
+    pub async fn get_open_interest(
+        &self,
+        category: Category,
+        symbol: &str,
+        hours: u32,
+    ) -> Result<Vec<OpenInterest>, BoxError> {
+        let limit = (hours + 1).to_string();
+        let url = Url::parse_with_params(
+            &format!("{}/v5/market/open-interest", self.base_url),
+            &[
+                ("category", category.as_param()),
+                ("symbol", symbol),
+                ("intervalTime", "1h"),
+                ("limit", &limit),
+            ],
+        )?;
+        let response: ApiResponse<OpenInterestList> = self.public_get("open-interest", url).await?;
+        Ok(response.into_result("open-interest")?.list)
+    }
+
+    /// Mark price of `symbol`, or the last traded price for spot, which
+    /// has no mark.
+    pub async fn get_mark_price(&self, category: Category, symbol: &str) -> Result<f64, BoxError> {
+        let ticker = self.get_ticker(category, symbol).await?;
+        let price = if ticker.mark_price.is_empty() {
+            ticker.last_price
+        } else {
+            ticker.mark_price
+        };
+        Ok(price.parse()?)
+    }
+
+    /// Open linear positions on `symbol`, one per side in hedge mode.
+    pub async fn get_positions(&self, symbol: &str) -> Result<Vec<PositionInfo>, BoxError> {
+        let result: PositionList = self
+            .signed_get(
+                "/v5/position/list",
+                &[("category", "linear"), ("symbol", symbol)],
+            )
+            .await?;
+        Ok(result.list)
+    }
+
+    /// Funding settlements of linear `symbol` between `start` and `end`,
+    /// at most 7 days apart, following the page cursor.
+    pub async fn get_funding_log(
+        &self,
+        symbol: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Transaction>, BoxError> {
+        let start = start.timestamp_millis().to_string();
+        let end = end.timestamp_millis().to_string();
+        let mut transactions = Vec::new();
+        let mut cursor = String::new();
+        loop {
+            let mut query = vec![
+                ("accountType", "UNIFIED"),
+                ("category", "linear"),
+                ("symbol", symbol),
+                ("type", "SETTLEMENT"),
+                ("startTime", start.as_str()),
+                ("endTime", end.as_str()),
+                ("limit", "50"),
+            ];
+            if !cursor.is_empty() {
+                query.push(("cursor", &cursor));
+            }
+            let page: TransactionLog = self
+                .signed_get("/v5/account/transaction-log", &query)
+                .await?;
+            transactions.extend(page.list);
+            if page.next_page_cursor.is_empty() {
+                return Ok(transactions);
+            }
+            cursor = page.next_page_cursor;
+        }
+    }
+
+    /// The exchange's clock.
+    pub async fn get_server_time(&self) -> Result<DateTime<Utc>, BoxError> {
+        let url = Url::parse(&format!("{}/v5/market/time", self.base_url))?;
+        let response: ApiResponse<ServerTime> = self.public_get("time", url).await?;
+        let nanos: i64 = response.into_result("time")?.time_nano.parse()?;
+        DateTime::from_timestamp(
+            nanos.div_euclid(1_000_000_000),
+            nanos.rem_euclid(1_000_000_000) as u32,
+        )
+        .ok_or_else(|| format!("bad server time {}", nanos).into())
+    }
+
+    pub async fn get_api_key_info(&self) -> Result<ApiKeyInfo, BoxError> {
+        self.signed_get("/v5/user/query-api", &[]).await
+    }
+
+    pub async fn get_account_info(&self) -> Result<AccountInfo, BoxError> {
+        self.signed_get("/v5/account/info", &[]).await
+    }
+
+    pub async fn get_spot_margin_state(&self) -> Result<SpotMarginState, BoxError> {
+        self.signed_get("/v5/spot-margin-trade/state", &[]).await
+    }
+
+    /// How much quote coin a spot buy of `symbol` could spend on margin.
+    pub async fn spot_borrow_check(&self, symbol: &str) -> Result<SpotBorrowCheck, BoxError> {
+        self.signed_get(
+            "/v5/order/spot-borrow-check",
+            &[("category", "spot"), ("symbol", symbol), ("side", "Buy")],
+        )
+        .await
+    }
+
+    /// Balances in `account_type`, for `coin` or every coin held when it
+    /// is empty.
+    pub async fn get_wallet_balance(
+        &self,
+        account_type: &str,
+        coin: &str,
+    ) -> Result<Vec<WalletAccount>, BoxError> {
+        let mut query = vec![("accountType", account_type)];
+        if !coin.is_empty() {
+            query.push(("coin", coin));
+        }
+        let result: WalletBalanceResult = self
+            .signed_get("/v5/account/wallet-balance", &query)
+            .await?;
+        Ok(result.list)
+    }
+
+    /// Open orders for `symbol`, plus those under `order_filter` when the
+    /// default listing leaves them out.
+    pub async fn get_open_orders(
+        &self,
+        category: Category,
+        symbol: &str,
+        order_filter: Option<&str>,
+    ) -> Result<Vec<Order>, BoxError> {
+        let mut orders = Vec::new();
+        let mut filters = vec![None];
+        if order_filter.is_some() {
+            filters.push(order_filter);
+        }
+        for filter in filters {
+            let mut query = vec![
+                ("category", category.as_param()),
+                ("symbol", symbol),
+                ("limit", "50"),
+            ];
+            if let Some(filter) = filter {
+                query.push(("orderFilter", filter));
+            }
+            let result: OrderListResult = self.signed_get("/v5/order/realtime", &query).await?;
+            orders.extend(result.list);
+        }
+        Ok(orders)
+    }
+
+    /// Looks up one order, falling back to the live order list when it
+    /// hasn't reached the history yet.
+    pub async fn get_order(
+        &self,
+        category: Category,
+        symbol: &str,
+        order_id: &str,
+        order_filter: Option<&str>,
+    ) -> Result<Option<Order>, BoxError> {
+        let mut query = vec![
+            ("category", category.as_param()),
+            ("symbol", symbol),
+            ("orderId", order_id),
+        ];
+        if let Some(filter) = order_filter {
+            query.push(("orderFilter", filter));
+        }
+        for path in ["/v5/order/history", "/v5/order/realtime"] {
+            let result: OrderListResult = self.signed_get(path, &query).await?;
+            if let Some(order) = result.list.into_iter().next() {
+                return Ok(Some(order));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Signed POST of `body` to `path` on the base url, retried under
+    /// `policy`. Any retCode in `ok_codes` besides 0 also counts as
+    /// success. `critical` calls wait out a cooldown instead of failing.
+    async fn signed_post<B: Serialize>(
+        &self,
+        path: &str,
+        body: &B,
+        ok_codes: &[i32],
+        critical: bool,
+        policy: &RetryPolicy,
+    ) -> Result<ApiResponse<Value>, BoxError> {
+        // signed and sent as the same bytes, so they can't drift apart
+        let body = serde_json::to_string(body)?;
+        if !body.starts_with('{') {
+            return Err(format!("{} body must serialize to an object", path).into());
+        }
+        let url = format!("{}{}", self.base_url, path);
+        let response_data =
+            retry::with_retry(policy, path, || self.post_once(&url, &body, critical))
+                .await
+                .map_err(Failure::into_error)?;
+        if response_data.ret_code != 0 && !ok_codes.contains(&response_data.ret_code) {
+            return Err(format!(
+                "{} failed: {} {}",
+                path, response_data.ret_code, response_data.ret_msg
+            )
+            .into());
+        }
+        Ok(response_data)
+    }
+
+    pub async fn cancel_order(&self, request: &CancelOrderRequest) -> Result<(), BoxError> {
+        self.signed_post("/v5/order/cancel", request, &[], true, &self.retry.cancels)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels every open order on `symbol`, or every conditional one with
+    /// `order_filter` "StopOrder" on spot. Returns what was cancelled.
+    pub async fn cancel_all(
+        &self,
+        category: Category,
+        symbol: &str,
+        order_filter: Option<&str>,
+    ) -> Result<Vec<CancelledOrder>, BoxError> {
+        let request = CancelAllRequest {
+            category: category.as_param().to_string(),
+            symbol: symbol.to_string(),
+            order_filter: order_filter.map(str::to_string),
+        };
+        let response = self
+            .signed_post(
+                "/v5/order/cancel-all",
+                &request,
+                &[],
+                true,
+                &self.retry.cancels,
+            )
+            .await?;
+        let result: CancelAllResult = serde_json::from_value(response.into_result("cancel-all")?)?;
+        Ok(result.list)
+    }
+
+    /// Sets TP/SL/trailing stop on a position. Setting the same values
+    /// again counts as success.
+    pub async fn set_trading_stop(&self, request: &TradingStopRequest) -> Result<(), BoxError> {
+        self.signed_post(
+            "/v5/position/trading-stop",
+            request,
+            &[NOT_MODIFIED],
+            true,
+            &self.retry.orders,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Switches a symbol between cross and isolated margin. Already being
+    /// in the requested mode counts as success.
+    pub async fn switch_isolated(&self, request: &SwitchIsolatedRequest) -> Result<(), BoxError> {
+        self.signed_post(
+            "/v5/position/switch-isolated",
+            request,
+            &[MARGIN_MODE_NOT_MODIFIED],
+            false,
+            &self.retry.orders,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Places `orders` in as many batch requests as the batch limit needs.
+    /// Once a batch has gone through, a later one failing outright leaves
+    /// its orders and the rest rejected instead of an error, so what was
+    /// placed is still tracked.
+    pub async fn place_batch_order(
+        &self,
+        category: Category,
+        orders: &[OrderRequest],
+    ) -> Result<Placement, BoxError> {
+        let mut placement = Placement::default();
+        let mut chunks = orders.chunks(BATCH_LIMIT);
+        while let Some(chunk) = chunks.next() {
+            match self.place_batch_chunk(category, chunk).await {
+                Ok(chunk) => {
+                    placement.placed.extend(chunk.placed);
+                    placement.rejected.extend(chunk.rejected);
+                }
+                Err(e) if placement.placed.is_empty() => return Err(e),
+                Err(e) => {
+                    let msg = e.to_string();
+                    let unsent = chunk.iter().chain(chunks.flatten());
+                    placement.rejected.extend(unsent.map(|order| Rejection {
+                        order_link_id: order.order_link_id.clone(),
+                        code: -1,
+                        msg: msg.clone(),
+                    }));
+                    break;
+                }
+            }
+        }
+        Ok(placement)
+    }
+
+    async fn place_batch_chunk(
+        &self,
+        category: Category,
+        orders: &[OrderRequest],
+    ) -> Result<Placement, BoxError> {
+        // shared with each attempt, which shrinks it after a lost reply
+        let pending = Mutex::new(orders.to_vec());
+        let found = Mutex::new(Vec::new());
+        let (shared_pending, shared_found) = (&pending, &found);
+        let sent = retry::with_retry(&self.retry.orders, "batch order", || async move {
+            let batch = shared_pending.lock().unwrap().clone();
+            let body = serde_json::to_string(&BatchOrderRequest::new(category, batch.clone()))
+                .map_err(|e| Failure::new(ErrorClass::Rejected, e))?;
+            let failure = match self.post_once(&self.batch_order_url, &body, false).await {
+                Ok(response_data) => return Ok(Some(response_data)),
+                Err(failure) if failure.class == ErrorClass::Ambiguous => failure,
+                Err(failure) => return Err(failure),
+            };
+            // the batch may have gone through; only resend what isn't there
+            let placed = self
+                .find_placed(category, &batch)
+                .await
+                .map_err(|e| Failure::new(ErrorClass::Rejected, e))?;
+            let mut pending = shared_pending.lock().unwrap();
+            pending.retain(|order| {
+                !placed
+                    .iter()
+                    .any(|placed| placed.order_link_id == order.order_link_id)
+            });
+            shared_found.lock().unwrap().extend(placed);
+            if pending.is_empty() {
+                return Ok(None);
+            }
+            Err(failure)
+        })
+        .await;
+        let pending = pending.into_inner().unwrap();
+        let mut placement = Placement {
+            placed: found.into_inner().unwrap(),
+            ..Placement::default()
+        };
+        match sent {
+            Ok(Some(response_data)) => {
+                debug!("Response: {:#?}", response_data);
+                match parse_placement(response_data, &pending) {
+                    Ok(mut chunk) => {
+                        self.adopt_duplicates(category, &pending, &mut chunk).await;
+                        placement.placed.extend(chunk.placed);
+                        placement.rejected.extend(chunk.rejected);
+                    }
+                    Err(e) if placement.placed.is_empty() => return Err(e),
+                    // what an earlier attempt found placed is still tracked
+                    Err(e) => placement
+                        .rejected
+                        .extend(pending.iter().map(|order| Rejection {
+                            order_link_id: order.order_link_id.clone(),
+                            code: -1,
+                            msg: e.to_string(),
+                        })),
+                }
+            }
+            Ok(None) => {}
+            // a ban is an error rather than a retry, so retries don't extend it
+            Err(failure) if failure.class == ErrorClass::Rejected => {
+                return Err(failure.into_error())
+            }
+            Err(failure) => placement
+                .rejected
+                .extend(pending.iter().map(|order| Rejection {
+                    order_link_id: order.order_link_id.clone(),
+                    code: -1,
+                    msg: failure.to_string(),
+                })),
+        }
+        Ok(placement)
+    }
+
+    /// Moves orders rejected for a taken orderLinkId over to `placed`,
+    /// looking up the order that already carries the id. Ones that can't be
+    /// found, or not looked up, stay rejected.
+    async fn adopt_duplicates(
+        &self,
+        category: Category,
+        orders: &[OrderRequest],
+        chunk: &mut Placement,
+    ) {
+        let duplicates: Vec<OrderRequest> = orders
+            .iter()
+            .filter(|order| {
+                chunk.rejected.iter().any(|rejection| {
+                    rejection.is_duplicate() && rejection.order_link_id == order.order_link_id
+                })
+            })
+            .cloned()
+            .collect();
+        if duplicates.is_empty() {
+            return;
+        }
+        let found = match self.find_placed(category, &duplicates).await {
+            Ok(found) => found,
+            Err(e) => {
+                for rejection in &mut chunk.rejected {
+                    if rejection.is_duplicate() {
+                        rejection.msg =
+                            format!("{}, and looking it up failed: {}", rejection.msg, e);
+                    }
+                }
+                return;
+            }
+        };
+        for existing in &found {
+            info!(
+                "{} already placed as {}, adopting it",
+                existing.order_link_id, existing.order_id
+            );
+        }
+        chunk.rejected.retain(|rejection| {
+            !found
+                .iter()
+                .any(|existing| existing.order_link_id == rejection.order_link_id)
+        });
+        chunk.placed.extend(found);
+    }
+
+    /// Which of `orders` exist on the exchange, looked up by orderLinkId.
+    async fn find_placed(
+        &self,
+        category: Category,
+        orders: &[OrderRequest],
+    ) -> Result<Vec<BatchOrderResponse>, BoxError> {
+        let mut found = Vec::new();
+        for order in orders {
+            let query = [
+                ("category", category.as_param()),
+                ("symbol", order.symbol.as_str()),
+                ("orderLinkId", order.order_link_id.as_str()),
+            ];
+            for path in ["/v5/order/realtime", "/v5/order/history"] {
+                let result: OrderListResult = self.signed_get(path, &query).await?;
+                if let Some(existing) = result.list.into_iter().next() {
+                    found.push(BatchOrderResponse {
+                        category: category.as_param().to_string(),
+                        symbol: existing.symbol,
+                        order_id: existing.order_id,
+                        order_link_id: existing.order_link_id,
+                        created_at: String::new(),
+                    });
+                    break;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Cancels in batches of at most `BATCH_LIMIT`, in the given order.
+    /// Returns how each order came out, in `cancel_order_data` order; a
+    /// chunk that keeps failing leaves its orders failed and the rest go on.
+    pub async fn cancel_batch_order(
+        &self,
+        category: Category,
+        cancel_order_data: &[CancelOrderData],
+    ) -> Result<Vec<CancelOutcome>, BoxError> {
+        let mut outcomes = Vec::new();
+        for chunk in cancel_order_data.chunks(BATCH_LIMIT) {
+            outcomes.extend(self.cancel_batch_chunk(category, chunk).await?);
+        }
+        Ok(outcomes)
+    }
+
+    /// Cancels are safe to repeat, so every failed attempt is retried. Only
+    /// a ban or cooldown is an error; otherwise a chunk that can't be
+    /// cancelled comes back as failed outcomes.
+    async fn cancel_batch_chunk(
+        &self,
+        category: Category,
+        cancel_order_data: &[CancelOrderData],
+    ) -> Result<Vec<CancelOutcome>, BoxError> {
+        let failed = |reason: String| {
+            println!(
+                "batch cancel of {} orders failed: {}",
+                cancel_order_data.len(),
+                reason
+            );
+            cancel_order_data
+                .iter()
+                .map(|order| CancelOutcome::failed(order, reason.clone()))
+                .collect()
+        };
+        let body = BatchCancelRequest::new(category, cancel_order_data.to_vec());
+        let body = serde_json::to_string(&body)?;
+        let sent = retry::with_retry(&self.retry.cancels, "batch cancel", || {
+            self.post_once(&self.batch_cancel_order_url, &body, true)
+        })
+        .await;
+        match sent {
+            Ok(response_data) => {
+                debug!("cancel response = {:?}", response_data);
+                Ok(cancels::parse_cancels(response_data, cancel_order_data)
+                    .unwrap_or_else(|e| failed(e.to_string())))
+            }
+            Err(failure) if failure.class == ErrorClass::Rejected => Err(failure.into_error()),
+            Err(failure) => Ok(failed(failure.to_string())),
+        }
+    }
+}
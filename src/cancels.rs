@@ -0,0 +1,160 @@
+//! A batch cancel's reply, order by order. A 0 retCode only means the
+//! batch was read; each order's own code is in `retExtInfo.list`.
+
+use crate::response::ApiResponse;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Per-order retCode for an order that's already filled, cancelled or
+/// unknown to the exchange.
+pub const NOT_EXISTS_OR_TOO_LATE: i32 = 110001;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CancelOrderData {
+    pub symbol: String,
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchCancelResult {
+    pub list: Vec<BatchCancelResponse>,
+}
+
+/// Blank ids, or none at all, for an order the cancel didn't find.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchCancelResponse {
+    #[serde(rename = "orderId", default)]
+    pub order_id: String,
+    #[serde(rename = "orderLinkId", default)]
+    pub order_link_id: String,
+}
+
+/// What an order the cancel found already gone turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Settled {
+    /// Executed, in full or before being closed: a fill to report.
+    Filled,
+    /// Closed with nothing executed, e.g. cancelled in the app.
+    ClosedElsewhere,
+    /// Unknown to the exchange.
+    Missing,
+    /// Still open after all, so the cancel didn't happen.
+    Open,
+}
+
+impl Settled {
+    /// Classifies an order by its final `orderStatus` and executed qty.
+    pub fn from_status(status: &str, executed: f64) -> Settled {
+        match status {
+            "Filled" => Settled::Filled,
+            "Cancelled" | "PartiallyFilledCanceled" | "Deactivated" | "Rejected" => {
+                if executed > 0.0 {
+                    Settled::Filled
+                } else {
+                    Settled::ClosedElsewhere
+                }
+            }
+            _ => Settled::Open,
+        }
+    }
+}
+
+/// How one order in a cancel came out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CancelOutcome {
+    pub symbol: String,
+    pub order_id: String,
+    pub order_link_id: String,
+    pub code: i32,
+    pub msg: String,
+    /// For an order that was already gone, what its final status showed.
+    pub settled: Option<Settled>,
+}
+
+impl CancelOutcome {
+    pub fn cancelled(order: &CancelOrderData) -> CancelOutcome {
+        CancelOutcome {
+            symbol: order.symbol.clone(),
+            order_id: order.order_id.clone(),
+            order_link_id: String::new(),
+            code: 0,
+            msg: "OK".to_string(),
+            settled: None,
+        }
+    }
+
+    /// An order whose cancel never got a usable reply.
+    pub fn failed(order: &CancelOrderData, msg: String) -> CancelOutcome {
+        CancelOutcome {
+            code: -1,
+            msg,
+            ..CancelOutcome::cancelled(order)
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.code == 0
+    }
+
+    /// Filled or closed before the cancel got there, so resending it
+    /// changes nothing. Not if its status shows it's still open.
+    pub fn is_gone(&self) -> bool {
+        self.code == NOT_EXISTS_OR_TOO_LATE && self.settled != Some(Settled::Open)
+    }
+
+    /// Neither cancelled nor gone: still resting, worth another try.
+    pub fn is_failed(&self) -> bool {
+        !self.is_cancelled() && !self.is_gone()
+    }
+}
+
+/// One outcome per order in `requests`, in the same order. An order the
+/// reply doesn't mention counts as failed.
+pub fn parse_cancels(
+    response: ApiResponse<Value>,
+    requests: &[CancelOrderData],
+) -> Result<Vec<CancelOutcome>, Box<dyn std::error::Error + Send + Sync>> {
+    let codes = response
+        .ret_ext_info
+        .get("list")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let result: BatchCancelResult = serde_json::from_value(response.into_result("batch cancel")?)?;
+    let outcomes = requests
+        .iter()
+        .enumerate()
+        .map(|(index, request)| {
+            let info = codes.get(index);
+            let entry = result.list.get(index);
+            let code = match (entry, info.and_then(|info| info.get("code"))) {
+                (Some(_), Some(code)) => code.as_i64().unwrap_or(-1) as i32,
+                (Some(_), None) => 0,
+                (None, _) => -1,
+            };
+            let msg = info
+                .and_then(|info| info.get("msg"))
+                .and_then(Value::as_str)
+                .unwrap_or(if entry.is_some() {
+                    ""
+                } else {
+                    "missing from reply"
+                });
+            CancelOutcome {
+                symbol: request.symbol.clone(),
+                order_id: entry
+                    .map(|entry| entry.order_id.clone())
+                    .filter(|order_id| !order_id.is_empty())
+                    .unwrap_or_else(|| request.order_id.clone()),
+                order_link_id: entry
+                    .map(|entry| entry.order_link_id.clone())
+                    .unwrap_or_default(),
+                code,
+                msg: msg.to_string(),
+                settled: None,
+            }
+        })
+        .collect();
+    Ok(outcomes)
+}
@@ -0,0 +1,134 @@
+use crate::bybit::{BoxError, CancelOrderData, Order, OrderRequest};
+use crate::config::{CascadeConfig, Category, OrderType, StrategyConfig};
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::ladder;
+use crate::sizing::Level;
+use crate::state::StateOrder;
+
+/// Follows the fill of `order`, `percent` below the open, with a bid
+/// `step_percent` deeper, tracked with the cycle's entries. Returns what
+/// was done for the fill event, including why nothing was.
+pub async fn place(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &CascadeConfig,
+    cycle: &mut Cycle,
+    order: &Order,
+    percent: f64,
+) -> Result<String, BoxError> {
+    let symbol = order.symbol.as_str();
+    let (level, depth) = match ladder::cascade_from_link_id(&order.order_link_id) {
+        Some((level, depth)) => (level, depth + 1),
+        None => match ladder::level_from_link_id(&order.order_link_id) {
+            Some(level) => (level, 1),
+            None => return Ok("not cascaded, not a ladder entry".to_string()),
+        },
+    };
+    if depth > config.max_depth {
+        return Ok(format!(
+            "not cascaded, max_depth {} reached",
+            config.max_depth
+        ));
+    }
+    // a symbol whose hold is over has no anchor left
+    let Some(anchor) = cycle.anchors.get(symbol).copied() else {
+        return Ok("not cascaded, hold is over".to_string());
+    };
+    let link_id = format!(
+        "{}-{}-{}-{}d{}",
+        strategy.link_id_prefix,
+        symbol,
+        cycle.report.open.timestamp_millis(),
+        level,
+        depth
+    );
+    // the children of a split level cascade once, on the first fill
+    let exists = ctx.store.read(|state| {
+        state.strategies.get(&strategy.name).is_some_and(|state| {
+            state
+                .entries
+                .iter()
+                .any(|entry| entry.order_link_id == link_id)
+        })
+    });
+    if exists {
+        return Ok("already cascaded".to_string());
+    }
+    let notional = match config.budget {
+        Some(budget) => budget,
+        None => strategy.budgets.get(level).copied().unwrap_or_default(),
+    };
+    let cascaded = cycle.cascaded.get(symbol).copied().unwrap_or_default();
+    if let Some(max) = config.max_notional {
+        if cascaded + notional > max {
+            return Ok(format!(
+                "not cascaded, {:.2} more would pass max_notional {:.2}",
+                notional, max
+            ));
+        }
+    }
+    let percent = percent + config.step_percent;
+    let category = strategy.category_of(symbol);
+    let instrument = ctx.instrument_or_refresh(category, symbol).await?;
+    let planned = ladder::calculate_position(
+        anchor,
+        symbol,
+        &instrument,
+        &[Level {
+            percent,
+            notional,
+            max_child_notional: None,
+            child_band_percent: 0.0,
+            over_max: strategy.over_max_order,
+        }],
+    )?;
+    let orders: Vec<OrderRequest> = planned
+        .into_iter()
+        .map(|planned| {
+            OrderRequest::builder(
+                symbol,
+                "Buy",
+                OrderType::Limit.as_param(),
+                planned.qty,
+                planned.price,
+                link_id.clone(),
+            )
+            .leverage(category == Category::Spot && ctx.config.symbols[symbol].spot_margin)
+            .build()
+        })
+        .collect();
+    let placement = ctx.client.place_batch_order(category, &orders).await;
+    ctx.lifecycle
+        .placed(&strategy.name, category, &orders, &placement);
+    let placement = placement?;
+    ctx.note_rejections(strategy, &placement.rejected).await;
+    if let Some(rejection) = placement.rejected.first() {
+        return Err(rejection.msg.clone().into());
+    }
+    let placed_notional: f64 = orders.iter().map(ladder::notional).sum();
+    let mut entries = StateOrder::placed(strategy, &orders, &placement.placed);
+    for entry in &mut entries {
+        entry.level_percent = Some(percent);
+    }
+    ctx.store.update_strategy(&strategy.name, |state| {
+        state.entries.extend(entries);
+        state.counters.placed += 1;
+        state.counters.notional_deployed += placed_notional;
+        state.record_placed(&[(link_id.as_str(), format!("{}%", percent))]);
+    });
+    *cycle.cascaded.entry(symbol.to_string()).or_default() += notional;
+    cycle
+        .tracked
+        .extend(placement.placed.iter().map(CancelOrderData::from));
+    Ok(orders
+        .iter()
+        .map(|order| {
+            format!(
+                "cascaded to {}%, qty {} @ {}",
+                percent, order.qty, order.price
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", "))
+}
@@ -0,0 +1,78 @@
+//! Whether a bot that starts after a placement time still places for it,
+//! given the `catch_up` policy and how late it is.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Deserialize;
+use std::io::{BufRead, Write};
+
+/// Starting within this long after the placement time counts as being on
+/// time.
+pub const ON_TIME_GRACE_SECS: i64 = 300;
+
+/// What to do when the bot starts after the daily open has already passed.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Wait for the next open.
+    Skip,
+    /// Place immediately against today's open if still within the catch-up window.
+    PlaceLate,
+    /// Ask on stdin, falling back to `Skip` when not attached to a terminal.
+    Prompt,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StartupAction {
+    PlaceNow,
+    PlaceLate,
+    WaitForOpen,
+}
+
+/// What to do on starting at `now` for the cycle due at `placement`.
+/// Starting before it, or within the grace after it, is on time; later
+/// than `window_hours` waits for the next open. In between the policy
+/// decides, and `ask` is called with how late it is for `Prompt`.
+pub fn startup_action(
+    policy: CatchUpPolicy,
+    window_hours: u64,
+    placement: DateTime<Utc>,
+    now: DateTime<Utc>,
+    ask: impl FnOnce(ChronoDuration) -> bool,
+) -> StartupAction {
+    let late = now - placement;
+    if late <= ChronoDuration::seconds(ON_TIME_GRACE_SECS) {
+        return StartupAction::PlaceNow;
+    }
+    if late > ChronoDuration::hours(window_hours as i64) {
+        return StartupAction::WaitForOpen;
+    }
+    let place_late = match policy {
+        CatchUpPolicy::Skip => false,
+        CatchUpPolicy::PlaceLate => true,
+        CatchUpPolicy::Prompt => ask(late),
+    };
+    if place_late {
+        StartupAction::PlaceLate
+    } else {
+        StartupAction::WaitForOpen
+    }
+}
+
+/// Asks whether to place `late`, reading the answer from `input`. Without
+/// a terminal nobody is there to answer, so it skips without reading.
+pub fn prompt_place_late(input: &mut impl BufRead, terminal: bool, late: ChronoDuration) -> bool {
+    if !terminal {
+        println!("catch_up = prompt but stdin is not a terminal, skipping");
+        return false;
+    }
+    print!(
+        "Missed today's open by {}m. Place the ladder now? [y/N] ",
+        late.num_minutes()
+    );
+    std::io::stdout().flush().ok();
+    let mut answer = String::new();
+    if input.read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
@@ -0,0 +1,58 @@
+//! How far our clock is from the exchange's. Signed requests carry our
+//! timestamp and are refused once it falls outside the recv window.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One reading of the exchange's clock against ours.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub at: DateTime<Utc>,
+    /// Milliseconds the exchange's clock is ahead of ours.
+    pub offset_ms: i64,
+    pub round_trip_ms: i64,
+}
+
+impl Measurement {
+    /// The exchange reported `server` between our `sent` and `received`,
+    /// taken to be at the middle of the round trip.
+    pub fn new(sent: DateTime<Utc>, received: DateTime<Utc>, server: DateTime<Utc>) -> Measurement {
+        let round_trip = received - sent;
+        Measurement {
+            at: received,
+            offset_ms: (server - (sent + round_trip / 2)).num_milliseconds(),
+            round_trip_ms: round_trip.num_milliseconds(),
+        }
+    }
+
+    /// Whether the offset is over half the recv window, close enough to it
+    /// that a slow request would be refused.
+    pub fn is_risky(&self, recv_window_ms: i64) -> bool {
+        self.offset_ms.abs() > recv_window_ms / 2
+    }
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = if self.offset_ms > 0 {
+            "behind"
+        } else {
+            "ahead of"
+        };
+        write!(
+            f,
+            "clock {}ms {} the exchange, {}ms round trip",
+            self.offset_ms.abs(),
+            side,
+            self.round_trip_ms
+        )
+    }
+}
+
+/// How far our wall clock moved on its own since `since`, as read against
+/// the `elapsed` a monotonic clock counted, e.g. an NTP step or a resumed
+/// VM. Positive when it jumped forward.
+pub fn drift(since: DateTime<Utc>, elapsed: Duration, now: DateTime<Utc>) -> i64 {
+    (now - since).num_milliseconds() - elapsed.num_milliseconds()
+}
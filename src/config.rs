@@ -0,0 +1,2168 @@
+use crate::anchor::Reducer;
+use crate::catch_up::CatchUpPolicy;
+use crate::grid::{self, GridAllocation, GridSpacing};
+use crate::guards::Guards;
+use crate::localtime::LocalTime;
+use crate::notify::{Topic, EVENT_KINDS};
+use crate::order_request::TimeInForce;
+use crate::profile;
+use crate::retry::RetryPolicy;
+use crate::sizing::{Level, OverMaxOrder};
+use crate::stagger::Spread;
+use crate::storage;
+use crate::throttle::ThrottleConfig;
+use chrono::Duration as ChronoDuration;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env, fmt, fs,
+    path::Path,
+    time::Duration,
+};
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub schedule: ScheduleConfig,
+    pub strategies: Vec<StrategyConfig>,
+    /// Symbols to trade, with their per-symbol settings.
+    pub symbols: BTreeMap<String, SymbolConfig>,
+    /// Old symbol names mapped to what the exchange lists them as now,
+    /// e.g. after a merge or rename.
+    pub symbol_aliases: BTreeMap<String, String>,
+    /// Refuse to start when a configured symbol isn't listed, instead of
+    /// skipping it.
+    pub strict_symbols: bool,
+    pub rate_limits: RateLimitConfig,
+    pub maintenance: MaintenanceConfig,
+    pub retry: RetryConfig,
+    /// Bounds on order and cycle sizes that hold a cycle back when breached.
+    pub guards: Guards,
+    /// p95 of order placement requests above which a warning is logged.
+    pub latency_warn_ms: u64,
+    /// Jump of the system clock, or of its offset from the exchange's,
+    /// that has the clock remeasured and warned about.
+    pub clock_jump_ms: u64,
+    /// Log each request (credentials redacted) and failed reply bodies.
+    pub trace_requests: bool,
+    pub email: Option<EmailConfig>,
+    pub slack: Option<SlackConfig>,
+    pub ntfy: Option<NtfyConfig>,
+    /// Dedupe, digests and rate caps for every channel.
+    pub notifications: ThrottleConfig,
+    /// Which channels get which topics, in place of each channel's
+    /// `events`.
+    pub routes: Option<RoutesConfig>,
+    pub http: Option<HttpConfig>,
+    pub journal: Option<JournalConfig>,
+    /// Where the API key and secret come from.
+    pub credentials: CredentialsConfig,
+    /// Where the state file, caches, journal and reports live. The paths
+    /// below are taken relative to it unless absolute.
+    pub data_dir: String,
+    /// Each cycle report is also written here as a dated markdown file.
+    pub reports_dir: String,
+    /// CSV that an equity snapshot is appended to every cycle.
+    pub equity_csv: String,
+    /// How long cached instrument rules are used before being refetched.
+    pub instrument_ttl_hours: u64,
+    /// Age past which falling back to the cached instrument rules, when
+    /// instruments-info can't be fetched, warns.
+    pub instrument_cache_max_age_hours: u64,
+    pub state_path: String,
+    /// Exchange REST host in place of `BYBIT_BASE_URL`, `mainnet`,
+    /// `testnet` or a URL. The other endpoint URLs are moved onto it.
+    pub base_url: Option<String>,
+    /// Named profiles selected with `--profile` or `STINK_PROFILE`.
+    pub profiles: BTreeMap<String, ProfileConfig>,
+    /// The profile this config was loaded for, if any.
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            schedule: ScheduleConfig::default(),
+            strategies: vec![StrategyConfig::daily()],
+            symbols: ["ALTUSDT", "MANTAUSDT", "TAOUSDT"]
+                .into_iter()
+                .map(|symbol| (symbol.to_string(), SymbolConfig::default()))
+                .collect(),
+            symbol_aliases: BTreeMap::new(),
+            strict_symbols: true,
+            instrument_ttl_hours: 24,
+            instrument_cache_max_age_hours: 72,
+            rate_limits: RateLimitConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            retry: RetryConfig::default(),
+            guards: Guards::default(),
+            latency_warn_ms: 1000,
+            clock_jump_ms: 1000,
+            trace_requests: false,
+            email: None,
+            slack: None,
+            ntfy: None,
+            notifications: ThrottleConfig::default(),
+            routes: None,
+            http: None,
+            journal: None,
+            credentials: CredentialsConfig::default(),
+            data_dir: storage::default_data_dir(
+                env::var("XDG_DATA_HOME").ok().as_deref(),
+                env::var("HOME").ok().as_deref(),
+            ),
+            reports_dir: "reports".to_string(),
+            equity_csv: "equity.csv".to_string(),
+            state_path: "state.json".to_string(),
+            base_url: None,
+            profiles: BTreeMap::new(),
+            profile: None,
+        }
+    }
+}
+
+/// What a profile changes. Every field is optional, so a profile with its
+/// own `config.<name>.toml` needs no section at all.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ProfileConfig {
+    /// Env file the credentials come from, `.env.<name>` when unset.
+    pub env_file: Option<String>,
+    /// Overrides the top-level `base_url`.
+    pub base_url: Option<String>,
+    /// Directory under `data_dir` for the profile's state, journal and
+    /// reports, the profile's name when unset.
+    pub namespace: Option<String>,
+    /// Put in front of every strategy's `link_id_prefix`.
+    pub link_id_prefix: String,
+}
+
+/// Just the profiles of a config file, read before the rest to find the
+/// env file.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProfilesOnly {
+    profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// Requests per second the client paces itself to, kept under Bybit's own
+/// limits so bursts across many symbols don't run into 10006.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// Shared by every signed endpoint: orders, positions and account.
+    pub signed_per_second: f64,
+    /// Market data such as klines and instruments-info.
+    pub public_per_second: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        RateLimitConfig {
+            signed_per_second: 10.0,
+            public_per_second: 20.0,
+        }
+    }
+}
+
+/// Retry policy per kind of exchange call.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Market data, account and order lookups.
+    pub reads: RetryPolicy,
+    /// Placements, trading stops and margin mode switches.
+    pub orders: RetryPolicy,
+    pub cancels: RetryPolicy,
+}
+
+/// How long a placement waits out exchange maintenance before the cycle
+/// is skipped.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    pub retry_minutes: u64,
+    /// Measured from the candle open.
+    pub deadline_minutes: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        MaintenanceConfig {
+            retry_minutes: 2,
+            deadline_minutes: 60,
+        }
+    }
+}
+
+/// SMTP server for critical alerts and the daily report digest. The login
+/// comes from `SMTP_USERNAME` and `SMTP_PASSWORD`.
+#[derive(Deserialize, Debug)]
+pub struct EmailConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    pub from: String,
+    pub to: String,
+    /// UTC hour the digest of the day's cycle reports is sent.
+    #[serde(default)]
+    pub digest_hour: u32,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// Slack incoming webhook. The URL comes from `SLACK_WEBHOOK_URL`.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct SlackConfig {
+    /// Event kinds posted: notice, critical, fill and report.
+    pub events: Vec<String>,
+    /// More events than this within `coalesce_seconds` go out as one
+    /// message.
+    pub coalesce_after: usize,
+    pub coalesce_seconds: u64,
+}
+
+impl Default for SlackConfig {
+    fn default() -> Self {
+        SlackConfig {
+            events: all_events(),
+            coalesce_after: 3,
+            coalesce_seconds: 3,
+        }
+    }
+}
+
+/// The optional HTTP API. Requests must carry the `HTTP_TOKEN` secret.
+#[derive(Deserialize, Debug)]
+pub struct HttpConfig {
+    /// e.g. `127.0.0.1:8787`
+    pub bind: String,
+}
+
+/// Where the API key and secret are read from, and how often again.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct CredentialsConfig {
+    pub source: CredentialSource,
+    /// Fetches them again this often, e.g. "1h", so a rotated secret is
+    /// picked up without a restart. Only at startup when unset.
+    #[serde(with = "humantime_serde")]
+    pub refresh: Option<Duration>,
+    /// Fields of the stored secret holding the key and the secret.
+    pub key_field: String,
+    pub secret_field: String,
+    pub aws: Option<AwsSecretConfig>,
+    pub vault: Option<VaultConfig>,
+}
+
+impl Default for CredentialsConfig {
+    fn default() -> Self {
+        CredentialsConfig {
+            source: CredentialSource::Env,
+            refresh: None,
+            key_field: "api_key".to_string(),
+            secret_field: "api_secret".to_string(),
+            aws: None,
+            vault: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialSource {
+    /// `API_KEY` and `API_SECRET`.
+    Env,
+    /// A secret in AWS Secrets Manager.
+    Aws,
+    /// A secret in HashiCorp Vault.
+    Vault,
+}
+
+/// An AWS Secrets Manager secret. The AWS access key comes from the
+/// default chain: the environment, `~/.aws/credentials`, a web identity,
+/// the container endpoint or the instance role.
+#[derive(Deserialize, Debug)]
+pub struct AwsSecretConfig {
+    /// The secret's ARN or name.
+    pub secret_id: String,
+    /// Taken from the ARN when unset.
+    pub region: Option<String>,
+    /// In place of `https://secretsmanager.<region>.amazonaws.com`, e.g. a
+    /// VPC endpoint.
+    pub endpoint: Option<String>,
+}
+
+/// A HashiCorp Vault secret.
+#[derive(Deserialize, Debug)]
+pub struct VaultConfig {
+    /// e.g. `https://vault.internal:8200`
+    pub address: String,
+    /// API path of the secret after `/v1/`, e.g. `secret/data/stink-bid`
+    /// for a KV v2 engine mounted at `secret`.
+    pub path: String,
+    #[serde(default)]
+    pub auth: VaultAuth,
+    /// The AppRole's role_id. Its secret_id comes from `VAULT_SECRET_ID`.
+    pub role_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultAuth {
+    /// A token from `VAULT_TOKEN`.
+    #[default]
+    Token,
+    /// An AppRole login, for a fresh token on every fetch.
+    Approle,
+}
+
+/// Name and orderLinkId prefix of a one-shot `place` run's strategy.
+pub const AD_HOC: &str = "adhoc";
+
+/// A ladder given on the command line, placed once outside the schedule.
+#[derive(Debug)]
+pub struct AdHoc {
+    pub symbols: Vec<String>,
+    /// Total per symbol, split evenly across the levels.
+    pub budget: f64,
+    pub levels: Vec<f64>,
+    /// The base strategy's hold when unset.
+    pub hold: Option<Duration>,
+    /// Strategy the other settings come from, the first when unset.
+    pub strategy: Option<String>,
+}
+
+/// Append-only JSON lines record of what the bot decided and did.
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct JournalConfig {
+    pub dir: String,
+    /// A file is closed and the next one started at this size.
+    pub max_mb: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        JournalConfig {
+            dir: "journal".to_string(),
+            max_mb: 50,
+        }
+    }
+}
+
+/// ntfy push notifications. An access token, if the topic needs one, comes
+/// from `NTFY_TOKEN`.
+#[derive(Deserialize, Debug)]
+pub struct NtfyConfig {
+    #[serde(default = "default_ntfy_server")]
+    pub server: String,
+    pub topic: String,
+    #[serde(default = "all_events")]
+    pub events: Vec<String>,
+    /// Fills at this level (1 = nearest the open) or deeper push as urgent.
+    #[serde(default = "default_urgent_from_level")]
+    pub urgent_from_level: usize,
+    /// Opened when the notification is tapped.
+    pub click_url: Option<String>,
+}
+
+/// Channel names (`email`, `slack`, `ntfy`) per topic. A topic left out
+/// goes to `default`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RoutesConfig {
+    #[serde(default)]
+    pub default: Vec<String>,
+    pub placement: Option<Vec<String>>,
+    pub fill: Option<Vec<String>>,
+    pub deep_fill: Option<Vec<String>>,
+    pub cancel: Option<Vec<String>>,
+    pub summary: Option<Vec<String>>,
+    pub error: Option<Vec<String>>,
+    pub critical: Option<Vec<String>>,
+}
+
+impl RoutesConfig {
+    /// Channels `topic` is sent to.
+    pub fn channels(&self, topic: Topic) -> &[String] {
+        let route = match topic {
+            Topic::Placement => &self.placement,
+            Topic::Fill => &self.fill,
+            Topic::DeepFill => &self.deep_fill,
+            Topic::Cancel => &self.cancel,
+            Topic::Summary => &self.summary,
+            Topic::Error => &self.error,
+            Topic::Critical => &self.critical,
+        };
+        route.as_deref().unwrap_or(&self.default)
+    }
+}
+
+fn default_ntfy_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn all_events() -> Vec<String> {
+    EVENT_KINDS.iter().map(|kind| kind.to_string()).collect()
+}
+
+fn default_urgent_from_level() -> usize {
+    3
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SymbolConfig {
+    /// Switched to at startup; placement is blocked if the switch fails.
+    pub margin_mode: Option<MarginMode>,
+    /// Leverage applied with the margin mode switch.
+    pub leverage: Option<f64>,
+    /// Currency the budgets for this symbol are in. Inferred from the
+    /// symbol's suffix when unset, and must match the instrument's quoteCoin.
+    pub budget_currency: Option<String>,
+    /// Lets spot entries borrow on spot margin instead of needing the full
+    /// balance upfront.
+    pub spot_margin: bool,
+    /// Overrides every strategy's hold for this symbol.
+    #[serde(with = "humantime_serde")]
+    pub hold: Option<Duration>,
+    /// Which kline the ladder is anchored on.
+    pub anchor_price: AnchorPrice,
+    /// How the anchor price is taken from the last `anchor_candles`.
+    pub anchor_reducer: Reducer,
+    /// Candles the reducer reads, the current one included. One when unset.
+    pub anchor_candles: Option<usize>,
+    /// Trades this symbol in this category instead of each strategy's.
+    pub category: Option<Category>,
+    /// How entries go in: a resting ladder, or time-sliced once triggered.
+    pub entry_mode: EntryMode,
+    /// Places ahead of lower priorities within the `placement_stagger`.
+    pub priority: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryMode {
+    /// Every level rests on the book from the open.
+    #[default]
+    Ladder,
+    /// Nothing rests until price falls to the strategy's `twap` trigger,
+    /// then the budget goes in as small limits at the market over time.
+    Twap,
+}
+
+/// Price a symbol's anchor candle is built from.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorPrice {
+    /// Traded price.
+    #[default]
+    Last,
+    /// Mark price, which a single wick on a thin perp can't move much.
+    Mark,
+    /// Index price, from the spot venues behind the perp.
+    Index,
+}
+
+impl AnchorPrice {
+    /// Last path segment of the kline endpoint serving this price.
+    pub fn kline_endpoint(&self) -> &'static str {
+        match self {
+            AnchorPrice::Last => "kline",
+            AnchorPrice::Mark => "mark-price-kline",
+            AnchorPrice::Index => "index-price-kline",
+        }
+    }
+}
+
+impl fmt::Display for AnchorPrice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AnchorPrice::Last => "last",
+            AnchorPrice::Mark => "mark",
+            AnchorPrice::Index => "index",
+        })
+    }
+}
+
+impl SymbolConfig {
+    pub fn anchor_candles(&self) -> usize {
+        self.anchor_candles.unwrap_or(1)
+    }
+
+    pub fn budget_currency(&self, symbol: &str) -> Option<String> {
+        self.budget_currency
+            .clone()
+            .or_else(|| inferred_quote(symbol).map(str::to_string))
+    }
+}
+
+/// Quote coin implied by a symbol's name. USDC perpetuals are named
+/// `<BASE>PERP`.
+fn inferred_quote(symbol: &str) -> Option<&'static str> {
+    if symbol.ends_with("USDT") {
+        Some("USDT")
+    } else if symbol.ends_with("USDC") || symbol.ends_with("PERP") {
+        Some("USDC")
+    } else {
+        None
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginMode {
+    Cross,
+    Isolated,
+}
+
+/// Bybit product category a strategy trades.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    /// USDT and USDC perpetuals.
+    #[default]
+    Linear,
+    Spot,
+}
+
+impl Category {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            Category::Linear => "linear",
+            Category::Spot => "spot",
+        }
+    }
+}
+
+/// How a spot level's quote budget is sent to the exchange.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SpotSizing {
+    /// Base qty computed from the level price, floored to basePrecision.
+    #[default]
+    Base,
+    /// The quote amount itself with `marketUnit = quoteCoin` on market
+    /// orders. Limit orders only take a base qty, so they size as `Base`.
+    Quote,
+}
+
+/// How much of the ladder's budget a cycle places.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Sizing {
+    /// The budgets as configured, whatever is already held.
+    #[default]
+    Budgets,
+    /// Only the headroom between the position already held and
+    /// `target_position_notional`, split across the levels in the
+    /// proportions of the budgets.
+    TargetPosition,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ScheduleConfig {
+    pub catch_up: CatchUpPolicy,
+    pub catch_up_window_hours: u64,
+    /// Looks for this cycle's entries among the open orders before
+    /// placing, in case the state file was lost.
+    pub verify_before_placing: bool,
+    /// Places at the first time the clock reads this after each candle
+    /// opens, e.g. "00:05 America/New_York", instead of at the open.
+    pub place_at: Option<LocalTime>,
+    /// Spreads the symbols' placements over this long after each
+    /// placement time, e.g. "90s".
+    #[serde(with = "humantime_serde")]
+    pub placement_stagger: Option<Duration>,
+    pub stagger: Spread,
+}
+
+impl Default for ScheduleConfig {
+    fn default() -> Self {
+        ScheduleConfig {
+            catch_up: CatchUpPolicy::Skip,
+            catch_up_window_hours: 6,
+            verify_before_placing: false,
+            place_at: None,
+            placement_stagger: None,
+            stagger: Spread::Even,
+        }
+    }
+}
+
+/// How a strategy's entries are cancelled when their hold ends.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelMode {
+    /// The tracked entries by orderId, leaving anything else alone.
+    #[default]
+    Tracked,
+    /// Every open order on each symbol, via cancel-all. Only for accounts
+    /// where nothing else trades those symbols.
+    SymbolAll,
+}
+
+/// One ladder run independently over the symbols: its own candle interval,
+/// levels, budgets, hold and orderLinkId namespace.
+#[derive(Deserialize, Debug, Clone)]
+pub struct StrategyConfig {
+    pub name: String,
+    #[serde(default = "default_interval")]
+    pub interval: Interval,
+    #[serde(default)]
+    pub category: Category,
+    /// Category each configured symbol trades in, resolved from the
+    /// symbol overrides at load.
+    #[serde(skip)]
+    pub symbol_categories: BTreeMap<String, Category>,
+    /// How spot level budgets become order quantities.
+    #[serde(default)]
+    pub spot_sizing: SpotSizing,
+    /// What's done with a level the instrument's maxOrderQty or
+    /// maxOrderAmt can't take.
+    #[serde(default)]
+    pub over_max_order: OverMaxOrder,
+    /// Whether levels come from the fields below or from `grid`.
+    #[serde(default)]
+    pub mode: LadderMode,
+    pub grid: Option<GridConfig>,
+    #[serde(default)]
+    pub spacing: Spacing,
+    /// Percent below the open for each level. Generated from the spacing
+    /// parameters unless spacing is explicit.
+    #[serde(default)]
+    pub levels: Vec<f64>,
+    /// First level percent for arithmetic and geometric spacing.
+    pub first_level: Option<f64>,
+    /// Percent added per level for arithmetic spacing.
+    pub step: Option<f64>,
+    /// Multiplier per level for geometric spacing.
+    pub ratio: Option<f64>,
+    pub level_count: Option<usize>,
+    /// Notional in the symbol's budget currency for each level. Mutually
+    /// exclusive with `weights`.
+    #[serde(default)]
+    pub budgets: Vec<f64>,
+    /// Share of `symbol_budget` for each level, normalized to sum to 1.
+    pub weights: Option<Vec<f64>>,
+    /// Total per symbol, in its budget currency, split across the levels by
+    /// `weights`.
+    pub symbol_budget: Option<f64>,
+    #[serde(default)]
+    pub sizing: Sizing,
+    /// Position per symbol, valued at the open, that a fully filled ladder
+    /// tops up to in `target_position` sizing.
+    pub target_position_notional: Option<f64>,
+    /// How long the ladder rests, e.g. "20h" or "3d". Defaults to one
+    /// interval.
+    #[serde(default, with = "humantime_serde")]
+    pub hold: Option<Duration>,
+    /// Older form of `hold`, in hours.
+    pub hold_hours: Option<u64>,
+    /// Lets a hold run past the next placement, so two cycles' ladders
+    /// rest at once. Needs `schedule.verify_before_placing`.
+    #[serde(default)]
+    pub allow_overlap: bool,
+    /// How the end-of-cycle sweep cancels.
+    #[serde(default)]
+    pub cancel_mode: CancelMode,
+    pub link_id_prefix: String,
+    pub reanchor: Option<ReanchorConfig>,
+    /// Skips or downsizes a symbol whose open interest jumped.
+    pub open_interest: Option<OpenInterestConfig>,
+    /// Scales down or refuses a ladder whose margin the balance can't carry.
+    pub margin_check: Option<MarginCheckConfig>,
+    /// Places the levels as conditional orders instead of resting limits.
+    pub conditional: Option<ConditionalConfig>,
+    /// Order settings of single levels, overriding the strategy's.
+    #[serde(default)]
+    pub level_orders: Vec<LevelOrderConfig>,
+    /// How symbols with `entry_mode = "twap"` enter.
+    pub twap: Option<TwapConfig>,
+    pub exits: Option<ExitConfig>,
+    pub trailing_stop: Option<TrailingStopConfig>,
+    /// Bids again deeper once a level fills.
+    pub cascade: Option<CascadeConfig>,
+    /// How often resting entries are polled for fills when exits, a
+    /// trailing stop or a cascade are configured.
+    #[serde(default = "default_fill_check_minutes")]
+    pub fill_check_minutes: u64,
+}
+
+/// A filled entry is followed by a new resting bid `step_percent` (of the
+/// open) deeper, tracked and swept with the rest of the cycle. A cascaded
+/// bid filling cascades again, up to `max_depth` rungs below its level.
+#[derive(Deserialize, Debug, Clone)]
+pub struct CascadeConfig {
+    pub step_percent: f64,
+    /// Budget of each cascaded bid, the filled level's when unset.
+    pub budget: Option<f64>,
+    #[serde(default = "default_cascade_depth")]
+    pub max_depth: u32,
+    /// Most the cascades may add to a symbol in one cycle.
+    pub max_notional: Option<f64>,
+}
+
+fn default_cascade_depth() -> u32 {
+    1
+}
+
+/// Each level becomes a trigger order at the level price that submits its
+/// limit only once price trades down to it, so nothing rests on the book.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ConditionalConfig {
+    #[serde(default)]
+    pub trigger_by: TriggerBy,
+    /// How far below the trigger the submitted limit sits.
+    #[serde(default)]
+    pub limit_offset_percent: f64,
+}
+
+/// How one level is sent, in place of the strategy's resting limit.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelOrderConfig {
+    /// Index of the level, 0 being the shallowest, as in its orderLinkId.
+    pub level: usize,
+    #[serde(default)]
+    pub order_type: OrderType,
+    pub time_in_force: Option<TimeInForce>,
+    /// Makes the level a conditional order, or a plain one with `false`,
+    /// whatever the strategy's `conditional` says.
+    pub conditional: Option<bool>,
+    /// Price the level's trigger watches, in place of the strategy's.
+    pub trigger_by: Option<TriggerBy>,
+    /// Splits a level worth more than this into child orders of at most
+    /// this much each.
+    pub max_child_notional: Option<f64>,
+    /// How far below the level price the deepest child sits, in percent of
+    /// the level price.
+    #[serde(default = "default_child_band_percent")]
+    pub child_band_percent: f64,
+}
+
+/// Time-sliced entry: once price trades `trigger_percent` below the open,
+/// the symbol's budget goes in over `slices` limit orders at the last
+/// price, one every `every`. A slice still resting when the next is due is
+/// cancelled and its remainder carried over.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TwapConfig {
+    pub trigger_percent: f64,
+    #[serde(default = "default_twap_slices")]
+    pub slices: usize,
+    #[serde(default = "default_twap_every", with = "humantime_serde")]
+    pub every: Duration,
+    /// Stops slicing this long after the trigger. The hold's end always
+    /// stops it.
+    #[serde(default, with = "humantime_serde")]
+    pub deadline: Option<Duration>,
+}
+
+fn default_twap_slices() -> usize {
+    12
+}
+
+fn default_twap_every() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+fn default_child_band_percent() -> f64 {
+    0.5
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    #[default]
+    Limit,
+    /// Only as a conditional: buys at market once the trigger is hit.
+    Market,
+}
+
+impl OrderType {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            OrderType::Limit => "Limit",
+            OrderType::Market => "Market",
+        }
+    }
+}
+
+/// Price a conditional order watches. Spot only triggers on last price.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum TriggerBy {
+    #[default]
+    #[serde(rename = "LastPrice")]
+    Last,
+    #[serde(rename = "MarkPrice")]
+    Mark,
+    #[serde(rename = "IndexPrice")]
+    Index,
+}
+
+impl TriggerBy {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            TriggerBy::Last => "LastPrice",
+            TriggerBy::Mark => "MarkPrice",
+            TriggerBy::Index => "IndexPrice",
+        }
+    }
+}
+
+/// Take-profit ladder placed as reduce-only sells once an entry fills.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExitConfig {
+    #[serde(default)]
+    pub mode: ExitMode,
+    /// Percent above the average fill price for each exit.
+    #[serde(default)]
+    pub levels: Vec<f64>,
+    /// Share of the filled quantity for each exit, equal shares by default.
+    pub fractions: Option<Vec<f64>>,
+    /// Cancels an exit still resting this long after it was placed, in
+    /// whichever cycle that happens.
+    #[serde(default, with = "humantime_serde")]
+    pub expiry: Option<Duration>,
+    /// Moves the position's stop loss to its average entry once the first
+    /// exit of a fill fills. Linear only.
+    #[serde(default)]
+    pub break_even: bool,
+}
+
+/// Where exits are priced.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitMode {
+    /// `levels` percent above the average fill price.
+    #[default]
+    Ladder,
+    /// One sell of the whole fill at the anchor the entry was placed from,
+    /// selling the bounce back to the open.
+    TpAtAnchor,
+}
+
+/// Trailing stop attached to positions opened by filled levels, given
+/// either as a price distance or as a percent of the fill price.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrailingStopConfig {
+    pub distance: Option<f64>,
+    pub percent: Option<f64>,
+}
+
+impl ExitConfig {
+    /// Exit fractions normalized to sum to 1.
+    pub fn fractions(&self) -> Vec<f64> {
+        match &self.fractions {
+            Some(fractions) => {
+                let total: f64 = fractions.iter().sum();
+                fractions.iter().map(|fraction| fraction / total).collect()
+            }
+            None => vec![1.0 / self.levels.len() as f64; self.levels.len()],
+        }
+    }
+}
+
+fn default_fill_check_minutes() -> u64 {
+    1
+}
+
+/// Checks the open interest change before placing. Dip buys into a
+/// fresh wave of positions tend to get run over.
+#[derive(Deserialize, Debug, Clone)]
+pub struct OpenInterestConfig {
+    /// How far back the change is measured, in hours.
+    #[serde(default = "default_oi_lookback_hours")]
+    pub lookback_hours: u32,
+    /// Change above which the filter acts, e.g. 30 for +30%.
+    pub max_change_percent: f64,
+    #[serde(default)]
+    pub action: OpenInterestAction,
+    /// Share of the budgets placed when downsizing.
+    #[serde(default = "default_oi_downsize")]
+    pub downsize_to: f64,
+}
+
+/// Checks, before placing, that the balance covers the initial margin of
+/// every level filling at the symbol's leverage.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MarginCheckConfig {
+    /// Share of the available balance kept free, in percent.
+    #[serde(default = "default_margin_buffer")]
+    pub buffer_percent: f64,
+    #[serde(default)]
+    pub action: MarginAction,
+}
+
+fn default_margin_buffer() -> f64 {
+    10.0
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginAction {
+    /// Places the share of the ladder that fits.
+    #[default]
+    Downsize,
+    /// Leaves the symbol out of the cycle.
+    Refuse,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenInterestAction {
+    /// Leaves the symbol out of the cycle.
+    #[default]
+    Skip,
+    /// Places the ladder with its budgets scaled by `downsize_to`.
+    Downsize,
+}
+
+fn default_oi_lookback_hours() -> u32 {
+    24
+}
+
+fn default_oi_downsize() -> f64 {
+    0.5
+}
+
+/// Moves the unfilled levels up when price runs away from the anchor
+/// during the hold.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReanchorConfig {
+    pub check_minutes: u64,
+    /// How far above the anchor price must be before re-anchoring.
+    pub threshold_percent: f64,
+    #[serde(default = "default_max_reanchors")]
+    pub max_per_day: u32,
+    #[serde(default)]
+    pub source: ReanchorSource,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReanchorSource {
+    /// Last traded price.
+    #[default]
+    Last,
+    /// Volume-weighted average price of the current candle.
+    Vwap,
+}
+
+fn default_max_reanchors() -> u32 {
+    2
+}
+
+impl StrategyConfig {
+    fn daily() -> StrategyConfig {
+        StrategyConfig {
+            name: "daily".to_string(),
+            interval: Interval::Daily,
+            category: Category::Linear,
+            symbol_categories: BTreeMap::new(),
+            spot_sizing: SpotSizing::Base,
+            over_max_order: OverMaxOrder::Split,
+            mode: LadderMode::Levels,
+            grid: None,
+            spacing: Spacing::Explicit,
+            levels: vec![20.0, 25.0, 30.0],
+            first_level: None,
+            step: None,
+            ratio: None,
+            level_count: None,
+            budgets: vec![1000.0, 1000.0, 2000.0],
+            weights: None,
+            symbol_budget: None,
+            sizing: Sizing::Budgets,
+            target_position_notional: None,
+            hold: None,
+            hold_hours: None,
+            allow_overlap: false,
+            cancel_mode: CancelMode::Tracked,
+            link_id_prefix: "sbd".to_string(),
+            reanchor: None,
+            open_interest: None,
+            margin_check: None,
+            conditional: None,
+            level_orders: Vec::new(),
+            twap: None,
+            exits: None,
+            trailing_stop: None,
+            cascade: None,
+            fill_check_minutes: default_fill_check_minutes(),
+        }
+    }
+
+    /// Fills `levels` and `budgets` from the grid settings in grid mode.
+    fn generate_grid(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.mode != LadderMode::Grid {
+            return Ok(());
+        }
+        let Some(settings) = &self.grid else {
+            return Err(format!("strategy {} grid mode needs [strategies.grid]", self.name).into());
+        };
+        if !self.levels.is_empty()
+            || !self.budgets.is_empty()
+            || self.weights.is_some()
+            || self.spacing != Spacing::Explicit
+        {
+            return Err(format!(
+                "strategy {} grid generates its levels and budgets, drop levels, budgets, weights and spacing",
+                self.name
+            )
+            .into());
+        }
+        if !(settings.top_percent > 0.0
+            && settings.top_percent < settings.bottom_percent
+            && settings.bottom_percent < 100.0)
+        {
+            return Err(format!(
+                "strategy {} grid needs 0 < top_percent < bottom_percent < 100",
+                self.name
+            )
+            .into());
+        }
+        if settings.levels == 0 || settings.levels > MAX_GRID_LEVELS {
+            return Err(format!(
+                "strategy {} grid needs 1 to {} levels",
+                self.name, MAX_GRID_LEVELS
+            )
+            .into());
+        }
+        if settings.budget.is_nan() || settings.budget <= 0.0 {
+            return Err(format!("strategy {} grid budget must be positive", self.name).into());
+        }
+        let levels = grid::levels(
+            settings.top_percent,
+            settings.bottom_percent,
+            settings.levels,
+            settings.spacing,
+        );
+        if !levels.windows(2).all(|pair| pair[0] < pair[1]) {
+            return Err(format!(
+                "strategy {} grid has levels closer than 0.01% apart, use fewer levels or a wider range",
+                self.name
+            )
+            .into());
+        }
+        self.budgets = grid::allocations(settings.budget, settings.levels, settings.allocation);
+        self.levels = levels;
+        Ok(())
+    }
+
+    /// The smallest level budget of a grid, which every symbol's minimum
+    /// order value has to fit under.
+    pub fn smallest_grid_level(&self) -> Option<f64> {
+        if self.mode != LadderMode::Grid {
+            return None;
+        }
+        self.budgets.iter().copied().reduce(f64::min)
+    }
+
+    /// Fills `levels` from the spacing parameters.
+    fn generate_levels(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.spacing == Spacing::Explicit {
+            return Ok(());
+        }
+        if !self.levels.is_empty() {
+            return Err(format!(
+                "strategy {} sets both levels and spacing, drop levels or set spacing = \"explicit\"",
+                self.name
+            )
+            .into());
+        }
+        let missing = |param: &str| format!("strategy {} spacing needs {}", self.name, param);
+        let first = self.first_level.ok_or_else(|| missing("first_level"))?;
+        let count = self.level_count.ok_or_else(|| missing("level_count"))?;
+        let levels: Vec<f64> = match self.spacing {
+            Spacing::Arithmetic => {
+                let step = self.step.ok_or_else(|| missing("step"))?;
+                (0..count).map(|i| first + step * i as f64).collect()
+            }
+            Spacing::Geometric => {
+                let ratio = self.ratio.ok_or_else(|| missing("ratio"))?;
+                (0..count).map(|i| first * ratio.powi(i as i32)).collect()
+            }
+            Spacing::Explicit => unreachable!(),
+        };
+        self.levels = levels
+            .into_iter()
+            .map(|level| (level * 100.0).round() / 100.0)
+            .collect();
+        Ok(())
+    }
+
+    /// Fills `budgets` from `weights` and `symbol_budget`.
+    fn generate_budgets(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(weights) = &self.weights else {
+            if self.symbol_budget.is_some() {
+                return Err(format!(
+                    "strategy {} symbol_budget needs weights to split it across the levels",
+                    self.name
+                )
+                .into());
+            }
+            return Ok(());
+        };
+        if !self.budgets.is_empty() {
+            return Err(format!(
+                "strategy {} sets both weights and budgets, use one or the other",
+                self.name
+            )
+            .into());
+        }
+        let symbol_budget = self
+            .symbol_budget
+            .ok_or_else(|| format!("strategy {} weights need symbol_budget", self.name))?;
+        if weights.len() != self.levels.len() {
+            return Err(format!(
+                "strategy {} has {} weights for {} levels",
+                self.name,
+                weights.len(),
+                self.levels.len()
+            )
+            .into());
+        }
+        if weights.iter().any(|weight| *weight <= 0.0) {
+            return Err(format!("strategy {} weights must be positive", self.name).into());
+        }
+        let total: f64 = weights.iter().sum();
+        self.budgets = weights
+            .iter()
+            .map(|weight| symbol_budget * weight / total)
+            .collect();
+        Ok(())
+    }
+
+    /// The same strategy with every level's budget scaled by `share`.
+    pub fn downsized(&self, share: f64) -> StrategyConfig {
+        StrategyConfig {
+            budgets: self.budgets.iter().map(|budget| budget * share).collect(),
+            ..self.clone()
+        }
+    }
+
+    pub fn ladder(&self) -> Vec<Level> {
+        self.levels
+            .iter()
+            .zip(&self.budgets)
+            .enumerate()
+            .map(|(index, (percent, notional))| {
+                let overrides = self.level_order(index);
+                Level {
+                    percent: *percent,
+                    notional: *notional,
+                    max_child_notional: overrides.and_then(|o| o.max_child_notional),
+                    child_band_percent: overrides.map_or(0.0, |o| o.child_band_percent),
+                    over_max: self.over_max_order,
+                }
+            })
+            .collect()
+    }
+
+    /// Category `symbol` trades in under this strategy.
+    pub fn category_of(&self, symbol: &str) -> Category {
+        self.symbol_categories
+            .get(symbol)
+            .copied()
+            .unwrap_or(self.category)
+    }
+
+    /// Every category the strategy places orders in.
+    pub fn categories(&self) -> BTreeSet<Category> {
+        if self.symbol_categories.is_empty() {
+            return BTreeSet::from([self.category]);
+        }
+        self.symbol_categories.values().copied().collect()
+    }
+
+    /// Extra orderFilter needed to see this strategy's entries in
+    /// `category`. Spot only lists conditional orders when asked for them.
+    pub fn filter_in(&self, category: Category) -> Option<&'static str> {
+        (category == Category::Spot && self.has_conditionals()).then_some("StopOrder")
+    }
+
+    /// `filter_in` for the category `symbol` trades in.
+    pub fn order_filter(&self, symbol: &str) -> Option<&'static str> {
+        self.filter_in(self.category_of(symbol))
+    }
+
+    /// The order settings of `level`, if it overrides the strategy's.
+    pub fn level_order(&self, level: usize) -> Option<&LevelOrderConfig> {
+        self.level_orders
+            .iter()
+            .find(|settings| settings.level == level)
+    }
+
+    /// The trigger `level` is placed with, if it's conditional.
+    pub fn conditional_for(&self, level: usize) -> Option<ConditionalConfig> {
+        let settings = self.level_order(level);
+        let mut conditional = match settings.and_then(|settings| settings.conditional) {
+            Some(true) => Some(self.conditional.clone().unwrap_or_default()),
+            Some(false) => None,
+            None => self.conditional.clone(),
+        }?;
+        if let Some(trigger_by) = settings.and_then(|settings| settings.trigger_by) {
+            conditional.trigger_by = trigger_by;
+        }
+        Some(conditional)
+    }
+
+    /// Whether any level goes out as a conditional order.
+    pub fn has_conditionals(&self) -> bool {
+        (0..self.levels.len()).any(|level| self.conditional_for(level).is_some())
+    }
+
+    pub fn watches_fills(&self) -> bool {
+        self.exits.is_some() || self.trailing_stop.is_some() || self.cascade.is_some()
+    }
+
+    pub fn hold(&self) -> ChronoDuration {
+        match (self.hold, self.hold_hours) {
+            (Some(hold), _) => {
+                ChronoDuration::from_std(hold).unwrap_or_else(|_| ChronoDuration::max_value())
+            }
+            (None, Some(hours)) => ChronoDuration::hours(hours as i64),
+            (None, None) => self.interval.length(),
+        }
+    }
+
+    /// The hold for `symbol`: its own override, else the strategy's.
+    pub fn hold_for(&self, settings: Option<&SymbolConfig>) -> ChronoDuration {
+        match settings.and_then(|settings| settings.hold) {
+            Some(hold) => {
+                ChronoDuration::from_std(hold).unwrap_or_else(|_| ChronoDuration::max_value())
+            }
+            None => self.hold(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LadderMode {
+    /// `levels` and `budgets`, written out or generated by `spacing` and
+    /// `weights`.
+    #[default]
+    Levels,
+    /// Generated from `[strategies.grid]`.
+    Grid,
+}
+
+/// `levels` levels from `top_percent` down to `bottom_percent` below the
+/// open, sharing `budget`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct GridConfig {
+    pub top_percent: f64,
+    pub bottom_percent: f64,
+    pub levels: usize,
+    pub budget: f64,
+    #[serde(default)]
+    pub spacing: GridSpacing,
+    #[serde(default)]
+    pub allocation: GridAllocation,
+}
+
+/// Most candles an anchor may be reduced from.
+const MAX_ANCHOR_CANDLES: usize = 200;
+
+/// Most levels a grid may have: five batches of ten.
+const MAX_GRID_LEVELS: usize = 50;
+
+/// Longest orderLinkId Bybit takes.
+const MAX_LINK_ID: usize = 36;
+
+/// What an orderLinkId holds besides its prefix and symbol: three dashes,
+/// a 13-digit millisecond timestamp and a suffix of up to five
+/// characters, e.g. `12c34` for a child or `x3` for an exit.
+const LINK_ID_OVERHEAD: usize = 3 + 13 + 5;
+
+/// How a strategy's level percentages are produced.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Spacing {
+    /// Use `levels` as written.
+    #[default]
+    Explicit,
+    /// `first_level`, then `step` percent deeper per level.
+    Arithmetic,
+    /// `first_level`, then each level `ratio` times deeper than the previous.
+    Geometric,
+}
+
+fn default_interval() -> Interval {
+    Interval::Daily
+}
+
+/// Bybit kline interval a strategy anchors on.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Interval {
+    #[serde(rename = "D")]
+    Daily,
+    #[serde(rename = "W")]
+    Weekly,
+}
+
+impl Interval {
+    pub fn as_param(&self) -> &'static str {
+        match self {
+            Interval::Daily => "D",
+            Interval::Weekly => "W",
+        }
+    }
+
+    pub fn length(&self) -> ChronoDuration {
+        match self {
+            Interval::Daily => ChronoDuration::days(1),
+            Interval::Weekly => ChronoDuration::weeks(1),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from `CONFIG_PATH` (default `config.toml`). A missing
+    /// file yields the defaults so the bot still runs without one, unless a
+    /// profile is active.
+    pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
+        let path = Config::path();
+        if !Path::new(&path).exists() {
+            if let Some(name) = Config::active_profile() {
+                return Err(format!("profile {} needs {}, which doesn't exist", name, path).into());
+            }
+            let mut config = Config::default();
+            config.resolve_paths();
+            return Ok(config);
+        }
+        Config::load_from(&path)
+    }
+
+    /// `CONFIG_PATH`, or `config.toml` when unset. With a profile active,
+    /// its own `config.<name>.toml` beside that when there is one.
+    pub fn path() -> String {
+        let path = env::var("CONFIG_PATH").unwrap_or_else(|_| "config.toml".to_string());
+        match Config::active_profile() {
+            Some(name) if Path::new(&profile::config_file(&path, &name)).exists() => {
+                profile::config_file(&path, &name)
+            }
+            _ => path,
+        }
+    }
+
+    /// `STINK_PROFILE`, which `--profile` sets.
+    pub fn active_profile() -> Option<String> {
+        env::var(profile::ENV_VAR)
+            .ok()
+            .filter(|name| !name.is_empty())
+    }
+
+    /// The env file the active profile `name` takes its credentials from.
+    pub fn profile_env_file(name: &str) -> String {
+        fs::read_to_string(Config::path())
+            .ok()
+            .and_then(|contents| toml::from_str::<ProfilesOnly>(&contents).ok())
+            .and_then(|only| only.profiles.get(name)?.env_file.clone())
+            .unwrap_or_else(|| profile::env_file(name))
+    }
+
+    /// Loads and validates the config at `path`, which has to exist.
+    pub fn load_from(path: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        let contents = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&contents)?;
+        for strategy in &mut config.strategies {
+            strategy.generate_grid()?;
+            strategy.generate_levels()?;
+            strategy.generate_budgets()?;
+        }
+        config.resolve_aliases()?;
+        config.resolve_categories();
+        if let Some(name) = Config::active_profile() {
+            config.apply_profile(path, &name)?;
+        }
+        config.base_url = config.base_url.as_deref().map(profile::base_url);
+        config.resolve_paths();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Applies profile `name`, from its section in the file at `path`. The
+    /// section can only be left out of the profile's own file.
+    fn apply_profile(&mut self, path: &str, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        profile::check_name(name)?;
+        let section = match self.profiles.get(name) {
+            Some(section) => section.clone(),
+            None if profile::is_own_file(path, name) => ProfileConfig::default(),
+            None => {
+                return Err(format!(
+                    "profile {} isn't defined: no {} and no [profiles.{}] in {}",
+                    name,
+                    profile::config_file(path, name),
+                    name,
+                    path
+                )
+                .into())
+            }
+        };
+        if section.base_url.is_some() {
+            self.base_url = section.base_url;
+        }
+        for strategy in &mut self.strategies {
+            strategy.link_id_prefix =
+                format!("{}{}", section.link_id_prefix, strategy.link_id_prefix);
+        }
+        let namespace = section.namespace.unwrap_or_else(|| name.to_string());
+        self.data_dir = storage::resolve(&self.data_dir, &namespace);
+        self.profile = Some(name.to_string());
+        Ok(())
+    }
+
+    /// The active profile's orderLinkId prefix, empty without one.
+    fn profile_prefix(&self) -> String {
+        self.profile
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+            .map(|section| section.link_id_prefix.clone())
+            .unwrap_or_default()
+    }
+
+    /// Renames aliased symbols to their current names so every API call
+    /// uses those.
+    fn resolve_aliases(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        for (alias, symbol) in &self.symbol_aliases {
+            let Some(settings) = self.symbols.remove(alias) else {
+                continue;
+            };
+            if self.symbols.contains_key(symbol) {
+                return Err(format!(
+                    "{} is aliased to {}, which is also configured directly",
+                    alias, symbol
+                )
+                .into());
+            }
+            info!("symbol alias: {} -> {}", alias, symbol);
+            self.symbols.insert(symbol.clone(), settings);
+        }
+        Ok(())
+    }
+
+    /// The symbols traded in each category, across every strategy.
+    pub fn traded_categories(&self) -> BTreeMap<Category, BTreeSet<String>> {
+        let mut traded: BTreeMap<Category, BTreeSet<String>> = BTreeMap::new();
+        for strategy in &self.strategies {
+            for symbol in self.symbols.keys() {
+                traded
+                    .entry(strategy.category_of(symbol))
+                    .or_default()
+                    .insert(symbol.clone());
+            }
+        }
+        traded
+    }
+
+    /// Turns the config into a one-shot run of `ad_hoc`: one strategy,
+    /// named and prefixed "adhoc" so its orders and state don't collide
+    /// with the scheduled ones, on only the given symbols. It takes its
+    /// other settings from the named strategy, or the first, and is
+    /// validated like a loaded config.
+    pub fn ad_hoc(&mut self, ad_hoc: &AdHoc) -> Result<(), Box<dyn std::error::Error>> {
+        let base = match &ad_hoc.strategy {
+            Some(name) => self
+                .strategies
+                .iter()
+                .find(|strategy| &strategy.name == name)
+                .ok_or_else(|| format!("no strategy {}", name))?,
+            None => self
+                .strategies
+                .first()
+                .ok_or("the config has no strategy to base the run on")?,
+        };
+        let mut strategy = base.clone();
+        strategy.name = AD_HOC.to_string();
+        strategy.link_id_prefix = format!("{}{}", self.profile_prefix(), AD_HOC);
+        strategy.mode = LadderMode::Levels;
+        strategy.grid = None;
+        strategy.spacing = Spacing::Explicit;
+        strategy.levels = ad_hoc.levels.clone();
+        strategy.budgets = Vec::new();
+        strategy.weights = Some(vec![1.0; ad_hoc.levels.len()]);
+        strategy.symbol_budget = Some(ad_hoc.budget);
+        // they're by level index, which means something else here
+        strategy.level_orders = Vec::new();
+        if ad_hoc.hold.is_some() {
+            strategy.hold = ad_hoc.hold;
+            strategy.hold_hours = None;
+        }
+        strategy.generate_budgets()?;
+        self.strategies = vec![strategy];
+
+        let mut symbols = BTreeMap::new();
+        for symbol in &ad_hoc.symbols {
+            let symbol = symbol.to_uppercase();
+            let symbol = self.symbol_aliases.get(&symbol).cloned().unwrap_or(symbol);
+            let settings = self.symbols.remove(&symbol).unwrap_or_default();
+            symbols.insert(symbol, settings);
+        }
+        self.symbols = symbols;
+        // a running bot may hold the port and the state file
+        self.http = None;
+        self.state_path = Path::new(&self.state_path)
+            .with_file_name("adhoc.json")
+            .to_string_lossy()
+            .into_owned();
+        self.resolve_categories();
+        self.validate()
+    }
+
+    /// Places the relative file paths under `data_dir`.
+    fn resolve_paths(&mut self) {
+        let data_dir = self.data_dir.clone();
+        for path in [
+            &mut self.state_path,
+            &mut self.reports_dir,
+            &mut self.equity_csv,
+        ] {
+            *path = storage::resolve(&data_dir, path);
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.dir = storage::resolve(&data_dir, &journal.dir);
+        }
+    }
+
+    /// Creates `data_dir` and the directories under it the bot writes to.
+    pub fn create_layout(&self) -> std::io::Result<()> {
+        fs::create_dir_all(&self.data_dir)?;
+        fs::create_dir_all(&self.reports_dir)?;
+        for file in [&self.state_path, &self.equity_csv] {
+            if let Some(parent) = Path::new(file).parent() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the category each strategy trades each symbol in.
+    pub fn resolve_categories(&mut self) {
+        for strategy in &mut self.strategies {
+            strategy.symbol_categories = self
+                .symbols
+                .iter()
+                .map(|(symbol, settings)| {
+                    (
+                        symbol.clone(),
+                        settings.category.unwrap_or(strategy.category),
+                    )
+                })
+                .collect();
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.strategies.is_empty() {
+            return Err("at least one strategy must be configured".into());
+        }
+        if self.symbols.is_empty() {
+            return Err("at least one symbol must be configured".into());
+        }
+        if self.rate_limits.signed_per_second <= 0.0 || self.rate_limits.public_per_second <= 0.0 {
+            return Err("rate_limits must be positive".into());
+        }
+        if self.maintenance.retry_minutes == 0 {
+            return Err("maintenance.retry_minutes must be positive".into());
+        }
+        for (name, policy) in [
+            ("reads", &self.retry.reads),
+            ("orders", &self.retry.orders),
+            ("cancels", &self.retry.cancels),
+        ] {
+            if policy.max_attempts == 0 || policy.base_delay > policy.max_delay {
+                return Err(format!(
+                    "retry.{} needs max_attempts of at least 1 and base_delay no longer than max_delay",
+                    name
+                )
+                .into());
+            }
+        }
+        if self
+            .email
+            .as_ref()
+            .is_some_and(|email| email.digest_hour > 23)
+        {
+            return Err("email.digest_hour must be 0-23".into());
+        }
+        let routes = [
+            ("slack", self.slack.as_ref().map(|slack| &slack.events)),
+            ("ntfy", self.ntfy.as_ref().map(|ntfy| &ntfy.events)),
+        ];
+        for (channel, events) in routes {
+            if let Some(kind) = events
+                .into_iter()
+                .flatten()
+                .find(|kind| !EVENT_KINDS.contains(&kind.as_str()))
+            {
+                return Err(format!(
+                    "{}.events: unknown event {}, expected one of {}",
+                    channel,
+                    kind,
+                    EVENT_KINDS.join(", ")
+                )
+                .into());
+            }
+        }
+        if self
+            .ntfy
+            .as_ref()
+            .is_some_and(|ntfy| ntfy.urgent_from_level == 0)
+        {
+            return Err("ntfy.urgent_from_level must be at least 1".into());
+        }
+        if let Some(routes) = &self.routes {
+            let configured = [
+                ("email", self.email.is_some()),
+                ("slack", self.slack.is_some()),
+                ("ntfy", self.ntfy.is_some()),
+            ];
+            let topics = [
+                Topic::Placement,
+                Topic::Fill,
+                Topic::DeepFill,
+                Topic::Cancel,
+                Topic::Summary,
+                Topic::Error,
+                Topic::Critical,
+            ];
+            for name in topics
+                .iter()
+                .flat_map(|topic| routes.channels(*topic))
+                .chain(&routes.default)
+            {
+                if !configured
+                    .iter()
+                    .any(|(channel, present)| *present && channel == name)
+                {
+                    let present: Vec<&str> = configured
+                        .iter()
+                        .filter(|(_, present)| *present)
+                        .map(|(channel, _)| *channel)
+                        .collect();
+                    return Err(format!(
+                        "routes: unknown notifier {}, configured are {}",
+                        name,
+                        if present.is_empty() {
+                            "none".to_string()
+                        } else {
+                            present.join(", ")
+                        }
+                    )
+                    .into());
+                }
+            }
+            if routes.channels(Topic::Critical).is_empty() {
+                return Err("routes: critical must go to at least one notifier".into());
+            }
+        }
+        if self.notifications.urgent_from_level == 0 {
+            return Err("notifications.urgent_from_level must be at least 1".into());
+        }
+        if self
+            .journal
+            .as_ref()
+            .is_some_and(|journal| journal.max_mb == 0)
+        {
+            return Err("journal.max_mb must be at least 1".into());
+        }
+        if self.latency_warn_ms == 0 {
+            return Err("latency_warn_ms must be positive".into());
+        }
+        if self.clock_jump_ms == 0 {
+            return Err("clock_jump_ms must be positive".into());
+        }
+        if self.instrument_ttl_hours == 0 {
+            return Err("instrument_ttl_hours must be positive".into());
+        }
+        let credentials = &self.credentials;
+        if credentials.refresh == Some(Duration::ZERO) {
+            return Err("credentials.refresh must be positive".into());
+        }
+        match credentials.source {
+            CredentialSource::Env => {}
+            CredentialSource::Aws if credentials.aws.is_none() => {
+                return Err("credentials source aws needs [credentials.aws]".into());
+            }
+            CredentialSource::Aws => {}
+            CredentialSource::Vault => match &credentials.vault {
+                None => return Err("credentials source vault needs [credentials.vault]".into()),
+                Some(vault) if vault.auth == VaultAuth::Approle && vault.role_id.is_none() => {
+                    return Err("credentials.vault approle auth needs role_id".into());
+                }
+                Some(_) => {}
+            },
+        }
+        for (name, bound) in [
+            ("max_order_notional", self.guards.max_order_notional),
+            ("max_volume_multiple", self.guards.max_volume_multiple),
+            ("max_cycle_notional", self.guards.max_cycle_notional),
+        ] {
+            if bound.is_some_and(|bound| bound <= 0.0) {
+                return Err(format!("guards.{} must be positive", name).into());
+            }
+        }
+        for (symbol, settings) in &self.symbols {
+            if settings.margin_mode.is_some() && !settings.leverage.is_some_and(|l| l > 0.0) {
+                return Err(format!("{} margin_mode needs a positive leverage", symbol).into());
+            }
+            match (&settings.budget_currency, inferred_quote(symbol)) {
+                (None, None) => {
+                    return Err(format!(
+                        "{} needs a budget_currency, it can't be inferred from the symbol",
+                        symbol
+                    )
+                    .into())
+                }
+                (Some(currency), Some(quote)) if currency != quote => {
+                    return Err(format!(
+                        "{} budget_currency {} doesn't match its quote currency {}",
+                        symbol, currency, quote
+                    )
+                    .into())
+                }
+                _ => {}
+            }
+            if !(1..=MAX_ANCHOR_CANDLES).contains(&settings.anchor_candles()) {
+                return Err(format!(
+                    "{} anchor_candles must be between 1 and {}",
+                    symbol, MAX_ANCHOR_CANDLES
+                )
+                .into());
+            }
+            if settings.anchor_candles() > 1 && settings.anchor_reducer == Reducer::Open {
+                return Err(format!(
+                    "{} anchor_candles needs an anchor_reducer other than open",
+                    symbol
+                )
+                .into());
+            }
+        }
+        let profile_prefix = self.profile_prefix();
+        if let Some(name) = &self.profile {
+            if profile_prefix.len() > 8
+                || !profile_prefix.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(format!(
+                    "profile {} link_id_prefix must be at most 8 alphanumeric characters",
+                    name
+                )
+                .into());
+            }
+        }
+        let mut names = HashSet::new();
+        let mut prefixes = HashSet::new();
+        for strategy in &self.strategies {
+            if !names.insert(strategy.name.as_str()) {
+                return Err(format!("duplicate strategy name {}", strategy.name).into());
+            }
+            let prefix = &strategy.link_id_prefix;
+            // the profile's part was checked on its own above
+            let own = prefix
+                .strip_prefix(profile_prefix.as_str())
+                .unwrap_or(prefix);
+            if own.is_empty() || own.len() > 8 || !own.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!(
+                    "strategy {} link_id_prefix must be 1-8 alphanumeric characters",
+                    strategy.name
+                )
+                .into());
+            }
+            if let Some(symbol) = self
+                .symbols
+                .keys()
+                .find(|symbol| prefix.len() + symbol.len() + LINK_ID_OVERHEAD > MAX_LINK_ID)
+            {
+                let room = MAX_LINK_ID.saturating_sub(symbol.len() + LINK_ID_OVERHEAD);
+                let shorten = match &self.profile {
+                    Some(name) if !profile_prefix.is_empty() => format!(
+                        "shorten link_id_prefix and profile {}'s to {} at most between them",
+                        name, room
+                    ),
+                    _ => format!("shorten link_id_prefix to {} at most", room),
+                };
+                return Err(format!(
+                    "strategy {} orderLinkIds for {} could be longer than Bybit's {} characters, {}",
+                    strategy.name, symbol, MAX_LINK_ID, shorten
+                )
+                .into());
+            }
+            if let Some((symbol, settings)) = self.symbols.iter().find(|(symbol, settings)| {
+                settings.anchor_price != AnchorPrice::Last
+                    && strategy.category_of(symbol) == Category::Spot
+            }) {
+                return Err(format!(
+                    "{} anchor_price {} has no spot kline, strategy {} trades it on spot",
+                    symbol, settings.anchor_price, strategy.name
+                )
+                .into());
+            }
+            let trades_spot = strategy.categories().contains(&Category::Spot);
+            if !prefixes.insert(prefix.as_str()) {
+                return Err(format!(
+                    "strategy {} reuses link_id_prefix {}",
+                    strategy.name, strategy.link_id_prefix
+                )
+                .into());
+            }
+            if strategy.levels.is_empty() || strategy.levels.len() != strategy.budgets.len() {
+                return Err(format!(
+                    "strategy {} needs at least one level and one budget per level",
+                    strategy.name
+                )
+                .into());
+            }
+            if !strategy.levels.windows(2).all(|pair| pair[0] < pair[1])
+                || strategy
+                    .levels
+                    .iter()
+                    .any(|level| *level <= 0.0 || *level >= 100.0)
+            {
+                return Err(format!(
+                    "strategy {} levels must be strictly increasing and between 0 and 100",
+                    strategy.name
+                )
+                .into());
+            }
+            if strategy.budgets.iter().any(|budget| *budget <= 0.0) {
+                return Err(format!("strategy {} budgets must be positive", strategy.name).into());
+            }
+            match (strategy.sizing, strategy.target_position_notional) {
+                (Sizing::Budgets, None) => {}
+                (Sizing::TargetPosition, Some(target)) if target > 0.0 => {}
+                (Sizing::TargetPosition, _) => {
+                    return Err(format!(
+                        "strategy {} target_position sizing needs a positive target_position_notional",
+                        strategy.name
+                    )
+                    .into());
+                }
+                (Sizing::Budgets, Some(_)) => {
+                    return Err(format!(
+                        "strategy {} target_position_notional needs sizing = \"target_position\"",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if let Some(check) = &strategy.margin_check {
+                if check.buffer_percent.is_nan()
+                    || check.buffer_percent < 0.0
+                    || check.buffer_percent >= 100.0
+                {
+                    return Err(format!(
+                        "strategy {} margin_check.buffer_percent must be 0-100",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if let Some(oi) = &strategy.open_interest {
+                if trades_spot {
+                    return Err(format!(
+                        "strategy {} open_interest needs every symbol on linear",
+                        strategy.name
+                    )
+                    .into());
+                }
+                // one point per hour, and Bybit returns at most 200
+                if oi.lookback_hours == 0 || oi.lookback_hours > 199 {
+                    return Err(format!(
+                        "strategy {} open_interest.lookback_hours must be 1-199",
+                        strategy.name
+                    )
+                    .into());
+                }
+                if oi.max_change_percent <= 0.0 || oi.downsize_to <= 0.0 || oi.downsize_to >= 1.0 {
+                    return Err(format!(
+                        "strategy {} open_interest needs a positive max_change_percent and downsize_to between 0 and 1",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if let Some(reanchor) = &strategy.reanchor {
+                if reanchor.check_minutes == 0 || reanchor.threshold_percent <= 0.0 {
+                    return Err(format!(
+                        "strategy {} reanchor needs positive check_minutes and threshold_percent",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if let Some(conditional) = &strategy.conditional {
+                if !(0.0..100.0).contains(&conditional.limit_offset_percent) {
+                    return Err(format!(
+                        "strategy {} conditional limit_offset_percent must be between 0 and 100",
+                        strategy.name
+                    )
+                    .into());
+                }
+                if trades_spot && conditional.trigger_by != TriggerBy::Last {
+                    return Err(format!(
+                        "strategy {} spot conditionals can only trigger by LastPrice",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            let mut overridden = HashSet::new();
+            for settings in &strategy.level_orders {
+                let level = settings.level;
+                if level >= strategy.levels.len() || !overridden.insert(level) {
+                    return Err(format!(
+                        "strategy {} level_orders level {} is out of range or set twice",
+                        strategy.name, level
+                    )
+                    .into());
+                }
+                let conditional = strategy.conditional_for(level);
+                let unsupported = match (settings.order_type, settings.time_in_force) {
+                    // a market level without a trigger would buy at placement
+                    (OrderType::Market, _) if conditional.is_none() => {
+                        Some("a market order has to be conditional")
+                    }
+                    (OrderType::Market, Some(_)) => Some("a market order takes no time_in_force"),
+                    (
+                        OrderType::Limit,
+                        Some(TimeInForce::ImmediateOrCancel | TimeInForce::FillOrKill),
+                    ) if conditional.is_none() => {
+                        Some("IOC and FOK would cancel a resting limit right away")
+                    }
+                    _ => None,
+                };
+                let unsupported = unsupported.or_else(|| match &conditional {
+                    None if settings.trigger_by.is_some() => {
+                        Some("trigger_by needs a conditional level")
+                    }
+                    Some(conditional)
+                        if conditional.trigger_by != TriggerBy::Last && trades_spot =>
+                    {
+                        Some("spot conditionals can only trigger by LastPrice")
+                    }
+                    _ => None,
+                });
+                let unsupported = unsupported.or_else(|| {
+                    if settings
+                        .max_child_notional
+                        .is_some_and(|max| max.is_nan() || max <= 0.0)
+                        || !(0.0..100.0).contains(&settings.child_band_percent)
+                    {
+                        Some("max_child_notional must be positive and child_band_percent between 0 and 100")
+                    } else if settings.max_child_notional.is_some()
+                        && settings.order_type == OrderType::Market
+                    {
+                        Some("a market order can't be split into children")
+                    } else {
+                        None
+                    }
+                });
+                if let Some(reason) = unsupported {
+                    return Err(format!(
+                        "strategy {} level_orders level {}: {}",
+                        strategy.name, level, reason
+                    )
+                    .into());
+                }
+            }
+            if let Some(twap) = &strategy.twap {
+                if !(twap.trigger_percent > 0.0 && twap.trigger_percent < 100.0)
+                    || twap.slices == 0
+                    || twap.every.is_zero()
+                    || twap.deadline.is_some_and(|deadline| deadline.is_zero())
+                {
+                    return Err(format!(
+                        "strategy {} twap needs trigger_percent between 0 and 100, and positive slices, every and deadline",
+                        strategy.name
+                    )
+                    .into());
+                }
+            } else if let Some((symbol, _)) = self
+                .symbols
+                .iter()
+                .find(|(_, settings)| settings.entry_mode == EntryMode::Twap)
+            {
+                return Err(format!(
+                    "{} enters by twap, which strategy {} has no twap settings for",
+                    symbol, strategy.name
+                )
+                .into());
+            }
+            if let Some(cascade) = &strategy.cascade {
+                let deepest = strategy.levels.last().copied().unwrap_or_default()
+                    + cascade.step_percent * cascade.max_depth as f64;
+                let not_positive = |value: f64| value.is_nan() || value <= 0.0;
+                if not_positive(cascade.step_percent)
+                    || cascade.max_depth == 0
+                    || deepest >= 100.0
+                    || cascade.budget.is_some_and(not_positive)
+                    || cascade.max_notional.is_some_and(not_positive)
+                {
+                    return Err(format!(
+                        "strategy {} cascade needs a positive step_percent, budget and max_notional, and max_depth of at least 1 that stays below 100%",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if let Some(exits) = &strategy.exits {
+                let fractions_valid = match &exits.fractions {
+                    Some(fractions) => {
+                        fractions.len() == exits.levels.len()
+                            && fractions.iter().all(|fraction| *fraction > 0.0)
+                    }
+                    None => true,
+                };
+                let levels_valid = match exits.mode {
+                    ExitMode::Ladder => {
+                        !exits.levels.is_empty()
+                            && exits.levels.iter().all(|level| *level > 0.0)
+                            && exits.levels.windows(2).all(|pair| pair[0] < pair[1])
+                            && fractions_valid
+                    }
+                    ExitMode::TpAtAnchor => exits.levels.is_empty() && exits.fractions.is_none(),
+                };
+                if !levels_valid {
+                    return Err(format!(
+                        "strategy {} exits need increasing positive levels and one positive fraction per level, or neither with tp_at_anchor",
+                        strategy.name
+                    )
+                    .into());
+                }
+                if exits.break_even && trades_spot {
+                    return Err(format!(
+                        "strategy {} exits break_even needs every symbol on linear",
+                        strategy.name
+                    )
+                    .into());
+                }
+                if exits.expiry.is_some_and(|expiry| expiry.is_zero()) {
+                    return Err(format!(
+                        "strategy {} exits expiry must be positive",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if strategy.spot_sizing != SpotSizing::Base && !trades_spot {
+                return Err(format!(
+                    "strategy {} spot_sizing only applies to symbols traded on spot",
+                    strategy.name
+                )
+                .into());
+            }
+            if let Some(stop) = &strategy.trailing_stop {
+                if trades_spot {
+                    return Err(format!(
+                        "strategy {} trailing_stop needs every symbol on linear",
+                        strategy.name
+                    )
+                    .into());
+                }
+                let valid = match (stop.distance, stop.percent) {
+                    (Some(distance), None) => distance > 0.0,
+                    (None, Some(percent)) => percent > 0.0 && percent < 100.0,
+                    _ => false,
+                };
+                if !valid {
+                    return Err(format!(
+                        "strategy {} trailing_stop needs exactly one of a positive distance or percent",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if strategy.fill_check_minutes == 0 {
+                return Err(format!(
+                    "strategy {} fill_check_minutes must be positive",
+                    strategy.name
+                )
+                .into());
+            }
+            if strategy.hold.is_some() && strategy.hold_hours.is_some() {
+                return Err(format!(
+                    "strategy {} sets both hold and hold_hours, use one or the other",
+                    strategy.name
+                )
+                .into());
+            }
+            if strategy.cancel_mode == CancelMode::SymbolAll {
+                // cancel-all would take the other ladder's orders with it
+                let shared = self.strategies.iter().any(|other| {
+                    other.name != strategy.name
+                        && !other.categories().is_disjoint(&strategy.categories())
+                });
+                if strategy.allow_overlap || shared {
+                    return Err(format!(
+                        "strategy {} cancel_mode = \"symbol_all\" can't be combined with allow_overlap or another strategy trading the same category",
+                        strategy.name
+                    )
+                    .into());
+                }
+            }
+            if strategy.allow_overlap && !self.schedule.verify_before_placing {
+                return Err(format!(
+                    "strategy {} allow_overlap needs schedule.verify_before_placing",
+                    strategy.name
+                )
+                .into());
+            }
+            let holds =
+                std::iter::once(("", strategy.hold())).chain(self.symbols.iter().map(
+                    |(symbol, settings)| (symbol.as_str(), strategy.hold_for(Some(settings))),
+                ));
+            for (symbol, hold) in holds {
+                let whose = if symbol.is_empty() {
+                    String::new()
+                } else {
+                    format!(" for {}", symbol)
+                };
+                if hold <= ChronoDuration::zero() {
+                    return Err(format!(
+                        "strategy {} hold{} must be positive",
+                        strategy.name, whose
+                    )
+                    .into());
+                }
+                let stagger = self.schedule.placement_stagger.unwrap_or_default();
+                if ChronoDuration::from_std(stagger).is_ok_and(|stagger| stagger >= hold) {
+                    return Err(format!(
+                        "strategy {} hold{} must be longer than the placement_stagger of {:?}",
+                        strategy.name, whose, stagger
+                    )
+                    .into());
+                }
+                if hold > strategy.interval.length() && !strategy.allow_overlap {
+                    return Err(format!(
+                        "strategy {} hold{} of {}h runs past the next placement, set allow_overlap to permit it",
+                        strategy.name,
+                        whose,
+                        hold.num_hours()
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
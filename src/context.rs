@@ -0,0 +1,624 @@
+use crate::account::AccountType;
+use crate::bybit::{
+    ApiKeyInfo, BoxError, BybitClient, CancelOrderData, CancelOrderRequest, CancelOutcome,
+    Rejection, Settled, SymbolUnavailable,
+};
+use crate::cancels::NOT_EXISTS_OR_TOO_LATE;
+use crate::config::{Category, Config, StrategyConfig};
+use crate::instruments::{self, Cache, Instrument};
+use crate::key_expiry;
+use crate::ladder;
+use crate::lifecycle::{Lifecycle, OrderEvent};
+use crate::notify;
+use crate::report::CancellationReport;
+use crate::state::StateStore;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::future::Future;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// How many times orders a batch cancel left resting are sent again.
+const CANCEL_RESENDS: usize = 2;
+/// How long shutdown waits for entry tasks to cancel their orders.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// Shared by every strategy task for the lifetime of the process.
+pub struct Context {
+    pub client: BybitClient,
+    pub account: AccountType,
+    pub store: StateStore,
+    pub config: Config,
+    /// Symbols refused by preflight or disabled during the session, with
+    /// the reason.
+    pub blocked: Mutex<BTreeMap<String, String>>,
+    /// Trading rules per category and symbol, loaded during preflight and
+    /// refreshed every `instrument_ttl_hours` or when they look stale.
+    pub instruments: RwLock<BTreeMap<(Category, String), Instrument>>,
+    /// Symbols with an HTTP-triggered cycle in flight.
+    pub triggered: Mutex<HashSet<String>>,
+    /// Every order's state, with the changes broadcast to subscribers.
+    pub lifecycle: Lifecycle,
+    /// From `run --note`, attached to each strategy's first cycle.
+    pub note: Option<String>,
+    /// Flipped once on shutdown, for entry tasks to wind down on.
+    pub shutdown: watch::Sender<bool>,
+    /// Entry tasks that outlive placement, such as TWAP slicing, awaited
+    /// on shutdown so they can cancel what they left resting.
+    pub entry_tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Context {
+    /// Runs `task` as an entry task, awaited on shutdown.
+    pub fn spawn_entry<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut tasks = self.entry_tasks.lock().expect("entry tasks lock poisoned");
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(tokio::spawn(task));
+    }
+
+    /// Tells entry tasks to stop and waits up to `SHUTDOWN_GRACE` for them.
+    pub async fn stop_entries(&self) {
+        self.shutdown.send_replace(true);
+        let tasks =
+            std::mem::take(&mut *self.entry_tasks.lock().expect("entry tasks lock poisoned"));
+        if tasks.is_empty() {
+            return;
+        }
+        info!("waiting for {} entry tasks to stop", tasks.len());
+        if tokio::time::timeout(SHUTDOWN_GRACE, futures::future::join_all(tasks))
+            .await
+            .is_err()
+        {
+            println!(
+                "WARNING: entry tasks still running after {:?}",
+                SHUTDOWN_GRACE
+            );
+        }
+    }
+
+    /// Configured symbols that passed preflight and haven't been disabled.
+    pub fn tradable_symbols(&self) -> Vec<String> {
+        let blocked = self.blocked();
+        self.config
+            .symbols
+            .keys()
+            .filter(|symbol| !blocked.contains_key(*symbol))
+            .cloned()
+            .collect()
+    }
+
+    pub fn blocked(&self) -> BTreeMap<String, String> {
+        self.blocked.lock().expect("blocked lock poisoned").clone()
+    }
+
+    /// Disables `symbol` for the rest of the session if `error` says the
+    /// exchange doesn't know or trade it. Returns whether it did.
+    pub fn disable_if_unavailable(&self, symbol: &str, error: &BoxError) -> bool {
+        let Some(unavailable) = error.downcast_ref::<SymbolUnavailable>() else {
+            return false;
+        };
+        let reason = format!("disabled, {}", unavailable.reason);
+        let newly = self
+            .blocked
+            .lock()
+            .expect("blocked lock poisoned")
+            .insert(symbol.to_string(), reason)
+            .is_none();
+        if newly {
+            notify::send(&unavailable_message(unavailable));
+        }
+        true
+    }
+
+    pub fn instrument(&self, category: Category, symbol: &str) -> Result<Instrument, BoxError> {
+        self.instruments
+            .read()
+            .expect("instruments lock poisoned")
+            .get(&(category, symbol.to_string()))
+            .cloned()
+            .ok_or_else(|| {
+                format!("no {} instrument info for {}", category.as_param(), symbol).into()
+            })
+    }
+
+    /// Like `instrument`, but refetches the rules once when the symbol
+    /// isn't cached yet.
+    pub async fn instrument_or_refresh(
+        &self,
+        category: Category,
+        symbol: &str,
+    ) -> Result<Instrument, BoxError> {
+        if let Ok(instrument) = self.instrument(category, symbol) {
+            return Ok(instrument);
+        }
+        self.refresh_instruments().await;
+        self.instrument(category, symbol)
+    }
+
+    /// Refetches the trading rules of the tradable symbols and rewrites
+    /// the instruments cache. On failure the cached rules keep being used.
+    pub async fn refresh_instruments(&self) {
+        let cache = match instruments::fetch(&self.client, &self.config).await {
+            Ok(cache) => cache,
+            Err(e) => {
+                println!(
+                    "warning: instrument refresh failed, keeping cached rules: {}",
+                    e
+                );
+                if let Ok(cached) = Cache::load(&self.config) {
+                    cached.warn_if_stale(&self.config);
+                }
+                return;
+            }
+        };
+        let traded = self.config.traded_categories();
+        let symbols = self.tradable_symbols();
+        let mut fresh = Vec::new();
+        for (category, in_category) in traded {
+            let mut listed = cache.in_category(category);
+            for symbol in symbols
+                .iter()
+                .filter(|symbol| in_category.contains(*symbol))
+            {
+                let Some(info) = listed.remove(symbol) else {
+                    continue;
+                };
+                match Instrument::from_info(info) {
+                    Ok(instrument) => fresh.push(((category, symbol.clone()), instrument)),
+                    Err(e) => println!("warning: keeping cached rules for {}: {}", symbol, e),
+                }
+            }
+        }
+        info!("refreshed instrument rules for {} symbols", fresh.len());
+        self.instruments
+            .write()
+            .expect("instruments lock poisoned")
+            .extend(fresh);
+    }
+
+    /// Rereads the API key's info and warns if its expiry is near. A failed
+    /// read only logs, as a rejected key alerts by itself.
+    pub async fn check_key_expiry(&self) {
+        match self.client.get_api_key_info().await {
+            Ok(info) => self.note_key_expiry(&info, Utc::now()),
+            Err(e) => println!("warning: API key check failed: {}", e),
+        }
+    }
+
+    /// Records when the key expires and sends the warning due for it, a
+    /// notice at first, critical in the last `CRITICAL_DAYS`. Each is sent
+    /// once per expiry, so a renewed key starts over.
+    pub fn note_key_expiry(&self, info: &ApiKeyInfo, now: DateTime<Utc>) {
+        let expires = match key_expiry::parse(&info.expired_at) {
+            Ok(expires) => expires,
+            Err(e) => {
+                println!("warning: API key {}", e);
+                return;
+            }
+        };
+        let mut due = None;
+        self.store.update(|state| {
+            if state.key_expires_at != expires {
+                state.key_expiry_warned = None;
+            }
+            state.key_checked_at = Some(now);
+            state.key_expires_at = expires;
+            due = expires.and_then(|at| key_expiry::due(at, now, state.key_expiry_warned));
+            if due.is_some() {
+                state.key_expiry_warned = due;
+            }
+        });
+        let (Some(expires), Some(days)) = (expires, due) else {
+            return;
+        };
+        let message = format!(
+            "API key {}. Create a new key in Bybit under API Management and update API_KEY and API_SECRET",
+            key_expiry::describe(expires, now)
+        );
+        if days <= key_expiry::CRITICAL_DAYS {
+            notify::critical(&message);
+        } else {
+            notify::send(&message);
+        }
+    }
+
+    /// Logs rejected orders, refreshing the instrument rules when the
+    /// exchange refused a price or qty format.
+    pub async fn note_rejections(&self, strategy: &StrategyConfig, rejected: &[Rejection]) {
+        for rejection in rejected {
+            println!(
+                "[{}] {} rejected: {} {}",
+                strategy.name, rejection.order_link_id, rejection.code, rejection.msg
+            );
+        }
+        if !rejected.is_empty() {
+            self.store.update_strategy(&strategy.name, |state| {
+                for rejection in rejected {
+                    *state
+                        .counters
+                        .rejected_by_code
+                        .entry(rejection.code)
+                        .or_default() += 1;
+                }
+            });
+        }
+        if rejected.iter().any(Rejection::is_precision) {
+            self.refresh_instruments().await;
+        }
+    }
+
+    /// Entries of `strategy` already placed on `symbol` for the cycle
+    /// opening at `open`, from the state file or, with
+    /// `verify_before_placing`, from the exchange's open orders. `None`
+    /// when the ladder still has to be placed.
+    pub async fn placed_entries(
+        &self,
+        strategy: &StrategyConfig,
+        symbol: &str,
+        open: DateTime<Utc>,
+    ) -> Result<Option<Vec<CancelOrderData>>, BoxError> {
+        let recorded = self.store.read(|state| {
+            let tracked = state.strategies.get(&strategy.name)?;
+            if tracked.placed_opens.get(symbol) != Some(&open) {
+                return None;
+            }
+            Some(
+                tracked
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.symbol == symbol)
+                    .map(|entry| CancelOrderData {
+                        symbol: entry.symbol.clone(),
+                        order_id: entry.order_id.clone(),
+                    })
+                    .collect(),
+            )
+        });
+        if recorded.is_some() || !self.config.schedule.verify_before_placing {
+            return Ok(recorded);
+        }
+        // entry ids are `<prefix>-<symbol>-<placed at ms>-<level>`
+        let prefix = format!("{}-{}-", strategy.link_id_prefix, symbol);
+        let resting: Vec<CancelOrderData> = self
+            .client
+            .get_open_orders(
+                strategy.category_of(symbol),
+                symbol,
+                strategy.order_filter(symbol),
+            )
+            .await?
+            .iter()
+            .filter(|order| {
+                let Some(rest) = order.order_link_id.strip_prefix(&prefix) else {
+                    return false;
+                };
+                let Some((placed_at, level)) = rest.split_once('-') else {
+                    return false;
+                };
+                ladder::level_from_link_id(level).is_some()
+                    && placed_at
+                        .parse::<i64>()
+                        .is_ok_and(|ms| ms >= open.timestamp_millis())
+            })
+            .map(|order| CancelOrderData {
+                symbol: order.symbol.clone(),
+                order_id: order.order_id.clone(),
+            })
+            .collect();
+        Ok((!resting.is_empty()).then_some(resting))
+    }
+
+    /// Cancels a strategy's entries and returns how each came out, one
+    /// batch per category their symbols trade in.
+    pub async fn cancel_entries(
+        &self,
+        strategy: &StrategyConfig,
+        orders: &[CancelOrderData],
+    ) -> Result<Vec<CancelOutcome>, BoxError> {
+        let mut by_category: BTreeMap<Category, Vec<CancelOrderData>> = BTreeMap::new();
+        for order in orders {
+            by_category
+                .entry(strategy.category_of(&order.symbol))
+                .or_default()
+                .push(order.clone());
+        }
+        let mut outcomes = Vec::new();
+        for (category, orders) in by_category {
+            outcomes.extend(self.cancel_in(strategy, category, &orders).await?);
+        }
+        Ok(outcomes)
+    }
+
+    /// Cancels entries in `category`. Orders the batch left resting are
+    /// sent again. Spot conditionals can't go through the batch endpoint,
+    /// so they are cancelled one by one.
+    async fn cancel_in(
+        &self,
+        strategy: &StrategyConfig,
+        category: Category,
+        orders: &[CancelOrderData],
+    ) -> Result<Vec<CancelOutcome>, BoxError> {
+        let Some(filter) = strategy.filter_in(category) else {
+            let mut outcomes = self.client.cancel_batch_order(category, orders).await?;
+            self.settle_gone(strategy, &mut outcomes).await;
+            for _ in 0..CANCEL_RESENDS {
+                let failed: Vec<CancelOrderData> = outcomes
+                    .iter()
+                    .filter(|outcome| outcome.is_failed())
+                    .map(|outcome| CancelOrderData {
+                        symbol: outcome.symbol.clone(),
+                        order_id: outcome.order_id.clone(),
+                    })
+                    .collect();
+                if failed.is_empty() {
+                    break;
+                }
+                println!(
+                    "[{}] {} cancels failed, sending them again",
+                    strategy.name,
+                    failed.len()
+                );
+                let resent = self.client.cancel_batch_order(category, &failed).await?;
+                for outcome in resent {
+                    if let Some(earlier) = outcomes
+                        .iter_mut()
+                        .find(|earlier| earlier.order_id == outcome.order_id)
+                    {
+                        *earlier = outcome;
+                    }
+                }
+                self.settle_gone(strategy, &mut outcomes).await;
+            }
+            return Ok(outcomes);
+        };
+        let mut outcomes = Vec::new();
+        for order in orders {
+            let request = CancelOrderRequest {
+                category: category.as_param().to_string(),
+                symbol: order.symbol.clone(),
+                order_id: order.order_id.clone(),
+                order_filter: Some(filter.to_string()),
+            };
+            self.client.cancel_order(&request).await?;
+            outcomes.push(CancelOutcome::cancelled(order));
+        }
+        Ok(outcomes)
+    }
+
+    /// Cancels every open order on the symbols of `orders` with one
+    /// cancel-all per symbol. Returns how each of `orders` came out, and
+    /// the orderLinkIds of everything cancelled, tracked or not.
+    pub async fn cancel_all_on_symbols(
+        &self,
+        strategy: &StrategyConfig,
+        orders: &[CancelOrderData],
+    ) -> Result<(Vec<CancelOutcome>, Vec<String>), BoxError> {
+        let symbols: BTreeSet<&str> = orders.iter().map(|order| order.symbol.as_str()).collect();
+        let mut cancelled = Vec::new();
+        for symbol in symbols {
+            let mut filters = vec![None];
+            if strategy.order_filter(symbol).is_some() {
+                filters.push(strategy.order_filter(symbol));
+            }
+            for filter in filters {
+                cancelled.extend(
+                    self.client
+                        .cancel_all(strategy.category_of(symbol), symbol, filter)
+                        .await?,
+                );
+            }
+        }
+        let mut outcomes: Vec<CancelOutcome> = orders
+            .iter()
+            .map(|order| {
+                if cancelled
+                    .iter()
+                    .any(|cancelled| cancelled.order_id == order.order_id)
+                {
+                    return CancelOutcome::cancelled(order);
+                }
+                // no longer open, so cancel-all passed it by
+                CancelOutcome {
+                    code: NOT_EXISTS_OR_TOO_LATE,
+                    msg: "not open at cancel-all".to_string(),
+                    ..CancelOutcome::cancelled(order)
+                }
+            })
+            .collect();
+        self.settle_gone(strategy, &mut outcomes).await;
+        let link_ids = cancelled
+            .into_iter()
+            .map(|cancelled| cancelled.order_link_id)
+            .collect();
+        Ok((outcomes, link_ids))
+    }
+
+    /// Looks up the final status of orders a cancel found already gone, so
+    /// a fill or a cancel made on the exchange is recorded, not retried.
+    async fn settle_gone(&self, strategy: &StrategyConfig, outcomes: &mut [CancelOutcome]) {
+        for outcome in outcomes
+            .iter_mut()
+            .filter(|outcome| outcome.is_gone() && outcome.settled.is_none())
+        {
+            let category = strategy.category_of(&outcome.symbol);
+            let order = match self
+                .client
+                .get_order(
+                    category,
+                    &outcome.symbol,
+                    &outcome.order_id,
+                    strategy.filter_in(category),
+                )
+                .await
+            {
+                Ok(order) => order,
+                Err(e) => {
+                    println!(
+                        "[{}] looking up {} after its cancel failed: {}",
+                        strategy.name, outcome.order_id, e
+                    );
+                    continue;
+                }
+            };
+            let Some(order) = order else {
+                outcome.settled = Some(Settled::Missing);
+                continue;
+            };
+            if let Some(event) = OrderEvent::observed(&strategy.name, category, &order) {
+                self.lifecycle.transition(event);
+            }
+            let executed: f64 = order.cum_exec_qty.parse().unwrap_or(0.0);
+            let settled = Settled::from_status(&order.order_status, executed);
+            if settled != Settled::Open {
+                self.store.update_strategy(&strategy.name, |state| {
+                    if let Some(entry) = state
+                        .entries
+                        .iter_mut()
+                        .find(|entry| entry.order_id == order.order_id)
+                    {
+                        entry.status = order.order_status.clone();
+                    }
+                });
+            }
+            outcome.settled = Some(settled);
+        }
+    }
+
+    /// Cancels a symbol's orders for every strategy, then lists the open
+    /// orders again to confirm they're gone. Nothing to cancel is a
+    /// success with an empty report.
+    pub async fn cancel_symbol(&self, symbol: &str, scope: CancelScope) -> CancellationReport {
+        let mut report = CancellationReport {
+            symbol: symbol.to_string(),
+            scope: scope.as_str().to_string(),
+            ..CancellationReport::default()
+        };
+        for strategy in &self.config.strategies {
+            let category = strategy.category_of(symbol);
+            let filter = strategy.filter_in(category);
+            let targets: Vec<(String, String)> = match scope {
+                CancelScope::Tracked => {
+                    let names = [strategy.name.clone(), format!("{}/trigger", strategy.name)];
+                    self.store.read(|state| {
+                        names
+                            .iter()
+                            .filter_map(|name| state.strategies.get(name))
+                            .flat_map(|tracked| &tracked.entries)
+                            .filter(|entry| entry.symbol == symbol)
+                            .map(|entry| (entry.order_id.clone(), entry.order_link_id.clone()))
+                            .collect()
+                    })
+                }
+                CancelScope::AllPrefixed => {
+                    let prefix = format!("{}-", strategy.link_id_prefix);
+                    match self.client.get_open_orders(category, symbol, filter).await {
+                        Ok(open) => open
+                            .into_iter()
+                            .filter(|order| order.order_link_id.starts_with(&prefix))
+                            .map(|order| (order.order_id, order.order_link_id))
+                            .collect(),
+                        Err(e) => {
+                            report
+                                .errors
+                                .push(format!("[{}] listing orders: {}", strategy.name, e));
+                            continue;
+                        }
+                    }
+                }
+            };
+            if targets.is_empty() {
+                continue;
+            }
+            let orders: Vec<CancelOrderData> = targets
+                .iter()
+                .map(|(order_id, _)| CancelOrderData {
+                    symbol: symbol.to_string(),
+                    order_id: order_id.clone(),
+                })
+                .collect();
+            match self.cancel_entries(strategy, &orders).await {
+                Ok(outcomes) => {
+                    report
+                        .errors
+                        .extend(outcomes.iter().filter(|outcome| outcome.is_failed()).map(
+                            |outcome| {
+                                format!(
+                                    "[{}] cancel {}: {} {}",
+                                    strategy.name, outcome.order_id, outcome.code, outcome.msg
+                                )
+                            },
+                        ))
+                }
+                Err(e) => report
+                    .errors
+                    .push(format!("[{}] cancel: {}", strategy.name, e)),
+            }
+            let open: Vec<String> =
+                match self.client.get_open_orders(category, symbol, filter).await {
+                    Ok(open) => open.into_iter().map(|order| order.order_id).collect(),
+                    Err(e) => {
+                        report
+                            .errors
+                            .push(format!("[{}] verifying: {}", strategy.name, e));
+                        continue;
+                    }
+                };
+            let mut gone = Vec::new();
+            let name = strategy.name.clone();
+            for (order_id, link_id) in targets {
+                if open.contains(&order_id) {
+                    report.still_open.push(link_id);
+                } else {
+                    gone.push(order_id);
+                    report.cancelled.push(link_id);
+                }
+            }
+            self.store.update(|state| {
+                for tracked in state.strategies.values_mut() {
+                    tracked
+                        .entries
+                        .retain(|entry| !gone.contains(&entry.order_id));
+                }
+                let counters = &mut state.strategies.entry(name).or_default().counters;
+                counters.cancelled += gone.len() as u64;
+            });
+        }
+        report
+    }
+}
+
+/// Which orders a remote cancel goes after.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelScope {
+    /// Entries in the state file, scheduled or triggered.
+    #[default]
+    Tracked,
+    /// Every open order carrying a strategy's orderLinkId prefix, exits
+    /// included.
+    AllPrefixed,
+}
+
+impl CancelScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CancelScope::Tracked => "tracked",
+            CancelScope::AllPrefixed => "all_prefixed",
+        }
+    }
+}
+
+/// Explains a symbol being dropped, with the likely fix.
+pub fn unavailable_message(unavailable: &SymbolUnavailable) -> String {
+    format!(
+        "{}, skipping it for this session. It may have been delisted or renamed; \
+         map the old name with symbol_aliases or remove it from the config.",
+        unavailable
+    )
+}
@@ -0,0 +1,139 @@
+use crate::bybit::BoxError;
+use crate::notify;
+use crate::schedule;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+/// How long Bybit keeps an IP banned after it starts answering 403.
+const BAN_MINUTES: i64 = 10;
+/// Requests let through once the ban window is over, to see if it lifted.
+const PROBES: u32 = 3;
+/// Wait before another round of probes when a round got no answer.
+const PROBE_RETRY_SECONDS: i64 = 30;
+
+/// Where the client stands with the exchange's IP ban. Written to the state
+/// file on every change so `status` shows it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CooldownStatus {
+    /// End of the current ban window, `None` when requests flow normally.
+    pub until: Option<DateTime<Utc>>,
+    pub reason: String,
+    /// Probes left in this round once the window is over.
+    pub probes_left: u32,
+    /// Cooldowns entered since startup.
+    pub entered: u64,
+    /// Non-critical calls refused while cooling down.
+    pub suspended: u64,
+}
+
+/// Holds requests back after a 403 or IP rate-limit reply so retries don't
+/// stretch the ban. Non-critical calls fail straight away; critical ones
+/// (cancels, stops) wait for the window to end and go out as probes.
+pub struct Cooldown {
+    status: watch::Sender<CooldownStatus>,
+}
+
+enum Admission {
+    Go,
+    Wait(DateTime<Utc>),
+    Refuse(DateTime<Utc>, String),
+}
+
+impl Cooldown {
+    pub fn new() -> Cooldown {
+        Cooldown {
+            status: watch::Sender::new(CooldownStatus::default()),
+        }
+    }
+
+    pub fn status(&self) -> CooldownStatus {
+        self.status.borrow().clone()
+    }
+
+    /// Receives the status each time a cooldown starts, moves on to another
+    /// round of probes or ends.
+    pub fn subscribe(&self) -> watch::Receiver<CooldownStatus> {
+        self.status.subscribe()
+    }
+
+    /// Lets a request through, holds it until the ban window ends if it is
+    /// `critical`, or refuses it.
+    pub async fn admit(&self, critical: bool) -> Result<(), BoxError> {
+        loop {
+            let mut admission = Admission::Go;
+            self.status.send_if_modified(|status| {
+                let Some(until) = status.until else {
+                    return false;
+                };
+                let now = Utc::now();
+                let mut changed = false;
+                if now >= until && status.probes_left == 0 {
+                    status.until = Some(now + Duration::seconds(PROBE_RETRY_SECONDS));
+                    status.probes_left = PROBES;
+                    changed = true;
+                }
+                let until = status.until.unwrap_or(until);
+                admission = if now >= until {
+                    status.probes_left -= 1;
+                    Admission::Go
+                } else if critical {
+                    Admission::Wait(until)
+                } else {
+                    status.suspended += 1;
+                    Admission::Refuse(until, status.reason.clone())
+                };
+                changed
+            });
+            match admission {
+                Admission::Go => return Ok(()),
+                Admission::Wait(until) => schedule::sleep_until(until).await,
+                Admission::Refuse(until, reason) => {
+                    return Err(format!("API cooldown until {} after {}", until, reason).into())
+                }
+            }
+        }
+    }
+
+    /// Starts (or restarts) the ban window and returns the error for the
+    /// request that hit it.
+    pub fn trip(&self, reason: String) -> BoxError {
+        let until = Utc::now() + Duration::minutes(BAN_MINUTES);
+        let mut entered = false;
+        self.status.send_modify(|status| {
+            entered = status.until.is_none();
+            if entered {
+                status.entered += 1;
+            }
+            status.until = Some(until);
+            status.reason = reason.clone();
+            status.probes_left = PROBES;
+        });
+        if entered {
+            notify::critical(&format!(
+                "API refused with {}, pausing non-critical calls until {}",
+                reason, until
+            ));
+        }
+        format!("API cooldown started: {}", reason).into()
+    }
+
+    /// Ends the cooldown on an answered probe. Replies to requests sent
+    /// before the ban are ignored.
+    pub fn clear(&self) {
+        let mut cleared = false;
+        self.status.send_if_modified(|status| {
+            match status.until {
+                Some(until) if Utc::now() >= until => {}
+                _ => return false,
+            }
+            status.until = None;
+            status.probes_left = 0;
+            cleared = true;
+            true
+        });
+        if cleared {
+            notify::send("API answering again, cooldown over");
+        }
+    }
+}
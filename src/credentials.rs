@@ -0,0 +1,315 @@
+//! Where the API key and secret come from: the environment, AWS Secrets
+//! Manager or HashiCorp Vault, chosen under `[credentials]`. A secrets
+//! store keeps them off the disk, and fetching again on `refresh` picks up
+//! a rotated secret without a restart.
+
+use crate::aws_credentials;
+use crate::bybit::BoxError;
+use crate::config::{
+    AwsSecretConfig, Config, CredentialSource, CredentialsConfig, VaultAuth, VaultConfig,
+};
+use crate::context::Context;
+use crate::journal;
+use crate::scrub::{self, Scrubbed, REDACTED};
+use crate::sigv4::{self, Request};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use reqwest::{Client, Response, Url};
+use serde_json::{json, Value};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An API key and secret. Both are registered for scrubbing as soon as
+/// they're read, and never printed.
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    api_key: String,
+    api_secret: String,
+}
+
+impl Credentials {
+    pub fn new(api_key: String, api_secret: String) -> Credentials {
+        scrub::register(&api_key);
+        scrub::register(&api_secret);
+        Credentials {
+            api_key,
+            api_secret,
+        }
+    }
+
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    pub fn api_secret(&self) -> &str {
+        &self.api_secret
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("api_key", &REDACTED)
+            .field("api_secret", &REDACTED)
+            .finish()
+    }
+}
+
+pub trait Provider: Send + Sync {
+    /// e.g. "AWS Secrets Manager", for logs.
+    fn name(&self) -> &'static str;
+    fn fetch(&self) -> BoxFuture<'_, Result<Credentials, BoxError>>;
+}
+
+/// The provider `config` selects.
+pub fn provider(config: &CredentialsConfig) -> Box<dyn Provider + '_> {
+    match (config.source, &config.aws, &config.vault) {
+        (CredentialSource::Aws, Some(aws), _) => Box::new(Aws {
+            settings: aws,
+            fields: config,
+        }),
+        (CredentialSource::Vault, _, Some(vault)) => Box::new(Vault {
+            settings: vault,
+            fields: config,
+        }),
+        // validation rules out a store without its section
+        _ => Box::new(Env),
+    }
+}
+
+/// Fetches the credentials from the configured provider.
+pub async fn fetch(config: &Config) -> Result<Credentials, BoxError> {
+    let provider = provider(&config.credentials);
+    provider.fetch().await.map_err(|e| {
+        format!(
+            "failed fetching credentials from {}: {}",
+            provider.name(),
+            e
+        )
+        .into()
+    })
+}
+
+/// Fetches the credentials again every `refresh`, handing the client any
+/// that changed. A failed fetch warns and keeps signing with the previous
+/// ones.
+pub async fn refresh(ctx: Arc<Context>) {
+    let Some(every) = ctx.config.credentials.refresh else {
+        return;
+    };
+    loop {
+        tokio::time::sleep(every).await;
+        match fetch(&ctx.config).await {
+            Ok(credentials) if credentials != ctx.client.credentials() => {
+                ctx.client.set_credentials(credentials);
+                let source = provider(&ctx.config.credentials).name();
+                info!(
+                    "credentials rotated, signing with the new ones from {}",
+                    source
+                );
+                journal::record("credentials_rotated", json!({ "source": source }));
+            }
+            Ok(_) => {}
+            Err(e) => println!(
+                "WARNING: {}, still signing with the previous credentials",
+                Scrubbed(e)
+            ),
+        }
+    }
+}
+
+/// The key and secret from the fields `fields` names in `secret`.
+fn pick(secret: &Value, fields: &CredentialsConfig) -> Result<Credentials, BoxError> {
+    let field = |name: &str| {
+        secret[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| format!("the secret has no {} field", name))
+    };
+    Ok(Credentials::new(
+        field(&fields.key_field)?,
+        field(&fields.secret_field)?,
+    ))
+}
+
+fn client() -> Client {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("failed building http client")
+}
+
+/// The body of a successful reply, else its status and body as the error.
+async fn body(response: Response) -> Result<Value, BoxError> {
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(format!("{}: {}", status, text).into());
+    }
+    Ok(serde_json::from_str(&text)?)
+}
+
+fn var(name: &str) -> Result<String, BoxError> {
+    env::var(name).map_err(|_| format!("{} is not set", name).into())
+}
+
+/// `API_KEY` and `API_SECRET`, as before there were providers.
+struct Env;
+
+impl Provider for Env {
+    fn name(&self) -> &'static str {
+        "the environment"
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Credentials, BoxError>> {
+        Box::pin(async { Ok(Credentials::new(var("API_KEY")?, var("API_SECRET")?)) })
+    }
+}
+
+/// A JSON secret in AWS Secrets Manager, read with a GetSecretValue call
+/// signed with the key the AWS default chain finds.
+struct Aws<'a> {
+    settings: &'a AwsSecretConfig,
+    fields: &'a CredentialsConfig,
+}
+
+impl Aws<'_> {
+    async fn get_secret_value(&self) -> Result<Value, BoxError> {
+        let settings = self.settings;
+        let region = match &settings.region {
+            Some(region) => region.clone(),
+            // arn:aws:secretsmanager:<region>:<account>:secret:<name>
+            None => settings
+                .secret_id
+                .strip_prefix("arn:")
+                .and_then(|arn| arn.split(':').nth(2))
+                .ok_or("credentials.aws needs a region when secret_id isn't an ARN")?
+                .to_string(),
+        };
+        let endpoint = settings
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| format!("https://secretsmanager.{}.amazonaws.com", region));
+        let url = Url::parse(&endpoint)?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(format!("{} has no host", endpoint).into()),
+        };
+        let client = client();
+        let (key, source) = aws_credentials::find(&client, &region).await?;
+        debug!("signing GetSecretValue with the AWS key from {}", source);
+
+        let payload = json!({ "SecretId": settings.secret_id }).to_string();
+        let now = Utc::now();
+        let date = sigv4::amz_date(now);
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1"),
+            ("host", host.as_str()),
+            ("x-amz-date", date.as_str()),
+            ("x-amz-target", "secretsmanager.GetSecretValue"),
+        ];
+        if let Some(token) = key.token() {
+            headers.push(("x-amz-security-token", token));
+        }
+        let request = Request {
+            method: "POST",
+            path: url.path(),
+            query: "",
+            headers: &headers,
+            body: payload.as_bytes(),
+        };
+        let authorization =
+            sigv4::authorization(&key.access_key(), &region, "secretsmanager", now, &request);
+        let mut post = client
+            .post(url)
+            .header("Authorization", authorization)
+            .body(payload);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            post = post.header(*name, *value);
+        }
+        body(post.send().await?).await
+    }
+}
+
+impl Provider for Aws<'_> {
+    fn name(&self) -> &'static str {
+        "AWS Secrets Manager"
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Credentials, BoxError>> {
+        Box::pin(async move {
+            let reply = self.get_secret_value().await?;
+            let secret = reply["SecretString"]
+                .as_str()
+                .ok_or("the secret has no SecretString")?;
+            pick(&serde_json::from_str(secret)?, self.fields)
+        })
+    }
+}
+
+/// A secret in HashiCorp Vault, KV v1 or v2, read with a token or after an
+/// AppRole login.
+struct Vault<'a> {
+    settings: &'a VaultConfig,
+    fields: &'a CredentialsConfig,
+}
+
+impl Vault<'_> {
+    async fn token(&self, client: &Client) -> Result<String, BoxError> {
+        let settings = self.settings;
+        let token = match settings.auth {
+            VaultAuth::Token => var("VAULT_TOKEN")?,
+            VaultAuth::Approle => {
+                let login = json!({
+                    "role_id": settings.role_id,
+                    "secret_id": var("VAULT_SECRET_ID")?,
+                });
+                let reply = client
+                    .post(format!("{}/v1/auth/approle/login", settings.address))
+                    .json(&login)
+                    .send()
+                    .await?;
+                body(reply).await?["auth"]["client_token"]
+                    .as_str()
+                    .ok_or("the approle login returned no client_token")?
+                    .to_string()
+            }
+        };
+        scrub::register(&token);
+        Ok(token)
+    }
+}
+
+impl Provider for Vault<'_> {
+    fn name(&self) -> &'static str {
+        "Vault"
+    }
+
+    fn fetch(&self) -> BoxFuture<'_, Result<Credentials, BoxError>> {
+        Box::pin(async move {
+            let client = client();
+            let token = self.token(&client).await?;
+            let reply = client
+                .get(format!(
+                    "{}/v1/{}",
+                    self.settings.address, self.settings.path
+                ))
+                .header("X-Vault-Token", token)
+                .send()
+                .await?;
+            let data = &body(reply).await?["data"];
+            // KV v2 nests the secret a level further down, under its metadata
+            let secret = if data["data"].is_object() {
+                &data["data"]
+            } else {
+                data
+            };
+            pick(secret, self.fields)
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use crate::bybit::{BoxError, BybitClient, CancelOrderData, Ticker};
+use crate::config::Category;
+use crate::notes::Note;
+use crate::report::CycleReport;
+use crate::state::StateStore;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// How long a fetched ticker is reused within a cycle.
+const TICKER_TTL: Duration = Duration::from_secs(30);
+
+/// What one strategy cycle tracks between placement and the cancel sweep.
+pub struct Cycle {
+    /// Anchor price per placed symbol whose hold is still running.
+    pub anchors: HashMap<String, f64>,
+    /// Anchors of symbols whose hold is over.
+    pub ended: HashMap<String, f64>,
+    /// Entry orders still expected to be resting.
+    pub tracked: Vec<CancelOrderData>,
+    /// Conditional entries already seen triggered.
+    pub triggered: HashSet<String>,
+    /// Notional cascaded bids added per symbol.
+    pub cascaded: HashMap<String, f64>,
+    /// Symbols entering by TWAP, with the anchor their trigger is below.
+    pub twaps: HashMap<String, f64>,
+    /// Last ticker fetched per symbol, with when.
+    pub tickers: HashMap<String, (Instant, Ticker)>,
+    pub report: CycleReport,
+}
+
+impl Cycle {
+    pub fn new(strategy: &str, open: DateTime<Utc>) -> Cycle {
+        Cycle {
+            anchors: HashMap::new(),
+            ended: HashMap::new(),
+            tracked: Vec::new(),
+            triggered: HashSet::new(),
+            cascaded: HashMap::new(),
+            twaps: HashMap::new(),
+            tickers: HashMap::new(),
+            report: CycleReport::new(strategy, open),
+        }
+    }
+
+    /// The anchor `symbol` was last placed from, its hold over or not.
+    pub fn anchor(&self, symbol: &str) -> Option<f64> {
+        self.anchors
+            .get(symbol)
+            .or_else(|| self.ended.get(symbol))
+            .copied()
+    }
+
+    /// `symbol`'s ticker, fetched again only once the last one is older
+    /// than `TICKER_TTL`, so checks running together share it.
+    pub async fn ticker(
+        &mut self,
+        client: &BybitClient,
+        category: Category,
+        symbol: &str,
+    ) -> Result<Ticker, BoxError> {
+        if let Some((at, ticker)) = self.tickers.get(symbol) {
+            if at.elapsed() < TICKER_TTL {
+                return Ok(ticker.clone());
+            }
+        }
+        let ticker = client.get_ticker(category, symbol).await?;
+        self.tickers
+            .insert(symbol.to_string(), (Instant::now(), ticker.clone()));
+        Ok(ticker)
+    }
+
+    /// Attaches a note to the cycle: kept in the state file and shown at
+    /// the top of its report. Each symbol's cycle for the same open shares
+    /// the note, which is kept once.
+    pub fn note(&mut self, store: &StateStore, text: &str) {
+        let note = Note::new(&Note::cycle(&self.report.strategy, self.report.open), text);
+        store.update(|state| {
+            let kept = state
+                .notes
+                .iter()
+                .any(|kept| kept.subject == note.subject && kept.text == note.text);
+            if !kept {
+                state.notes.push(note);
+            }
+        });
+        self.report.notes.push(text.to_string());
+    }
+}
@@ -0,0 +1,87 @@
+//! Every price and qty sent in an order payload is formatted here, so they
+//! are always whole multiples of the instrument's tick or qty step, as
+//! plain decimals with a leading zero and exactly the step's number of
+//! places: no exponent, no "-0", no extra zeros.
+
+/// Most places an instrument step is taken to have; anything finer is
+/// below f64's precision for realistic prices anyway.
+pub const MAX_DECIMALS: usize = 18;
+
+/// Which way a value is brought to the allowed number of places.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Nearest,
+    /// Never above the value, e.g. so a qty doesn't exceed the balance.
+    Down,
+    /// Never below the value, e.g. so an exit isn't priced under its target.
+    Up,
+}
+
+/// Decimal places of a step like "0.001". Steps finer than `MAX_DECIMALS`
+/// are treated as that.
+pub fn places(step: &str) -> usize {
+    step.split_once('.')
+        .map(|(_, fraction)| fraction.trim_end_matches('0').len())
+        .unwrap_or(0)
+        .min(MAX_DECIMALS)
+}
+
+/// `value` at `decimals` places. A value within 1e-9 of a step counts as
+/// on it, so float noise like 70.99999999 doesn't lose a step.
+pub fn round_to(value: f64, decimals: usize, rounding: Rounding) -> f64 {
+    let scale = 10f64.powi(decimals.min(MAX_DECIMALS) as i32);
+    let scaled = value * scale;
+    // past 2^53 an f64 has no fraction left to round, and scaling back
+    // would only add error
+    if !scaled.is_finite() || scaled.abs() >= 9_007_199_254_740_992.0 {
+        return value;
+    }
+    let steps = match rounding {
+        Rounding::Nearest => scaled.round(),
+        Rounding::Down => (scaled + 1e-9).floor(),
+        Rounding::Up => (scaled - 1e-9).ceil(),
+    };
+    steps / scale
+}
+
+/// `value` as an order field with exactly `decimals` places.
+pub fn format(value: f64, decimals: usize, rounding: Rounding) -> String {
+    let decimals = decimals.min(MAX_DECIMALS);
+    let rounded = round_to(value, decimals, rounding);
+    // a negative value that rounds to zero would print as "-0.00"
+    let rounded = if rounded == 0.0 { 0.0 } else { rounded };
+    // `{:.*}` never switches to an exponent, however small or large
+    format!("{:.*}", decimals, rounded)
+}
+
+/// `value` at a whole multiple of `step`, e.g. "0.05" or "10". As with
+/// `round_to`, a value within 1e-9 of a multiple counts as on it. A step
+/// that isn't a positive number only rounds to its places.
+pub fn round_to_step(value: f64, step: &str, rounding: Rounding) -> f64 {
+    let decimals = places(step);
+    let size = match step.parse::<f64>() {
+        Ok(size) if size > 0.0 && size.is_finite() => size,
+        _ => return round_to(value, decimals, rounding),
+    };
+    let scaled = value / size;
+    if !scaled.is_finite() || scaled.abs() >= 9_007_199_254_740_992.0 {
+        return value;
+    }
+    let steps = match rounding {
+        Rounding::Nearest => scaled.round(),
+        Rounding::Down => (scaled + 1e-9).floor(),
+        Rounding::Up => (scaled - 1e-9).ceil(),
+    };
+    // 3 * 0.05 is 0.15000000000000002; the step's places take the noise off
+    round_to(steps * size, decimals, Rounding::Nearest)
+}
+
+/// `value` as an order field: a whole multiple of `step` with exactly the
+/// step's places.
+pub fn format_step(value: f64, step: &str, rounding: Rounding) -> String {
+    format(
+        round_to_step(value, step, rounding),
+        places(step),
+        Rounding::Nearest,
+    )
+}
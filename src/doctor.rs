@@ -0,0 +1,353 @@
+//! `stink-bid doctor`: the startup preflight run on its own, as a
+//! checklist to paste when asking for help.
+
+use crate::account::AccountType;
+use crate::bybit::{ApiKeyInfo, BybitClient, RECV_WINDOW_MS};
+use crate::clock::Measurement;
+use crate::config::Config;
+use crate::email::EmailNotifier;
+use crate::instruments;
+use crate::key_expiry;
+use crate::notify::Notifier;
+use crate::ntfy::NtfyNotifier;
+use crate::preflight;
+use crate::scrub::Scrubbed;
+use crate::slack::SlackNotifier;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use std::fmt;
+use std::io::IsTerminal;
+
+const TEST_MESSAGE: &str = "stink-bid doctor: test message";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Pass,
+    /// Worth fixing, but the bot would still run.
+    Warn,
+    /// The bot would refuse to start or couldn't trade.
+    Fail,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Checklist {
+    checks: Vec<Check>,
+    /// Colours the marks, when printing to a terminal.
+    colour: bool,
+}
+
+impl Checklist {
+    fn add(&mut self, name: &str, status: Status, detail: impl fmt::Display) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            status,
+            detail: detail.to_string(),
+        });
+    }
+
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.status != Status::Fail)
+    }
+}
+
+impl fmt::Display for Checklist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            let (mark, colour) = match check.status {
+                Status::Pass => ("[ ok ]", "32"),
+                Status::Warn => ("[warn]", "33"),
+                Status::Fail => ("[FAIL]", "31"),
+            };
+            if self.colour {
+                write!(f, "\x1b[{}m{}\x1b[0m", colour, mark)?;
+            } else {
+                f.write_str(mark)?;
+            }
+            writeln!(f, " {}: {}", check.name, check.detail)?;
+        }
+        let count = |status| {
+            self.checks
+                .iter()
+                .filter(|check| check.status == status)
+                .count()
+        };
+        write!(
+            f,
+            "{} passed, {} warned, {} failed",
+            count(Status::Pass),
+            count(Status::Warn),
+            count(Status::Fail)
+        )
+    }
+}
+
+/// Runs every check it can. A failed config or name lookup ends it early,
+/// as everything after depends on them.
+pub async fn run(send_test: bool) -> Checklist {
+    let mut list = Checklist {
+        colour: std::io::stdout().is_terminal(),
+        ..Checklist::default()
+    };
+    let config = match Config::load() {
+        Ok(config) => {
+            list.add(
+                "config",
+                Status::Pass,
+                format!(
+                    "{}, {} strategies, {} symbols",
+                    Config::path(),
+                    config.strategies.len(),
+                    config.symbols.len()
+                ),
+            );
+            config
+        }
+        Err(e) => {
+            list.add("config", Status::Fail, Scrubbed(e));
+            return list;
+        }
+    };
+    let public = BybitClient::public(&config);
+    if !resolve(&mut list, public.base_url()).await {
+        return list;
+    }
+    server_time(&mut list, &public).await;
+    match crate::credentials::fetch(&config).await {
+        Ok(fetched) => {
+            let client = BybitClient::new(&config, fetched);
+            credentials(&mut list, &client, &config).await;
+        }
+        Err(e) => list.add("api key", Status::Fail, Scrubbed(e)),
+    }
+    symbols(&mut list, &public, &config).await;
+    notifiers(&mut list, &config, send_test).await;
+    list
+}
+
+async fn resolve(list: &mut Checklist, base_url: &str) -> bool {
+    let url = match Url::parse(base_url) {
+        Ok(url) => url,
+        Err(e) => {
+            list.add("base url", Status::Fail, format!("{}: {}", base_url, e));
+            return false;
+        }
+    };
+    let host = url.host_str().unwrap_or_default().to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addresses = tokio::net::lookup_host((host.as_str(), port)).await;
+    match addresses {
+        Ok(mut addresses) => match addresses.next() {
+            Some(address) => {
+                list.add(
+                    "base url",
+                    Status::Pass,
+                    format!("{} resolves to {}", host, address.ip()),
+                );
+                true
+            }
+            None => {
+                list.add("base url", Status::Fail, format!("{} has no address", host));
+                false
+            }
+        },
+        Err(e) => {
+            list.add("base url", Status::Fail, format!("{}: {}", host, e));
+            false
+        }
+    }
+}
+
+/// Pings the exchange for its clock and compares it with ours at the
+/// middle of the round trip, as the bot does at startup and hourly.
+async fn server_time(list: &mut Checklist, client: &BybitClient) {
+    let sent = Utc::now();
+    let server = match client.get_server_time().await {
+        Ok(server) => server,
+        Err(e) => {
+            list.add("server time", Status::Fail, Scrubbed(e));
+            return;
+        }
+    };
+    let clock = Measurement::new(sent, Utc::now(), server);
+    if clock.is_risky(RECV_WINDOW_MS) {
+        list.add(
+            "server time",
+            Status::Warn,
+            format!(
+                "{}, over half the {}ms recv window, sync the system clock",
+                clock, RECV_WINDOW_MS
+            ),
+        );
+    } else {
+        list.add("server time", Status::Pass, clock);
+    }
+}
+
+/// Authenticates with the account info call, then reads what the key
+/// may do, against what the config needs, and when it expires.
+async fn credentials(list: &mut Checklist, client: &BybitClient, config: &Config) {
+    match AccountType::detect(client).await {
+        Ok(account) => list.add(
+            "api key",
+            Status::Pass,
+            format!("authenticated, {:?} account", account),
+        ),
+        Err(e) => {
+            list.add("api key", Status::Fail, Scrubbed(e));
+            return;
+        }
+    }
+    let info = match client.get_api_key_info().await {
+        Ok(info) => info,
+        Err(e) => {
+            list.add("key permissions", Status::Fail, Scrubbed(e));
+            return;
+        }
+    };
+    match preflight::check_key_info(&info, config) {
+        Ok(()) => list.add(
+            "key permissions",
+            Status::Pass,
+            preflight::describe_key(&info),
+        ),
+        Err(e) => list.add("key permissions", Status::Fail, e),
+    }
+    expiry(list, &info, Utc::now());
+}
+
+fn expiry(list: &mut Checklist, info: &ApiKeyInfo, now: DateTime<Utc>) {
+    let expires = match key_expiry::parse(&info.expired_at) {
+        Ok(Some(expires)) => expires,
+        Ok(None) => {
+            list.add("key expiry", Status::Pass, "doesn't expire");
+            return;
+        }
+        Err(e) => {
+            list.add("key expiry", Status::Warn, e);
+            return;
+        }
+    };
+    let status = if expires <= now {
+        Status::Fail
+    } else if key_expiry::due(expires, now, None).is_some() {
+        Status::Warn
+    } else {
+        Status::Pass
+    };
+    list.add("key expiry", status, key_expiry::describe(expires, now));
+}
+
+/// Every configured symbol, with the rules its orders are sized by.
+/// Unknown symbols only fail under `strict_symbols`, as they would at
+/// startup.
+async fn symbols(list: &mut Checklist, client: &BybitClient, config: &Config) {
+    for (category, traded) in config.traded_categories() {
+        let listed = match instruments::listed(client, category).await {
+            Ok(listed) => listed,
+            Err(e) => {
+                list.add(
+                    &format!("{} symbols", category.as_param()),
+                    Status::Fail,
+                    Scrubbed(e),
+                );
+                continue;
+            }
+        };
+        for symbol in &traded {
+            let name = format!("{} {}", category.as_param(), symbol);
+            let Some(info) = listed.get(symbol) else {
+                let close = preflight::suggestions(symbol, listed.keys());
+                let mut detail = "not listed".to_string();
+                if !close.is_empty() {
+                    let close: Vec<&str> = close.iter().map(|s| s.as_str()).collect();
+                    detail.push_str(&format!(", did you mean {}?", close.join(" or ")));
+                }
+                let status = if config.strict_symbols {
+                    Status::Fail
+                } else {
+                    Status::Warn
+                };
+                list.add(&name, status, detail);
+                continue;
+            };
+            if info.status != "Trading" {
+                list.add(
+                    &name,
+                    Status::Warn,
+                    format!("status is {}, it would be skipped", info.status),
+                );
+                continue;
+            }
+            let lot = &info.lot_size_filter;
+            let step = lot.qty_step.as_ref().or(lot.base_precision.as_ref());
+            let min = lot
+                .min_notional_value
+                .as_ref()
+                .or(lot.min_order_amt.as_ref());
+            list.add(
+                &name,
+                Status::Pass,
+                format!(
+                    "tick {}, step {}, min order {} {}",
+                    info.price_filter.tick_size,
+                    step.map_or("?", String::as_str),
+                    min.map_or("0", String::as_str),
+                    info.quote_coin
+                ),
+            );
+        }
+    }
+}
+
+/// Builds each configured notifier, and with `send_test` sends it a test
+/// message. A broken channel only warns, as the bot runs without it.
+async fn notifiers(list: &mut Checklist, config: &Config, send_test: bool) {
+    let mut built: Vec<Box<dyn Notifier>> = Vec::new();
+    if let Some(settings) = &config.email {
+        match EmailNotifier::new(settings) {
+            Ok(email) => built.push(Box::new(email)),
+            Err(e) => list.add("email", Status::Warn, format!("disabled: {}", Scrubbed(e))),
+        }
+    }
+    if let Some(settings) = &config.slack {
+        match SlackNotifier::new(settings) {
+            Ok(slack) => built.push(Box::new(slack)),
+            Err(e) => list.add("slack", Status::Warn, format!("disabled: {}", Scrubbed(e))),
+        }
+    }
+    if let Some(settings) = &config.ntfy {
+        built.push(Box::new(NtfyNotifier::new(settings)));
+    }
+    if config.email.is_none() && config.slack.is_none() && config.ntfy.is_none() {
+        list.add(
+            "notifiers",
+            Status::Warn,
+            "none configured, alerts only go to the log",
+        );
+    }
+    for notifier in built {
+        if !send_test {
+            list.add(
+                notifier.name(),
+                Status::Pass,
+                "configured, --send-test sends a test message",
+            );
+            continue;
+        }
+        match notifier.send_test(TEST_MESSAGE).await {
+            Ok(()) => list.add(notifier.name(), Status::Pass, "test message sent"),
+            Err(e) => list.add(
+                notifier.name(),
+                Status::Warn,
+                format!("test message failed: {}", Scrubbed(e)),
+            ),
+        }
+    }
+}
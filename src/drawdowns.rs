@@ -0,0 +1,136 @@
+//! How deep daily candles trade below their open, for picking ladder
+//! depths from history.
+
+use serde::Serialize;
+use std::fmt;
+
+/// Percentiles of the daily drawdown that are printed.
+const PERCENTILES: [f64; 5] = [50.0, 75.0, 90.0, 95.0, 99.0];
+
+/// Depths whose frequency is printed, in percent below the open.
+const DEPTHS: [f64; 8] = [2.0, 5.0, 10.0, 15.0, 20.0, 25.0, 30.0, 40.0];
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub symbol: String,
+    pub days: usize,
+    pub percentiles: Vec<Percentile>,
+    pub frequencies: Vec<Frequency>,
+    /// Shallowest first, each filling half as often as the one above.
+    pub suggested: Vec<Frequency>,
+}
+
+/// `depth` is the drawdown not passed on `percentile` percent of days.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Percentile {
+    pub percentile: f64,
+    pub depth: f64,
+}
+
+/// A drawdown of `depth` percent or more happened on `days_percent` of
+/// days.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct Frequency {
+    pub depth: f64,
+    pub days_percent: f64,
+}
+
+/// Percent the day's low went below its open. None for a candle with no
+/// usable open.
+pub fn drawdown(open: f64, low: f64) -> Option<f64> {
+    (open > 0.0).then(|| ((open - low) / open * 100.0).max(0.0))
+}
+
+/// Percentiles and frequencies of `drawdowns`, and a ladder of `levels`
+/// whose shallowest level fills on about `target` percent of days.
+pub fn analyze(symbol: &str, drawdowns: &[f64], target: f64, levels: usize) -> Analysis {
+    let mut sorted = drawdowns.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mut suggested: Vec<Frequency> = (0..levels)
+        .map(|level| target / 2f64.powi(level as i32))
+        .filter_map(|share| depth_reached(&sorted, share))
+        .map(|depth| Frequency {
+            depth,
+            days_percent: frequency(&sorted, depth),
+        })
+        .collect();
+    // short histories run out of rarer days
+    suggested.dedup_by(|a, b| a.depth == b.depth);
+    Analysis {
+        symbol: symbol.to_string(),
+        days: sorted.len(),
+        percentiles: PERCENTILES
+            .iter()
+            .filter_map(|&percentile| {
+                let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+                let depth = *sorted.get(rank.max(1) - 1)?;
+                Some(Percentile {
+                    percentile,
+                    depth: round(depth, 100.0),
+                })
+            })
+            .collect(),
+        frequencies: DEPTHS
+            .iter()
+            .map(|&depth| Frequency {
+                depth,
+                days_percent: frequency(&sorted, depth),
+            })
+            .collect(),
+        suggested,
+    }
+}
+
+/// Percent of days in `sorted` that drew down `depth` or more.
+pub fn frequency(sorted: &[f64], depth: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let reached = sorted.iter().filter(|&&drawdown| drawdown >= depth).count();
+    round(reached as f64 * 100.0 / sorted.len() as f64, 10.0)
+}
+
+/// Deepest level, floored to a tenth of a percent, that `sorted` reached
+/// on at least `share` percent of days. None when no day is that rare.
+pub fn depth_reached(sorted: &[f64], share: f64) -> Option<f64> {
+    let days = (share / 100.0 * sorted.len() as f64).ceil() as usize;
+    if days == 0 {
+        return None;
+    }
+    let depth = *sorted.get(sorted.len().checked_sub(days)?)?;
+    Some((depth * 10.0).floor() / 10.0)
+}
+
+fn round(value: f64, scale: f64) -> f64 {
+    (value * scale).round() / scale
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: intraday drawdown below the open over {} days",
+            self.symbol, self.days
+        )?;
+        for percentile in &self.percentiles {
+            writeln!(f, "  p{:<3} {}%", percentile.percentile, percentile.depth)?;
+        }
+        for frequency in &self.frequencies {
+            writeln!(
+                f,
+                "  a {}% intraday drawdown occurred on {}% of days",
+                frequency.depth, frequency.days_percent
+            )?;
+        }
+        let levels: Vec<String> = self
+            .suggested
+            .iter()
+            .map(|level| format!("{}% ({}% of days)", level.depth, level.days_percent))
+            .collect();
+        if levels.is_empty() {
+            write!(f, "too few days to suggest a ladder")
+        } else {
+            write!(f, "suggested levels: {}", levels.join(", "))
+        }
+    }
+}
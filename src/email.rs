@@ -0,0 +1,105 @@
+use crate::bybit::BoxError;
+use crate::config::EmailConfig;
+use crate::notify::{Event, Notifier, RenderedReport};
+use crate::report;
+use futures::future::BoxFuture;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::env;
+use std::sync::Mutex;
+
+/// Emails critical failures straight away and collects cycle reports into
+/// one digest a day. Notices and fills are mailed only when routed here.
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Mailbox,
+    pending: Mutex<Vec<RenderedReport>>,
+}
+
+impl EmailNotifier {
+    /// Builds the notifier from `[email]` and the `SMTP_USERNAME` and
+    /// `SMTP_PASSWORD` env vars.
+    pub fn new(config: &EmailConfig) -> Result<EmailNotifier, BoxError> {
+        let username = env::var("SMTP_USERNAME").map_err(|_| "SMTP_USERNAME is missing")?;
+        let password = env::var("SMTP_PASSWORD").map_err(|_| "SMTP_PASSWORD is missing")?;
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+            .port(config.port)
+            .credentials(Credentials::new(username, password))
+            .build();
+        Ok(EmailNotifier {
+            transport,
+            from: config.from.parse()?,
+            to: config.to.parse()?,
+            pending: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Sends the reports collected since the last digest, if any.
+    pub async fn send_digest(&self) {
+        let reports = std::mem::take(&mut *self.pending.lock().expect("email lock poisoned"));
+        if reports.is_empty() {
+            return;
+        }
+        let text = reports
+            .iter()
+            .map(|report| report.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let html = reports
+            .iter()
+            .map(|report| report.html.as_str())
+            .collect::<Vec<_>>()
+            .join("<hr>\n");
+        let subject = format!("stink-bid daily report: {} cycles", reports.len());
+        if let Err(e) = self.mail(&subject, text, html).await {
+            println!("WARNING: email digest failed: {}", e);
+        }
+    }
+
+    async fn mail(&self, subject: &str, text: String, html: String) -> Result<(), BoxError> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(subject)
+            .multipart(MultiPart::alternative_plain_html(text, html))?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn wants(&self, event: &Event) -> bool {
+        matches!(event, Event::Critical(_) | Event::Report(_))
+    }
+
+    fn deliver<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, Result<(), BoxError>> {
+        Box::pin(async move {
+            match event {
+                Event::Critical(message) => {
+                    let html = format!("<p>{}</p>", report::escape(message));
+                    self.mail("stink-bid CRITICAL", message.clone(), html).await
+                }
+                Event::Report(report) => {
+                    self.pending
+                        .lock()
+                        .expect("email lock poisoned")
+                        .push(report.clone());
+                    Ok(())
+                }
+                // only sent when routed here
+                Event::Notice(..) | Event::Fill { .. } => {
+                    let message = event.summary();
+                    let html = format!("<p>{}</p>", report::escape(message));
+                    let subject = format!("stink-bid {}", event.kind());
+                    self.mail(&subject, message.to_string(), html).await
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,103 @@
+use crate::bybit::BoxError;
+use crate::context::Context;
+use crate::state::State;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+pub const CSV_HEADER: &str = "time,strategy,equity,exposure,realized";
+
+/// Entry statuses that still tie up budget on the book.
+const RESTING: [&str; 3] = ["New", "PartiallyFilled", "Untriggered"];
+
+/// Account equity next to what the bot has at stake, taken each cycle.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EquitySnapshot {
+    pub at: DateTime<Utc>,
+    /// Strategy whose cycle took the snapshot.
+    pub strategy: String,
+    /// USD equity from the wallet balance.
+    pub equity: f64,
+    /// Resting entries at their limit price plus the ledger's cost basis.
+    pub exposure: f64,
+    /// Ledger PnL realized to date.
+    pub realized: f64,
+}
+
+impl EquitySnapshot {
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{:.4},{:.4},{:.4}",
+            self.at.to_rfc3339(),
+            self.strategy,
+            self.equity,
+            self.exposure,
+            self.realized
+        )
+    }
+}
+
+/// Takes a snapshot for `strategy`'s cycle, keeps it in the state and
+/// appends it to `equity_csv`.
+pub async fn record(ctx: &Context, strategy: &str) -> Result<EquitySnapshot, BoxError> {
+    let equity = ctx.account.equity(&ctx.client).await?;
+    let snapshot = ctx.store.read(|state| EquitySnapshot {
+        at: Utc::now(),
+        strategy: strategy.to_string(),
+        equity,
+        exposure: exposure(state),
+        realized: state
+            .ledger
+            .values()
+            .flat_map(|positions| positions.values())
+            .map(|position| position.realized)
+            .sum(),
+    });
+    ctx.store
+        .update(|state| state.equity.push(snapshot.clone()));
+    append_csv(&ctx.config.equity_csv, &snapshot)?;
+    Ok(snapshot)
+}
+
+fn exposure(state: &State) -> f64 {
+    let resting: f64 = state
+        .strategies
+        .values()
+        .flat_map(|strategy| &strategy.entries)
+        .filter(|entry| RESTING.contains(&entry.status.as_str()))
+        .map(|entry| {
+            entry.price.parse::<f64>().unwrap_or(0.0) * entry.qty.parse::<f64>().unwrap_or(0.0)
+        })
+        .sum();
+    let held: f64 = state
+        .ledger
+        .values()
+        .flat_map(|positions| positions.values())
+        .map(|position| position.cost)
+        .sum();
+    resting + held
+}
+
+fn append_csv(path: &str, snapshot: &EquitySnapshot) -> std::io::Result<()> {
+    let new = !Path::new(path).exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if new {
+        writeln!(file, "{}", CSV_HEADER)?;
+    }
+    writeln!(file, "{}", snapshot.to_csv())
+}
+
+/// The snapshots kept in `state` from `since` on, as CSV.
+pub fn export(state: &State, since: Option<NaiveDate>) -> String {
+    let mut csv = vec![CSV_HEADER.to_string()];
+    csv.extend(
+        state
+            .equity
+            .iter()
+            .filter(|snapshot| since.is_none_or(|since| snapshot.at.date_naive() >= since))
+            .map(EquitySnapshot::to_csv),
+    );
+    csv.join("\n")
+}
@@ -0,0 +1,171 @@
+use crate::bybit::{BatchOrderResponse, BoxError, OrderRequest};
+use crate::config::{Category, ExitConfig, ExitMode, StrategyConfig};
+use crate::context::Context;
+use crate::decimal::{self, Rounding};
+use crate::instruments::Instrument;
+use crate::state::StateOrder;
+use chrono::{Duration as ChronoDuration, Utc};
+
+/// An entry's execution, as exits are placed for it.
+pub struct Fill<'a> {
+    pub symbol: &'a str,
+    /// orderLinkId of the entry.
+    pub entry: &'a str,
+    pub average: f64,
+    pub executed: f64,
+    /// The entry's level and its percent below the anchor, which the
+    /// exits' realized PnL is booked to.
+    pub level: Option<usize>,
+    pub percent: Option<f64>,
+    /// Price the entry's ladder was anchored on.
+    pub anchor: Option<f64>,
+}
+
+/// Places the exits for `fill`, returning those that went out.
+pub async fn place_exits(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &ExitConfig,
+    fill: &Fill<'_>,
+) -> Result<Vec<BatchOrderResponse>, BoxError> {
+    let category = strategy.category_of(fill.symbol);
+    let instrument = ctx.instrument(category, fill.symbol)?;
+    let exits = match config.mode {
+        ExitMode::Ladder => plan_exits(strategy, config, &instrument, fill)?,
+        ExitMode::TpAtAnchor => plan_anchor_exit(strategy, &instrument, fill)?,
+    };
+    let placement = ctx.client.place_batch_order(category, &exits).await;
+    ctx.lifecycle
+        .placed(&strategy.name, category, &exits, &placement);
+    let placement = placement?;
+    ctx.note_rejections(strategy, &placement.rejected).await;
+    let placed = placement.placed;
+    let expires_at = config
+        .expiry
+        .and_then(|expiry| ChronoDuration::from_std(expiry).ok())
+        .map(|expiry| Utc::now() + expiry);
+    let mut tracked = StateOrder::placed(strategy, &exits, &placed);
+    for exit in &mut tracked {
+        exit.level = fill.level;
+        exit.level_percent = fill.percent;
+        exit.expires_at = expires_at;
+        exit.entry = Some(fill.entry.to_string());
+    }
+    ctx.store
+        .update_strategy(&strategy.name, |state| state.exits.extend(tracked));
+    Ok(placed)
+}
+
+/// Looks the placed exits up and returns the orderLinkIds of any the
+/// exchange didn't record as reduce-only. Spot has no reduce-only, so
+/// nothing is checked there.
+pub async fn unverified_reduce_only(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    placed: &[BatchOrderResponse],
+) -> Result<Vec<String>, BoxError> {
+    let mut unverified = Vec::new();
+    for exit in placed {
+        if strategy.category_of(&exit.symbol) != Category::Linear {
+            continue;
+        }
+        let order = ctx
+            .client
+            .get_order(Category::Linear, &exit.symbol, &exit.order_id, None)
+            .await?;
+        if !order.is_some_and(|order| order.reduce_only) {
+            unverified.push(exit.order_link_id.clone());
+        }
+    }
+    Ok(unverified)
+}
+
+/// Sells above `average`, splitting `executed` by the exit fractions.
+/// Rounding dust goes to the last exit. Linear exits are reduce-only; spot
+/// has no position to reduce.
+pub fn plan_exits(
+    strategy: &StrategyConfig,
+    config: &ExitConfig,
+    instrument: &Instrument,
+    fill: &Fill,
+) -> Result<Vec<OrderRequest>, BoxError> {
+    let step: f64 = instrument.qty_step.parse()?;
+    let floor = |qty: f64| decimal::round_to_step(qty, &instrument.qty_step, Rounding::Down);
+    let fractions = config.fractions();
+    let mut remaining = floor(fill.executed);
+    let mut exits = Vec::new();
+
+    for (index, (percent, fraction)) in config.levels.iter().zip(&fractions).enumerate() {
+        let qty = if index == config.levels.len() - 1 {
+            remaining
+        } else {
+            floor(fill.executed * fraction).min(remaining)
+        };
+        if qty < step {
+            continue;
+        }
+        remaining = floor(remaining - qty);
+        let price = fill.average * (1.0 + percent / 100.0);
+        exits.push(exit_order(
+            strategy,
+            instrument,
+            fill.symbol,
+            index,
+            price,
+            qty,
+        ));
+    }
+    Ok(exits)
+}
+
+/// Sells all of `executed` at the anchor, rounded up to the tick so the
+/// sell never sits below the open.
+fn plan_anchor_exit(
+    strategy: &StrategyConfig,
+    instrument: &Instrument,
+    fill: &Fill,
+) -> Result<Vec<OrderRequest>, BoxError> {
+    let anchor = fill
+        .anchor
+        .ok_or_else(|| format!("no anchor known for {}", fill.symbol))?;
+    let qty = decimal::round_to_step(fill.executed, &instrument.qty_step, Rounding::Down);
+    if qty < instrument.qty_step.parse()? {
+        return Ok(Vec::new());
+    }
+    Ok(vec![exit_order(
+        strategy,
+        instrument,
+        fill.symbol,
+        0,
+        anchor,
+        qty,
+    )])
+}
+
+/// Exit `index` as a limit sell of `qty` at `price`, rounded up to the tick.
+fn exit_order(
+    strategy: &StrategyConfig,
+    instrument: &Instrument,
+    symbol: &str,
+    index: usize,
+    price: f64,
+    qty: f64,
+) -> OrderRequest {
+    let link_id = format!(
+        "{}-{}-{}-x{}",
+        strategy.link_id_prefix,
+        symbol,
+        Utc::now().timestamp_millis(),
+        index
+    );
+    OrderRequest::builder(
+        symbol,
+        "Sell",
+        "Limit",
+        decimal::format_step(qty, &instrument.qty_step, Rounding::Down),
+        decimal::format_step(price, &instrument.tick_size, Rounding::Up),
+        link_id,
+    )
+    .reduce_only(strategy.category_of(symbol) == Category::Linear)
+    .build()
+}
@@ -0,0 +1,105 @@
+//! How long entries rest before they fill, per level depth.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// One placed level: when it went out and when it first filled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FillTime {
+    /// Percent below the open, e.g. "5%".
+    pub level: String,
+    /// orderLinkId of the level, without a child suffix.
+    pub order_link_id: String,
+    pub placed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filled_at: Option<DateTime<Utc>>,
+}
+
+impl FillTime {
+    pub fn time_to_fill(&self) -> Option<Duration> {
+        self.filled_at.map(|filled_at| filled_at - self.placed_at)
+    }
+}
+
+/// Time-to-fill of one level across its samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub placed: usize,
+    pub filled: usize,
+    pub median: Option<Duration>,
+    pub p90: Option<Duration>,
+    /// Share of the levels placed at least an hour before `now` that
+    /// filled within that hour.
+    pub first_hour: Option<f64>,
+}
+
+/// The distribution of each level in `samples`, as of `now`.
+pub fn by_level(samples: &[FillTime], now: DateTime<Utc>) -> BTreeMap<String, Distribution> {
+    let mut levels: BTreeMap<String, Vec<&FillTime>> = BTreeMap::new();
+    for sample in samples {
+        levels.entry(sample.level.clone()).or_default().push(sample);
+    }
+    levels
+        .into_iter()
+        .map(|(level, samples)| (level, distribution(&samples, now)))
+        .collect()
+}
+
+pub fn distribution(samples: &[&FillTime], now: DateTime<Utc>) -> Distribution {
+    let hour = Duration::hours(1);
+    let mut times: Vec<Duration> = samples
+        .iter()
+        .filter_map(|sample| sample.time_to_fill())
+        .collect();
+    times.sort();
+    // a level placed within the hour may still fill inside it
+    let settled: Vec<&&FillTime> = samples
+        .iter()
+        .filter(|sample| now - sample.placed_at >= hour)
+        .collect();
+    let early = settled
+        .iter()
+        .filter(|sample| sample.time_to_fill().is_some_and(|time| time <= hour))
+        .count();
+    Distribution {
+        placed: samples.len(),
+        filled: times.len(),
+        median: percentile(&times, 50.0),
+        p90: percentile(&times, 90.0),
+        first_hour: (!settled.is_empty()).then(|| early as f64 / settled.len() as f64),
+    }
+}
+
+/// Nearest-rank percentile of `sorted`.
+pub fn percentile(sorted: &[Duration], percent: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "filled {}/{}", self.filled, self.placed)?;
+        if let (Some(median), Some(p90)) = (self.median, self.p90) {
+            write!(f, ", median {}, p90 {}", span(median), span(p90))?;
+        }
+        if let Some(first_hour) = self.first_hour {
+            write!(f, ", {:.0}% in the first hour", first_hour * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// A duration in its largest whole unit and the next, e.g. "2h 15m".
+fn span(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    match minutes {
+        m if m < 60 => format!("{}m", m),
+        m if m < 24 * 60 => format!("{}h {}m", m / 60, m % 60),
+        m => format!("{}d {}h", m / (24 * 60), m % (24 * 60) / 60),
+    }
+}
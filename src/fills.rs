@@ -0,0 +1,234 @@
+use crate::bybit::{BoxError, CancelOrderData, Order};
+use crate::cascade;
+use crate::config::StrategyConfig;
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::exits::{self, Fill};
+use crate::ladder;
+use crate::ledger;
+use crate::lifecycle::OrderEvent;
+use crate::notify;
+use crate::stops;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashSet};
+
+/// Looks for tracked entries that stopped resting and hands whatever they
+/// executed to the exit ladder and trailing stop.
+pub async fn check_fills(ctx: &Context, strategy: &StrategyConfig, cycle: &mut Cycle) {
+    let symbols: BTreeSet<String> = cycle
+        .tracked
+        .iter()
+        .map(|order| order.symbol.clone())
+        .collect();
+    for symbol in symbols {
+        if let Err(e) = check_symbol(ctx, strategy, &symbol, cycle).await {
+            println!(
+                "[{}] fill check for {} failed: {}",
+                strategy.name, symbol, e
+            );
+        }
+    }
+    ledger::check_exits(ctx, strategy, cycle).await;
+}
+
+async fn check_symbol(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+    cycle: &mut Cycle,
+) -> Result<(), BoxError> {
+    let category = strategy.category_of(symbol);
+    let filter = strategy.filter_in(category);
+    let open_orders = ctx.client.get_open_orders(category, symbol, filter).await?;
+    if strategy.has_conditionals() {
+        note_triggered(ctx, strategy, cycle, &open_orders);
+    }
+    for order in open_orders.iter().filter(|order| {
+        cycle
+            .tracked
+            .iter()
+            .any(|tracked| tracked.order_id == order.order_id)
+    }) {
+        if let Some(event) = OrderEvent::observed(&strategy.name, category, order) {
+            ctx.lifecycle.transition(event);
+        }
+    }
+    let open_ids: HashSet<String> = open_orders
+        .into_iter()
+        .map(|order| order.order_id)
+        .collect();
+    let stopped: Vec<CancelOrderData> = cycle
+        .tracked
+        .iter()
+        .filter(|order| order.symbol == symbol && !open_ids.contains(&order.order_id))
+        .cloned()
+        .collect();
+
+    for entry in stopped {
+        let Some(order) = ctx
+            .client
+            .get_order(category, symbol, &entry.order_id, filter)
+            .await?
+        else {
+            continue;
+        };
+        if let Some(event) = OrderEvent::observed(&strategy.name, category, &order) {
+            ctx.lifecycle.transition(event);
+        }
+        cycle
+            .tracked
+            .retain(|tracked| tracked.order_id != entry.order_id);
+        ctx.store.update_strategy(&strategy.name, |state| {
+            if let Some(tracked) = state
+                .entries
+                .iter_mut()
+                .find(|tracked| tracked.order_id == order.order_id)
+            {
+                tracked.status = order.order_status.clone();
+            }
+        });
+
+        let executed: f64 = order.cum_exec_qty.parse().unwrap_or(0.0);
+        if executed <= 0.0 {
+            continue;
+        }
+        let average: f64 = order.avg_price.parse()?;
+        let placed = ctx.store.entry(&strategy.name, &order.order_id);
+        let level = placed
+            .as_ref()
+            .and_then(|placed| placed.level)
+            .or_else(|| ladder::level_from_link_id(&order.order_link_id));
+        let percent = placed
+            .as_ref()
+            .and_then(|placed| placed.level_percent)
+            .map(|percent| format!("{}%", percent))
+            .or_else(|| ladder::level_percent(strategy, &order.order_link_id));
+        if let Some(percent) = &percent {
+            ctx.store.update_strategy(&strategy.name, |state| {
+                // a split level counts as filled once, whichever child is first
+                let parent = ladder::parent_link_id(&order.order_link_id);
+                if state.entries.iter().any(|entry| {
+                    entry.fill_counted && ladder::parent_link_id(&entry.order_link_id) == parent
+                }) {
+                    return;
+                }
+                if let Some(entry) = state
+                    .entries
+                    .iter_mut()
+                    .find(|entry| entry.order_id == order.order_id)
+                {
+                    entry.fill_counted = true;
+                }
+                *state
+                    .counters
+                    .fills_by_level
+                    .entry(percent.clone())
+                    .or_default() += 1;
+                let filled_at = order
+                    .updated_time
+                    .parse()
+                    .ok()
+                    .and_then(DateTime::from_timestamp_millis)
+                    .unwrap_or_else(Utc::now);
+                state.record_fill(percent, parent, filled_at);
+            });
+            cycle
+                .report
+                .note_fill(symbol, format!("{} {} @ {}", percent, executed, average));
+        }
+        let filled_percent = placed
+            .as_ref()
+            .and_then(|placed| placed.level_percent)
+            .or_else(|| level.and_then(|level| strategy.levels.get(level).copied()));
+        let label = placed
+            .map(|placed| placed.label())
+            .unwrap_or_else(|| order.order_link_id.clone());
+        let mut event = format!("{} {} filled {} @ {}", symbol, label, executed, average);
+        if let Some(config) = &strategy.exits {
+            let fill = Fill {
+                symbol,
+                entry: &order.order_link_id,
+                average,
+                executed,
+                level,
+                percent: filled_percent,
+                anchor: cycle.anchor(symbol),
+            };
+            match exits::place_exits(ctx, strategy, config, &fill).await {
+                Ok(placed) => {
+                    event.push_str(&format!(", {} exits placed", placed.len()));
+                    match exits::unverified_reduce_only(ctx, strategy, &placed).await {
+                        Ok(ids) if ids.is_empty() => {}
+                        Ok(ids) => event.push_str(&format!(
+                            ", ALERT exits not reduce-only on the exchange: {}",
+                            ids.join(", ")
+                        )),
+                        Err(e) => {
+                            event.push_str(&format!(", ALERT reduce-only check failed: {}", e))
+                        }
+                    }
+                }
+                Err(e) => event.push_str(&format!(", exits failed: {}", e)),
+            }
+        }
+        if let Some(config) = &strategy.trailing_stop {
+            match stops::attach_trailing_stop(ctx, strategy, config, symbol, average).await {
+                Ok(distance) => event.push_str(&format!(", trailing stop {}", distance)),
+                Err(e) => event.push_str(&format!(", trailing stop failed: {}", e)),
+            }
+        }
+        if let (Some(config), Some(percent)) = (&strategy.cascade, filled_percent) {
+            match cascade::place(ctx, strategy, config, cycle, &order, percent).await {
+                Ok(done) => event.push_str(&format!(", {}", done)),
+                Err(e) => event.push_str(&format!(", cascade failed: {}", e)),
+            }
+        }
+        info!("[{}] {}", strategy.name, event);
+        notify::fill(&format!("[{}] {}", strategy.name, event), level);
+        cycle.report.events.push(event);
+    }
+    Ok(())
+}
+
+/// Reports conditional entries that have triggered and are now working as
+/// limits. Untriggered ones are still just watching the price.
+fn note_triggered(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    cycle: &mut Cycle,
+    open_orders: &[Order],
+) {
+    for order in open_orders {
+        let tracked = cycle
+            .tracked
+            .iter()
+            .any(|tracked| tracked.order_id == order.order_id);
+        if !tracked
+            || order.order_status == "Untriggered"
+            || cycle.triggered.contains(&order.order_id)
+        {
+            continue;
+        }
+        cycle.triggered.insert(order.order_id.clone());
+        ctx.store.update_strategy(&strategy.name, |state| {
+            if let Some(entry) = state
+                .entries
+                .iter_mut()
+                .find(|entry| entry.order_id == order.order_id)
+            {
+                entry.status = order.order_status.clone();
+            }
+        });
+        let label = ctx
+            .store
+            .entry(&strategy.name, &order.order_id)
+            .map(|entry| entry.label())
+            .unwrap_or_else(|| order.order_link_id.clone());
+        let event = format!(
+            "{} {} triggered, limit working @ {}",
+            order.symbol, label, order.price
+        );
+        info!("[{}] {}", strategy.name, event);
+        cycle.report.events.push(event);
+    }
+}
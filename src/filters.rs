@@ -0,0 +1,232 @@
+use crate::bybit::BoxError;
+use crate::config::{
+    Category, MarginAction, MarginCheckConfig, OpenInterestAction, OpenInterestConfig,
+    StrategyConfig,
+};
+use crate::context::Context;
+use crate::margin::{self, MarginFit};
+use std::fmt;
+
+/// What a pre-placement filter decided for a symbol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Place,
+    /// Place with the budgets scaled by this share.
+    Downsize(f64),
+    Skip,
+}
+
+/// The open interest change a decision was made on, for the log and the
+/// report.
+pub struct OpenInterestCheck {
+    pub change_percent: f64,
+    pub lookback_hours: u32,
+    pub threshold_percent: f64,
+    pub decision: Decision,
+}
+
+impl fmt::Display for OpenInterestCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "open interest {:+.1}% over {}h, threshold {}%: ",
+            self.change_percent, self.lookback_hours, self.threshold_percent
+        )?;
+        match self.decision {
+            Decision::Place => write!(f, "placed"),
+            Decision::Downsize(share) => write!(f, "downsized to {}%", share * 100.0),
+            Decision::Skip => write!(f, "skipped"),
+        }
+    }
+}
+
+/// Measures `symbol`'s open interest change over the lookback and decides
+/// whether to place its ladder in full, smaller or not at all.
+pub async fn open_interest(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &OpenInterestConfig,
+    symbol: &str,
+) -> Result<OpenInterestCheck, BoxError> {
+    let history = ctx
+        .client
+        .get_open_interest(strategy.category_of(symbol), symbol, config.lookback_hours)
+        .await?;
+    let (Some(newest), Some(oldest)) = (history.first(), history.last()) else {
+        return Err(format!("no open interest history for {}", symbol).into());
+    };
+    let newest: f64 = newest.open_interest.parse()?;
+    let oldest: f64 = oldest.open_interest.parse()?;
+    if oldest <= 0.0 {
+        return Err(format!(
+            "{} had no open interest {}h ago",
+            symbol, config.lookback_hours
+        )
+        .into());
+    }
+    let change_percent = (newest - oldest) / oldest * 100.0;
+    let decision = if change_percent <= config.max_change_percent {
+        Decision::Place
+    } else {
+        match config.action {
+            OpenInterestAction::Skip => Decision::Skip,
+            OpenInterestAction::Downsize => Decision::Downsize(config.downsize_to),
+        }
+    };
+    Ok(OpenInterestCheck {
+        change_percent,
+        lookback_hours: config.lookback_hours,
+        threshold_percent: config.max_change_percent,
+        decision,
+    })
+}
+
+/// The position a `target_position` ladder was sized against, for the log
+/// and the report.
+pub struct HeadroomCheck {
+    /// Held now, valued at the open.
+    pub position: f64,
+    pub target: f64,
+    pub headroom: f64,
+    pub decision: Decision,
+}
+
+impl fmt::Display for HeadroomCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "position {:.2}, target {:.2}, headroom {:.2}: ",
+            self.position, self.target, self.headroom
+        )?;
+        match self.decision {
+            Decision::Place => write!(f, "placed in full"),
+            Decision::Downsize(_) => write!(f, "ladder sized to the headroom"),
+            Decision::Skip => write!(f, "skipped, target already met"),
+        }
+    }
+}
+
+/// Values what's already held of `symbol` at `open` and sizes the ladder
+/// to fill only the headroom up to `target`.
+pub async fn position_headroom(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    target: f64,
+    symbol: &str,
+    open: f64,
+) -> Result<HeadroomCheck, BoxError> {
+    let position = held_qty(ctx, strategy, symbol).await? * open;
+    let headroom = (target - position).max(0.0);
+    let budget: f64 = strategy.budgets.iter().sum();
+    let decision = if headroom <= 0.0 {
+        Decision::Skip
+    } else if headroom == budget {
+        Decision::Place
+    } else {
+        Decision::Downsize(headroom / budget)
+    };
+    Ok(HeadroomCheck {
+        position,
+        target,
+        headroom,
+        decision,
+    })
+}
+
+/// Long position on a perp, base coin balance on spot.
+async fn held_qty(ctx: &Context, strategy: &StrategyConfig, symbol: &str) -> Result<f64, BoxError> {
+    let category = strategy.category_of(symbol);
+    if category == Category::Linear {
+        let mut held = 0.0;
+        for position in ctx.client.get_positions(symbol).await? {
+            if position.side == "Buy" {
+                held += position.size.parse::<f64>()?;
+            }
+        }
+        return Ok(held);
+    }
+    let instrument = ctx.instrument_or_refresh(category, symbol).await?;
+    let base = symbol
+        .strip_suffix(instrument.quote_coin.as_str())
+        .ok_or_else(|| format!("{} doesn't end in {}", symbol, instrument.quote_coin))?;
+    let wallet = ctx.account.wallet(category);
+    let mut held = 0.0;
+    for account in ctx.client.get_wallet_balance(wallet, base).await? {
+        for coin in account.coin.iter().filter(|coin| coin.coin == base) {
+            held += coin.wallet_balance.parse::<f64>().unwrap_or(0.0);
+        }
+    }
+    Ok(held)
+}
+
+/// The margin math a ladder was placed on, for the log and the report.
+pub struct MarginCheck {
+    pub notional: f64,
+    pub leverage: f64,
+    pub available: f64,
+    pub buffer_percent: f64,
+    pub fit: MarginFit,
+    pub decision: Decision,
+}
+
+impl fmt::Display for MarginCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "margin {:.2} / {}x = {:.2}, available {:.2} less {}% = {:.2}: ",
+            self.notional,
+            self.leverage,
+            self.fit.required,
+            self.available,
+            self.buffer_percent,
+            self.fit.usable
+        )?;
+        match self.decision {
+            Decision::Place => write!(f, "placed"),
+            Decision::Downsize(share) => write!(f, "downsized to {:.1}%", share * 100.0),
+            Decision::Skip => write!(f, "refused"),
+        }
+    }
+}
+
+/// Compares the initial margin of `strategy`'s whole ladder on `symbol`
+/// filling with the balance it's placed from, and decides whether it
+/// goes in full, smaller or not at all.
+pub async fn margin(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &MarginCheckConfig,
+    symbol: &str,
+) -> Result<MarginCheck, BoxError> {
+    let category = strategy.category_of(symbol);
+    let settings = &ctx.config.symbols[symbol];
+    let coin = settings
+        .budget_currency(symbol)
+        .ok_or_else(|| format!("{} has no budget currency", symbol))?;
+    let instrument = ctx.instrument_or_refresh(category, symbol).await?;
+    let leverage = match category {
+        Category::Linear => margin::effective_leverage(settings.leverage, instrument.max_leverage),
+        Category::Spot => 1.0,
+    };
+    let available = ctx
+        .account
+        .available_balance(&ctx.client, category, &coin)
+        .await?;
+    let notional: f64 = strategy.budgets.iter().sum();
+    let fit = margin::fit(notional, leverage, available, config.buffer_percent);
+    let decision = if fit.share >= 1.0 {
+        Decision::Place
+    } else if fit.share <= 0.0 || config.action == MarginAction::Refuse {
+        Decision::Skip
+    } else {
+        Decision::Downsize(fit.share)
+    };
+    Ok(MarginCheck {
+        notional,
+        leverage,
+        available,
+        buffer_percent: config.buffer_percent,
+        fit,
+        decision,
+    })
+}
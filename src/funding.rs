@@ -0,0 +1,127 @@
+use crate::account::AccountType;
+use crate::bybit::Transaction;
+use crate::config::Category;
+use crate::context::Context;
+use chrono::{DateTime, Duration as ChronoDuration, TimeZone, Utc};
+
+/// How often the funding log is read. Funding settles every 8 hours on
+/// most perps.
+pub const POLL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+/// Longest window the transaction log takes in one query.
+const MAX_WINDOW_DAYS: i64 = 7;
+/// Read again on each poll, so a settlement logged late isn't missed.
+const OVERLAP_HOURS: i64 = 2;
+
+/// Books funding settled on linear symbols the bot holds a position in.
+/// Each payment counts once, by transaction id, in proportion to the
+/// bot's share of the position it was settled on.
+pub async fn poll(ctx: &Context) {
+    if ctx.account != AccountType::Unified {
+        // classic accounts log funding elsewhere
+        return;
+    }
+    let now = Utc::now();
+    let oldest = now - ChronoDuration::days(MAX_WINDOW_DAYS);
+    let (held, start) = ctx.store.read(|state| {
+        let held: Vec<(String, f64)> = state
+            .ledger
+            .get(&Category::Linear)
+            .into_iter()
+            .flatten()
+            .filter(|(_, position)| position.qty > 0.0)
+            .map(|(symbol, position)| (symbol.clone(), position.qty))
+            .collect();
+        let start = state
+            .funding_polled_at
+            .map_or(oldest, |at| at - ChronoDuration::hours(OVERLAP_HOURS))
+            .max(oldest);
+        (held, start)
+    });
+    for (symbol, qty) in held {
+        match ctx.client.get_funding_log(&symbol, start, now).await {
+            Ok(transactions) => book(ctx, &symbol, qty, &transactions),
+            Err(e) => println!("WARNING: funding log of {} not read: {}", symbol, e),
+        }
+    }
+    ctx.store.update(|state| {
+        state.funding_polled_at = Some(now);
+        state.funding_seen.retain(|_, at| *at >= oldest);
+    });
+}
+
+fn book(ctx: &Context, symbol: &str, qty: f64, transactions: &[Transaction]) {
+    let mut paid = 0.0;
+    let mut received = 0.0;
+    ctx.store.update(|state| {
+        for transaction in transactions {
+            if state.funding_seen.contains_key(&transaction.id) {
+                continue;
+            }
+            let funding: f64 = transaction.funding.parse().unwrap_or(0.0);
+            let size: f64 = transaction.size.parse().unwrap_or(0.0);
+            // the rest of the position isn't the bot's
+            let share = if size > 0.0 {
+                (qty / size).min(1.0)
+            } else {
+                1.0
+            };
+            if funding > 0.0 {
+                paid += funding * share;
+            } else {
+                received -= funding * share;
+            }
+            state
+                .funding_seen
+                .insert(transaction.id.clone(), settled_at(transaction));
+        }
+        let position = state
+            .ledger
+            .entry(Category::Linear)
+            .or_default()
+            .entry(symbol.to_string())
+            .or_default();
+        position.funding_paid += paid;
+        position.funding_received += received;
+    });
+    if paid > 0.0 || received > 0.0 {
+        info!(
+            "{} funding paid {:.4}, received {:.4}",
+            symbol, paid, received
+        );
+    }
+}
+
+fn settled_at(transaction: &Transaction) -> DateTime<Utc> {
+    transaction
+        .transaction_time
+        .parse()
+        .ok()
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+        .unwrap_or_else(Utc::now)
+}
+
+/// Funding paid and received over every ledger position so far, for the
+/// weekly summary.
+pub fn summary(ctx: &Context) -> Option<String> {
+    let (paid, received) = ctx.store.read(|state| {
+        state
+            .ledger
+            .values()
+            .flat_map(|positions| positions.values())
+            .fold((0.0, 0.0), |(paid, received), position| {
+                (
+                    paid + position.funding_paid,
+                    received + position.funding_received,
+                )
+            })
+    });
+    if paid == 0.0 && received == 0.0 {
+        return None;
+    }
+    Some(format!(
+        "funding on the bot's positions so far: paid {:.4}, received {:.4}, net {:+.4}",
+        paid,
+        received,
+        received - paid
+    ))
+}
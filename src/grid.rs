@@ -0,0 +1,61 @@
+//! Grid ladders: many small levels spread between two discounts, in place
+//! of a few large ones written out by hand.
+
+use serde::Deserialize;
+
+/// How the levels are spread between the top and bottom discounts.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GridSpacing {
+    /// The same number of percent between neighbours.
+    #[default]
+    Even,
+    /// The same ratio between neighbours, so levels bunch up near the top.
+    Geometric,
+}
+
+/// How the grid's budget is split across its levels.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GridAllocation {
+    #[default]
+    Equal,
+    /// Grows with depth: the deepest level gets `levels` times the top one.
+    Linear,
+}
+
+/// Percent below the anchor of each of `count` levels from `top` down to
+/// `bottom`, both included, rounded to hundredths like generated spacing.
+pub fn levels(top: f64, bottom: f64, count: usize, spacing: GridSpacing) -> Vec<f64> {
+    if count <= 1 {
+        return vec![round(top)];
+    }
+    let last = (count - 1) as f64;
+    (0..count)
+        .map(|i| {
+            let share = i as f64 / last;
+            match spacing {
+                GridSpacing::Even => top + (bottom - top) * share,
+                GridSpacing::Geometric => top * (bottom / top).powf(share),
+            }
+        })
+        .map(round)
+        .collect()
+}
+
+/// `budget` split across `count` levels, shallowest first.
+pub fn allocations(budget: f64, count: usize, allocation: GridAllocation) -> Vec<f64> {
+    let weights: Vec<f64> = match allocation {
+        GridAllocation::Equal => vec![1.0; count],
+        GridAllocation::Linear => (1..=count).map(|weight| weight as f64).collect(),
+    };
+    let total: f64 = weights.iter().sum();
+    weights
+        .iter()
+        .map(|weight| budget * weight / total)
+        .collect()
+}
+
+fn round(percent: f64) -> f64 {
+    (percent * 100.0).round() / 100.0
+}
@@ -0,0 +1,137 @@
+//! Bounds no planned order or cycle should come near. Breaching one means
+//! an input is wrong, a budget typo or a misread price, so the whole cycle
+//! is held back rather than the one order.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// `[guards]`, each off when unset.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Guards {
+    /// Most quote value one order may have.
+    pub max_order_notional: Option<f64>,
+    /// Most qty one order may have, as a multiple of the symbol's 24h
+    /// volume, e.g. 0.01 for 1% of it.
+    pub max_volume_multiple: Option<f64>,
+    /// Most quote value one strategy's cycle may place, over every symbol.
+    pub max_cycle_notional: Option<f64>,
+}
+
+impl Guards {
+    pub fn is_empty(&self) -> bool {
+        self.max_order_notional.is_none()
+            && self.max_volume_multiple.is_none()
+            && self.max_cycle_notional.is_none()
+    }
+}
+
+/// An order as the guards see it.
+#[derive(Debug, Clone, Copy)]
+pub struct Order<'a> {
+    pub symbol: &'a str,
+    /// In the base coin.
+    pub qty: f64,
+    /// In the quote coin.
+    pub notional: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breach {
+    Order {
+        symbol: String,
+        notional: f64,
+        max: f64,
+    },
+    Volume {
+        symbol: String,
+        qty: f64,
+        volume: f64,
+        multiple: f64,
+    },
+    Cycle {
+        total: f64,
+        max: f64,
+    },
+}
+
+impl fmt::Display for Breach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breach::Order {
+                symbol,
+                notional,
+                max,
+            } => write!(
+                f,
+                "{} order of {:.2} is over max_order_notional {}",
+                symbol, notional, max
+            ),
+            Breach::Volume {
+                symbol,
+                qty,
+                volume,
+                multiple,
+            } => write!(
+                f,
+                "{} order qty {} is over {} times its 24h volume of {}",
+                symbol, qty, multiple, volume
+            ),
+            Breach::Cycle { total, max } => write!(
+                f,
+                "cycle total of {:.2} is over max_cycle_notional {}",
+                total, max
+            ),
+        }
+    }
+}
+
+/// Every guard `orders` breach. `volumes` holds each symbol's 24h volume,
+/// in the same unit as the qty; a symbol without one isn't held to
+/// `max_volume_multiple`. `resting` is what the strategy already placed
+/// this cycle, on symbols `orders` don't cover, and counts toward
+/// `max_cycle_notional`.
+pub fn check(
+    guards: &Guards,
+    orders: &[Order],
+    volumes: &BTreeMap<&str, f64>,
+    resting: f64,
+) -> Vec<Breach> {
+    let mut breaches = Vec::new();
+    for order in orders {
+        if let Some(max) = guards.max_order_notional {
+            if over(order.notional, max) {
+                breaches.push(Breach::Order {
+                    symbol: order.symbol.to_string(),
+                    notional: order.notional,
+                    max,
+                });
+            }
+        }
+        if let (Some(multiple), Some(volume)) =
+            (guards.max_volume_multiple, volumes.get(order.symbol))
+        {
+            if over(order.qty, volume * multiple) {
+                breaches.push(Breach::Volume {
+                    symbol: order.symbol.to_string(),
+                    qty: order.qty,
+                    volume: *volume,
+                    multiple,
+                });
+            }
+        }
+    }
+    if let Some(max) = guards.max_cycle_notional {
+        let total = resting + orders.iter().map(|order| order.notional).sum::<f64>();
+        if over(total, max) {
+            breaches.push(Breach::Cycle { total, max });
+        }
+    }
+    breaches
+}
+
+/// A misread price makes NaN, which is over any bound.
+fn over(value: f64, max: f64) -> bool {
+    value.is_nan() || value > max
+}
@@ -0,0 +1,53 @@
+use crate::config::StrategyConfig;
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::fills;
+use crate::reanchor;
+use crate::schedule;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use std::collections::HashMap;
+
+fn after_minutes(minutes: u64) -> DateTime<Utc> {
+    Utc::now() + ChronoDuration::minutes(minutes as i64)
+}
+
+/// Holds the cycle's orders until `cancel_at`, running the periodic
+/// re-anchor and fill checks the strategy has configured.
+pub async fn hold(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    cycle: &mut Cycle,
+    cancel_at: DateTime<Utc>,
+) {
+    let mut next_reanchor = strategy
+        .reanchor
+        .as_ref()
+        .map(|config| after_minutes(config.check_minutes));
+    let mut next_fill_check = strategy
+        .watches_fills()
+        .then(|| after_minutes(strategy.fill_check_minutes));
+    let mut reanchor_counts: HashMap<String, (NaiveDate, u32)> = HashMap::new();
+
+    loop {
+        let next = [next_reanchor, next_fill_check].into_iter().flatten().min();
+        match next {
+            Some(next) if next < cancel_at => schedule::sleep_until(next).await,
+            _ => {
+                schedule::sleep_until(cancel_at).await;
+                return;
+            }
+        }
+
+        let now = Utc::now();
+        if let (Some(config), Some(due)) = (&strategy.reanchor, next_reanchor) {
+            if due <= now {
+                reanchor::check(ctx, strategy, config, cycle, &mut reanchor_counts).await;
+                next_reanchor = Some(after_minutes(config.check_minutes));
+            }
+        }
+        if next_fill_check.is_some_and(|due| due <= now) {
+            fills::check_fills(ctx, strategy, cycle).await;
+            next_fill_check = Some(after_minutes(strategy.fill_check_minutes));
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::bybit::{BoxError, BybitClient, InstrumentInfo};
+use crate::config::{Category, Config};
+use crate::scrub::Scrubbed;
+use crate::sizing::Rules;
+use crate::storage;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The parts of a symbol's trading rules the ladders are sized against.
+#[derive(Debug, Clone)]
+pub struct Instrument {
+    pub quote_coin: String,
+    /// Price increment as listed, e.g. "0.05".
+    pub tick_size: String,
+    /// Qty increment as listed, e.g. "10".
+    pub qty_step: String,
+    /// Smallest order value the exchange accepts, in the quote coin.
+    pub min_notional: f64,
+    pub min_qty: f64,
+    /// Largest limit order qty, when the exchange lists one.
+    pub max_qty: Option<f64>,
+    /// Largest order value in the quote coin, spot only.
+    pub max_notional: Option<f64>,
+    /// Highest leverage allowed, perps only.
+    pub max_leverage: Option<f64>,
+}
+
+impl Instrument {
+    pub fn from_info(info: InstrumentInfo) -> Result<Instrument, BoxError> {
+        let lot = info.lot_size_filter;
+        let qty_step = lot
+            .qty_step
+            .or(lot.base_precision)
+            .ok_or_else(|| format!("{} has no qty step", info.symbol))?;
+        let min_notional = match lot.min_notional_value.or(lot.min_order_amt) {
+            Some(value) => value.parse()?,
+            None => 0.0,
+        };
+        let min_qty = match lot.min_order_qty {
+            Some(value) => value.parse()?,
+            None => 0.0,
+        };
+        let max_qty = lot.max_order_qty.map(|value| value.parse()).transpose()?;
+        let max_notional = lot.max_order_amt.map(|value| value.parse()).transpose()?;
+        let max_leverage = info
+            .leverage_filter
+            .map(|filter| filter.max_leverage.parse())
+            .transpose()?;
+        for (name, step) in [
+            ("tickSize", &info.price_filter.tick_size),
+            ("qty step", &qty_step),
+        ] {
+            if !step.parse::<f64>().is_ok_and(|step| step > 0.0) {
+                return Err(format!("{} has an unusable {} {:?}", info.symbol, name, step).into());
+            }
+        }
+        Ok(Instrument {
+            quote_coin: info.quote_coin,
+            tick_size: info.price_filter.tick_size,
+            qty_step,
+            min_notional,
+            min_qty,
+            max_qty,
+            max_notional,
+            max_leverage,
+        })
+    }
+
+    /// What a ladder is sized against.
+    pub fn rules(&self) -> Rules<'_> {
+        Rules {
+            quote_coin: &self.quote_coin,
+            tick_size: &self.tick_size,
+            qty_step: &self.qty_step,
+            min_notional: self.min_notional,
+            min_qty: self.min_qty,
+            max_qty: self.max_qty,
+            max_notional: self.max_notional,
+        }
+    }
+}
+
+/// Everything listed in `category`, by symbol.
+pub async fn listed(
+    client: &BybitClient,
+    category: Category,
+) -> Result<BTreeMap<String, InstrumentInfo>, BoxError> {
+    Ok(client
+        .list_instruments(category)
+        .await?
+        .into_iter()
+        .map(|info| (info.symbol.clone(), info))
+        .collect())
+}
+
+/// Schema version of the instruments cache.
+pub const CACHE_VERSION: u32 = 1;
+
+/// The last good listing of every traded category, kept beside the state
+/// file so the bot can start and size orders while instruments-info is
+/// down.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Cache {
+    /// `CACHE_VERSION` when written.
+    #[serde(default)]
+    pub version: u32,
+    pub fetched_at: DateTime<Utc>,
+    pub listed: BTreeMap<Category, Vec<InstrumentInfo>>,
+}
+
+impl Cache {
+    pub fn path(config: &Config) -> PathBuf {
+        Path::new(&config.state_path).with_file_name("instruments.json")
+    }
+
+    /// Reads the cache, refusing one written by a newer release.
+    pub fn load(config: &Config) -> Result<Cache, BoxError> {
+        let cache: Cache = serde_json::from_str(&fs::read_to_string(Cache::path(config))?)?;
+        storage::check_version(cache.version, CACHE_VERSION)?;
+        Ok(cache)
+    }
+
+    pub fn save(&self, config: &Config) {
+        let path = Cache::path(config);
+        let written = serde_json::to_string(self)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                storage::write_atomic(&path, json.as_bytes()).map_err(|e| e.to_string())
+            });
+        if let Err(e) = written {
+            println!("failed writing instruments {}: {}", path.display(), e);
+        }
+    }
+
+    /// Everything listed in `category`, by symbol.
+    pub fn in_category(&self, category: Category) -> BTreeMap<String, InstrumentInfo> {
+        self.listed
+            .get(&category)
+            .into_iter()
+            .flatten()
+            .map(|info| (info.symbol.clone(), info.clone()))
+            .collect()
+    }
+
+    fn covers(&self, config: &Config) -> bool {
+        config
+            .traded_categories()
+            .keys()
+            .all(|category| self.listed.contains_key(category))
+    }
+
+    fn age(&self) -> Duration {
+        Utc::now() - self.fetched_at
+    }
+
+    /// E.g. "fetched 2026-10-14 09:00 UTC, 26h ago".
+    pub fn describe(&self) -> String {
+        format!(
+            "fetched {}, {}h ago",
+            self.fetched_at.format("%Y-%m-%d %H:%M UTC"),
+            self.age().num_hours()
+        )
+    }
+
+    /// Warns when the rules are older than `instrument_cache_max_age_hours`,
+    /// as a tick or step change since would get orders rejected.
+    pub fn warn_if_stale(&self, config: &Config) {
+        if self.age() > Duration::hours(config.instrument_cache_max_age_hours as i64) {
+            println!(
+                "warning: instrument rules were {}, over the {}h instrument_cache_max_age_hours",
+                self.describe(),
+                config.instrument_cache_max_age_hours
+            );
+        }
+    }
+}
+
+/// Fetches every category `config` trades in and caches the listing.
+pub async fn fetch(client: &BybitClient, config: &Config) -> Result<Cache, BoxError> {
+    let mut listed = BTreeMap::new();
+    for category in config.traded_categories().into_keys() {
+        let infos = self::listed(client, category).await?;
+        listed.insert(category, infos.into_values().collect());
+    }
+    let cache = Cache {
+        version: CACHE_VERSION,
+        fetched_at: Utc::now(),
+        listed,
+    };
+    cache.save(config);
+    Ok(cache)
+}
+
+/// The listing preflight resolves symbols against: the cache while it's
+/// younger than `instrument_ttl_hours`, unless `refresh`, otherwise a live
+/// fetch. When that fails the cache is used however old it is.
+pub async fn load(client: &BybitClient, config: &Config, refresh: bool) -> Result<Cache, BoxError> {
+    let cached = match Cache::load(config) {
+        Ok(cache) if cache.covers(config) => Some(cache),
+        Ok(_) => None,
+        Err(e) if e.is::<serde_json::Error>() => {
+            let path = Cache::path(config);
+            match storage::quarantine(&path, Utc::now()) {
+                Ok(aside) => println!(
+                    "WARNING: instruments cache {} is corrupt ({}), moved it to {}",
+                    path.display(),
+                    e,
+                    aside.display()
+                ),
+                Err(moved) => println!(
+                    "WARNING: instruments cache {} is corrupt ({}) and couldn't be moved aside: {}",
+                    path.display(),
+                    e,
+                    moved
+                ),
+            }
+            None
+        }
+        Err(e) => {
+            if Cache::path(config).exists() {
+                println!("warning: ignoring instruments cache: {}", e);
+            }
+            None
+        }
+    };
+    let ttl = Duration::hours(config.instrument_ttl_hours as i64);
+    let cached = match cached {
+        Some(cache) if !refresh && cache.age() < ttl => {
+            info!("using cached instrument rules, {}", cache.describe());
+            return Ok(cache);
+        }
+        cached => cached,
+    };
+    let e = match fetch(client, config).await {
+        Ok(cache) => return Ok(cache),
+        Err(e) => e,
+    };
+    let Some(cache) = cached else {
+        return Err(e);
+    };
+    println!(
+        "warning: fetching instruments failed, using the cached rules {}: {}",
+        cache.describe(),
+        Scrubbed(&e)
+    );
+    cache.warn_if_stale(config);
+    Ok(cache)
+}
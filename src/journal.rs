@@ -0,0 +1,142 @@
+use crate::config::JournalConfig;
+use crate::lifecycle::OrderEvent;
+use crate::scrub;
+use chrono::{NaiveDate, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+/// Hands records to the writer thread. Set once by `start`.
+static JOURNAL: OnceLock<Mutex<Journal>> = OnceLock::new();
+
+/// Numbered and queued under one lock, so lines reach the file in
+/// sequence order.
+struct Journal {
+    sequence: u64,
+    /// Stamped on every record, so journals of different profiles can't be
+    /// mistaken for each other once mixed.
+    profile: Option<String>,
+    records: Sender<String>,
+}
+
+/// Opens the journal and starts its writer thread. Sequence numbers carry
+/// on from the newest file in `dir`.
+pub fn start(config: &JournalConfig, profile: Option<&str>) {
+    if let Err(e) = fs::create_dir_all(&config.dir) {
+        println!(
+            "WARNING: journal disabled, can't create {}: {}",
+            config.dir, e
+        );
+        return;
+    }
+    let dir = PathBuf::from(&config.dir);
+    let sequence = last_sequence(&dir).map_or(0, |last| last + 1);
+    let (records, pending) = mpsc::channel();
+    let max_bytes = config.max_mb * 1024 * 1024;
+    std::thread::spawn(move || write(dir, max_bytes, pending));
+    let journal = Journal {
+        sequence,
+        profile: profile.map(str::to_string),
+        records,
+    };
+    if JOURNAL.set(Mutex::new(journal)).is_err() {
+        println!("journal already started");
+    }
+}
+
+/// Journals `data` as a `kind` record. Never blocks: the line is queued for
+/// the writer thread, and nothing happens when the journal is off.
+pub fn record(kind: &str, data: Value) {
+    let Some(journal) = JOURNAL.get() else {
+        return;
+    };
+    let mut journal = journal.lock().expect("journal lock poisoned");
+    let line = json!({
+        "seq": journal.sequence,
+        "at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "kind": kind,
+        "profile": journal.profile,
+        "data": data,
+    });
+    journal.sequence += 1;
+    // only fails once the writer thread is gone, which it already reported
+    let _ = journal.records.send(scrub::scrub(&line.to_string()));
+}
+
+/// Journals every order state change.
+pub async fn follow(mut events: broadcast::Receiver<OrderEvent>) {
+    loop {
+        match events.recv().await {
+            Ok(event) => record("order", json!(event)),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                record("order_events_missed", json!({ "missed": missed }))
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Appends lines to `journal-<date>.jsonl`, moving to a new file each UTC
+/// day and to `journal-<date>.<n>.jsonl` once a file reaches `max_bytes`.
+fn write(dir: PathBuf, max_bytes: u64, pending: Receiver<String>) {
+    let mut current: Option<(NaiveDate, PathBuf, File)> = None;
+    for line in pending {
+        let today = Utc::now().date_naive();
+        let stale = match &current {
+            Some((date, path, _)) => {
+                *date != today || fs::metadata(path).map_or(0, |meta| meta.len()) >= max_bytes
+            }
+            None => true,
+        };
+        if stale {
+            let path = next_file(&dir, today, max_bytes);
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => current = Some((today, path, file)),
+                Err(e) => {
+                    println!("WARNING: failed opening journal {}: {}", path.display(), e);
+                    continue;
+                }
+            }
+        }
+        if let Some((_, path, file)) = &mut current {
+            if let Err(e) = writeln!(file, "{}", line) {
+                println!("WARNING: failed writing journal {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// The first file for `date` that still has room.
+fn next_file(dir: &Path, date: NaiveDate, max_bytes: u64) -> PathBuf {
+    let mut part = 0;
+    loop {
+        let name = if part == 0 {
+            format!("journal-{}.jsonl", date)
+        } else {
+            format!("journal-{}.{}.jsonl", date, part)
+        };
+        let path = dir.join(name);
+        if fs::metadata(&path).map_or(true, |meta| meta.len() < max_bytes) {
+            return path;
+        }
+        part += 1;
+    }
+}
+
+/// Sequence number of the last record in the most recently written file.
+fn last_sequence(dir: &Path) -> Option<u64> {
+    let newest = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("journal-"))
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())?;
+    let last = BufReader::new(File::open(newest.path()).ok()?)
+        .lines()
+        .map_while(Result::ok)
+        .last()?;
+    serde_json::from_str::<Value>(&last).ok()?["seq"].as_u64()
+}
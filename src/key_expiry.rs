@@ -0,0 +1,52 @@
+//! When the API key expires and when to warn about it. Keys without an IP
+//! whitelist expire after 90 days, and nothing fails until the day they do.
+
+use chrono::{DateTime, Utc};
+
+/// Days before expiry at which a warning goes out, loosest first.
+pub const WARN_DAYS: [i64; 4] = [14, 7, 3, 1];
+
+/// Days left from which a warning is critical rather than a notice.
+pub const CRITICAL_DAYS: i64 = 3;
+
+/// The key's `expiredAt`, None for a key that doesn't expire.
+pub fn parse(expired_at: &str) -> Result<Option<DateTime<Utc>>, String> {
+    if expired_at.is_empty() {
+        return Ok(None);
+    }
+    DateTime::parse_from_rfc3339(expired_at)
+        .map(|expires| Some(expires.with_timezone(&Utc)))
+        .map_err(|e| format!("unreadable expiry {:?}: {}", expired_at, e))
+}
+
+/// The tightest of `WARN_DAYS` a key expiring at `expires` is within at
+/// `now`, unless one as tight was `warned` about already. None once it has
+/// expired, as failed authentication alerts from then on.
+pub fn due(expires: DateTime<Utc>, now: DateTime<Utc>, warned: Option<i64>) -> Option<i64> {
+    let left = expires - now;
+    if left <= chrono::Duration::zero() {
+        return None;
+    }
+    let tightest = WARN_DAYS
+        .into_iter()
+        .filter(|days| left <= chrono::Duration::days(*days))
+        .min()?;
+    match warned {
+        Some(warned) if warned <= tightest => None,
+        _ => Some(tightest),
+    }
+}
+
+/// E.g. "expires 2026-10-29 00:00 UTC, in 13 days" or "expired 2026-10-01
+/// 00:00 UTC".
+pub fn describe(expires: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let at = expires.format("%Y-%m-%d %H:%M UTC");
+    let left = expires - now;
+    if left <= chrono::Duration::zero() {
+        format!("expired {}", at)
+    } else if left < chrono::Duration::days(1) {
+        format!("expires {}, in {} hours", at, left.num_hours())
+    } else {
+        format!("expires {}, in {} days", at, left.num_days())
+    }
+}
@@ -0,0 +1,294 @@
+use crate::bybit::{BoxError, OrderRequest};
+use crate::config::{
+    Category, ConditionalConfig, OrderType, SpotSizing, StrategyConfig, SymbolConfig,
+};
+use crate::decimal::{self, Rounding};
+use crate::instruments::Instrument;
+use crate::order_request::OrderRequestBuilder;
+use crate::sizing::{self, Fit, LadderError, Level, OverMaxOrder, PlannedOrder};
+use chrono::{DateTime, Utc};
+
+/// `marketUnit` of a spot market order whose qty is a quote amount.
+const QUOTE_UNIT: &str = "quoteCoin";
+
+/// Sizes `levels` below `price` against `instrument`, logging any level
+/// split or clamped under the maximum order.
+pub fn calculate_position(
+    price: f64,
+    symbol: &str,
+    instrument: &Instrument,
+    levels: &[Level],
+) -> Result<Vec<PlannedOrder>, LadderError> {
+    debug!("cal price: {}, symbol: {}", price, symbol);
+    let rules = instrument.rules();
+    let planned = sizing::size_levels(price, &rules, levels)?;
+    for (index, level) in levels.iter().enumerate() {
+        let level_price = price - (price * level.percent / 100.0);
+        let Fit {
+            children,
+            notional,
+            max: Some(max),
+        } = sizing::fit(level, level_price, &rules)
+        else {
+            continue;
+        };
+        match level.over_max {
+            OverMaxOrder::Split => info!(
+                "{} level {} of {:.2} split into {} orders under the maximum order of {:.2} {}",
+                symbol, index, notional, children, max, instrument.quote_coin
+            ),
+            OverMaxOrder::Clamp => info!(
+                "{} level {} clamped from {:.2} to {:.2} {}, the maximum order",
+                symbol, index, level.notional, notional, instrument.quote_coin
+            ),
+        }
+    }
+    Ok(planned)
+}
+
+/// Plans one TWAP slice: a limit buy of `notional` at `price`, the last
+/// trade, so it fills there or better and never chases. Errors name the
+/// slice as the level.
+pub fn plan_slice(
+    symbol: &str,
+    slice: usize,
+    price: f64,
+    notional: f64,
+    instrument: &Instrument,
+    link_id: String,
+) -> Result<OrderRequestBuilder, LadderError> {
+    let formatted_price = decimal::format_step(price, &instrument.tick_size, Rounding::Down);
+    let sent_price: f64 = formatted_price.parse().unwrap_or(f64::NAN);
+    if sent_price.is_nan() || sent_price <= 0.0 {
+        return Err(LadderError::BadPrice {
+            level: slice,
+            price,
+        });
+    }
+    let size = sizing::checked_qty(slice, price, sent_price, notional, &instrument.rules())?;
+    Ok(OrderRequest::builder(
+        symbol,
+        "Buy",
+        OrderType::Limit.as_param(),
+        decimal::format_step(size, &instrument.qty_step, Rounding::Down),
+        formatted_price,
+        link_id,
+    ))
+}
+
+/// Plans the ladder's orders below `price`. Each orderLinkId is
+/// `<prefix>-<symbol>-<placed_for ms>-<level>`, with `c<child>` appended
+/// for the children of a split level; with the cycle open as
+/// `placed_for`, placing the same level again reuses the id and the
+/// exchange refuses the copy.
+pub fn plan_orders(
+    strategy: &StrategyConfig,
+    symbol: &str,
+    price: &str,
+    instrument: &Instrument,
+    settings: &SymbolConfig,
+    placed_for: DateTime<Utc>,
+) -> Result<Vec<OrderRequest>, BoxError> {
+    let price_num: f64 = price.parse()?;
+    let ladder = strategy.ladder();
+    let category = strategy.category_of(symbol);
+    let planned = calculate_position(price_num, symbol, instrument, &ladder)?;
+    let summary: Vec<String> = planned
+        .iter()
+        .map(|order| format!("{}% {} @ {}", order.percent, order.qty, order.price))
+        .collect();
+    info!(
+        "[{}] ticker: {}, open price: {}, levels: {}",
+        strategy.name,
+        symbol,
+        price,
+        summary.join(", ")
+    );
+    let timestamp = placed_for.timestamp_millis();
+    planned
+        .into_iter()
+        .map(|order| {
+            let overrides = strategy.level_order(order.level);
+            let order_type = overrides
+                .map(|overrides| overrides.order_type)
+                .unwrap_or_default();
+            let link_id = format!(
+                "{}-{}-{}-{}",
+                strategy.link_id_prefix,
+                symbol,
+                timestamp,
+                order.link_suffix()
+            );
+            let (qty, market_unit) = sized_qty(
+                strategy,
+                category,
+                instrument,
+                order_type,
+                order.notional,
+                order.qty,
+            );
+            let mut builder = OrderRequest::builder(
+                symbol,
+                "Buy",
+                order_type.as_param(),
+                qty,
+                order.price,
+                link_id,
+            )
+            .leverage(category == Category::Spot && settings.spot_margin);
+            if let Some(unit) = market_unit {
+                builder = builder.market_unit(unit);
+            }
+            if let Some(time_in_force) = overrides.and_then(|overrides| overrides.time_in_force) {
+                builder = builder.time_in_force(time_in_force);
+            }
+            let mut request = builder.build();
+            if let Some(conditional) = strategy.conditional_for(order.level) {
+                make_conditional(&mut request, category, &conditional, &instrument.tick_size)?;
+            }
+            Ok(request)
+        })
+        .collect()
+}
+
+/// Turns a level's limit into a trigger at the level price that submits
+/// the limit `limit_offset_percent` below it once price falls there. A
+/// market level buys at whatever price the trigger finds.
+fn make_conditional(
+    request: &mut OrderRequest,
+    category: Category,
+    conditional: &ConditionalConfig,
+    tick_size: &str,
+) -> Result<(), BoxError> {
+    let trigger: f64 = request.price.parse()?;
+    request.trigger_price = Some(request.price.clone());
+    if request.order_type == OrderType::Limit.as_param() {
+        request.price = decimal::format_step(
+            trigger * (1.0 - conditional.limit_offset_percent / 100.0),
+            tick_size,
+            Rounding::Down,
+        );
+    }
+    match category {
+        Category::Linear => {
+            request.trigger_direction = Some(2);
+            request.trigger_by = Some(conditional.trigger_by.as_param().to_string());
+        }
+        Category::Spot => request.order_filter = Some("StopOrder".to_string()),
+    }
+    Ok(())
+}
+
+/// The qty to send for a level: the quote amount with a `marketUnit` for
+/// quote-sized spot market orders, the planned base qty otherwise. Spot
+/// market buys read qty as quote unless told it's base.
+fn sized_qty(
+    strategy: &StrategyConfig,
+    category: Category,
+    instrument: &Instrument,
+    order_type: OrderType,
+    notional: f64,
+    base_qty: String,
+) -> (String, Option<String>) {
+    if category != Category::Spot || order_type != OrderType::Market {
+        return (base_qty, None);
+    }
+    match strategy.spot_sizing {
+        SpotSizing::Quote => (
+            decimal::format(
+                notional,
+                decimal::places(&instrument.tick_size),
+                Rounding::Down,
+            ),
+            Some(QUOTE_UNIT.to_string()),
+        ),
+        SpotSizing::Base => (base_qty, Some("baseCoin".to_string())),
+    }
+}
+
+/// One line per level: the quote amount it asked for and the qty that was
+/// actually sent, at each child's price for a split level.
+pub fn describe(
+    strategy: &StrategyConfig,
+    instrument: &Instrument,
+    orders: &[OrderRequest],
+) -> Vec<String> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for order in orders {
+        let Some(level) = level_from_link_id(&order.order_link_id) else {
+            continue;
+        };
+        let (Some(percent), Some(budget)) =
+            (strategy.levels.get(level), strategy.budgets.get(level))
+        else {
+            continue;
+        };
+        let sent = match order.market_unit.as_deref() {
+            Some(QUOTE_UNIT) => format!(
+                "qty {} {} @ {}",
+                order.qty, instrument.quote_coin, order.price
+            ),
+            _ => format!("qty {} @ {}", order.qty, order.price),
+        };
+        match lines.last_mut() {
+            Some((last, line)) if *last == level => line.push_str(&format!(", {}", sent)),
+            _ => lines.push((
+                level,
+                format!(
+                    "{}% {:.2} {} -> {}",
+                    percent, budget, instrument.quote_coin, sent
+                ),
+            )),
+        }
+    }
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Quote value of an entry: its qty when sized in quote, else price * qty.
+pub fn notional(order: &OrderRequest) -> f64 {
+    let qty: f64 = order.qty.parse().unwrap_or(0.0);
+    if order.market_unit.as_deref() == Some(QUOTE_UNIT) {
+        return qty;
+    }
+    qty * order.price.parse::<f64>().unwrap_or(0.0)
+}
+
+/// The level's percent below the open (e.g. "5%"), used to key fill
+/// statistics.
+pub fn level_percent(strategy: &StrategyConfig, order_link_id: &str) -> Option<String> {
+    let level = level_from_link_id(order_link_id)?;
+    Some(format!("{}%", strategy.levels.get(level)?))
+}
+
+/// Level index encoded as the last segment of an orderLinkId, a child's
+/// included.
+pub fn level_from_link_id(order_link_id: &str) -> Option<usize> {
+    let last = order_link_id.rsplit('-').next()?;
+    last.split_once('c')
+        .map_or(last, |(level, _)| level)
+        .parse()
+        .ok()
+}
+
+/// Index of the child a split level's orderLinkId names.
+pub fn child_from_link_id(order_link_id: &str) -> Option<usize> {
+    let (_, child) = order_link_id.rsplit('-').next()?.split_once('c')?;
+    child.parse().ok()
+}
+
+/// The `<level>d<depth>` an orderLinkId of a cascaded bid ends with: the
+/// level whose fill started the cascade and how many rungs below it.
+pub fn cascade_from_link_id(order_link_id: &str) -> Option<(usize, u32)> {
+    let (level, depth) = order_link_id.rsplit('-').next()?.split_once('d')?;
+    Some((level.parse().ok()?, depth.parse().ok()?))
+}
+
+/// The orderLinkId a level would have unsplit, shared by its children.
+pub fn parent_link_id(order_link_id: &str) -> &str {
+    match child_from_link_id(order_link_id) {
+        Some(_) => order_link_id
+            .rsplit_once('c')
+            .map_or(order_link_id, |(parent, _)| parent),
+        None => order_link_id,
+    }
+}
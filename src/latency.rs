@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Requests kept per endpoint.
+const WINDOW: usize = 200;
+/// Requests needed on the watched endpoint before its p95 is judged.
+const MIN_SAMPLES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Success,
+    /// Answered with a non-zero retCode.
+    BybitError,
+    /// No answer, or an HTTP error status.
+    TransportError,
+}
+
+struct Sample {
+    duration: Duration,
+    outcome: Outcome,
+}
+
+/// Rolling request durations and outcomes per endpoint path. Warns when the
+/// p95 of the watched (order placement) endpoint goes over the threshold.
+pub struct LatencyTracker {
+    windows: Mutex<BTreeMap<String, VecDeque<Sample>>>,
+    watched: String,
+    warn_after: Duration,
+    warned: Mutex<bool>,
+}
+
+/// Summary of one endpoint's window.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct EndpointStats {
+    pub requests: usize,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+    pub bybit_errors: usize,
+    pub transport_errors: usize,
+}
+
+impl LatencyTracker {
+    pub fn new(watched: String, warn_after: Duration) -> LatencyTracker {
+        LatencyTracker {
+            windows: Mutex::new(BTreeMap::new()),
+            watched,
+            warn_after,
+            warned: Mutex::new(false),
+        }
+    }
+
+    /// Path of the order placement endpoint.
+    pub fn watched(&self) -> &str {
+        &self.watched
+    }
+
+    pub fn record(&self, endpoint: &str, duration: Duration, outcome: Outcome) {
+        let p95 = {
+            let mut windows = self.windows.lock().expect("latency lock poisoned");
+            let window = windows.entry(endpoint.to_string()).or_default();
+            if window.len() == WINDOW {
+                window.pop_front();
+            }
+            window.push_back(Sample { duration, outcome });
+            if endpoint != self.watched || window.len() < MIN_SAMPLES {
+                return;
+            }
+            percentile(window, 0.95)
+        };
+        let mut warned = self.warned.lock().expect("latency lock poisoned");
+        if p95 > self.warn_after && !*warned {
+            println!(
+                "WARNING: p95 latency of {} is {:?}, over {:?}",
+                endpoint, p95, self.warn_after
+            );
+        } else if p95 <= self.warn_after && *warned {
+            info!("p95 latency of {} back to {:?}", endpoint, p95);
+        }
+        *warned = p95 > self.warn_after;
+    }
+
+    /// Relabels the newest successful request to `endpoint` once its reply
+    /// turns out to carry a non-zero retCode.
+    pub fn mark_bybit_error(&self, endpoint: &str) {
+        let mut windows = self.windows.lock().expect("latency lock poisoned");
+        let newest = windows.get_mut(endpoint).and_then(|window| {
+            window
+                .iter_mut()
+                .rev()
+                .find(|sample| sample.outcome == Outcome::Success)
+        });
+        if let Some(sample) = newest {
+            sample.outcome = Outcome::BybitError;
+        }
+    }
+
+    pub fn summary(&self) -> BTreeMap<String, EndpointStats> {
+        let windows = self.windows.lock().expect("latency lock poisoned");
+        windows
+            .iter()
+            .map(|(endpoint, window)| {
+                let count = |outcome| window.iter().filter(|s| s.outcome == outcome).count();
+                let stats = EndpointStats {
+                    requests: window.len(),
+                    p50_ms: percentile(window, 0.5).as_millis() as u64,
+                    p95_ms: percentile(window, 0.95).as_millis() as u64,
+                    max_ms: percentile(window, 1.0).as_millis() as u64,
+                    bybit_errors: count(Outcome::BybitError),
+                    transport_errors: count(Outcome::TransportError),
+                };
+                (endpoint.clone(), stats)
+            })
+            .collect()
+    }
+}
+
+fn percentile(window: &VecDeque<Sample>, rank: f64) -> Duration {
+    let mut durations: Vec<Duration> = window.iter().map(|sample| sample.duration).collect();
+    durations.sort();
+    let index = ((durations.len() as f64 * rank).ceil() as usize).clamp(1, durations.len());
+    durations[index - 1]
+}
+
+impl fmt::Display for EndpointStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:>5} reqs  p50 {:>5}ms  p95 {:>5}ms  max {:>5}ms  bybit errors {:>3}  transport errors {:>3}",
+            self.requests,
+            self.p50_ms,
+            self.p95_ms,
+            self.max_ms,
+            self.bybit_errors,
+            self.transport_errors
+        )
+    }
+}
@@ -0,0 +1,421 @@
+use crate::bybit::{BoxError, BybitClient, CancelOrderRequest};
+use crate::config::{Category, StrategyConfig};
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::lifecycle::OrderEvent;
+use crate::notify;
+use crate::state::StateOrder;
+use crate::stops;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Statuses of an exit still resting on the book.
+const RESTING: [&str; 3] = ["New", "PartiallyFilled", "Untriggered"];
+/// Quantities closer than this (relative to the larger one) count as equal.
+const QTY_TOLERANCE: f64 = 1e-6;
+/// Average entries further apart than this fraction are flagged.
+const PRICE_TOLERANCE: f64 = 0.005;
+/// Days between reconciliations against the exchange.
+pub const RECONCILE_DAYS: i64 = 7;
+
+/// A symbol's position as built from the bot's own fills, before fees.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Position {
+    pub qty: f64,
+    /// What `qty` cost at the entry prices.
+    pub cost: f64,
+    /// PnL booked by exits so far.
+    pub realized: f64,
+    /// Funding paid while the bot held the position, perps only.
+    #[serde(default)]
+    pub funding_paid: f64,
+    #[serde(default)]
+    pub funding_received: f64,
+}
+
+/// Positions per category and symbol.
+pub type Ledger = BTreeMap<Category, BTreeMap<String, Position>>;
+
+impl Position {
+    pub fn buy(&mut self, qty: f64, price: f64) {
+        self.qty += qty;
+        self.cost += qty * price;
+    }
+
+    /// Closes `qty` at `price` against the average entry and returns the
+    /// PnL realized. Anything beyond the held qty is ignored.
+    pub fn sell(&mut self, qty: f64, price: f64) -> f64 {
+        let qty = qty.min(self.qty);
+        let average = self.average();
+        let pnl = (price - average) * qty;
+        self.realized += pnl;
+        self.qty -= qty;
+        self.cost -= average * qty;
+        if self.qty <= QTY_TOLERANCE {
+            self.qty = 0.0;
+            self.cost = 0.0;
+        }
+        pnl
+    }
+
+    pub fn average(&self) -> f64 {
+        if self.qty > 0.0 {
+            self.cost / self.qty
+        } else {
+            0.0
+        }
+    }
+}
+
+/// A ledger position valued at the current mark.
+#[derive(Serialize)]
+pub struct PnlLine {
+    pub category: Category,
+    pub symbol: String,
+    pub qty: f64,
+    pub average: f64,
+    /// `None` when the price couldn't be fetched.
+    pub mark: Option<f64>,
+    pub unrealized: Option<f64>,
+    pub realized: f64,
+    /// Funding received less funding paid.
+    pub funding: f64,
+}
+
+/// Values every ledger position at its mark price (last price for spot).
+pub async fn pnl(client: &BybitClient, ledger: &Ledger) -> Vec<PnlLine> {
+    let mut lines = Vec::new();
+    for (category, positions) in ledger {
+        for (symbol, position) in positions {
+            let mark = if position.qty > 0.0 {
+                match client.get_mark_price(*category, symbol).await {
+                    Ok(mark) => Some(mark),
+                    Err(e) => {
+                        println!("WARNING: failed fetching mark price of {}: {}", symbol, e);
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            lines.push(PnlLine {
+                category: *category,
+                symbol: symbol.clone(),
+                qty: position.qty,
+                average: position.average(),
+                mark,
+                unrealized: mark.map(|mark| (mark - position.average()) * position.qty),
+                realized: position.realized,
+                funding: position.funding_received - position.funding_paid,
+            });
+        }
+    }
+    lines
+}
+
+/// Books exits of `strategy` that stopped resting since the last check.
+pub async fn check_exits(ctx: &Context, strategy: &StrategyConfig, cycle: &mut Cycle) {
+    let resting: Vec<StateOrder> = ctx.store.read(|state| {
+        state
+            .strategies
+            .get(&strategy.name)
+            .map(|tracked| {
+                tracked
+                    .exits
+                    .iter()
+                    .filter(|exit| RESTING.contains(&exit.status.as_str()))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+    let symbols: BTreeSet<&str> = resting.iter().map(|exit| exit.symbol.as_str()).collect();
+    for symbol in symbols {
+        if let Err(e) = check_symbol(ctx, strategy, symbol, &resting, cycle).await {
+            println!(
+                "[{}] exit check for {} failed: {}",
+                strategy.name, symbol, e
+            );
+        }
+    }
+}
+
+async fn check_symbol(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+    resting: &[StateOrder],
+    cycle: &mut Cycle,
+) -> Result<(), BoxError> {
+    let category = strategy.category_of(symbol);
+    let mut open_ids: HashSet<String> = ctx
+        .client
+        .get_open_orders(category, symbol, None)
+        .await?
+        .into_iter()
+        .map(|order| order.order_id)
+        .collect();
+    let now = Utc::now();
+    let expired: Vec<&StateOrder> = resting
+        .iter()
+        .filter(|exit| {
+            exit.symbol == symbol
+                && open_ids.contains(&exit.order_id)
+                && exit.expires_at.is_some_and(|at| at <= now)
+        })
+        .collect();
+    for exit in expired {
+        let request = CancelOrderRequest {
+            category: category.as_param().to_string(),
+            symbol: symbol.to_string(),
+            order_id: exit.order_id.clone(),
+            order_filter: None,
+        };
+        match ctx.client.cancel_order(&request).await {
+            // looked up below like any exit that stopped resting
+            Ok(()) => {
+                open_ids.remove(&exit.order_id);
+                let event = format!("{} {} exit expired", symbol, exit.order_link_id);
+                info!("[{}] {}", strategy.name, event);
+                cycle.report.events.push(event);
+            }
+            Err(e) => println!(
+                "[{}] {} expired exit {} not cancelled: {}",
+                strategy.name, symbol, exit.order_link_id, e
+            ),
+        }
+    }
+    for exit in resting
+        .iter()
+        .filter(|exit| exit.symbol == symbol && !open_ids.contains(&exit.order_id))
+    {
+        let Some(order) = ctx
+            .client
+            .get_order(category, symbol, &exit.order_id, None)
+            .await?
+        else {
+            continue;
+        };
+        let executed: f64 = order.cum_exec_qty.parse().unwrap_or(0.0);
+        let average: f64 = order.avg_price.parse().unwrap_or(0.0);
+        if let Some(event) = OrderEvent::observed(&strategy.name, category, &order) {
+            ctx.lifecycle.transition(event);
+        }
+        ctx.store.update(|state| {
+            if let Some(tracked) = state
+                .strategies
+                .get_mut(&strategy.name)
+                .and_then(|tracked| {
+                    tracked
+                        .exits
+                        .iter_mut()
+                        .find(|tracked| tracked.order_id == order.order_id)
+                })
+            {
+                tracked.status = order.order_status.clone();
+            }
+        });
+        if executed > 0.0 {
+            let mut event = format!(
+                "{} {} exit filled {} @ {}",
+                symbol, order.order_link_id, executed, average
+            );
+            if strategy
+                .exits
+                .as_ref()
+                .is_some_and(|exits| exits.break_even)
+            {
+                if let Some(entry) = first_exit_fill(ctx, strategy, exit) {
+                    match stops::move_to_break_even(ctx, strategy, symbol).await {
+                        Ok(Some(price)) => {
+                            event.push_str(&format!(", stop moved to break-even {}", price))
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            event.push_str(&format!(", ALERT break-even stop failed: {}", e));
+                            // tried again on the entry's next exit fill
+                            ctx.store.update_strategy(&strategy.name, |state| {
+                                state.break_even.remove(&entry);
+                            });
+                        }
+                    }
+                }
+            }
+            info!("[{}] {}", strategy.name, event);
+            cycle.report.events.push(event);
+        }
+    }
+    Ok(())
+}
+
+/// Claims the break-even move for the entry `exit` sells. `None` if an
+/// earlier exit of that entry already made it, or the exit has no entry.
+fn first_exit_fill(ctx: &Context, strategy: &StrategyConfig, exit: &StateOrder) -> Option<String> {
+    let entry = exit.entry.clone()?;
+    let mut first = false;
+    ctx.store.update_strategy(&strategy.name, |state| {
+        first = state.break_even.insert(entry.clone());
+    });
+    first.then_some(entry)
+}
+
+/// Books orders into the ledger as they reach a final state: executed
+/// buys add to the position, executed sells realize PnL.
+pub async fn follow(ctx: Arc<Context>, mut events: broadcast::Receiver<OrderEvent>) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                println!("WARNING: ledger missed {} order events", missed);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if !event.to.is_final() || event.executed <= 0.0 {
+            continue;
+        }
+        let mut realized = None;
+        ctx.store.update(|state| {
+            let position = state
+                .ledger
+                .entry(event.category)
+                .or_default()
+                .entry(event.symbol.clone())
+                .or_default();
+            if event.side != "Sell" {
+                position.buy(event.executed, event.average);
+                return;
+            }
+            let pnl = position.sell(event.executed, event.average);
+            realized = Some(pnl);
+            // booked to the level whose fill the exit sold
+            if let Some(tracked) = state.strategies.get_mut(&event.strategy) {
+                let level = tracked
+                    .exits
+                    .iter()
+                    .find(|exit| exit.order_link_id == event.order_link_id)
+                    .and_then(|exit| exit.level_percent);
+                if let Some(level) = level {
+                    *tracked
+                        .counters
+                        .realized_by_level
+                        .entry(format!("{}%", level))
+                        .or_default() += pnl;
+                }
+            }
+        });
+        if let Some(realized) = realized {
+            info!(
+                "[{}] {} realized {:.4} on {}",
+                event.strategy, event.symbol, realized, event.order_link_id
+            );
+        }
+    }
+}
+
+/// Compares linear ledger positions with the exchange's long positions and
+/// sends a notice listing any mismatch. Neither side is corrected: manual
+/// trades, trailing stops and liquidations all show up here.
+pub async fn reconcile(ctx: &Context) {
+    let linear = ctx
+        .config
+        .traded_categories()
+        .remove(&Category::Linear)
+        .unwrap_or_default();
+    let positions = ctx.store.read(|state| {
+        state
+            .ledger
+            .get(&Category::Linear)
+            .cloned()
+            .unwrap_or_default()
+    });
+    let mut symbols: BTreeSet<String> = positions.keys().cloned().collect();
+    symbols.extend(
+        ctx.tradable_symbols()
+            .into_iter()
+            .filter(|symbol| linear.contains(symbol)),
+    );
+
+    let mut mismatches = Vec::new();
+    for symbol in symbols {
+        let position = positions.get(&symbol).cloned().unwrap_or_default();
+        let (qty, average) = match ctx.client.get_positions(&symbol).await {
+            Ok(list) => list
+                .iter()
+                .filter(|info| info.side == "Buy")
+                .map(|info| {
+                    (
+                        info.size.parse().unwrap_or(0.0),
+                        info.avg_price.parse().unwrap_or(0.0),
+                    )
+                })
+                .next()
+                .unwrap_or((0.0, 0.0)),
+            Err(e) => {
+                mismatches.push(format!("{}: position lookup failed: {}", symbol, e));
+                continue;
+            }
+        };
+        let qty_differs =
+            (qty - position.qty).abs() > QTY_TOLERANCE * qty.max(position.qty).max(1.0);
+        let price_differs = qty > 0.0
+            && position.qty > 0.0
+            && (average - position.average()).abs() > PRICE_TOLERANCE * average;
+        if qty_differs || price_differs {
+            mismatches.push(format!(
+                "{}: ledger {} @ {:.6}, exchange {} @ {:.6}",
+                symbol,
+                position.qty,
+                position.average(),
+                qty,
+                average
+            ));
+        }
+    }
+    ctx.store
+        .update(|state| state.reconciled_at = Some(Utc::now()));
+    if mismatches.is_empty() {
+        info!("ledger matches the exchange's positions");
+    } else {
+        notify::send(&format!(
+            "ledger differs from the exchange: {}",
+            mismatches.join("; ")
+        ));
+    }
+}
+
+/// When the next reconciliation is due, given the last one.
+pub fn next_reconcile(last: Option<DateTime<Utc>>, now: DateTime<Utc>) -> DateTime<Utc> {
+    match last {
+        Some(last) => (last + ChronoDuration::days(RECONCILE_DAYS)).max(now),
+        None => now,
+    }
+}
+
+impl fmt::Display for PnlLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} {:<6} qty {:<14} avg {:<14.6}",
+            self.symbol,
+            self.category.as_param(),
+            self.qty,
+            self.average
+        )?;
+        match (self.mark, self.unrealized) {
+            (Some(mark), Some(unrealized)) => {
+                write!(f, " mark {:<14} unrealized {:>12.4}", mark, unrealized)?
+            }
+            _ => write!(f, " mark {:<14} unrealized {:>12}", "-", "-")?,
+        }
+        write!(f, " realized {:>12.4}", self.realized)?;
+        if self.funding != 0.0 {
+            write!(f, " funding {:>12.4}", self.funding)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use crate::bybit::{BoxError, Order, OrderRequest, Placement};
+use crate::config::{Category, StrategyConfig};
+use crate::state::StateOrder;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Events a subscriber may fall behind by before it starts missing some.
+const EVENT_BUFFER: usize = 1024;
+
+/// Where an order the bot sent stands.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderState {
+    /// Sized and formatted, not sent yet.
+    Planned,
+    /// Sent, no answer yet.
+    Submitted,
+    /// Resting on the book, or waiting for its trigger.
+    Working,
+    PartiallyFilled,
+    Filled,
+    /// Cancelled, possibly after a partial fill.
+    Cancelled,
+    Rejected,
+    /// Sent without a usable answer; it may or may not exist.
+    Lost,
+}
+
+impl OrderState {
+    /// Maps a Bybit `orderStatus`.
+    pub fn from_status(status: &str) -> Option<OrderState> {
+        match status {
+            "New" | "Untriggered" | "Triggered" => Some(OrderState::Working),
+            "PartiallyFilled" => Some(OrderState::PartiallyFilled),
+            "Filled" => Some(OrderState::Filled),
+            "Cancelled" | "PartiallyFilledCanceled" | "Deactivated" => Some(OrderState::Cancelled),
+            "Rejected" => Some(OrderState::Rejected),
+            _ => None,
+        }
+    }
+
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            OrderState::Filled | OrderState::Cancelled | OrderState::Rejected
+        )
+    }
+
+    fn allows(self, next: OrderState) -> bool {
+        use OrderState::*;
+        match self {
+            Planned => matches!(next, Submitted | Rejected),
+            Submitted | Lost => matches!(
+                next,
+                Working | PartiallyFilled | Filled | Cancelled | Rejected | Lost
+            ),
+            Working | PartiallyFilled => {
+                matches!(next, PartiallyFilled | Filled | Cancelled | Lost)
+            }
+            Filled | Cancelled | Rejected => false,
+        }
+    }
+}
+
+/// A change of an order's state, broadcast to every subscriber.
+#[derive(Serialize, Debug, Clone)]
+pub struct OrderEvent {
+    pub at: DateTime<Utc>,
+    pub strategy: String,
+    pub category: Category,
+    pub symbol: String,
+    pub order_link_id: String,
+    /// Empty until the exchange assigns one.
+    pub order_id: String,
+    pub side: String,
+    /// Limit price and qty as sent or reported, when known.
+    pub price: String,
+    pub qty: String,
+    /// `None` the first time the order is seen.
+    pub from: Option<OrderState>,
+    pub to: OrderState,
+    /// Executed qty and average price so far.
+    pub executed: f64,
+    pub average: f64,
+}
+
+impl OrderEvent {
+    pub fn new(
+        strategy: &str,
+        category: Category,
+        symbol: &str,
+        order_link_id: &str,
+        side: &str,
+        to: OrderState,
+    ) -> OrderEvent {
+        OrderEvent {
+            at: Utc::now(),
+            strategy: strategy.to_string(),
+            category,
+            symbol: symbol.to_string(),
+            order_link_id: order_link_id.to_string(),
+            order_id: String::new(),
+            side: side.to_string(),
+            price: String::new(),
+            qty: String::new(),
+            from: None,
+            to,
+            executed: 0.0,
+            average: 0.0,
+        }
+    }
+
+    /// The state an exchange order reports, with its fills. `None` for a
+    /// status that doesn't map to a state.
+    pub fn observed(strategy: &str, category: Category, order: &Order) -> Option<OrderEvent> {
+        let to = OrderState::from_status(&order.order_status)?;
+        Some(OrderEvent {
+            order_id: order.order_id.clone(),
+            price: order.price.clone(),
+            qty: order.qty.clone(),
+            executed: order.cum_exec_qty.parse().unwrap_or(0.0),
+            average: order.avg_price.parse().unwrap_or(0.0),
+            ..OrderEvent::new(
+                strategy,
+                category,
+                &order.symbol,
+                &order.order_link_id,
+                &order.side,
+                to,
+            )
+        })
+    }
+}
+
+/// Every order's current state, keyed by orderLinkId, and the channel
+/// their changes go out on.
+pub struct Lifecycle {
+    states: Mutex<BTreeMap<String, OrderState>>,
+    events: broadcast::Sender<OrderEvent>,
+}
+
+impl Lifecycle {
+    pub fn new() -> Lifecycle {
+        Lifecycle {
+            states: Mutex::new(BTreeMap::new()),
+            events: broadcast::Sender::new(EVENT_BUFFER),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn states(&self) -> BTreeMap<String, OrderState> {
+        self.states.lock().expect("lifecycle lock poisoned").clone()
+    }
+
+    /// Moves the order to `event.to` and broadcasts the change. Repeating
+    /// the current state is a no-op, except for a partial fill growing.
+    /// A transition the state machine doesn't allow is logged as a bug
+    /// and dropped.
+    pub fn transition(&self, mut event: OrderEvent) {
+        {
+            let mut states = self.states.lock().expect("lifecycle lock poisoned");
+            let from = states.get(&event.order_link_id).copied();
+            if let Some(from) = from {
+                if from == event.to && from != OrderState::PartiallyFilled {
+                    return;
+                }
+                if !from.allows(event.to) {
+                    println!(
+                        "BUG: {} {} can't go from {:?} to {:?}, keeping {:?}",
+                        event.strategy, event.order_link_id, from, event.to, from
+                    );
+                    return;
+                }
+            }
+            states.insert(event.order_link_id.clone(), event.to);
+            event.from = from;
+        }
+        // no subscribers is fine
+        let _ = self.events.send(event);
+    }
+
+    /// Marks tracked orders cancelled by a sweep nobody follows up on.
+    pub fn cancelled(&self, strategy: &StrategyConfig, orders: &[StateOrder]) {
+        for order in orders {
+            self.transition(OrderEvent {
+                order_id: order.order_id.clone(),
+                price: order.price.clone(),
+                qty: order.qty.clone(),
+                ..OrderEvent::new(
+                    &strategy.name,
+                    strategy.category_of(&order.symbol),
+                    &order.symbol,
+                    &order.order_link_id,
+                    &order.side,
+                    OrderState::Cancelled,
+                )
+            });
+        }
+    }
+
+    /// Records a batch from planning through the exchange's answer: placed
+    /// orders end up working, refused ones rejected and, when the batch
+    /// failed outright, every order lost.
+    pub fn placed(
+        &self,
+        strategy: &str,
+        category: Category,
+        orders: &[OrderRequest],
+        placement: &Result<Placement, BoxError>,
+    ) {
+        for order in orders {
+            let event = |to| OrderEvent {
+                price: order.price.clone(),
+                qty: order.qty.clone(),
+                ..OrderEvent::new(
+                    strategy,
+                    category,
+                    &order.symbol,
+                    &order.order_link_id,
+                    &order.side,
+                    to,
+                )
+            };
+            self.transition(event(OrderState::Planned));
+            self.transition(event(OrderState::Submitted));
+            let Ok(placement) = placement else {
+                self.transition(event(OrderState::Lost));
+                continue;
+            };
+            if let Some(placed) = placement
+                .placed
+                .iter()
+                .find(|placed| placed.order_link_id == order.order_link_id)
+            {
+                self.transition(OrderEvent {
+                    order_id: placed.order_id.clone(),
+                    ..event(OrderState::Working)
+                });
+            } else if placement
+                .rejected
+                .iter()
+                .any(|rejection| rejection.order_link_id == order.order_link_id)
+            {
+                self.transition(event(OrderState::Rejected));
+            } else {
+                self.transition(event(OrderState::Lost));
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Days, Duration as ChronoDuration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A wall-clock time in a named timezone, written "00:05 America/New_York".
+/// Without a zone it is UTC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTime {
+    pub time: NaiveTime,
+    pub zone: Tz,
+}
+
+impl LocalTime {
+    /// The first instant at or after `after` when the clock in the zone
+    /// reads this time.
+    pub fn first_at_or_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        // the previous local day is checked too, for zones behind UTC
+        let day = after.with_timezone(&self.zone).date_naive() - Days::new(1);
+        (0..3)
+            .map(|offset| self.on(day + Days::new(offset)))
+            .find(|instant| *instant >= after)
+            .expect("a local time recurs within two days")
+    }
+
+    /// This time on the local `date`. A time skipped by a DST jump resolves
+    /// to when the clock jumps forward, and a repeated one to its first
+    /// occurrence, so each day gets exactly one instant.
+    fn on(&self, date: NaiveDate) -> DateTime<Utc> {
+        let mut local = date.and_time(self.time);
+        loop {
+            if let Some(instant) = self.zone.from_local_datetime(&local).earliest() {
+                return instant.with_timezone(&Utc);
+            }
+            local += ChronoDuration::minutes(1);
+        }
+    }
+
+    /// `instant` on this zone's clock, e.g. "2024-03-10 00:05 EST".
+    pub fn local(&self, instant: DateTime<Utc>) -> String {
+        instant
+            .with_timezone(&self.zone)
+            .format("%Y-%m-%d %H:%M %Z")
+            .to_string()
+    }
+}
+
+impl FromStr for LocalTime {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<LocalTime, String> {
+        let mut parts = text.split_whitespace();
+        let time = parts.next().ok_or("empty time")?;
+        let time = NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|e| format!("bad time {:?}, expected HH:MM: {}", time, e))?;
+        let zone = match parts.next() {
+            Some(zone) => zone
+                .parse()
+                .map_err(|_| format!("unknown timezone {:?}", zone))?,
+            None => Tz::UTC,
+        };
+        if parts.next().is_some() {
+            return Err(format!("expected \"HH:MM Zone\", got {:?}", text));
+        }
+        Ok(LocalTime { time, zone })
+    }
+}
+
+impl<'de> Deserialize<'de> for LocalTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<LocalTime, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for LocalTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.time.format("%H:%M"), self.zone.name())
+    }
+}
@@ -1,351 +1,1907 @@
-use chrono::Utc;
+/// Prints like `println!` when the verbosity allows info lines.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if crate::verbosity::enabled(crate::verbosity::Level::Info) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Prints like `println!` from `-v` on.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if crate::verbosity::enabled(crate::verbosity::Level::Debug) {
+            println!($($arg)*);
+        }
+    };
+}
+
+mod account;
+mod anchor;
+mod audit;
+mod aws_credentials;
+mod bybit;
+mod cancels;
+mod cascade;
+mod catch_up;
+mod clock;
+mod config;
+mod context;
+mod cooldown;
+mod credentials;
+mod cycle;
+mod decimal;
+mod doctor;
+mod drawdowns;
+mod email;
+mod equity;
+mod exits;
+mod fill_times;
+mod fills;
+mod filters;
+mod funding;
+mod grid;
+mod guards;
+mod hold;
+mod instruments;
+mod journal;
+mod key_expiry;
+mod ladder;
+mod latency;
+mod ledger;
+mod lifecycle;
+mod localtime;
+mod margin;
+mod notes;
+mod notify;
+mod ntfy;
+mod optimize;
+mod order_request;
+mod placements;
+mod positions;
+mod preflight;
+mod profile;
+mod ratelimit;
+mod reanchor;
+mod reconcile;
+mod report;
+mod response;
+mod restarts;
+mod retry;
+mod schedule;
+mod scrub;
+mod server;
+mod sigv4;
+mod sizing;
+mod slack;
+mod stagger;
+mod state;
+mod stops;
+mod storage;
+mod supervisor;
+mod throttle;
+mod trace;
+mod twap;
+mod validate;
+mod verbosity;
+
+use account::AccountType;
+use anchor::StaleCandle;
+use bybit::{BoxError, BybitClient, CancelOrderData, Kline, Maintenance, Settled};
+use catch_up::StartupAction;
+use chrono::{DateTime, Duration as ChronoDuration, Months, NaiveDate, Utc};
+use clap::{Parser, Subcommand};
+use config::{
+    AdHoc, AnchorPrice, CancelMode, Category, Config, EntryMode, Interval, JournalConfig, Sizing,
+    StrategyConfig,
+};
+use context::Context;
+use cycle::Cycle;
 use dotenv::dotenv;
-use hex;
-use hmac::{Hmac, Mac};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use sha2::Sha256;
-use std::{collections::HashMap, env, time::Duration};
-use tokio::time::sleep;
-
-type HmacSha256 = Hmac<Sha256>;
-
-#[derive(Serialize, Deserialize, Debug)]
-struct ApiResponse<T> {
-    #[serde(rename = "retCode")]
-    ret_code: i32,
-    #[serde(rename = "retMsg")]
-    ret_msg: String,
-    result: T,
-    #[serde(rename = "retExtInfo")]
-    ret_ext_info: HashMap<String, serde_json::Value>,
-    time: u64,
+use email::EmailNotifier;
+use filters::Decision;
+use instruments::Instrument;
+use lifecycle::Lifecycle;
+use notify::Topic;
+use ntfy::NtfyNotifier;
+use order_request::OrderRequest;
+use report::SymbolOutcome;
+use scrub::Scrubbed;
+use serde_json::json;
+use slack::SlackNotifier;
+use state::{State, StateOrder, StateStore};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::watch;
+
+#[derive(Parser)]
+#[command(about = "Places stink bids on Bybit")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Log more: -v for debug, -vv for trace with HTTP bodies
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Log warnings and failures only
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Config and credentials to use, e.g. prod or testnet, in place of
+    /// STINK_PROFILE
+    #[arg(long, global = true)]
+    profile: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct KlineData {
-    symbol: String,
-    category: String,
-    list: Vec<Kline>,
+#[derive(Subcommand)]
+enum Command {
+    /// Run the strategies (default)
+    Run {
+        /// Note attached to each strategy's first cycle
+        #[arg(long)]
+        note: Option<String>,
+        /// Fetch the instruments even if the cached ones are fresh
+        #[arg(long)]
+        refresh_instruments: bool,
+    },
+    /// Place one ladder now, outside the schedule, then cancel it when its
+    /// hold ends and exit
+    Place {
+        /// Symbol to place on, repeatable
+        #[arg(long = "symbol", required = true)]
+        symbols: Vec<String>,
+        /// Total per symbol, split evenly across the levels
+        #[arg(long)]
+        budget: f64,
+        /// Percent below the open of each level, e.g. 15,25,40
+        #[arg(long, value_delimiter = ',', required = true)]
+        levels: Vec<f64>,
+        /// How long the ladder rests, e.g. 12h, instead of the strategy's hold
+        #[arg(long, value_parser = humantime_serde::re::humantime::parse_duration)]
+        hold: Option<std::time::Duration>,
+        /// Strategy the other settings are taken from, the first when unset
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Note attached to the cycle
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Print the entry and exit orders tracked in the state file
+    Status {
+        /// Show request latency per endpoint instead
+        #[arg(long)]
+        latency: bool,
+        /// Show positions from the fill ledger, valued at the mark price
+        #[arg(long)]
+        pnl: bool,
+    },
+    /// Print a timeline of the journal for a day or an order
+    Audit {
+        /// UTC day to replay (YYYY-MM-DD)
+        #[arg(long)]
+        day: Option<NaiveDate>,
+        /// Only the records about this orderLinkId
+        #[arg(long)]
+        order: Option<String>,
+    },
+    /// Export series kept in the state file
+    Report {
+        #[command(subcommand)]
+        kind: ReportKind,
+    },
+    /// Study market history to pick ladder settings
+    Analyze {
+        #[command(subcommand)]
+        kind: AnalyzeKind,
+    },
+    /// Rank candidate ladders by a naive replay over daily candles
+    Optimize {
+        symbol: String,
+        /// Notional split evenly across each candidate's levels
+        #[arg(long)]
+        budget: f64,
+        /// A candidate, as percents below the open, e.g. 5,10,15. Repeat
+        /// for more
+        #[arg(long, required = true)]
+        levels: Vec<String>,
+        /// Months of daily candles to replay
+        #[arg(long, default_value_t = 12)]
+        months: u32,
+        #[arg(long, value_enum, default_value_t = Category::Linear)]
+        category: Category,
+        /// Percent above the fill a level sells at, when the close gets there
+        #[arg(long, default_value_t = 3.0)]
+        bounce: f64,
+        /// Bootstrap resamples of the days for the return range
+        #[arg(long, default_value_t = 1000)]
+        samples: usize,
+        /// Seed of the resampling, the same one giving the same ranges
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// Write the best candidate as a ladder config snippet
+        #[arg(long)]
+        write: Option<PathBuf>,
+    },
+    /// Print statistics over the history in the state file
+    Stats {
+        #[command(subcommand)]
+        kind: StatsKind,
+    },
+    /// Annotate orders and cycles
+    Note {
+        #[command(subcommand)]
+        action: NoteAction,
+    },
+    /// Check connectivity, credentials, symbols and notifiers, exiting 1
+    /// on any failure
+    Doctor {
+        /// Send each configured notifier a test message
+        #[arg(long)]
+        send_test: bool,
+    },
+    /// Check a config without trading, exiting 1 on any error
+    Validate {
+        /// Config to check, instead of CONFIG_PATH
+        #[arg(long)]
+        config: Option<String>,
+        /// Instruments snapshot to check minimum order values against
+        #[arg(long, conflicts_with = "online")]
+        instruments: Option<String>,
+        /// Fetch the instruments from the exchange instead
+        #[arg(long)]
+        online: bool,
+        /// Save the fetched instruments as a snapshot for later runs
+        #[arg(long, requires = "online")]
+        save_instruments: Option<String>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Kline {
-    start_time: String,
-    open_price: String,
-    high_price: String,
-    low_price: String,
-    close_price: String,
-    volume: String,
-    turnover: String,
+#[derive(Subcommand)]
+enum NoteAction {
+    /// Attach a note to an order
+    Add {
+        /// orderLinkId, or `<strategy>@<open>` for a cycle
+        order_link_id: String,
+        text: String,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct BatchOrderRequest {
-    category: String,
-    request: Vec<OrderRequest>,
+#[derive(Subcommand)]
+enum AnalyzeKind {
+    /// How far daily candles traded below their open, and a ladder for a
+    /// target fill frequency
+    Drawdowns {
+        symbol: String,
+        /// Months of daily candles to read
+        #[arg(long, default_value_t = 12)]
+        months: u32,
+        #[arg(long, value_enum, default_value_t = Category::Linear)]
+        category: Category,
+        /// Percent of days the shallowest suggested level should fill on
+        #[arg(long, default_value_t = 20.0)]
+        target: f64,
+        /// Levels to suggest, each filling half as often as the one above
+        #[arg(long, default_value_t = 3)]
+        levels: usize,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct OrderRequest {
-    symbol: String,
-    side: String,
-    #[serde(rename = "orderType")]
-    order_type: String,
-    qty: String,
-    price: String,
+#[derive(Subcommand)]
+enum StatsKind {
+    /// Time from placement to fill per level: median, p90 and the share
+    /// filled in the first hour
+    Fills {
+        /// Only this strategy
+        #[arg(long)]
+        strategy: Option<String>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct BatchOrderResult {
-    list: Vec<BatchOrderResponse>,
+#[derive(Subcommand)]
+enum ReportKind {
+    /// Equity, exposure and realized PnL per cycle, as CSV
+    Equity {
+        /// First day to include (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+    },
+    /// Notes on orders and cycles, as CSV
+    Notes {
+        /// First day to include (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<NaiveDate>,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct BatchOrderResponse {
-    category: String,
-    symbol: String,
-    #[serde(rename = "orderId")]
-    order_id: String,
-    #[serde(rename = "orderLinkId")]
-    order_link_id: String,
-    #[serde(rename = "createAt")]
-    create_at: String,
+/// Finished daily candles of `symbol` over the last `months`, oldest
+/// first, read without an API key.
+async fn daily_klines(
+    config: &Config,
+    category: Category,
+    symbol: &str,
+    months: u32,
+) -> Result<Vec<Kline>, BoxError> {
+    let client = BybitClient::public(config);
+    let end = Utc::now();
+    let start = end.checked_sub_months(Months::new(months)).unwrap_or(end);
+    let klines = client
+        .get_klines(category, symbol, Interval::Daily, start, end)
+        .await?;
+    // today's candle is still moving
+    let today = end.date_naive();
+    Ok(klines
+        .into_iter()
+        .filter(|kline| {
+            kline
+                .start_time
+                .parse()
+                .ok()
+                .and_then(DateTime::from_timestamp_millis)
+                .is_some_and(|at| at.date_naive() < today)
+        })
+        .collect())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Quantity {
-    twenty_percent_size: f64,
-    twenty_five_percent_size: f64,
-    thirty_percent_size: f64,
+/// Candles fetched per anchor: around the rollover the newest may be the
+/// next period's, already forming, with ours right behind it.
+const ANCHOR_CANDIDATES: usize = 2;
+
+/// What a symbol's ladder is anchored on this cycle.
+#[derive(Debug)]
+struct Anchor {
+    /// The candle of the period being placed.
+    kline: Kline,
+    /// Its open, or what the symbol's reducer took from it and the
+    /// candles before it.
+    price: String,
+    /// Candles the reducer read, fewer than configured on a new listing.
+    candles: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Price {
-    twenty_percent_price: f64,
-    twenty_five_percent_price: f64,
-    thirty_percent_price: f64,
+/// The anchor of `symbol` for the period opening at `expected`. Its candle
+/// is picked by start time from the newest few, and with none from that
+/// period they are refetched under the reads retry policy, in case the
+/// exchange hasn't rolled over yet, then given up on.
+async fn fetch_anchor(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+    expected: DateTime<Utc>,
+) -> Result<Anchor, BoxError> {
+    let settings = &ctx.config.symbols[symbol];
+    let wanted = settings.anchor_candles();
+    let policy = &ctx.config.retry.reads;
+    let mut attempt = 1;
+    loop {
+        let mut klines = ctx
+            .client
+            .get_latest_klines(
+                strategy.category_of(symbol),
+                symbol,
+                strategy.interval,
+                settings.anchor_price,
+                ANCHOR_CANDIDATES + wanted - 1,
+            )
+            .await?;
+        let starts: Vec<&str> = klines.iter().map(|k| k.start_time.as_str()).collect();
+        match anchor::select(&starts, expected) {
+            Ok(index) => {
+                // the chosen candle and the ones before it, newest first
+                let mut klines = klines.split_off(index);
+                klines.truncate(wanted);
+                let candles: Vec<anchor::Candle> = klines
+                    .iter()
+                    .map(|k| anchor::Candle {
+                        open: &k.open_price,
+                        high: &k.high_price,
+                        low: &k.low_price,
+                    })
+                    .collect();
+                let price = anchor::reduce(settings.anchor_reducer, &candles)
+                    .ok_or_else(|| format!("{} klines have unreadable prices", symbol))?;
+                let candles = klines.len();
+                let kline = klines.swap_remove(0);
+                info!(
+                    "[{}] {} anchored on the candle opening {}, open {}",
+                    strategy.name, symbol, expected, kline.open_price
+                );
+                if settings.anchor_reducer != anchor::Reducer::Open {
+                    if candles < wanted {
+                        println!(
+                            "[{}] {} has only {} of the {} anchor candles",
+                            strategy.name, symbol, candles, wanted
+                        );
+                    }
+                    info!(
+                        "[{}] {} anchor {} of {} candles: {}",
+                        strategy.name, symbol, settings.anchor_reducer, candles, price
+                    );
+                }
+                return Ok(Anchor {
+                    kline,
+                    price,
+                    candles,
+                });
+            }
+            Err(stale) if attempt >= policy.max_attempts => return Err(stale.into()),
+            Err(stale) => {
+                println!("[{}] {} {}, refetching", strategy.name, symbol, stale);
+                tokio::time::sleep(policy.delay(attempt)).await;
+                attempt += 1;
+            }
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct FormattedPosition {
-    twenty_percent_price: String,
-    twenty_five_percent_price: String,
-    thirty_percent_price: String,
-    twenty_percent_size: String,
-    twenty_five_percent_size: String,
-    thirty_percent_size: String,
+/// Places the ladders for `symbols` in this cycle. Fails without placing
+/// anything if the exchange is under maintenance.
+async fn place_ladders(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbols: &[String],
+    skip_breached: bool,
+    cycle: &mut Cycle,
+) -> Result<(), BoxError> {
+    let blocked = ctx.blocked();
+    let mut pending = Vec::new();
+    for symbol in symbols {
+        if let Some(reason) = blocked.get(symbol) {
+            cycle
+                .report
+                .add(symbol, "-", SymbolOutcome::Skipped(reason.clone()));
+            continue;
+        }
+        match ctx
+            .placed_entries(strategy, symbol, cycle.report.open)
+            .await
+        {
+            Ok(Some(entries)) => {
+                info!(
+                    "[{}] {} ladder for {} already placed, taking over {} entries",
+                    strategy.name,
+                    symbol,
+                    cycle.report.open,
+                    entries.len()
+                );
+                cycle.report.add(
+                    symbol,
+                    "-",
+                    SymbolOutcome::Skipped("already placed for this open".to_string()),
+                );
+                cycle.tracked.extend(entries);
+            }
+            Ok(None) => pending.push(symbol.clone()),
+            Err(e) => {
+                let reason = format!("couldn't check for an earlier placement: {}", e);
+                cycle.report.add(symbol, "-", SymbolOutcome::Failed(reason));
+            }
+        }
+    }
+    let symbols = &pending;
+    // a triggered cycle opens now, inside the candle it anchors on
+    let expected = schedule::current_open(cycle.report.open, strategy.interval);
+    let futures = symbols
+        .iter()
+        .map(|symbol| fetch_anchor(ctx, strategy, symbol, expected));
+    let mut results = futures::future::join_all(futures).await;
+    if let Some(position) = results
+        .iter()
+        .position(|result| result.as_ref().is_err_and(Maintenance::is))
+    {
+        return Err(results.swap_remove(position).unwrap_err());
+    }
+    let report = &mut cycle.report;
+
+    let mut planned = Vec::new();
+    for (symbol, result) in symbols.iter().zip(results) {
+        let Anchor {
+            kline,
+            price: open_price,
+            candles,
+        } = match result {
+            Ok(anchor) => anchor,
+            Err(e) if e.is::<StaleCandle>() => {
+                notify::send(&format!("[{}] {} not placed: {}", strategy.name, symbol, e));
+                report.add(symbol, "-", SymbolOutcome::Failed(e.to_string()));
+                continue;
+            }
+            Err(e) if ctx.disable_if_unavailable(symbol, &e) => {
+                report.add(symbol, "-", SymbolOutcome::Skipped(e.to_string()));
+                continue;
+            }
+            Err(e) => {
+                report.add(symbol, "-", SymbolOutcome::Failed(e.to_string()));
+                continue;
+            }
+        };
+        let source = ctx.config.symbols[symbol].anchor_price;
+        let reducer = ctx.config.symbols[symbol].anchor_reducer;
+        journal::record(
+            "anchor",
+            json!({
+                "strategy": strategy.name,
+                "symbol": symbol,
+                "open": kline.open_price,
+                "low": kline.low_price,
+                "source": source.to_string(),
+                "anchor": open_price,
+                "reducer": reducer.to_string(),
+                "candles": candles,
+            }),
+        );
+        // the report names the price and reducer when they aren't the default
+        let mut notes = Vec::new();
+        if source != AnchorPrice::Last {
+            notes.push(source.to_string());
+        }
+        if reducer != anchor::Reducer::Open {
+            notes.push(format!("{} of {}", reducer, candles));
+        }
+        let anchor = if notes.is_empty() {
+            open_price.clone()
+        } else {
+            format!("{} ({})", open_price, notes.join(", "))
+        };
+        if ctx.config.symbols[symbol].entry_mode == EntryMode::Twap {
+            let twap = strategy
+                .twap
+                .as_ref()
+                .expect("validated: twap symbols need twap settings");
+            let open: f64 = open_price
+                .parse()
+                .expect("failed converting open to number");
+            let condition = format!(
+                "twap once below {}",
+                open * (1.0 - twap.trigger_percent / 100.0)
+            );
+            report.add(symbol, &anchor, SymbolOutcome::Armed(condition));
+            cycle.twaps.insert(symbol.clone(), open);
+            continue;
+        }
+        if skip_breached {
+            let open: f64 = open_price
+                .parse()
+                .expect("failed converting open to number");
+            let low: f64 = kline
+                .low_price
+                .parse()
+                .expect("failed converting low to number");
+            let first_level = open - (open * strategy.levels[0] / 100.0);
+            if low <= first_level {
+                let reason = format!("low {} already breached first level {}", low, first_level);
+                report.add(symbol, &anchor, SymbolOutcome::Skipped(reason));
+                continue;
+            }
+        }
+        let targeted;
+        let strategy = match strategy.target_position_notional {
+            Some(target) if strategy.sizing == Sizing::TargetPosition => {
+                let open: f64 = open_price
+                    .parse()
+                    .expect("failed converting open to number");
+                match filters::position_headroom(ctx, strategy, target, symbol, open).await {
+                    Ok(check) => {
+                        info!("[{}] {} {}", strategy.name, symbol, check);
+                        report.events.push(format!("{} {}", symbol, check));
+                        match check.decision {
+                            Decision::Place => strategy,
+                            Decision::Skip => {
+                                report.add(
+                                    symbol,
+                                    &anchor,
+                                    SymbolOutcome::Skipped(check.to_string()),
+                                );
+                                continue;
+                            }
+                            Decision::Downsize(share) => {
+                                targeted = strategy.downsized(share);
+                                &targeted
+                            }
+                        }
+                    }
+                    // placing blind could stack past the target
+                    Err(e) => {
+                        let reason = format!("couldn't read the position: {}", e);
+                        report.add(symbol, &anchor, SymbolOutcome::Failed(reason));
+                        continue;
+                    }
+                }
+            }
+            _ => strategy,
+        };
+        let sized;
+        let strategy = match &strategy.open_interest {
+            None => strategy,
+            Some(config) => match filters::open_interest(ctx, strategy, config, symbol).await {
+                Ok(check) => {
+                    info!("[{}] {} {}", strategy.name, symbol, check);
+                    report.events.push(format!("{} {}", symbol, check));
+                    match check.decision {
+                        Decision::Place => strategy,
+                        Decision::Skip => {
+                            report.add(symbol, &anchor, SymbolOutcome::Skipped(check.to_string()));
+                            continue;
+                        }
+                        Decision::Downsize(share) => {
+                            sized = strategy.downsized(share);
+                            &sized
+                        }
+                    }
+                }
+                // the filter only ever holds back, so a failed check places as before
+                Err(e) => {
+                    let line = format!(
+                        "{} open interest check failed, placed in full: {}",
+                        symbol, e
+                    );
+                    println!("[{}] {}", strategy.name, line);
+                    report.events.push(line);
+                    strategy
+                }
+            },
+        };
+        let fitted;
+        let strategy = match &strategy.margin_check {
+            // spot margin entries are held to the borrow limit instead
+            Some(config)
+                if !(strategy.category_of(symbol) == Category::Spot
+                    && ctx.config.symbols[symbol].spot_margin) =>
+            {
+                match filters::margin(ctx, strategy, config, symbol).await {
+                    Ok(check) => {
+                        info!("[{}] {} {}", strategy.name, symbol, check);
+                        report.events.push(format!("{} {}", symbol, check));
+                        match check.decision {
+                            Decision::Place => strategy,
+                            Decision::Skip => {
+                                report.add(
+                                    symbol,
+                                    &anchor,
+                                    SymbolOutcome::Skipped(check.to_string()),
+                                );
+                                continue;
+                            }
+                            Decision::Downsize(share) => {
+                                fitted = strategy.downsized(share);
+                                &fitted
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let line = format!("{} margin check failed, placed in full: {}", symbol, e);
+                        println!("[{}] {}", strategy.name, line);
+                        report.events.push(line);
+                        strategy
+                    }
+                }
+            }
+            _ => strategy,
+        };
+        let category = strategy.category_of(symbol);
+        let instrument = match ctx.instrument_or_refresh(category, symbol).await {
+            Ok(instrument) => instrument,
+            Err(e) => {
+                report.add(symbol, &anchor, SymbolOutcome::Failed(e.to_string()));
+                continue;
+            }
+        };
+        let ladder = ladder::plan_orders(
+            strategy,
+            symbol,
+            &open_price,
+            &instrument,
+            &ctx.config.symbols[symbol],
+            report.open,
+        );
+        let orders = match ladder {
+            Ok(orders) => orders,
+            Err(e) => {
+                report.add(symbol, &anchor, SymbolOutcome::Failed(e.to_string()));
+                continue;
+            }
+        };
+        planned.push(Planned {
+            symbol: symbol.clone(),
+            anchor,
+            open_price,
+            strategy: strategy.clone(),
+            instrument,
+            orders,
+        });
+    }
+    if let Err(breaches) = guard(ctx, &planned).await {
+        let breaches: Vec<String> = breaches.iter().map(|b| b.to_string()).collect();
+        journal::record(
+            "guards_breached",
+            json!({ "strategy": strategy.name, "open": cycle.report.open, "breaches": breaches }),
+        );
+        notify::critical(&format!(
+            "[{}] {} cycle held back, nothing placed: {}",
+            strategy.name,
+            cycle.report.open,
+            breaches.join("; ")
+        ));
+        for plan in planned {
+            let reason = format!("held back by guards: {}", breaches.join("; "));
+            cycle
+                .report
+                .add(&plan.symbol, &plan.anchor, SymbolOutcome::Failed(reason));
+        }
+        return Ok(());
+    }
+    for plan in planned {
+        place_planned(ctx, plan, cycle).await;
+    }
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct CancelOrderData {
+/// A symbol's ladder, sized and ready to send.
+struct Planned {
     symbol: String,
-    #[serde(rename = "orderId")]
-    order_id: String,
+    /// As the cycle report shows it.
+    anchor: String,
+    open_price: String,
+    /// After any downsizing by the filters.
+    strategy: StrategyConfig,
+    instrument: Instrument,
+    orders: Vec<OrderRequest>,
 }
 
-pub async fn get_kline(symbol: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let base_url = env::var("KLINE_URL").expect("KLINE_URL env var is missing");
-    let url = format!("{}&symbol={}", base_url, symbol);
-
-    let response = reqwest::get(&url).await?;
+/// Holds the planned ladders to `[guards]`, reading each symbol's 24h
+/// volume when `max_volume_multiple` is set. A volume that can't be read
+/// leaves that symbol unchecked by it.
+async fn guard(ctx: &Context, planned: &[Planned]) -> Result<(), Vec<guards::Breach>> {
+    let bounds = &ctx.config.guards;
+    if bounds.is_empty() {
+        return Ok(());
+    }
+    let mut volumes = BTreeMap::new();
+    if bounds.max_volume_multiple.is_some() {
+        for plan in planned {
+            let category = plan.strategy.category_of(&plan.symbol);
+            match ctx.client.get_ticker(category, &plan.symbol).await {
+                Ok(ticker) => match ticker.volume_24h.parse::<f64>() {
+                    Ok(volume) => {
+                        volumes.insert(plan.symbol.as_str(), volume);
+                    }
+                    Err(_) => println!(
+                        "[{}] {} 24h volume {:?} unreadable, volume guard not checked",
+                        plan.strategy.name, plan.symbol, ticker.volume_24h
+                    ),
+                },
+                Err(e) => println!(
+                    "[{}] {} volume guard not checked: {}",
+                    plan.strategy.name, plan.symbol, e
+                ),
+            }
+        }
+    }
+    let orders: Vec<guards::Order> = planned
+        .iter()
+        .flat_map(|plan| {
+            let anchor: f64 = plan.open_price.parse().unwrap_or(f64::NAN);
+            plan.orders.iter().map(move |order| {
+                // market orders carry no price, so they're valued at the anchor
+                let price = order.price.parse().unwrap_or(anchor);
+                let qty: f64 = order.qty.parse().unwrap_or(f64::NAN);
+                // a quote amount on a spot market order
+                let (qty, notional) = if order.market_unit.is_some() {
+                    (qty / price, qty)
+                } else {
+                    (qty, qty * price)
+                };
+                guards::Order {
+                    symbol: &plan.symbol,
+                    qty,
+                    notional,
+                }
+            })
+        })
+        .collect();
+    // each symbol's cycle runs on its own, so the strategy's entries on
+    // other symbols count toward the cycle total
+    let symbols: Vec<&str> = planned.iter().map(|plan| plan.symbol.as_str()).collect();
+    let resting = planned.first().map_or(0.0, |plan| {
+        ctx.store.read(|state| {
+            state
+                .strategies
+                .get(&plan.strategy.name)
+                .into_iter()
+                .flat_map(|tracked| &tracked.entries)
+                .filter(|entry| !symbols.contains(&entry.symbol.as_str()))
+                .map(|entry| {
+                    let qty = entry.qty.parse::<f64>().unwrap_or(0.0);
+                    qty * entry.price.parse::<f64>().unwrap_or(0.0)
+                })
+                .sum()
+        })
+    });
+    let breaches = guards::check(bounds, &orders, &volumes, resting);
+    if breaches.is_empty() {
+        Ok(())
+    } else {
+        Err(breaches)
+    }
+}
 
-    let api_response: ApiResponse<KlineData> = response.json().await?;
+/// Sends a planned ladder and records what was placed.
+async fn place_planned(ctx: &Context, plan: Planned, cycle: &mut Cycle) {
+    let Planned {
+        symbol,
+        anchor,
+        open_price,
+        strategy,
+        instrument,
+        orders,
+    } = plan;
+    let (symbol, strategy) = (&symbol, &strategy);
+    let category = strategy.category_of(symbol);
+    let report = &mut cycle.report;
+    info!(
+        "[{}] Placing batch order for {}, open price: {}",
+        strategy.name, symbol, open_price
+    );
+    let placement = ctx.client.place_batch_order(category, &orders).await;
+    ctx.lifecycle
+        .placed(&strategy.name, category, &orders, &placement);
+    match placement {
+        Ok(placement) => {
+            ctx.note_rejections(strategy, &placement.rejected).await;
+            for rejection in &placement.rejected {
+                report.events.push(format!(
+                    "{} {} rejected: {}",
+                    symbol, rejection.order_link_id, rejection.msg
+                ));
+            }
+            let placed = placement.placed;
+            let sent: Vec<_> = orders
+                .iter()
+                .filter(|order| {
+                    placed
+                        .iter()
+                        .any(|response| response.order_link_id == order.order_link_id)
+                })
+                .cloned()
+                .collect();
+            let notional: f64 = sent.iter().map(ladder::notional).sum();
+            report.add_placed(
+                symbol,
+                &anchor,
+                ladder::describe(strategy, &instrument, &sent),
+                notional,
+                strategy.budgets.iter().sum(),
+            );
+            // a split level's children are placed as that one level
+            let parents: BTreeSet<&str> = sent
+                .iter()
+                .map(|order| ladder::parent_link_id(&order.order_link_id))
+                .collect();
+            let levels: Vec<(&str, String)> = parents
+                .iter()
+                .filter_map(|parent| Some((*parent, ladder::level_percent(strategy, parent)?)))
+                .collect();
+            ctx.store.update_strategy(&strategy.name, |state| {
+                state
+                    .entries
+                    .extend(StateOrder::placed(strategy, &orders, &placed));
+                state.counters.placed += parents.len() as u64;
+                state.counters.notional_deployed += notional;
+                state.record_placed(&levels);
+                state.placed_opens.insert(symbol.clone(), report.open);
+            });
+            cycle
+                .tracked
+                .extend(placed.iter().map(CancelOrderData::from));
+            if let Ok(anchor) = open_price.parse() {
+                cycle.anchors.insert(symbol.to_string(), anchor);
+            }
+        }
+        Err(e) => report.add(symbol, &anchor, SymbolOutcome::Failed(e.to_string())),
+    }
+}
 
-    let first_kline = api_response.result.list.first().unwrap();
-    Ok((symbol.to_string(), first_kline.open_price.clone()))
+/// Starts `symbol`'s cycle for `open`, retrying through exchange
+/// maintenance until the configured deadline. `None` when the cycle had to
+/// be skipped.
+async fn start_cycle(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+    open: DateTime<Utc>,
+    late: bool,
+) -> Option<Cycle> {
+    let maintenance = &ctx.config.maintenance;
+    let deadline = open + ChronoDuration::minutes(maintenance.deadline_minutes as i64);
+    let retry = ChronoDuration::minutes(maintenance.retry_minutes as i64);
+    let mut late = late;
+    loop {
+        journal::record(
+            "cycle_started",
+            json!({ "strategy": strategy.name, "symbol": symbol, "open": open, "late": late }),
+        );
+        let mut cycle = Cycle::new(&strategy.name, open);
+        let symbols = [symbol.to_string()];
+        let Err(e) = place_ladders(ctx, strategy, &symbols, late, &mut cycle).await else {
+            return Some(cycle);
+        };
+        let next = Utc::now() + retry;
+        if next > deadline {
+            notify::send(&format!(
+                "[{}] skipped the {} {} cycle, {} past {}",
+                strategy.name, symbol, open, e, deadline
+            ));
+            return None;
+        }
+        println!(
+            "[{}] {} {}, retrying at {}",
+            strategy.name,
+            symbol,
+            Scrubbed(&e),
+            next
+        );
+        schedule::sleep_until(next).await;
+        // the candle has been trading while we waited
+        late = true;
+    }
 }
 
-fn generate_post_signature(
-    timestamp: &str,
-    api_key: &str,
-    recv_window: &str,
-    params: &serde_json::Map<String, Value>,
-    api_secret: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let mut mac =
-        HmacSha256::new_from_slice(api_secret.as_bytes()).expect("HMAC can take key of any size");
-    mac.update(timestamp.as_bytes());
-    mac.update(api_key.as_bytes());
-    mac.update(recv_window.as_bytes());
-    mac.update(serde_json::to_string(&params)?.as_bytes());
-
-    let result = mac.finalize();
-    let code_bytes = result.into_bytes();
-    Ok(hex::encode(code_bytes))
+/// Starts a TWAP entry for each symbol the cycle armed, running until the
+/// symbol's cancel time.
+fn start_twaps(
+    ctx: &Arc<Context>,
+    strategy: &StrategyConfig,
+    cycle: &mut Cycle,
+    cancel_at: &BTreeMap<String, DateTime<Utc>>,
+) {
+    for (symbol, anchor) in cycle.twaps.drain() {
+        let until = cancel_at[&symbol];
+        ctx.spawn_entry(twap::run(
+            ctx.clone(),
+            strategy.clone(),
+            symbol,
+            cycle.report.open,
+            anchor,
+            until,
+        ));
+    }
 }
 
-fn calculate_position(price: &f64, symbol: &str) -> Option<FormattedPosition> {
-    println!("cal price: {}, symbol: {}", price, symbol);
-    let price = Price {
-        twenty_percent_price: price - (price * 0.2),
-        twenty_five_percent_price: price - (price * 0.25),
-        thirty_percent_price: price - (price * 0.3),
+/// Resolves on Ctrl-C, or SIGTERM on unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed listening for ctrl-c");
     };
-    let size = Quantity {
-        twenty_percent_size: 1000.0 / price.twenty_percent_price,
-        twenty_five_percent_size: 1000.0 / price.twenty_five_percent_price,
-        thirty_percent_size: 2000.0 / price.thirty_percent_price,
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed listening for SIGTERM")
+            .recv()
+            .await;
     };
-
-    //ideally i'd hit the intrument info api to get the tickSize and qtyStep
-    //when starting the app
-
-    let formatted_position = match symbol {
-        "ALTUSDT" => FormattedPosition {
-            twenty_percent_price: format!("{:.4}", price.twenty_percent_price),
-            twenty_five_percent_price: format!("{:.4}", price.twenty_five_percent_price),
-            thirty_percent_price: format!("{:.4}", price.thirty_percent_price),
-            twenty_percent_size: format!("{}", size.twenty_percent_size.round()),
-            twenty_five_percent_size: format!("{}", size.twenty_five_percent_size.round()),
-            thirty_percent_size: format!("{}", size.thirty_percent_size.round()),
-        },
-        "MANTAUSDT" => FormattedPosition {
-            twenty_percent_price: format!("{:.4}", price.twenty_percent_price),
-            twenty_five_percent_price: format!("{:.4}", price.twenty_five_percent_price),
-            thirty_percent_price: format!("{:.4}", price.thirty_percent_price),
-            twenty_percent_size: format!("{:.1}", size.twenty_percent_size.round()),
-            twenty_five_percent_size: format!("{:.1}", size.twenty_five_percent_size.round()),
-            thirty_percent_size: format!("{:.1}", size.thirty_percent_size.round()),
-        },
-        "TAOUSDT" => FormattedPosition {
-            twenty_percent_price: format!("{:.2}", price.twenty_percent_price),
-            twenty_five_percent_price: format!("{:.2}", price.twenty_five_percent_price),
-            thirty_percent_price: format!("{:.2}", price.thirty_percent_price),
-            twenty_percent_size: format!("{:.3}", size.twenty_percent_size.round()),
-            twenty_five_percent_size: format!("{:.3}", size.twenty_five_percent_size.round()),
-            thirty_percent_size: format!("{:.3}", size.thirty_percent_size.round()),
-        },
-        _ => return None,
-    };
-
-    Some(formatted_position)
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
 
-async fn place_batch_order(
-    api_key: &str,
-    api_secret: &str,
-    recv_window: &str,
-    batch_order_url: &str,
-    symbol: &str,
-    price: &str,
-) -> Result<Vec<CancelOrderData>, Box<dyn std::error::Error>> {
-    let timestamp = Utc::now().timestamp_millis().to_string();
-    let price_num: f64 = price.parse().expect("failed converting price to number");
-    let position = calculate_position(&price_num, symbol).expect("Failed calculating position");
-    println!(
-        "ticker: {},open price: {}, price: {}, {}, {}, size: {}, {}, {}",
-        symbol,
-        price,
-        position.twenty_percent_price,
-        position.twenty_five_percent_price,
-        position.thirty_percent_price,
-        position.twenty_percent_size,
-        position.twenty_five_percent_size,
-        position.thirty_percent_size
-    );
-    let client = Client::new();
-    let parameters: [OrderRequest; 3] = [
-        OrderRequest {
-            symbol: symbol.to_string(),
-            side: "Buy".to_string(),
-            order_type: "Limit".to_string(),
-            qty: position.twenty_percent_size,
-            price: position.twenty_percent_price,
-        },
-        OrderRequest {
-            symbol: symbol.to_string(),
-            side: "Buy".to_string(),
-            order_type: "Limit".to_string(),
-            qty: position.twenty_five_percent_size,
-            price: position.twenty_five_percent_price,
-        },
-        OrderRequest {
-            symbol: symbol.to_string(),
-            side: "Buy".to_string(),
-            order_type: "Limit".to_string(),
-            qty: position.thirty_percent_size,
-            price: position.thirty_percent_price,
-        },
-    ];
-    let mut params = serde_json::Map::new();
-    params.insert("category".to_string(), json!("linear"));
-    params.insert("request".to_string(), json!(&parameters));
-
-    let signature = generate_post_signature(&timestamp, api_key, recv_window, &params, api_secret)?;
-
-    let response = client
-        .post(batch_order_url)
-        .json(&params)
-        .header("X-BAPI-API-KEY", api_key)
-        .header("X-BAPI-SIGN", &signature)
-        .header("X-BAPI-SIGN-TYPE", "2")
-        .header("X-BAPI-TIMESTAMP", &timestamp)
-        .header("X-BAPI-RECV-WINDOW", recv_window)
-        .header("Content-Type", "application/json")
-        .send()
-        .await?;
+/// Runs `strategy`'s cycles on `symbol`, each with its own hold and cancel,
+/// independently of its other symbols.
+async fn run_strategy(
+    ctx: Arc<Context>,
+    strategy: StrategyConfig,
+    symbol: String,
+    mut open: DateTime<Utc>,
+    mut late: bool,
+) {
+    let mut note = ctx.note.clone();
+    loop {
+        let stagger = schedule::stagger_offset(&ctx.config, &symbol, open);
+        let placement = schedule::placement_at(&ctx.config.schedule, open) + stagger;
+        let staggered = if ctx.config.schedule.placement_stagger.is_some() {
+            format!(
+                ", {:.1}s into the stagger",
+                stagger.num_milliseconds() as f64 / 1000.0
+            )
+        } else {
+            String::new()
+        };
+        info!(
+            "[{}] {} next placement at {} for the {} open{}",
+            strategy.name,
+            symbol,
+            schedule::describe(&ctx.config.schedule, placement),
+            open,
+            staggered
+        );
+        schedule::sleep_until(placement).await;
+        let Some(mut cycle) = start_cycle(&ctx, &strategy, &symbol, open, late).await else {
+            open = schedule::following_open(open, strategy.interval, Utc::now());
+            late = false;
+            continue;
+        };
+        if ctx.config.schedule.placement_stagger.is_some() {
+            cycle
+                .report
+                .note_stagger(&symbol, stagger.num_milliseconds());
+        }
+        if let Some(note) = note.take() {
+            cycle.note(&ctx.store, &note);
+        }
 
-    let response_data: ApiResponse<BatchOrderResult> = response.json().await?;
-    println!("Response: {:#?}", response_data);
+        let cancel_at = schedule::cancel_times(&ctx.config, &strategy, open);
+        start_twaps(&ctx, &strategy, &mut cycle, &cancel_at);
+        if strategy.allow_overlap {
+            // the next placement may come before this hold ends
+            let (ctx, strategy) = (ctx.clone(), strategy.clone());
+            tokio::spawn(async move { finish_cycle(&ctx, &strategy, cycle, cancel_at).await });
+        } else {
+            finish_cycle(&ctx, &strategy, cycle, cancel_at).await;
+        }
+        open = schedule::following_open(open, strategy.interval, Utc::now());
+        late = false;
+    }
+}
 
-    let cancel_order_data: Vec<CancelOrderData> = response_data
-        .result
-        .list
+/// Places the one strategy of a `place` run on its symbols now, holds it
+/// and cancels what's left, like a triggered cycle.
+async fn run_ad_hoc(ctx: &Arc<Context>) {
+    let strategy = ctx.config.strategies[0].clone();
+    let open = Utc::now();
+    let mut cycle = Cycle::new(&strategy.name, open);
+    if let Some(note) = &ctx.note {
+        cycle.note(&ctx.store, note);
+    }
+    let symbols: Vec<String> = ctx.config.symbols.keys().cloned().collect();
+    if let Err(e) = place_ladders(ctx, &strategy, &symbols, true, &mut cycle).await {
+        println!("[{}] not placed: {}", strategy.name, Scrubbed(&e));
+        std::process::exit(1);
+    }
+    let cancel_at = symbols
         .iter()
-        .map(|order_response| CancelOrderData {
-            symbol: order_response.symbol.clone(),
-            order_id: order_response.order_id.clone(),
+        .map(|symbol| {
+            let hold = strategy.hold_for(ctx.config.symbols.get(symbol));
+            (symbol.clone(), open + hold)
         })
         .collect();
-
-    Ok(cancel_order_data)
+    start_twaps(ctx, &strategy, &mut cycle, &cancel_at);
+    finish_cycle(ctx, &strategy, cycle, cancel_at).await;
+    ctx.stop_entries().await;
 }
 
-async fn cancel_batch_order(
-    api_key: &str,
-    api_secret: &str,
-    recv_window: &str,
-    batch_cancel_order_url: &str,
-    cancel_order_data: &Vec<CancelOrderData>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let client = Client::new();
-    let timestamp = Utc::now().timestamp_millis().to_string();
-    let mut params = serde_json::Map::new();
-    params.insert("category".to_string(), json!("linear"));
-    params.insert("request".to_string(), json!(cancel_order_data));
-
-    let signature = generate_post_signature(&timestamp, api_key, recv_window, &params, api_secret)?;
-
-    let response = client
-        .post(batch_cancel_order_url)
-        .json(&params)
-        .header("X-BAPI-API-KEY", api_key)
-        .header("X-BAPI-SIGN", &signature)
-        .header("X-BAPI-SIGN-TYPE", "2")
-        .header("X-BAPI-TIMESTAMP", &timestamp)
-        .header("X-BAPI-RECV-WINDOW", recv_window)
-        .header("Content-Type", "application/json")
-        .send()
-        .await?;
+/// Cancels `due`, a share of the cycle's entries whose hold is over.
+async fn cancel_due(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    cycle: &mut Cycle,
+    due: &[CancelOrderData],
+) {
+    if due.is_empty() {
+        return;
+    }
+    let swept = match strategy.cancel_mode {
+        CancelMode::Tracked => ctx.cancel_entries(strategy, due).await,
+        CancelMode::SymbolAll => {
+            ctx.cancel_all_on_symbols(strategy, due)
+                .await
+                .map(|(outcomes, link_ids)| {
+                    cycle.report.events.push(format!(
+                        "cancel-all cancelled {} orders: {}",
+                        link_ids.len(),
+                        link_ids.join(", ")
+                    ));
+                    outcomes
+                })
+        }
+    };
+    let outcomes = match swept {
+        Ok(outcomes) => {
+            let cancelled: Vec<&str> = outcomes
+                .iter()
+                .filter(|outcome| outcome.is_cancelled())
+                .map(|outcome| outcome.order_id.as_str())
+                .collect();
+            for outcome in &outcomes {
+                if outcome.is_failed() {
+                    println!(
+                        "[{}] {} not cancelled: {} {}",
+                        strategy.name, outcome.order_id, outcome.code, outcome.msg
+                    );
+                    continue;
+                }
+                // already looked up; exits for a fill are left to the fill check
+                let note = match outcome.settled {
+                    Some(Settled::Filled) => "filled before its cancel",
+                    Some(Settled::ClosedElsewhere) => "was closed outside the bot",
+                    Some(Settled::Missing) => "is unknown to the exchange, marked lost",
+                    Some(Settled::Open) | None => continue,
+                };
+                let label = ctx
+                    .store
+                    .entry(&strategy.name, &outcome.order_id)
+                    .map(|entry| format!("{} ({})", entry.label(), outcome.order_id))
+                    .unwrap_or_else(|| outcome.order_id.clone());
+                cycle
+                    .report
+                    .events
+                    .push(format!("{} {} {}", outcome.symbol, label, note));
+            }
+            if !strategy.watches_fills() {
+                // otherwise the fill check sees how each one ended
+                let entries: Vec<StateOrder> = ctx.store.read(|state| {
+                    state
+                        .strategies
+                        .get(&strategy.name)
+                        .into_iter()
+                        .flat_map(|tracked| &tracked.entries)
+                        .filter(|entry| cancelled.contains(&entry.order_id.as_str()))
+                        .cloned()
+                        .collect()
+                });
+                ctx.lifecycle.cancelled(strategy, &entries);
+            }
+            // the children of a split level count as that one level
+            let levels: HashSet<String> = cancelled
+                .iter()
+                .map(|order_id| match ctx.store.entry(&strategy.name, order_id) {
+                    Some(entry) => ladder::parent_link_id(&entry.order_link_id).to_string(),
+                    None => order_id.to_string(),
+                })
+                .collect();
+            cycle.report.cancelled += levels.len();
+            ctx.store.update_strategy(&strategy.name, |state| {
+                state.counters.cancelled += levels.len() as u64
+            });
+            outcomes
+        }
+        Err(e) => {
+            println!(
+                "[{}] Failed canceling orders: {}",
+                strategy.name,
+                Scrubbed(e)
+            );
+            Vec::new()
+        }
+    };
+    reconcile::after_sweep(ctx, strategy, cycle, due, &outcomes).await;
+}
 
-    println!("cancel response = {}", response.text().await?);
-    Ok(())
+/// Holds a placed cycle, cancelling each symbol's entries at its own
+/// `cancel_at`, then handles late fills and sends the report.
+async fn finish_cycle(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    mut cycle: Cycle,
+    cancel_at: BTreeMap<String, DateTime<Utc>>,
+) {
+    let mut instants: Vec<DateTime<Utc>> = cycle
+        .tracked
+        .iter()
+        .filter_map(|order| cancel_at.get(&order.symbol).copied())
+        .collect();
+    instants.sort();
+    instants.dedup();
+    for (index, instant) in instants.into_iter().enumerate() {
+        let pending = |order: &CancelOrderData| cancel_at.get(&order.symbol) == Some(&instant);
+        if index > 0 && !cycle.tracked.iter().any(pending) {
+            // swept early along with an overdue instant
+            continue;
+        }
+        debug!(
+            "[{}] holding until {}: {:#?}",
+            strategy.name,
+            schedule::describe(&ctx.config.schedule, instant),
+            &cycle.tracked
+        );
+        hold::hold(ctx, strategy, &mut cycle, instant).await;
+        if index == 0 {
+            // taken before the first cancel, while the entries still count as exposure
+            match equity::record(ctx, &strategy.name).await {
+                Ok(snapshot) => cycle.report.events.push(format!(
+                    "equity {:.2}, exposure {:.2}, realized {:.2}",
+                    snapshot.equity, snapshot.exposure, snapshot.realized
+                )),
+                Err(e) => println!(
+                    "[{}] equity snapshot failed: {}",
+                    strategy.name,
+                    Scrubbed(e)
+                ),
+            }
+        }
+        // a hold that ran late sweeps every overdue symbol, those due
+        // first (so placing again soonest) first
+        let now = instant.max(Utc::now());
+        let mut due: Vec<CancelOrderData> = cycle
+            .tracked
+            .iter()
+            .filter(|order| cancel_at.get(&order.symbol).is_some_and(|at| *at <= now))
+            .cloned()
+            .collect();
+        due.sort_by_key(|order| (cancel_at[&order.symbol], order.symbol.clone()));
+        cancel_due(ctx, strategy, &mut cycle, &due).await;
+        // a symbol whose hold is over isn't re-anchored any more
+        for order in &due {
+            if let Some(anchor) = cycle.anchors.remove(&order.symbol) {
+                cycle.ended.insert(order.symbol.clone(), anchor);
+            }
+        }
+    }
+    if strategy.watches_fills() {
+        // Orders partially filled before the cancel still need handling.
+        fills::check_fills(ctx, strategy, &mut cycle).await;
+    }
+    // other symbols' cycles of the strategy may still be holding theirs
+    let symbols: HashSet<&str> = cycle
+        .report
+        .symbols
+        .iter()
+        .map(|report| report.symbol.as_str())
+        .collect();
+    ctx.store.update_strategy(&strategy.name, |state| {
+        state
+            .entries
+            .retain(|entry| !symbols.contains(entry.symbol.as_str()))
+    });
+    positions::snapshot(ctx, strategy, &mut cycle).await;
+    let (signed, public) = ctx.client.limiter_stats();
+    cycle.report.events.push(format!(
+        "rate limiter waits so far: signed {:.2?} over {} requests, public {:.2?} over {}",
+        signed.waited, signed.requests, public.waited, public.requests
+    ));
+    let latency = ctx.client.latency();
+    if let Some(stats) = latency.summary().get(latency.watched()) {
+        cycle
+            .report
+            .events
+            .push(format!("order endpoint latency: {}", stats));
+    }
+    let cooldown = ctx.client.cooldown().status();
+    if cooldown.entered > 0 {
+        cycle.report.events.push(format!(
+            "API cooldowns so far: {}, {} calls suspended{}",
+            cooldown.entered,
+            cooldown.suspended,
+            match cooldown.until {
+                Some(until) => format!(", cooling down until {}", until),
+                None => String::new(),
+            }
+        ));
+    }
+    cycle.report.fill_stats = ctx.store.read(|state| {
+        state
+            .strategies
+            .get(&strategy.name)
+            .map(|tracked| tracked.fill_stats())
+            .unwrap_or_default()
+    });
+    info!("{}", cycle.report);
+    journal::record("cycle_finished", json!(cycle.report));
+    notify::report(&cycle.report);
+    if let Err(e) = report::write_markdown(&ctx.config.reports_dir, &cycle.report) {
+        println!("WARNING: failed writing report: {}", e);
+    }
 }
 
-#[tokio::main]
-async fn main() {
-    dotenv().ok();
-    let api_key = env::var("API_KEY").expect("api key is missing");
-    let api_secret = env::var("API_SECRET").expect("api secret is missing");
-    let recv_window = "10000";
-    let batch_order_url = env::var("BATCH_ORDER_URL").expect("batch order url is missing");
-    let batch_cancel_order_url =
-        env::var("BATCH_CANCEL_ORDER_URL").expect("batch cancel order url is missing");
+/// Env vars whose values are scrubbed from everything printed or sent.
+const SECRET_VARS: [&str; 11] = [
+    "API_KEY",
+    "API_SECRET",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+    "AWS_CONTAINER_AUTHORIZATION_TOKEN",
+    "VAULT_TOKEN",
+    "VAULT_SECRET_ID",
+    "HTTP_TOKEN",
+    "SMTP_PASSWORD",
+    "NTFY_TOKEN",
+    "SLACK_WEBHOOK_URL",
+];
 
-    loop {
-        let symbols = vec!["ALTUSDT", "MANTAUSDT", "TAOUSDT"];
-        let futures = symbols.into_iter().map(|symbol| get_kline(&symbol));
-        let results = futures::future::join_all(futures).await;
-        let mut cancel_order_data: Vec<CancelOrderData> = Vec::new();
+/// How often our clock is measured against the exchange's after startup.
+const CLOCK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
-        for result in results {
-            if let Ok((symbol, open_price)) = result {
+/// How often the API key is reread for its expiry after startup.
+const KEY_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+#[tokio::main]
+async fn main() {
+    scrub::install_panic_hook();
+    let cli = Cli::parse();
+    if let Some(name) = &cli.profile {
+        std::env::set_var(profile::ENV_VAR, name);
+    }
+    match Config::active_profile() {
+        Some(name) => {
+            if let Err(e) = profile::check_name(&name) {
+                println!("error: {}", e);
+                std::process::exit(1);
+            }
+            // the profile's env file alone, so another's credentials can't
+            // leak in from .env
+            let env_file = Config::profile_env_file(&name);
+            if let Err(e) = profile::load_env(&env_file) {
                 println!(
-                    "Placing batch order for {}, open price: {}",
-                    symbol, open_price
+                    "error: profile {} takes its credentials from {}: {}",
+                    name, env_file, e
                 );
-                let cancel_data = place_batch_order(
-                    &api_key,
-                    &api_secret,
-                    &recv_window,
-                    &batch_order_url,
-                    &symbol,
-                    &open_price,
+                std::process::exit(1);
+            }
+        }
+        None => {
+            dotenv().ok();
+        }
+    }
+    for var in SECRET_VARS {
+        if let Ok(secret) = std::env::var(var) {
+            scrub::register(&secret);
+        }
+    }
+    let (level, source) = verbosity::resolve(
+        cli.verbose,
+        cli.quiet,
+        std::env::var("RUST_LOG").ok().as_deref(),
+    );
+    verbosity::set(level, source);
+    if let Some(Command::Validate {
+        config,
+        instruments,
+        online,
+        save_instruments,
+    }) = &cli.command
+    {
+        let path = config.clone().unwrap_or_else(Config::path);
+        let config = match Config::load_from(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                println!("error: {}: {}", path, Scrubbed(e));
+                std::process::exit(1);
+            }
+        };
+        let tickers = if *online {
+            let client = BybitClient::public(&config);
+            match validate::fetch_tickers(&client, &config).await {
+                Ok(tickers) => Some(tickers),
+                Err(e) => {
+                    println!("failed fetching tickers: {}", Scrubbed(e));
+                    std::process::exit(1);
+                }
+            }
+        } else {
+            None
+        };
+        let snapshot = match (instruments, online) {
+            (Some(snapshot), _) => match validate::load_snapshot(snapshot) {
+                Ok(snapshot) => Some(snapshot),
+                Err(e) => {
+                    println!("failed reading instruments snapshot {}: {}", snapshot, e);
+                    std::process::exit(1);
+                }
+            },
+            (None, true) => {
+                let client = BybitClient::public(&config);
+                match validate::fetch_snapshot(&client, &config).await {
+                    Ok(snapshot) => Some(snapshot),
+                    Err(e) => {
+                        println!("failed fetching instruments: {}", Scrubbed(e));
+                        std::process::exit(1);
+                    }
+                }
+            }
+            (None, false) => None,
+        };
+        if let (Some(out), Some(snapshot)) = (save_instruments, &snapshot) {
+            let written = serde_json::to_string_pretty(snapshot)
+                .map_err(BoxError::from)
+                .and_then(|json| Ok(std::fs::write(out, json)?));
+            match written {
+                Ok(()) => println!("saved instruments to {}", out),
+                Err(e) => println!("failed saving instruments to {}: {}", out, e),
+            }
+        }
+        let findings = validate::check(&config, snapshot.as_ref(), tickers.as_ref());
+        println!("{}", findings);
+        std::process::exit(if findings.passed() { 0 } else { 1 });
+    }
+    if let Some(Command::Doctor { send_test }) = &cli.command {
+        let checklist = doctor::run(*send_test).await;
+        println!("{}", checklist);
+        std::process::exit(if checklist.passed() { 0 } else { 1 });
+    }
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            println!("failed loading config: {}", Scrubbed(e));
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(Command::Status { latency, pnl }) = cli.command {
+        match State::load(&config.state_path) {
+            Ok(state) if latency => println!("{}", state.latency_table()),
+            Ok(state) if pnl => {
+                let client = match credentials::fetch(&config).await {
+                    Ok(credentials) => BybitClient::new(&config, credentials),
+                    Err(e) => {
+                        println!("{}", Scrubbed(e));
+                        return;
+                    }
+                };
+                let lines = ledger::pnl(&client, &state.ledger).await;
+                if lines.is_empty() {
+                    println!("no fills booked");
+                }
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            Ok(state) => println!("{}", state),
+            Err(e) => println!("failed reading state {}: {}", config.state_path, e),
+        }
+        return;
+    }
+    if let Some(Command::Audit { day, order }) = &cli.command {
+        if day.is_none() && order.is_none() {
+            println!("audit needs --day or --order");
+            return;
+        }
+        let dir = config.journal.as_ref().map_or_else(
+            || storage::resolve(&config.data_dir, &JournalConfig::default().dir),
+            |journal| journal.dir.clone(),
+        );
+        let notes = State::load(&config.state_path)
+            .map(|state| state.notes)
+            .unwrap_or_default();
+        match audit::timeline(&dir, *day, order.as_deref(), &notes) {
+            Ok(timeline) => println!("{}", timeline),
+            Err(e) => println!("{}", e),
+        }
+        return;
+    }
+    if let Some(Command::Report { kind }) = cli.command {
+        match (State::load(&config.state_path), kind) {
+            (Ok(state), ReportKind::Equity { since }) => {
+                println!("{}", equity::export(&state, since))
+            }
+            (Ok(state), ReportKind::Notes { since }) => {
+                println!("{}", notes::export(&state, since))
+            }
+            (Err(e), _) => println!("failed reading state {}: {}", config.state_path, e),
+        }
+        return;
+    }
+    if let Some(Command::Analyze {
+        kind:
+            AnalyzeKind::Drawdowns {
+                symbol,
+                months,
+                category,
+                target,
+                levels,
+                json,
+            },
+    }) = &cli.command
+    {
+        let klines = match daily_klines(&config, *category, symbol, *months).await {
+            Ok(klines) => klines,
+            Err(e) => {
+                println!("failed reading {} klines: {}", symbol, Scrubbed(e));
+                std::process::exit(1);
+            }
+        };
+        let drawdowns: Vec<f64> = klines
+            .iter()
+            .filter_map(|kline| {
+                drawdowns::drawdown(
+                    kline.open_price.parse().ok()?,
+                    kline.low_price.parse().ok()?,
                 )
-                .await
-                .expect("Error placing order");
+            })
+            .collect();
+        let analysis = drawdowns::analyze(symbol, &drawdowns, *target, *levels);
+        if *json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&analysis).expect("analysis serializes")
+            );
+        } else {
+            println!("{}", analysis);
+        }
+        return;
+    }
+    if let Some(Command::Optimize {
+        symbol,
+        budget,
+        levels,
+        months,
+        category,
+        bounce,
+        samples,
+        seed,
+        write,
+    }) = &cli.command
+    {
+        if *budget <= 0.0 {
+            println!("--budget must be positive");
+            std::process::exit(1);
+        }
+        let candidates = match levels
+            .iter()
+            .map(|levels| optimize::parse_levels(levels))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                println!("bad --levels: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let klines = match daily_klines(&config, *category, symbol, *months).await {
+            Ok(klines) => klines,
+            Err(e) => {
+                println!("failed reading {} klines: {}", symbol, Scrubbed(e));
+                std::process::exit(1);
+            }
+        };
+        let days: Vec<optimize::Day> = klines
+            .iter()
+            .filter_map(|kline| {
+                Some(optimize::Day {
+                    open: kline.open_price.parse().ok()?,
+                    low: kline.low_price.parse().ok()?,
+                    close: kline.close_price.parse().ok()?,
+                })
+            })
+            .collect();
+        let rankings = optimize::rank(&days, &candidates, *budget, *bounce, *samples, *seed);
+        println!("{}", optimize::LABEL);
+        println!(
+            "{} over {} days, budget {}, exits {}% above the fill or at the close",
+            symbol,
+            days.len(),
+            budget,
+            bounce
+        );
+        println!("{}", optimize::header());
+        for ranking in &rankings {
+            println!("{}", ranking);
+        }
+        if let (Some(path), Some(best)) = (write, rankings.first()) {
+            let snippet = optimize::snippet(best, *budget, symbol, days.len());
+            match std::fs::write(path, snippet) {
+                Ok(()) => println!("wrote {}", path.display()),
+                Err(e) => println!("failed writing {}: {}", path.display(), e),
+            }
+        }
+        return;
+    }
+    if let Some(Command::Stats {
+        kind: StatsKind::Fills { strategy },
+    }) = &cli.command
+    {
+        match State::load(&config.state_path) {
+            Ok(state) => {
+                let lines = state.time_to_fill(strategy.as_deref());
+                if lines.is_empty() {
+                    println!("no placements recorded");
+                }
+                for line in lines {
+                    println!("{}", line);
+                }
+            }
+            Err(e) => println!("failed reading state {}: {}", config.state_path, e),
+        }
+        return;
+    }
+    if let Some(Command::Note {
+        action: NoteAction::Add {
+            order_link_id,
+            text,
+        },
+    }) = &cli.command
+    {
+        match notes::add(&config, order_link_id, text).await {
+            Ok(()) => println!("noted {}", order_link_id),
+            Err(e) => {
+                println!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let (note, refresh_instruments, ad_hoc) = match cli.command {
+        Some(Command::Run {
+            note,
+            refresh_instruments,
+        }) => (note, refresh_instruments, None),
+        Some(Command::Place {
+            symbols,
+            budget,
+            levels,
+            hold,
+            strategy,
+            note,
+        }) => {
+            let ad_hoc = AdHoc {
+                symbols,
+                budget,
+                levels,
+                hold,
+                strategy,
+            };
+            (note, false, Some(ad_hoc))
+        }
+        _ => (None, false, None),
+    };
+    let mut config = config;
+    if let Some(ad_hoc) = &ad_hoc {
+        if let Err(e) = config.ad_hoc(ad_hoc) {
+            println!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(e) = config.create_layout() {
+        println!("failed creating data_dir {}: {}", config.data_dir, e);
+        std::process::exit(1);
+    }
+    // where the state file was kept before data_dir
+    if !std::path::Path::new(&config.state_path).exists()
+        && std::path::Path::new("state.json").exists()
+    {
+        println!(
+            "WARNING: found state.json in the working directory but none at {}. Move it there, or set data_dir = \".\" to keep using it",
+            config.state_path
+        );
+    }
+    if let Some(settings) = &config.journal {
+        journal::start(settings, config.profile.as_deref());
+    }
+    if let Some(ad_hoc) = &ad_hoc {
+        journal::record(
+            "adhoc_run",
+            json!({
+                "symbols": config.symbols.keys().collect::<Vec<_>>(),
+                "budget": ad_hoc.budget,
+                "levels": ad_hoc.levels,
+                "hold_secs": config.strategies[0].hold().num_seconds(),
+            }),
+        );
+    }
+    let mut notifiers: Vec<Arc<dyn notify::Notifier>> = Vec::new();
+    if let Some(settings) = &config.email {
+        match EmailNotifier::new(settings) {
+            Ok(email) => {
+                let email = Arc::new(email);
+                let digest_hour = settings.digest_hour;
+                let digest = email.clone();
+                tokio::spawn(async move {
+                    loop {
+                        schedule::sleep_until(schedule::next_hour_of_day(Utc::now(), digest_hour))
+                            .await;
+                        digest.send_digest().await;
+                    }
+                });
+                notifiers.push(email);
+            }
+            Err(e) => println!("WARNING: email notifications disabled: {}", e),
+        }
+    }
+    if let Some(settings) = &config.slack {
+        match SlackNotifier::new(settings) {
+            Ok(slack) => notifiers.push(Arc::new(slack)),
+            Err(e) => println!("WARNING: slack notifications disabled: {}", e),
+        }
+    }
+    if let Some(settings) = &config.ntfy {
+        notifiers.push(Arc::new(NtfyNotifier::new(settings)));
+    }
+    notify::install(notifiers, &config.notifications, config.routes.as_ref());
+
+    let client = match credentials::fetch(&config).await {
+        Ok(credentials) => BybitClient::new(&config, credentials),
+        Err(e) => {
+            println!("{}", Scrubbed(e));
+            std::process::exit(1);
+        }
+    };
+    let store = match StateStore::open(&config.state_path) {
+        Ok(store) => store,
+        Err(e) => {
+            println!("failed reading state {}: {}", config.state_path, e);
+            std::process::exit(1);
+        }
+    };
+    store.update(|state| state.backfill(&config.strategies));
+    match client.measure_clock().await {
+        Ok(clock) => store.update(|state| state.clock = Some(clock)),
+        Err(e) => println!("warning: clock check failed: {}", Scrubbed(e)),
+    }
+    let key = match preflight::check_key(&client, &config).await {
+        Ok(key) => key,
+        Err(e) => {
+            println!("{}", Scrubbed(e));
+            std::process::exit(1);
+        }
+    };
+    let account = match AccountType::detect(&client).await {
+        Ok(account) => account,
+        Err(e) => {
+            println!("failed detecting account type: {}", Scrubbed(e));
+            std::process::exit(1);
+        }
+    };
+    info!("account type: {:?}", account);
+    if let Err(e) = preflight::check_categories(&client, account, &config).await {
+        println!("{}", Scrubbed(e));
+        std::process::exit(1);
+    }
+    let mut blocked = BTreeMap::new();
+    if let Err(e) = preflight::check_account(&client, account, &config, &mut blocked).await {
+        println!("account preflight failed: {}", Scrubbed(e));
+        std::process::exit(1);
+    }
+    let cache = match instruments::load(&client, &config, refresh_instruments).await {
+        Ok(cache) => cache,
+        Err(e) => {
+            println!("failed fetching instruments: {}", Scrubbed(e));
+            std::process::exit(1);
+        }
+    };
+    let instruments = match preflight::load_instruments(&cache, &config, &mut blocked) {
+        Ok(instruments) => instruments,
+        Err(e) => {
+            println!("{}", Scrubbed(e));
+            std::process::exit(1);
+        }
+    };
+    let failed = preflight::apply_margin_modes(&client, &config, &blocked).await;
+    blocked.extend(failed);
+    if let Err(e) = preflight::check_margin(&client, account, &config, &blocked).await {
+        println!("margin check failed: {}", Scrubbed(e));
+    }
+    println!("{}", preflight::banner(account, &key, &config, &blocked));
+    let ctx = Arc::new(Context {
+        client,
+        account,
+        store,
+        config,
+        blocked: Mutex::new(blocked),
+        instruments: RwLock::new(instruments),
+        triggered: Mutex::new(HashSet::new()),
+        lifecycle: Lifecycle::new(),
+        note,
+        shutdown: watch::Sender::new(false),
+        entry_tasks: Mutex::new(Vec::new()),
+    });
+    ctx.note_key_expiry(&key, Utc::now());
+    // health from an earlier run would outlive tasks no longer configured
+    ctx.store.update(|state| state.tasks.clear());
+    supervisor::spawn(&ctx, "ledger", |ctx| {
+        let events = ctx.lifecycle.subscribe();
+        ledger::follow(ctx, events)
+    });
+    supervisor::spawn(&ctx, "journal", |ctx| {
+        journal::follow(ctx.lifecycle.subscribe())
+    });
+    supervisor::spawn(&ctx, "http server", server::serve);
+
+    let stopper = ctx.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("shutting down");
+        stopper.stop_entries().await;
+        std::process::exit(0);
+    });
+
+    supervisor::spawn(&ctx, "cooldown watcher", |watcher| async move {
+        let mut changes = watcher.client.cooldown().subscribe();
+        while changes.changed().await.is_ok() {
+            let status = changes.borrow_and_update().clone();
+            watcher.store.update(|state| state.cooldown = status);
+        }
+    });
+
+    supervisor::spawn(&ctx, "latency recorder", |recorder| async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            let latency = recorder.client.latency().summary();
+            let clock = recorder.client.clock();
+            recorder.store.update(|state| {
+                state.latency = latency;
+                state.clock = clock;
+            });
+        }
+    });
+
+    if ctx.config.credentials.refresh.is_some() {
+        supervisor::spawn(&ctx, "credentials refresh", credentials::refresh);
+    }
 
-                cancel_order_data.extend(cancel_data);
+    if ad_hoc.is_some() {
+        // the account-wide bookkeeping is left to the scheduled bot
+        run_ad_hoc(&ctx).await;
+        return;
+    }
+
+    supervisor::spawn(&ctx, "reconciler", |reconciler| async move {
+        loop {
+            let last = reconciler.store.read(|state| state.reconciled_at);
+            schedule::sleep_until(ledger::next_reconcile(last, Utc::now())).await;
+            ledger::reconcile(&reconciler).await;
+            // the weekly summary rides on the reconciliation
+            if let Some(summary) = funding::summary(&reconciler) {
+                notify::notice(Topic::Summary, &summary);
+            }
+            let fill_times = reconciler.store.read(|state| state.time_to_fill(None));
+            if !fill_times.is_empty() {
+                notify::notice(
+                    Topic::Summary,
+                    &format!("time to fill:\n{}", fill_times.join("\n")),
+                );
             }
         }
+    });
 
-        println!("waiting 24hrs: {:#?}", &cancel_order_data);
-        sleep(Duration::from_secs(86400)).await;
+    supervisor::spawn(&ctx, "funding", |funder| async move {
+        loop {
+            funding::poll(&funder).await;
+            tokio::time::sleep(funding::POLL).await;
+        }
+    });
 
-        if !cancel_order_data.is_empty() {
-            cancel_batch_order(
-                &api_key,
-                &api_secret,
-                recv_window,
-                &batch_cancel_order_url,
-                &cancel_order_data,
-            )
-            .await
-            .expect("Failed canceling orders")
+    supervisor::spawn(&ctx, "clock check", |clock_checker| async move {
+        loop {
+            tokio::time::sleep(CLOCK_CHECK_INTERVAL).await;
+            match clock_checker.client.measure_clock().await {
+                Ok(clock) => clock_checker
+                    .store
+                    .update(|state| state.clock = Some(clock)),
+                Err(e) => println!("warning: clock check failed: {}", Scrubbed(e)),
+            }
+        }
+    });
+
+    supervisor::spawn(&ctx, "key check", |key_checker| async move {
+        loop {
+            tokio::time::sleep(KEY_CHECK_INTERVAL).await;
+            key_checker.check_key_expiry().await;
+        }
+    });
+
+    let fetched_at = cache.fetched_at;
+    supervisor::spawn(&ctx, "instrument refresh", move |refresher| async move {
+        let ttl = ChronoDuration::hours(refresher.config.instrument_ttl_hours as i64);
+        // rules loaded from the cache are due from when they were fetched
+        let mut due = fetched_at + ttl;
+        loop {
+            schedule::sleep_until(due).await;
+            refresher.refresh_instruments().await;
+            due = Utc::now() + ttl;
+        }
+    });
+
+    let now = Utc::now();
+    let mut tasks = Vec::new();
+    for strategy in &ctx.config.strategies {
+        let interval = strategy.interval;
+        let (open, late) = match schedule::startup_action(&ctx.config.schedule, interval, now) {
+            StartupAction::PlaceNow => (schedule::current_open(now, interval), false),
+            StartupAction::PlaceLate => (schedule::current_open(now, interval), true),
+            StartupAction::WaitForOpen => (schedule::next_open(now, interval), false),
+        };
+        for symbol in ctx.config.symbols.keys() {
+            tasks.push(supervisor::spawn_core(
+                &ctx,
+                &format!("strategy {} {}", strategy.name, symbol),
+                run_strategy(ctx.clone(), strategy.clone(), symbol.clone(), open, late),
+            ));
         }
-        println!("canceled order data: {:#?}", &cancel_order_data);
-        sleep(Duration::from_secs(60)).await;
     }
+
+    futures::future::join_all(tasks).await;
 }
@@ -0,0 +1,40 @@
+//! Whether a ladder's initial margin fits the balance it's placed from.
+
+/// The margin a fully filled ladder ties up against what the account can
+/// spare.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginFit {
+    /// Initial margin of every level filling, the notional over leverage.
+    pub required: f64,
+    /// Available balance less the buffer.
+    pub usable: f64,
+    /// Share of the ladder that fits: 1 when all of it does, 0 when none.
+    pub share: f64,
+}
+
+/// Leverage the ladder is margined at: the configured one, 1x when unset,
+/// capped by the instrument's maximum.
+pub fn effective_leverage(configured: Option<f64>, max: Option<f64>) -> f64 {
+    let leverage = configured.unwrap_or(1.0);
+    match max {
+        Some(max) if max > 0.0 => leverage.min(max),
+        _ => leverage,
+    }
+}
+
+/// Fits `notional` at `leverage` into `available`, keeping
+/// `buffer_percent` of it free.
+pub fn fit(notional: f64, leverage: f64, available: f64, buffer_percent: f64) -> MarginFit {
+    let required = notional / leverage;
+    let usable = (available * (1.0 - buffer_percent / 100.0)).max(0.0);
+    let share = if required <= usable {
+        1.0
+    } else {
+        usable / required
+    };
+    MarginFit {
+        required,
+        usable,
+        share,
+    }
+}
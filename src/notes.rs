@@ -0,0 +1,97 @@
+use crate::bybit::BoxError;
+use crate::config::Config;
+use crate::state::State;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{env, path::Path};
+
+pub const CSV_HEADER: &str = "time,subject,text";
+
+/// A freeform annotation on a cycle or an order. Metadata only: nothing
+/// reads it when placing or sizing orders.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Note {
+    pub at: DateTime<Utc>,
+    /// An orderLinkId, or `<strategy>@<open>` for a cycle.
+    pub subject: String,
+    pub text: String,
+}
+
+impl Note {
+    pub fn new(subject: &str, text: &str) -> Note {
+        Note {
+            at: Utc::now(),
+            subject: subject.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    /// Subject naming `strategy`'s cycle for `open`.
+    pub fn cycle(strategy: &str, open: DateTime<Utc>) -> String {
+        format!(
+            "{}@{}",
+            strategy,
+            open.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )
+    }
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{}",
+            self.at.to_rfc3339(),
+            csv_field(&self.subject),
+            csv_field(&self.text)
+        )
+    }
+}
+
+/// Quotes a field holding a comma, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The notes kept in `state` from `since` on, as CSV.
+pub fn export(state: &State, since: Option<NaiveDate>) -> String {
+    let mut csv = vec![CSV_HEADER.to_string()];
+    csv.extend(
+        state
+            .notes
+            .iter()
+            .filter(|note| since.is_none_or(|since| note.at.date_naive() >= since))
+            .map(Note::to_csv),
+    );
+    csv.join("\n")
+}
+
+/// Adds a note from the command line. A running bot owns the state file,
+/// so the note goes through its HTTP API when one is configured; the file
+/// is only written directly when nothing is listening.
+pub async fn add(config: &Config, subject: &str, text: &str) -> Result<(), BoxError> {
+    if let (Some(http), Ok(token)) = (&config.http, env::var("HTTP_TOKEN")) {
+        let sent = reqwest::Client::new()
+            .post(format!("http://{}/notes", http.bind))
+            .header("x-stink-bid-token", token)
+            .json(&json!({ "subject": subject, "text": text }))
+            .send()
+            .await;
+        match sent {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) => {
+                return Err(format!("bot refused the note: {}", response.text().await?).into())
+            }
+            Err(e) if e.is_connect() => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    // unlike a starting bot, never replace a state file that doesn't read
+    let mut state = State::load(&config.state_path)
+        .map_err(|e| format!("failed reading state {}: {}", config.state_path, e))?;
+    state.notes.push(Note::new(subject, text));
+    state.save(Path::new(&config.state_path))?;
+    Ok(())
+}
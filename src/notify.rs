@@ -0,0 +1,381 @@
+use crate::bybit::BoxError;
+use crate::config::RoutesConfig;
+use crate::journal;
+use crate::report::{CycleReport, SymbolOutcome};
+use crate::scrub::scrub;
+use crate::throttle::{Admit, Batcher, RateCap, ThrottleConfig};
+use chrono::Utc;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Something worth telling the operator about.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Needs attention, but the bot carries on.
+    Notice(Topic, String),
+    /// Trading is impaired until someone steps in.
+    Critical(String),
+    /// An entry executed.
+    Fill {
+        message: String,
+        /// Ladder level of the entry, 0 being nearest the open.
+        level: Option<usize>,
+    },
+    Report(RenderedReport),
+}
+
+impl Event {
+    /// One line saying what the event is about.
+    pub fn summary(&self) -> &str {
+        match self {
+            Event::Notice(_, message) | Event::Critical(message) | Event::Fill { message, .. } => {
+                message
+            }
+            Event::Report(report) => &report.title,
+        }
+    }
+
+    /// Name used to route events in channel configs.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::Notice(..) => "notice",
+            Event::Critical(_) => "critical",
+            Event::Fill { .. } => "fill",
+            Event::Report(_) => "report",
+        }
+    }
+}
+
+/// Every event kind, in the order channels list them.
+pub const EVENT_KINDS: [&str; 4] = ["notice", "critical", "fill", "report"];
+
+/// What an event is about, for routing it to channels.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    /// Orders placed outside the schedule, such as TWAP slices.
+    Placement,
+    Fill,
+    /// A fill at `urgent_from_level` or deeper.
+    DeepFill,
+    /// Orders cancelled on request.
+    Cancel,
+    /// Cycle reports and periodic summaries.
+    Summary,
+    Error,
+    Critical,
+}
+
+impl Topic {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::Placement => "placement",
+            Topic::Fill => "fill",
+            Topic::DeepFill => "deep_fill",
+            Topic::Cancel => "cancel",
+            Topic::Summary => "summary",
+            Topic::Error => "error",
+            Topic::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Event {
+    /// The event's topic, fills at `urgent_from_level` or deeper being
+    /// deep fills.
+    pub fn topic(&self, urgent_from_level: usize) -> Topic {
+        match self {
+            Event::Notice(topic, _) => *topic,
+            Event::Critical(_) => Topic::Critical,
+            Event::Fill { level, .. }
+                if level.is_some_and(|level| level + 1 >= urgent_from_level) =>
+            {
+                Topic::DeepFill
+            }
+            Event::Fill { .. } => Topic::Fill,
+            Event::Report(_) => Topic::Summary,
+        }
+    }
+}
+
+/// A cycle report in both plaintext and HTML.
+#[derive(Debug, Clone)]
+pub struct RenderedReport {
+    pub title: String,
+    pub text: String,
+    pub html: String,
+    pub symbols: Vec<SymbolLine>,
+    pub events: Vec<String>,
+}
+
+/// One symbol's row of a report.
+#[derive(Debug, Clone)]
+pub struct SymbolLine {
+    pub symbol: String,
+    pub anchor: String,
+    pub outcome: String,
+    pub placed: bool,
+    pub failed: bool,
+    pub orders: Vec<String>,
+}
+
+/// A channel events are delivered through besides stdout.
+pub trait Notifier: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn wants(&self, event: &Event) -> bool;
+    fn deliver<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, Result<(), BoxError>>;
+
+    /// Sends `message` right away, bypassing any queueing, to check that
+    /// the channel works.
+    fn send_test<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), BoxError>> {
+        Box::pin(async move {
+            let event = Event::Notice(Topic::Summary, message.to_string());
+            self.deliver(&event).await
+        })
+    }
+}
+
+static NOTIFIERS: OnceLock<Vec<Arc<dyn Notifier>>> = OnceLock::new();
+
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+static ROUTES: OnceLock<RoutesConfig> = OnceLock::new();
+
+/// Dedupe and burst state per event kind, and sends per channel.
+struct Throttle {
+    config: ThrottleConfig,
+    batchers: Mutex<BTreeMap<Topic, Batcher>>,
+    caps: Mutex<BTreeMap<&'static str, RateCap>>,
+}
+
+/// Sets the channels used for the rest of the process, how bursts to them
+/// are thinned and, with `routes`, which topics go to which. Call once at
+/// startup.
+pub fn install(
+    notifiers: Vec<Arc<dyn Notifier>>,
+    throttle: &ThrottleConfig,
+    routes: Option<&RoutesConfig>,
+) {
+    if NOTIFIERS.set(notifiers).is_err() {
+        println!("notifiers already installed");
+    }
+    if let Some(routes) = routes {
+        let _ = ROUTES.set(routes.clone());
+    }
+    let _ = THROTTLE.set(Throttle {
+        config: throttle.clone(),
+        batchers: Mutex::new(BTreeMap::new()),
+        caps: Mutex::new(BTreeMap::new()),
+    });
+}
+
+/// Sends a problem that needs the operator's attention.
+pub fn send(message: &str) {
+    notice(Topic::Error, message);
+}
+
+/// Sends a message about `topic`.
+pub fn notice(topic: Topic, message: &str) {
+    let message = scrub(message);
+    println!("NOTICE: {}", message);
+    dispatch(Event::Notice(topic, message));
+}
+
+/// Sends a failure that stops trading until it is fixed.
+pub fn critical(message: &str) {
+    let message = scrub(message);
+    println!("CRITICAL: {}", message);
+    dispatch(Event::Critical(message));
+}
+
+/// Reports an executed entry at ladder `level`.
+pub fn fill(message: &str, level: Option<usize>) {
+    dispatch(Event::Fill {
+        message: scrub(message),
+        level,
+    });
+}
+
+/// Hands a finished cycle report to the channels that collect them.
+pub fn report(report: &CycleReport) {
+    dispatch(Event::Report(RenderedReport {
+        title: format!("{} cycle opened {}", report.strategy, report.open),
+        text: scrub(&report.to_string()),
+        html: scrub(&report.to_html()),
+        symbols: report
+            .symbols
+            .iter()
+            .map(|entry| SymbolLine {
+                symbol: entry.symbol.clone(),
+                anchor: entry.anchor.clone(),
+                outcome: scrub(&entry.summary()),
+                placed: matches!(entry.outcome, SymbolOutcome::Placed(_)),
+                failed: matches!(entry.outcome, SymbolOutcome::Failed(_)),
+                orders: entry.details(),
+            })
+            .collect(),
+        events: report
+            .events
+            .iter()
+            .cloned()
+            .chain(
+                (!report.fill_stats.is_empty())
+                    .then(|| format!("7-day fills: {}", report.fill_stats.join(", "))),
+            )
+            .chain(
+                report
+                    .positions
+                    .iter()
+                    .map(|line| format!("position {}", line)),
+            )
+            .map(|event| scrub(&event))
+            .collect(),
+    }));
+}
+
+/// Sends `event` through the throttle. Critical alerts, reports and deep
+/// fills go straight out; a repeated notice or fill is dropped, and the
+/// rest of a burst waits for its digest.
+fn dispatch(event: Event) {
+    let Some(throttle) = THROTTLE.get() else {
+        deliver(event, false);
+        return;
+    };
+    let topic = event.topic(throttle.config.urgent_from_level);
+    if matches!(event, Event::Report(_)) || matches!(topic, Topic::Critical | Topic::DeepFill) {
+        deliver(event, false);
+        return;
+    }
+    let admit = throttle
+        .batchers
+        .lock()
+        .expect("throttle lock poisoned")
+        .entry(topic)
+        .or_insert_with(|| Batcher::new(&throttle.config))
+        .offer(event.summary(), Utc::now());
+    match admit {
+        Admit::Now => {
+            deliver(event, true);
+            tokio::spawn(flush(throttle, topic));
+        }
+        Admit::Held => {}
+        Admit::Duplicate => info!("repeat notification dropped: {}", event.summary()),
+    }
+}
+
+/// Sends what a burst about `topic` held once its window closes.
+async fn flush(throttle: &'static Throttle, topic: Topic) {
+    let ends = throttle
+        .batchers
+        .lock()
+        .expect("throttle lock poisoned")
+        .get(&topic)
+        .and_then(Batcher::window_ends);
+    let wait = ends.map_or(Default::default(), |ends| {
+        (ends - Utc::now()).to_std().unwrap_or_default()
+    });
+    tokio::time::sleep(wait).await;
+    let label = match topic {
+        Topic::Fill => "fills".to_string(),
+        topic => format!("{} notices", topic),
+    };
+    let digest = throttle
+        .batchers
+        .lock()
+        .expect("throttle lock poisoned")
+        .get_mut(&topic)
+        .and_then(|batcher| batcher.flush(&label, Utc::now()));
+    if let Some(message) = digest {
+        deliver(
+            match topic {
+                Topic::Fill => Event::Fill {
+                    message,
+                    level: None,
+                },
+                topic => Event::Notice(topic, message),
+            },
+            true,
+        );
+    }
+}
+
+/// Delivers `event` on its own task per channel, so a slow or failing
+/// channel never holds up trading. Failures only warn. A `capped` event
+/// is dropped for a channel over its rate cap.
+fn deliver(event: Event, capped: bool) {
+    for notifier in NOTIFIERS.get().into_iter().flatten() {
+        if !routed(notifier.as_ref(), &event) {
+            continue;
+        }
+        if capped && !admitted(notifier.name()) {
+            println!(
+                "WARNING: {} notification over the rate cap, dropped: {}",
+                notifier.name(),
+                event.summary()
+            );
+            continue;
+        }
+        let notifier = notifier.clone();
+        let event = event.clone();
+        tokio::spawn(async move {
+            let delivered = notifier.deliver(&event).await;
+            journal::record(
+                "notification",
+                json!({
+                    "channel": notifier.name(),
+                    "kind": event.kind(),
+                    "summary": event.summary(),
+                    "error": delivered.as_ref().err().map(|e| e.to_string()),
+                }),
+            );
+            if let Err(e) = delivered {
+                println!(
+                    "WARNING: {} notification failed ({}): {}",
+                    notifier.name(),
+                    event.summary(),
+                    e
+                );
+            }
+        });
+    }
+}
+
+/// Whether `notifier` gets `event`: by the routing table when there is
+/// one, else by the channel's own event list.
+fn routed(notifier: &dyn Notifier, event: &Event) -> bool {
+    let Some(routes) = ROUTES.get() else {
+        return notifier.wants(event);
+    };
+    let urgent_from_level = THROTTLE.get().map_or_else(
+        || ThrottleConfig::default().urgent_from_level,
+        |throttle| throttle.config.urgent_from_level,
+    );
+    routes
+        .channels(event.topic(urgent_from_level))
+        .iter()
+        .any(|name| name == notifier.name())
+}
+
+/// Whether `channel` is still under its rate cap, counting one more send.
+fn admitted(channel: &'static str) -> bool {
+    let Some(throttle) = THROTTLE.get() else {
+        return true;
+    };
+    throttle
+        .caps
+        .lock()
+        .expect("throttle lock poisoned")
+        .entry(channel)
+        .or_default()
+        .admit(throttle.config.per_channel_per_minute, Utc::now())
+}
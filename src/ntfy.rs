@@ -0,0 +1,93 @@
+use crate::bybit::BoxError;
+use crate::config::NtfyConfig;
+use crate::notify::{Event, Notifier};
+use futures::future::BoxFuture;
+use reqwest::Client;
+use std::env;
+
+/// Pushes events to an ntfy topic. Fills are high priority, and fills at
+/// `urgent_from_level` or deeper are urgent. The optional bearer token
+/// comes from `NTFY_TOKEN`.
+pub struct NtfyNotifier {
+    client: Client,
+    url: String,
+    token: Option<String>,
+    events: Vec<String>,
+    urgent_from_level: usize,
+    click_url: Option<String>,
+}
+
+impl NtfyNotifier {
+    pub fn new(config: &NtfyConfig) -> NtfyNotifier {
+        NtfyNotifier {
+            client: Client::new(),
+            url: format!("{}/{}", config.server.trim_end_matches('/'), config.topic),
+            token: env::var("NTFY_TOKEN").ok(),
+            events: config.events.clone(),
+            urgent_from_level: config.urgent_from_level,
+            click_url: config.click_url.clone(),
+        }
+    }
+
+    /// Title, priority and tags for `event`.
+    fn headers(&self, event: &Event) -> (String, &'static str, &'static str) {
+        match event {
+            Event::Fill {
+                level: Some(level), ..
+            } if level + 1 >= self.urgent_from_level => (
+                format!("Deep fill, level {}", level + 1),
+                "urgent",
+                "rotating_light,moneybag",
+            ),
+            Event::Fill { level, .. } => (
+                match level {
+                    Some(level) => format!("Fill, level {}", level + 1),
+                    None => "Fill".to_string(),
+                },
+                "high",
+                "moneybag",
+            ),
+            Event::Critical(_) => ("Critical".to_string(), "urgent", "warning"),
+            Event::Report(report) => (report.title.clone(), "default", "clipboard"),
+            Event::Notice(..) => ("Notice".to_string(), "default", "information_source"),
+        }
+    }
+}
+
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn wants(&self, event: &Event) -> bool {
+        self.events.iter().any(|kind| kind == event.kind())
+    }
+
+    fn deliver<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, Result<(), BoxError>> {
+        Box::pin(async move {
+            let (title, priority, tags) = self.headers(event);
+            let body = match event {
+                Event::Report(report) => report.text.clone(),
+                other => other.summary().to_string(),
+            };
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header("Title", title)
+                .header("Priority", priority)
+                .header("Tags", tags)
+                .body(body);
+            if let Some(click_url) = &self.click_url {
+                request = request.header("Click", click_url);
+            }
+            if let Some(token) = &self.token {
+                request = request.bearer_auth(token);
+            }
+            let response = request.send().await?;
+            if !response.status().is_success() {
+                return Err(format!("{} {}", response.status(), response.text().await?).into());
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,215 @@
+//! A naive replay of candidate ladders over daily candles, ranked by what
+//! they would have made. Fills happen whenever the low touches a level;
+//! fees, slippage and queue position are ignored.
+
+use std::fmt;
+
+/// Printed above every result so it isn't mistaken for a backtest.
+pub const LABEL: &str = "naive simulation: daily candles only, no fees, slippage or queue position";
+
+/// Percentiles of the bootstrap returns shown as the range.
+const RANGE: (f64, f64) = (5.0, 95.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Day {
+    pub open: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// What one ladder made over one day.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DayResult {
+    pub fills: usize,
+    /// Notional of the levels that filled.
+    pub deployed: f64,
+    pub pnl: f64,
+}
+
+/// One candidate's totals over the history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ranking {
+    pub levels: Vec<f64>,
+    pub fills: usize,
+    pub deployed: f64,
+    pub pnl: f64,
+    /// PnL over the budget, in percent.
+    pub return_percent: f64,
+    /// PnL over the notional that filled, in percent.
+    pub efficiency_percent: f64,
+    /// Return range over bootstrap resamples of the days.
+    pub low_percent: f64,
+    pub high_percent: f64,
+}
+
+/// Levels written as percents below the open, e.g. "5,10,15".
+pub fn parse_levels(text: &str) -> Result<Vec<f64>, String> {
+    let levels: Vec<f64> = text
+        .split(',')
+        .map(|level| {
+            level
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|level| *level > 0.0 && *level < 100.0)
+                .ok_or_else(|| format!("{:?} is not a percent between 0 and 100", level))
+        })
+        .collect::<Result<_, _>>()?;
+    if levels.is_empty() {
+        return Err("no levels".to_string());
+    }
+    Ok(levels)
+}
+
+/// A ladder of `levels` splitting `budget` evenly, placed at the open.
+/// Each filled level sells at `bounce` percent above its price if the
+/// close made it back there, else at the close.
+pub fn simulate_day(day: &Day, levels: &[f64], budget: f64, bounce: f64) -> DayResult {
+    let notional = budget / levels.len() as f64;
+    let mut result = DayResult::default();
+    for level in levels {
+        let price = day.open * (1.0 - level / 100.0);
+        if day.low > price {
+            continue;
+        }
+        let target = price * (1.0 + bounce / 100.0);
+        let exit = if day.close >= target {
+            target
+        } else {
+            day.close
+        };
+        result.fills += 1;
+        result.deployed += notional;
+        result.pnl += notional / price * (exit - price);
+    }
+    result
+}
+
+/// Replays each of `candidates` over `days`, best return first. `samples`
+/// resamples of the days, drawn from `seed`, give each return's range.
+pub fn rank(
+    days: &[Day],
+    candidates: &[Vec<f64>],
+    budget: f64,
+    bounce: f64,
+    samples: usize,
+    seed: u64,
+) -> Vec<Ranking> {
+    let mut rankings: Vec<Ranking> = candidates
+        .iter()
+        .map(|levels| {
+            let results: Vec<DayResult> = days
+                .iter()
+                .map(|day| simulate_day(day, levels, budget, bounce))
+                .collect();
+            let fills = results.iter().map(|result| result.fills).sum();
+            let deployed: f64 = results.iter().map(|result| result.deployed).sum();
+            let pnl: f64 = results.iter().map(|result| result.pnl).sum();
+            let (low_percent, high_percent) = bootstrap(&results, budget, samples, seed);
+            Ranking {
+                levels: levels.clone(),
+                fills,
+                deployed,
+                pnl,
+                return_percent: pnl / budget * 100.0,
+                efficiency_percent: if deployed > 0.0 {
+                    pnl / deployed * 100.0
+                } else {
+                    0.0
+                },
+                low_percent,
+                high_percent,
+            }
+        })
+        .collect();
+    rankings.sort_by(|a, b| b.return_percent.total_cmp(&a.return_percent));
+    rankings
+}
+
+/// The `RANGE` percentiles of the return over `samples` draws of as many
+/// days as `results`, with replacement.
+fn bootstrap(results: &[DayResult], budget: f64, samples: usize, seed: u64) -> (f64, f64) {
+    if results.is_empty() || samples == 0 {
+        return (0.0, 0.0);
+    }
+    let mut rng = XorShift(seed.max(1));
+    let mut returns: Vec<f64> = (0..samples)
+        .map(|_| {
+            let pnl: f64 = (0..results.len())
+                .map(|_| results[rng.below(results.len())].pnl)
+                .sum();
+            pnl / budget * 100.0
+        })
+        .collect();
+    returns.sort_by(f64::total_cmp);
+    let at = |percent: f64| {
+        let rank = (percent / 100.0 * returns.len() as f64).ceil() as usize;
+        returns[rank.clamp(1, returns.len()) - 1]
+    };
+    (at(RANGE.0), at(RANGE.1))
+}
+
+/// Small seeded generator, so a run can be repeated.
+struct XorShift(u64);
+
+impl XorShift {
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+/// The ladder as config lines to paste into a strategy.
+pub fn snippet(ranking: &Ranking, budget: f64, symbol: &str, days: usize) -> String {
+    let notional = budget / ranking.levels.len() as f64;
+    let list = |values: Vec<String>| values.join(", ");
+    format!(
+        "# best of a naive simulation on {} days of {}: return {:.2}%, no fees or slippage\n\
+         levels = [{}]\nbudgets = [{}]\n",
+        days,
+        symbol,
+        ranking.return_percent,
+        list(
+            ranking
+                .levels
+                .iter()
+                .map(|level| format!("{:?}", level))
+                .collect()
+        ),
+        list(
+            ranking
+                .levels
+                .iter()
+                .map(|_| format!("{:?}", (notional * 100.0).round() / 100.0))
+                .collect()
+        ),
+    )
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let levels: Vec<String> = self.levels.iter().map(|level| level.to_string()).collect();
+        write!(
+            f,
+            "{:<20} {:>6} {:>12.2} {:>10.2} {:>8.2}% {:>8.2}% {:>8.2}% .. {:.2}%",
+            levels.join(","),
+            self.fills,
+            self.deployed,
+            self.pnl,
+            self.return_percent,
+            self.efficiency_percent,
+            self.low_percent,
+            self.high_percent
+        )
+    }
+}
+
+/// Column names lined up with `Ranking`'s Display.
+pub fn header() -> String {
+    format!(
+        "{:<20} {:>6} {:>12} {:>10} {:>9} {:>9} {:>9}",
+        "levels", "fills", "deployed", "pnl", "return", "on filled", "range"
+    )
+}
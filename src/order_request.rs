@@ -0,0 +1,151 @@
+//! One order in a create-batch payload. Only the five mandatory fields and
+//! the orderLinkId are always sent; everything else is left out unless set,
+//! so plain orders stay minimal.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TimeInForce {
+    #[serde(rename = "GTC")]
+    GoodTillCancel,
+    #[serde(rename = "IOC")]
+    ImmediateOrCancel,
+    #[serde(rename = "FOK")]
+    FillOrKill,
+    PostOnly,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: String,
+    #[serde(rename = "orderType")]
+    pub order_type: String,
+    pub qty: String,
+    pub price: String,
+    #[serde(rename = "orderLinkId")]
+    pub order_link_id: String,
+    #[serde(rename = "reduceOnly", skip_serializing_if = "Option::is_none")]
+    pub reduce_only: Option<bool>,
+    #[serde(rename = "triggerPrice", skip_serializing_if = "Option::is_none")]
+    pub trigger_price: Option<String>,
+    /// 1 triggers when price rises to `trigger_price`, 2 when it falls to it.
+    #[serde(rename = "triggerDirection", skip_serializing_if = "Option::is_none")]
+    pub trigger_direction: Option<u8>,
+    #[serde(rename = "triggerBy", skip_serializing_if = "Option::is_none")]
+    pub trigger_by: Option<String>,
+    /// "StopOrder" for spot conditional orders.
+    #[serde(rename = "orderFilter", skip_serializing_if = "Option::is_none")]
+    pub order_filter: Option<String>,
+    /// "quoteCoin" when `qty` is a quote amount on a spot market order.
+    #[serde(rename = "marketUnit", skip_serializing_if = "Option::is_none")]
+    pub market_unit: Option<String>,
+    /// 1 to borrow on spot margin for spot orders.
+    #[serde(rename = "isLeverage", skip_serializing_if = "Option::is_none")]
+    pub is_leverage: Option<u8>,
+    #[serde(rename = "timeInForce", skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    /// 0 in one-way mode; 1 (buy side) or 2 (sell side) in hedge mode.
+    #[serde(rename = "positionIdx", skip_serializing_if = "Option::is_none")]
+    pub position_idx: Option<u8>,
+    #[serde(rename = "takeProfit", skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<String>,
+    #[serde(rename = "stopLoss", skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<String>,
+}
+
+impl OrderRequest {
+    pub fn builder(
+        symbol: impl Into<String>,
+        side: impl Into<String>,
+        order_type: impl Into<String>,
+        qty: impl Into<String>,
+        price: impl Into<String>,
+        order_link_id: impl Into<String>,
+    ) -> OrderRequestBuilder {
+        OrderRequestBuilder(OrderRequest {
+            symbol: symbol.into(),
+            side: side.into(),
+            order_type: order_type.into(),
+            qty: qty.into(),
+            price: price.into(),
+            order_link_id: order_link_id.into(),
+            reduce_only: None,
+            trigger_price: None,
+            trigger_direction: None,
+            trigger_by: None,
+            order_filter: None,
+            market_unit: None,
+            is_leverage: None,
+            time_in_force: None,
+            position_idx: None,
+            take_profit: None,
+            stop_loss: None,
+        })
+    }
+}
+
+/// Sets the optional fields of an `OrderRequest`. Flags whose Bybit default
+/// is off are only sent when turned on.
+#[derive(Debug, Clone)]
+pub struct OrderRequestBuilder(OrderRequest);
+
+// not every field has a feature setting it yet
+#[allow(dead_code)]
+impl OrderRequestBuilder {
+    pub fn reduce_only(mut self, reduce_only: bool) -> Self {
+        self.0.reduce_only = reduce_only.then_some(true);
+        self
+    }
+
+    /// `direction` is 1 for rising to `price`, 2 for falling to it.
+    pub fn trigger(mut self, price: impl Into<String>, direction: u8) -> Self {
+        self.0.trigger_price = Some(price.into());
+        self.0.trigger_direction = Some(direction);
+        self
+    }
+
+    pub fn trigger_by(mut self, trigger_by: impl Into<String>) -> Self {
+        self.0.trigger_by = Some(trigger_by.into());
+        self
+    }
+
+    pub fn order_filter(mut self, filter: impl Into<String>) -> Self {
+        self.0.order_filter = Some(filter.into());
+        self
+    }
+
+    pub fn market_unit(mut self, unit: impl Into<String>) -> Self {
+        self.0.market_unit = Some(unit.into());
+        self
+    }
+
+    pub fn leverage(mut self, leverage: bool) -> Self {
+        self.0.is_leverage = leverage.then_some(1);
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.0.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn position_idx(mut self, position_idx: u8) -> Self {
+        self.0.position_idx = Some(position_idx);
+        self
+    }
+
+    pub fn take_profit(mut self, price: impl Into<String>) -> Self {
+        self.0.take_profit = Some(price.into());
+        self
+    }
+
+    pub fn stop_loss(mut self, price: impl Into<String>) -> Self {
+        self.0.stop_loss = Some(price.into());
+        self
+    }
+
+    pub fn build(self) -> OrderRequest {
+        self.0
+    }
+}
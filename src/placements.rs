@@ -0,0 +1,32 @@
+//! A batch order's reply, order by order. Every field has a default, so
+//! a rejected order's blank entry or a renamed field parses rather than
+//! failing the whole batch; the recorded fixtures catch the drift instead.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchOrderResult {
+    pub list: Vec<BatchOrderResponse>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchOrderResponse {
+    /// Blank, like the rest, for an order the batch rejected.
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(rename = "orderId", default)]
+    pub order_id: String,
+    #[serde(rename = "orderLinkId", default)]
+    pub order_link_id: String,
+    /// Creation time in ms. `createAt` in the v5 docs, with the names
+    /// other endpoint versions use taken too.
+    #[serde(
+        rename = "createAt",
+        alias = "createdAt",
+        alias = "createTime",
+        default
+    )]
+    pub created_at: String,
+}
@@ -0,0 +1,63 @@
+use crate::config::{Category, StrategyConfig};
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::notify;
+use crate::report::SymbolOutcome;
+use std::collections::BTreeSet;
+
+/// Adds the open positions on the cycle's symbols to its report, after the
+/// cancel sweep. Positions without a TP, SL or trailing stop are flagged and
+/// sent as a notice. Symbols traded on spot have no positions to list.
+pub async fn snapshot(ctx: &Context, strategy: &StrategyConfig, cycle: &mut Cycle) {
+    if !strategy.categories().contains(&Category::Linear) {
+        return;
+    }
+    let mut symbols: BTreeSet<String> = cycle
+        .report
+        .symbols
+        .iter()
+        .filter(|entry| matches!(entry.outcome, SymbolOutcome::Placed(_)))
+        .filter(|entry| strategy.category_of(&entry.symbol) == Category::Linear)
+        .map(|entry| entry.symbol.clone())
+        .collect();
+    ctx.store.read(|state| {
+        if let Some(positions) = state.ledger.get(&Category::Linear) {
+            symbols.extend(
+                positions
+                    .iter()
+                    .filter(|(_, position)| position.qty > 0.0)
+                    .map(|(symbol, _)| symbol.clone()),
+            );
+        }
+    });
+
+    let mut unprotected = Vec::new();
+    for symbol in symbols {
+        let positions = match ctx.client.get_positions(&symbol).await {
+            Ok(positions) => positions,
+            Err(e) => {
+                cycle
+                    .report
+                    .positions
+                    .push(format!("{}: position lookup failed: {}", symbol, e));
+                continue;
+            }
+        };
+        for position in positions.iter().filter(|position| position.is_open()) {
+            let line = position.to_string();
+            if !position.protected() {
+                unprotected.push(line.clone());
+                cycle.report.positions.push(format!("WARNING {}", line));
+            } else {
+                cycle.report.positions.push(line);
+            }
+        }
+    }
+    if !unprotected.is_empty() {
+        notify::send(&format!(
+            "[{}] positions without protection: {}",
+            strategy.name,
+            unprotected.join("; ")
+        ));
+    }
+}
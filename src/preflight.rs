@@ -0,0 +1,591 @@
+use crate::account::AccountType;
+use crate::bybit::{
+    ApiKeyInfo, BoxError, BybitClient, SwitchIsolatedRequest, SymbolUnavailable, UNMATCHED_IP,
+};
+use crate::config::{Category, Config, MarginMode};
+use crate::context::unavailable_message;
+use crate::instruments::{Cache, Instrument};
+use crate::notify;
+use crate::response::ApiError;
+use crate::verbosity;
+use std::collections::BTreeMap;
+
+/// Margin every configured ladder would tie up if all levels filled at
+/// once, per category and budget currency. Spot buys aren't leveraged.
+fn required_margin(
+    config: &Config,
+    blocked: &BTreeMap<String, String>,
+) -> BTreeMap<(Category, String), f64> {
+    let mut required = BTreeMap::new();
+    for strategy in &config.strategies {
+        let ladder_total: f64 = strategy.budgets.iter().sum();
+        for (symbol, settings) in &config.symbols {
+            let Some(currency) = settings.budget_currency(symbol) else {
+                continue;
+            };
+            if blocked.contains_key(symbol) {
+                continue;
+            }
+            let category = strategy.category_of(symbol);
+            if category == Category::Spot && settings.spot_margin {
+                // checked against the symbol's borrow limit instead
+                continue;
+            }
+            let leverage = match category {
+                Category::Linear => settings.leverage.unwrap_or(1.0),
+                Category::Spot => 1.0,
+            };
+            *required.entry((category, currency)).or_insert(0.0) += ladder_total / leverage;
+        }
+    }
+    required
+}
+
+/// Checks the account can carry every ladder filling at once and warns if
+/// not. Unified accounts pool everything into one portfolio-wide balance.
+/// Errors only when a balance can't be read.
+pub async fn check_margin(
+    client: &BybitClient,
+    account: AccountType,
+    config: &Config,
+    blocked: &BTreeMap<String, String>,
+) -> Result<(), BoxError> {
+    let mut required = required_margin(config, blocked);
+    if account == AccountType::Unified {
+        let total = required.values().sum();
+        required = BTreeMap::from([((Category::Linear, "USDT".to_string()), total)]);
+    }
+    for ((category, coin), needed) in required {
+        let available = account.available_balance(client, category, &coin).await?;
+        if available < needed {
+            println!(
+                "warning: {:.2} {} available but fully filled ladders need {:.2}",
+                available, coin, needed
+            );
+        } else {
+            info!(
+                "{:.2} {} available, ladders need up to {:.2}",
+                available, coin, needed
+            );
+        }
+    }
+    check_spot_borrow(client, config, blocked).await
+}
+
+/// Symbols whose spot entries borrow on spot margin.
+fn spot_margin_symbols(config: &Config) -> Vec<&String> {
+    let spot = config
+        .traded_categories()
+        .remove(&Category::Spot)
+        .unwrap_or_default();
+    config
+        .symbols
+        .iter()
+        .filter(|(symbol, settings)| settings.spot_margin && spot.contains(*symbol))
+        .map(|(symbol, _)| symbol)
+        .collect()
+}
+
+/// Checks each spot-margin symbol's spot ladders against what a buy could
+/// spend including borrowing.
+async fn check_spot_borrow(
+    client: &BybitClient,
+    config: &Config,
+    blocked: &BTreeMap<String, String>,
+) -> Result<(), BoxError> {
+    for symbol in spot_margin_symbols(config) {
+        if blocked.contains_key(symbol) {
+            continue;
+        }
+        let spot_total: f64 = config
+            .strategies
+            .iter()
+            .filter(|strategy| strategy.category_of(symbol) == Category::Spot)
+            .map(|strategy| strategy.budgets.iter().sum::<f64>())
+            .sum();
+        let check = client.spot_borrow_check(symbol).await?;
+        let available: f64 = check.spot_max_trade_amount.parse()?;
+        if available < spot_total {
+            println!(
+                "warning: {} can spend {:.2} with borrowing but its spot ladders need {:.2}",
+                symbol, available, spot_total
+            );
+        } else {
+            info!(
+                "{} can spend {:.2} with borrowing, spot ladders need up to {:.2}",
+                symbol, available, spot_total
+            );
+        }
+    }
+    Ok(())
+}
+
+/// A setting the account has that a symbol's config can't trade with, and
+/// how to change it on the exchange.
+struct Conflict {
+    symbols: Vec<String>,
+    problem: String,
+    fix: String,
+}
+
+/// Why spot margin can't be used for `symbols` on this account, if it
+/// can't.
+async fn spot_margin_conflict(
+    client: &BybitClient,
+    account: AccountType,
+    symbols: &[&String],
+) -> Result<Option<Conflict>, BoxError> {
+    if account != AccountType::Unified {
+        return Ok(Some(Conflict {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            problem: "spot_margin needs a Unified Trading Account".to_string(),
+            fix: "upgrade to a Unified Trading Account in Bybit, or remove spot_margin".to_string(),
+        }));
+    }
+    let state = client.get_spot_margin_state().await?;
+    if state.spot_margin_mode != "1" {
+        return Ok(Some(Conflict {
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            problem: "spot_margin is set but spot margin trading is off for this account"
+                .to_string(),
+            fix: "turn it on in Bybit under Assets > Unified Trading > Spot Margin, \
+                  or remove spot_margin"
+                .to_string(),
+        }));
+    }
+    Ok(None)
+}
+
+/// A unified account's `marginMode` as the Bybit UI names it.
+fn account_margin_name(mode: &str) -> &str {
+    match mode {
+        "REGULAR_MARGIN" => "cross margin",
+        "ISOLATED_MARGIN" => "isolated margin",
+        "PORTFOLIO_MARGIN" => "portfolio margin",
+        "" => "an unreported margin mode",
+        other => other,
+    }
+}
+
+/// Why `symbol`'s margin mode can't be had on a unified account in
+/// `account_mode`, if it can't. Unified accounts set it account-wide.
+fn margin_mode_conflict(symbol: &str, wanted: MarginMode, account_mode: &str) -> Option<Conflict> {
+    let isolated = account_mode == "ISOLATED_MARGIN";
+    if (wanted == MarginMode::Isolated) == isolated {
+        return None;
+    }
+    let (setting, fix) = match wanted {
+        MarginMode::Isolated => ("isolated", "Isolated Margin"),
+        MarginMode::Cross => ("cross", "Cross Margin"),
+    };
+    Some(Conflict {
+        symbols: vec![symbol.to_string()],
+        problem: format!(
+            "margin_mode is {} but the unified account is in {}",
+            setting,
+            account_margin_name(account_mode)
+        ),
+        fix: format!(
+            "set the account's margin mode to {} in Bybit under Unified Trading > Margin Mode, \
+             or change margin_mode",
+            fix
+        ),
+    })
+}
+
+/// Compares the account's type, margin mode, position mode and spot
+/// margin with what `config` needs, and logs a summary. A symbol that
+/// conflicts is added to `blocked` with the problem, as symbols that fail
+/// validation are. Errors only when the account can't be read.
+pub async fn check_account(
+    client: &BybitClient,
+    account: AccountType,
+    config: &Config,
+    blocked: &mut BTreeMap<String, String>,
+) -> Result<(), BoxError> {
+    let info = client.get_account_info().await?;
+    let mut summary = Vec::new();
+    let mut conflicts = Vec::new();
+    match account {
+        AccountType::Unified => summary.push(format!(
+            "ok: Unified account in {}",
+            account_margin_name(&info.margin_mode)
+        )),
+        AccountType::Classic => summary.push("ok: Classic account".to_string()),
+    }
+    let mut traded = config.traded_categories();
+    for symbol in traded.remove(&Category::Linear).unwrap_or_default() {
+        if blocked.contains_key(&symbol) {
+            continue;
+        }
+        if let (AccountType::Unified, Some(wanted)) = (account, config.symbols[&symbol].margin_mode)
+        {
+            conflicts.extend(margin_mode_conflict(&symbol, wanted, &info.margin_mode));
+        }
+        let positions = client.get_positions(&symbol).await?;
+        if positions.iter().any(|position| position.position_idx != 0) {
+            conflicts.push(Conflict {
+                problem: "in hedge mode, but orders and stops are placed one-way".to_string(),
+                fix: format!(
+                    "switch {} to one-way mode in Bybit under Derivatives > Position Mode, \
+                     with no position open",
+                    symbol
+                ),
+                symbols: vec![symbol],
+            });
+        } else {
+            summary.push(format!("ok: {} in one-way mode", symbol));
+        }
+    }
+    let spot_margin = spot_margin_symbols(config);
+    if !spot_margin.is_empty() {
+        match spot_margin_conflict(client, account, &spot_margin).await? {
+            Some(conflict) => conflicts.push(conflict),
+            None => summary.push(format!("ok: spot margin on for {:?}", spot_margin)),
+        }
+    }
+    for conflict in &conflicts {
+        summary.push(format!(
+            "error: {} {}\n    fix: {}",
+            conflict.symbols.join(", "),
+            conflict.problem,
+            conflict.fix
+        ));
+        for symbol in &conflict.symbols {
+            blocked.insert(symbol.clone(), conflict.problem.clone());
+        }
+    }
+    info!("account preflight:\n  {}", summary.join("\n  "));
+    Ok(())
+}
+
+/// Permissions the key needs for what `config` trades, as (group, name,
+/// what for).
+fn required_permissions(config: &Config) -> Vec<(&'static str, &'static str, String)> {
+    let traded = config.traded_categories();
+    let mut required = Vec::new();
+    if let Some(symbols) = traded.get(&Category::Linear) {
+        let names: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        required.push((
+            "ContractTrade",
+            "Order",
+            format!("to trade {}", names.join(", ")),
+        ));
+        let switched: Vec<&str> = names
+            .into_iter()
+            .filter(|symbol| config.symbols[*symbol].margin_mode.is_some())
+            .collect();
+        if !switched.is_empty() {
+            required.push((
+                "ContractTrade",
+                "Position",
+                format!("to set the margin mode of {}", switched.join(", ")),
+            ));
+        }
+    }
+    if let Some(symbols) = traded.get(&Category::Spot) {
+        let names: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        required.push((
+            "Spot",
+            "SpotTrade",
+            format!("to trade {}", names.join(", ")),
+        ));
+    }
+    required
+}
+
+/// Whether a key with `info` can do what `config` needs, with every
+/// permission it's missing spelled out.
+pub fn check_key_info(info: &ApiKeyInfo, config: &Config) -> Result<(), String> {
+    if info.read_only == 1 {
+        return Err(
+            "the API key is read-only and can't place or cancel orders. \
+             Create a Read-Write key in Bybit under API Management"
+                .to_string(),
+        );
+    }
+    let missing: Vec<String> = required_permissions(config)
+        .into_iter()
+        .filter(|(group, name, _)| {
+            !info
+                .permissions
+                .get(*group)
+                .is_some_and(|names| names.iter().any(|granted| granted == name))
+        })
+        .map(|(group, name, purpose)| format!("{}: {} {}", group, name, purpose))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "the API key is missing {}. Add the missing permissions to the key in Bybit under API Management",
+        missing.join("; ")
+    ))
+}
+
+/// Reads the key's permissions and refuses a key that couldn't trade what
+/// `config` does. A key bound to other IPs is named as such, since every
+/// call would fail with the same code.
+pub async fn check_key(client: &BybitClient, config: &Config) -> Result<ApiKeyInfo, BoxError> {
+    let info = match client.get_api_key_info().await {
+        Ok(info) => info,
+        Err(e)
+            if e.downcast_ref::<ApiError>()
+                .is_some_and(|e| e.code == UNMATCHED_IP) =>
+        {
+            return Err(format!(
+                "the API key is bound to IPs that don't include this host: {}. \
+                 Add this host's IP to the key in Bybit under API Management",
+                e
+            )
+            .into())
+        }
+        Err(e) => return Err(e),
+    };
+    check_key_info(&info, config)?;
+    Ok(info)
+}
+
+/// What the key may do, where from and for which account, e.g.
+/// "ContractTrade: Order, Position; any IP; unified".
+pub fn describe_key(info: &ApiKeyInfo) -> String {
+    let mut parts: Vec<String> = info
+        .permissions
+        .iter()
+        .filter(|(_, names)| !names.is_empty())
+        .map(|(group, names)| format!("{}: {}", group, names.join(", ")))
+        .collect();
+    if parts.is_empty() {
+        parts.push("none".to_string());
+    }
+    match info.ips.as_slice() {
+        [] => {}
+        [any] if any == "*" => parts.push("any IP".to_string()),
+        ips => parts.push(format!("bound to {}", ips.join(", "))),
+    }
+    if info.uta == 1 {
+        parts.push("unified".to_string());
+    }
+    parts.join("; ")
+}
+
+/// Makes sure the account has a wallet for every category a symbol is
+/// traded in, so a spot symbol on an account without one is refused before
+/// anything is placed.
+pub async fn check_categories(
+    client: &BybitClient,
+    account: AccountType,
+    config: &Config,
+) -> Result<(), BoxError> {
+    for (category, symbols) in config.traded_categories() {
+        let wallet = account.wallet(category);
+        if client.get_wallet_balance(wallet, "").await?.is_empty() {
+            return Err(format!(
+                "{:?} are traded on {} but the account has no {} wallet",
+                symbols,
+                category.as_param(),
+                wallet
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Edit distance between two symbols.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Listed symbols within a couple of edits of `symbol`, closest first.
+pub fn suggestions<'a>(symbol: &str, listed: impl Iterator<Item = &'a String>) -> Vec<&'a String> {
+    let mut close: Vec<(usize, &String)> = listed
+        .map(|candidate| (distance(symbol, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .collect();
+    close.sort();
+    close
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Resolves every configured symbol against the instruments `cache` lists
+/// in each category it's traded in and keeps their trading rules. Unknown
+/// symbols are collected into one error under `strict_symbols`, otherwise
+/// they're warned about and added to `blocked` like symbols that aren't
+/// trading or whose quote coin differs from their budget currency.
+pub fn load_instruments(
+    cache: &Cache,
+    config: &Config,
+    blocked: &mut BTreeMap<String, String>,
+) -> Result<BTreeMap<(Category, String), Instrument>, BoxError> {
+    let mut instruments = BTreeMap::new();
+    let mut unknown = Vec::new();
+    for (category, traded) in config.traded_categories() {
+        let mut listed = cache.in_category(category);
+        for (symbol, settings) in &config.symbols {
+            if blocked.contains_key(symbol) || !traded.contains(symbol) {
+                continue;
+            }
+            let Some(info) = listed.remove(symbol) else {
+                let close = suggestions(symbol, listed.keys());
+                let mut reason = format!("not a known {} symbol", category.as_param());
+                if !close.is_empty() {
+                    reason.push_str(&format!(
+                        ", did you mean {}?",
+                        close
+                            .iter()
+                            .map(|s| s.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    ));
+                }
+                unknown.push(format!("{}: {}", symbol, reason));
+                blocked.insert(symbol.clone(), reason);
+                continue;
+            };
+            if info.status != "Trading" {
+                let unavailable = SymbolUnavailable {
+                    symbol: symbol.clone(),
+                    reason: format!("status is {}", info.status),
+                };
+                notify::send(&unavailable_message(&unavailable));
+                blocked.insert(symbol.clone(), unavailable.reason);
+                continue;
+            }
+            let instrument = match Instrument::from_info(info) {
+                Ok(instrument) => instrument,
+                Err(e) => {
+                    let reason = format!("unreadable instrument info: {}", e);
+                    println!("{} blocked, {}", symbol, reason);
+                    blocked.insert(symbol.clone(), reason);
+                    continue;
+                }
+            };
+            let currency = settings.budget_currency(symbol).unwrap_or_default();
+            if instrument.quote_coin != currency {
+                let reason = format!(
+                    "quotes in {} but budgets are in {}",
+                    instrument.quote_coin, currency
+                );
+                println!("{} blocked, {}", symbol, reason);
+                blocked.insert(symbol.clone(), reason);
+                continue;
+            }
+            // one small grid level under the minimum would fail the whole ladder
+            if let Some((strategy, smallest)) = config
+                .strategies
+                .iter()
+                .filter(|strategy| strategy.category_of(symbol) == category)
+                .filter_map(|strategy| Some((strategy, strategy.smallest_grid_level()?)))
+                .find(|(_, smallest)| *smallest < instrument.min_notional)
+            {
+                let reason = format!(
+                    "strategy {} grid levels of {:.2} {} are below the {} minimum",
+                    strategy.name, smallest, instrument.quote_coin, instrument.min_notional
+                );
+                println!("{} blocked, {}", symbol, reason);
+                blocked.insert(symbol.clone(), reason);
+                continue;
+            }
+            instruments.insert((category, symbol.clone()), instrument);
+        }
+    }
+    if !unknown.is_empty() {
+        let message = format!("unknown symbols:\n  {}", unknown.join("\n  "));
+        if config.strict_symbols {
+            return Err(message.into());
+        }
+        println!("warning: {}, skipping them", message);
+    }
+    Ok(instruments)
+}
+
+/// Startup summary of what will run and what preflight left out.
+pub fn banner(
+    account: AccountType,
+    key: &ApiKeyInfo,
+    config: &Config,
+    blocked: &BTreeMap<String, String>,
+) -> String {
+    let mut lines = vec![
+        "== stink-bid ==".to_string(),
+        format!("profile: {}", config.profile.as_deref().unwrap_or("none")),
+        format!("account: {:?}", account),
+        format!("api key: {}", describe_key(key)),
+        format!("log level: {}", verbosity::describe()),
+    ];
+    for strategy in &config.strategies {
+        lines.push(format!(
+            "strategy {}: {} {} levels {:?}",
+            strategy.name,
+            strategy.category.as_param(),
+            strategy.interval.as_param(),
+            strategy.levels
+        ));
+    }
+    for (symbol, settings) in &config.symbols {
+        match (blocked.get(symbol), settings.category) {
+            (Some(reason), _) => lines.push(format!("{:<12} skipped: {}", symbol, reason)),
+            (None, Some(category)) => {
+                lines.push(format!("{:<12} ok, on {}", symbol, category.as_param()))
+            }
+            (None, None) => lines.push(format!("{:<12} ok", symbol)),
+        }
+    }
+    lines.join("\n")
+}
+
+/// Switches every symbol with a configured margin mode, returning the
+/// symbols whose switch failed and why.
+pub async fn apply_margin_modes(
+    client: &BybitClient,
+    config: &Config,
+    blocked: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut failed = BTreeMap::new();
+    for (symbol, settings) in &config.symbols {
+        let (Some(mode), Some(leverage)) = (settings.margin_mode, settings.leverage) else {
+            continue;
+        };
+        // margin modes only exist for the perp
+        let linear = config
+            .strategies
+            .iter()
+            .any(|strategy| strategy.category_of(symbol) == Category::Linear);
+        if blocked.contains_key(symbol) || !linear {
+            continue;
+        }
+        let request = SwitchIsolatedRequest {
+            category: "linear".to_string(),
+            symbol: symbol.clone(),
+            trade_mode: match mode {
+                MarginMode::Cross => 0,
+                MarginMode::Isolated => 1,
+            },
+            buy_leverage: leverage.to_string(),
+            sell_leverage: leverage.to_string(),
+        };
+        match client.switch_isolated(&request).await {
+            Ok(()) => info!("{} margin mode {:?} at {}x", symbol, mode, leverage),
+            Err(e) => {
+                let reason = format!("margin mode switch failed: {}", e);
+                println!("{} blocked, {}", symbol, reason);
+                failed.insert(symbol.clone(), reason);
+            }
+        }
+    }
+    failed
+}
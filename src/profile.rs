@@ -0,0 +1,103 @@
+//! Named profiles, e.g. prod and testnet, so the config, credentials,
+//! exchange, data directory and order ids in use are picked together by
+//! one name instead of by which `.env` happens to be loaded.
+
+use reqwest::Url;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Selects the profile when `--profile` isn't given.
+pub const ENV_VAR: &str = "STINK_PROFILE";
+
+const MAINNET: &str = "https://api.bybit.com";
+const TESTNET: &str = "https://api-testnet.bybit.com";
+
+/// Profile names end up in file and directory names, so they're kept to
+/// letters, digits, `-` and `_`.
+pub fn check_name(name: &str) -> Result<(), String> {
+    let valid = (1..=32).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(format!(
+            "profile {:?} must be 1-32 letters, digits, - or _",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// The profile's own config file beside `base`: `config.toml` becomes
+/// `config.prod.toml`.
+pub fn config_file(base: &str, name: &str) -> String {
+    let path = Path::new(base);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, name, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, name),
+    };
+    path.with_file_name(file).to_string_lossy().into_owned()
+}
+
+/// Whether `path` is a profile's own config file, named as `config_file`
+/// names it.
+pub fn is_own_file(path: &str, name: &str) -> bool {
+    Path::new(path)
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().ends_with(&format!(".{}", name)))
+}
+
+/// Where a profile's credentials come from unless it names a file.
+pub fn env_file(name: &str) -> String {
+    format!(".env.{}", name)
+}
+
+/// `mainnet` and `testnet` as Bybit's REST hosts; anything else is taken
+/// as a URL.
+pub fn base_url(value: &str) -> String {
+    match value {
+        "mainnet" => MAINNET.to_string(),
+        "testnet" => TESTNET.to_string(),
+        url => url.trim_end_matches('/').to_string(),
+    }
+}
+
+/// `url` moved onto `base`'s scheme, host and port, keeping its path and
+/// query, so every endpoint follows the profile's exchange.
+pub fn rebase(url: &str, base: &str) -> Result<String, String> {
+    let mut url = Url::parse(url).map_err(|e| format!("{}: {}", url, e))?;
+    let base = Url::parse(base).map_err(|e| format!("{}: {}", base, e))?;
+    url.set_scheme(base.scheme())
+        .and_then(|()| url.set_host(base.host_str()).map_err(|_| ()))
+        .and_then(|()| url.set_port(base.port()))
+        .map_err(|()| format!("can't move {} onto {}", url, base))?;
+    Ok(url.to_string())
+}
+
+/// Sets every variable in the env file at `path`, over any already set,
+/// since the profile decides which credentials are used. Returns their
+/// names.
+pub fn load_env(path: &str) -> Result<Vec<String>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut names = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            return Err(format!("line {} isn't NAME=value", number + 1));
+        };
+        let value = value.trim();
+        let unquoted = ['"', '\'']
+            .iter()
+            .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+            .unwrap_or(value);
+        env::set_var(name.trim(), unquoted);
+        names.push(name.trim().to_string());
+    }
+    Ok(names)
+}
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Token bucket pacing requests to `per_second`, with bursts up to the same
+/// size. Waiters queue on a fair mutex and are served in arrival order, so
+/// no placement task gets starved by the others.
+pub struct RateLimiter {
+    per_second: f64,
+    bucket: Mutex<Bucket>,
+    waited_micros: AtomicU64,
+    acquired: AtomicU64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// How much a limiter has held requests back.
+#[derive(Debug, Clone, Copy)]
+pub struct LimiterStats {
+    pub requests: u64,
+    pub waited: Duration,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64) -> RateLimiter {
+        RateLimiter {
+            per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: per_second,
+                updated: Instant::now(),
+            }),
+            waited_micros: AtomicU64::new(0),
+            acquired: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits for a token. The lock is held while sleeping so later callers
+    /// queue behind this one instead of racing it for the next token.
+    pub async fn acquire(&self) {
+        let started = Instant::now();
+        let mut bucket = self.bucket.lock().await;
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.per_second);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second);
+            sleep(wait).await;
+            bucket.tokens = 1.0;
+            bucket.updated = Instant::now();
+        }
+        bucket.tokens -= 1.0;
+        drop(bucket);
+
+        let waited = started.elapsed().as_micros() as u64;
+        self.waited_micros.fetch_add(waited, Ordering::Relaxed);
+        self.acquired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> LimiterStats {
+        LimiterStats {
+            requests: self.acquired.load(Ordering::Relaxed),
+            waited: Duration::from_micros(self.waited_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::bybit::{BoxError, CancelOrderData};
+use crate::config::{AnchorPrice, ReanchorConfig, ReanchorSource, StrategyConfig};
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::ladder;
+use crate::lifecycle::{OrderEvent, OrderState};
+use crate::state::StateOrder;
+use chrono::{NaiveDate, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// Re-anchors the unfilled levels of any symbol whose price has run away
+/// above its anchor, at most `max_per_day` times per symbol.
+pub async fn check(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &ReanchorConfig,
+    cycle: &mut Cycle,
+    counts: &mut HashMap<String, (NaiveDate, u32)>,
+) {
+    let symbols: Vec<String> = cycle.anchors.keys().cloned().collect();
+    for symbol in symbols {
+        let today = Utc::now().date_naive();
+        let count = counts.entry(symbol.clone()).or_insert((today, 0));
+        if count.0 != today {
+            *count = (today, 0);
+        }
+        if count.1 >= config.max_per_day {
+            continue;
+        }
+        match reanchor_symbol(ctx, strategy, config, &symbol, cycle).await {
+            Ok(Some(line)) => {
+                count.1 += 1;
+                info!("[{}] {}", strategy.name, line);
+                cycle.report.events.push(line);
+            }
+            Ok(None) => {}
+            Err(e) if ctx.disable_if_unavailable(&symbol, &e) => {}
+            Err(e) => println!(
+                "[{}] re-anchor check for {} failed: {}",
+                strategy.name, symbol, e
+            ),
+        }
+    }
+}
+
+async fn reanchor_symbol(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &ReanchorConfig,
+    symbol: &str,
+    cycle: &mut Cycle,
+) -> Result<Option<String>, BoxError> {
+    let anchor = cycle.anchors[symbol];
+    let category = strategy.category_of(symbol);
+    let price: f64 = match config.source {
+        ReanchorSource::Last => cycle
+            .ticker(&ctx.client, category, symbol)
+            .await?
+            .last_price
+            .parse()?,
+        ReanchorSource::Vwap => {
+            let (_, kline) = ctx
+                .client
+                .get_kline(category, symbol, strategy.interval, AnchorPrice::Last)
+                .await?;
+            let volume: f64 = kline.volume.parse()?;
+            let turnover: f64 = kline.turnover.parse()?;
+            if volume <= 0.0 {
+                return Ok(None);
+            }
+            turnover / volume
+        }
+    };
+    if price <= anchor * (1.0 + config.threshold_percent / 100.0) {
+        return Ok(None);
+    }
+
+    let open_orders = ctx
+        .client
+        .get_open_orders(category, symbol, strategy.filter_in(category))
+        .await?;
+    let tracked_ids: HashSet<&str> = cycle
+        .tracked
+        .iter()
+        .filter(|order| order.symbol == symbol)
+        .map(|order| order.order_id.as_str())
+        .collect();
+    let unfilled: Vec<_> = open_orders
+        .iter()
+        .filter(|order| tracked_ids.contains(order.order_id.as_str()))
+        .collect();
+    if unfilled.is_empty() {
+        return Ok(None);
+    }
+    let to_cancel: Vec<CancelOrderData> = unfilled
+        .iter()
+        .map(|order| CancelOrderData {
+            symbol: order.symbol.clone(),
+            order_id: order.order_id.clone(),
+        })
+        .collect();
+
+    let outcomes = ctx.cancel_entries(strategy, &to_cancel).await?;
+    // a level that filled meanwhile keeps its entry and isn't re-placed
+    let unfilled: Vec<_> = unfilled
+        .into_iter()
+        .filter(|order| {
+            outcomes
+                .iter()
+                .any(|outcome| outcome.order_id == order.order_id && outcome.is_cancelled())
+        })
+        .collect();
+    if unfilled.is_empty() {
+        return Ok(None);
+    }
+    // level and child, so only the children that were cancelled come back
+    let levels: HashSet<(usize, Option<usize>)> = unfilled
+        .iter()
+        .filter_map(|order| {
+            let level = ladder::level_from_link_id(&order.order_link_id)?;
+            Some((level, ladder::child_from_link_id(&order.order_link_id)))
+        })
+        .collect();
+    for order in &unfilled {
+        ctx.lifecycle.transition(OrderEvent {
+            order_id: order.order_id.clone(),
+            ..OrderEvent::new(
+                &strategy.name,
+                category,
+                symbol,
+                &order.order_link_id,
+                &order.side,
+                OrderState::Cancelled,
+            )
+        });
+    }
+    let cancelled = |order_id: &str| unfilled.iter().any(|order| order.order_id == order_id);
+    cycle.tracked.retain(|order| !cancelled(&order.order_id));
+
+    let instrument = ctx.instrument(category, symbol)?;
+    let orders: Vec<_> = ladder::plan_orders(
+        strategy,
+        symbol,
+        &price.to_string(),
+        &instrument,
+        &ctx.config.symbols[symbol],
+        // a fresh id, the cancelled levels still hold the cycle's ones
+        Utc::now(),
+    )?
+    .into_iter()
+    .filter(|order| {
+        ladder::level_from_link_id(&order.order_link_id).is_some_and(|level| {
+            levels.contains(&(level, ladder::child_from_link_id(&order.order_link_id)))
+        })
+    })
+    .collect();
+    let placement = ctx.client.place_batch_order(category, &orders).await;
+    ctx.lifecycle
+        .placed(&strategy.name, category, &orders, &placement);
+    let placement = placement?;
+    ctx.note_rejections(strategy, &placement.rejected).await;
+    let placed = placement.placed;
+    let count = placed.len();
+    ctx.store.update_strategy(&strategy.name, |state| {
+        state.entries.retain(|order| !cancelled(&order.order_id));
+        state
+            .entries
+            .extend(StateOrder::placed(strategy, &orders, &placed));
+    });
+    cycle
+        .tracked
+        .extend(placed.iter().map(CancelOrderData::from));
+    cycle.anchors.insert(symbol.to_string(), price);
+
+    Ok(Some(format!(
+        "re-anchored {} from {} to {}, {} levels re-placed",
+        symbol, anchor, price, count
+    )))
+}
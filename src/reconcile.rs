@@ -0,0 +1,141 @@
+use crate::bybit::{BoxError, CancelOrderData, CancelOutcome, Settled};
+use crate::config::StrategyConfig;
+use crate::context::Context;
+use crate::cycle::Cycle;
+use crate::ladder;
+use crate::lifecycle::{OrderEvent, OrderState};
+use crate::notify;
+use std::collections::{BTreeSet, HashSet};
+
+/// Checks the open orders of the symbols a sweep covered. Entries still
+/// resting get one more cancel, and any that survive it are sent as a
+/// critical notice to cancel by hand. Swept entries the exchange has no
+/// record of are marked lost.
+pub async fn after_sweep(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    cycle: &mut Cycle,
+    swept: &[CancelOrderData],
+    outcomes: &[CancelOutcome],
+) {
+    mark_lost(ctx, strategy, outcomes);
+    let swept_ids: HashSet<&str> = swept.iter().map(|order| order.order_id.as_str()).collect();
+    let symbols: BTreeSet<&str> = swept.iter().map(|order| order.symbol.as_str()).collect();
+    let stragglers = stragglers(ctx, strategy, &symbols, &swept_ids).await;
+    if stragglers.is_empty() {
+        return;
+    }
+    println!(
+        "[{}] {} entries still resting after the sweep, cancelling them again",
+        strategy.name,
+        stragglers.len()
+    );
+    if let Err(e) = ctx.cancel_entries(strategy, &stragglers).await {
+        println!("[{}] second cancel failed: {}", strategy.name, e);
+    }
+    let left = self::stragglers(ctx, strategy, &symbols, &swept_ids).await;
+    if left.is_empty() {
+        cycle.report.events.push(format!(
+            "{} entries left by the sweep were cancelled on a second pass",
+            stragglers.len()
+        ));
+        return;
+    }
+    let ids: Vec<String> = left
+        .iter()
+        .map(
+            |order| match ctx.store.entry(&strategy.name, &order.order_id) {
+                Some(entry) => format!("{} {} {}", order.symbol, order.order_id, entry.label()),
+                None => format!("{} {}", order.symbol, order.order_id),
+            },
+        )
+        .collect();
+    let message = format!(
+        "[{}] entries still resting after two cancels, cancel them by hand: {}",
+        strategy.name,
+        ids.join(", ")
+    );
+    println!("{}", message);
+    notify::critical(&message);
+    cycle.report.events.push(message);
+}
+
+/// The strategy's entries resting on `symbols` that should be gone: ones
+/// the sweep covered, and ones no cycle tracks any more.
+async fn stragglers(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbols: &BTreeSet<&str>,
+    swept_ids: &HashSet<&str>,
+) -> Vec<CancelOrderData> {
+    let tracked: HashSet<String> = ctx.store.read(|state| {
+        state
+            .strategies
+            .values()
+            .flat_map(|tracked| &tracked.entries)
+            .map(|entry| entry.order_id.clone())
+            .collect()
+    });
+    let mut stragglers = Vec::new();
+    for symbol in symbols {
+        match resting_entries(ctx, strategy, symbol).await {
+            Ok(resting) => stragglers.extend(resting.into_iter().filter(|order| {
+                swept_ids.contains(order.order_id.as_str()) || !tracked.contains(&order.order_id)
+            })),
+            Err(e) => println!("[{}] reconciling {} failed: {}", strategy.name, symbol, e),
+        }
+    }
+    stragglers
+}
+
+/// Open entries on `symbol` with the strategy's orderLinkId prefix. Exits
+/// (`-x<n>`) are left out.
+async fn resting_entries(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+) -> Result<Vec<CancelOrderData>, BoxError> {
+    let prefix = format!("{}-{}-", strategy.link_id_prefix, symbol);
+    Ok(ctx
+        .client
+        .get_open_orders(
+            strategy.category_of(symbol),
+            symbol,
+            strategy.order_filter(symbol),
+        )
+        .await?
+        .into_iter()
+        .filter(|order| {
+            order.order_link_id.starts_with(&prefix)
+                && ladder::level_from_link_id(&order.order_link_id).is_some()
+        })
+        .map(|order| CancelOrderData {
+            symbol: order.symbol,
+            order_id: order.order_id,
+        })
+        .collect())
+}
+
+fn mark_lost(ctx: &Context, strategy: &StrategyConfig, outcomes: &[CancelOutcome]) {
+    for outcome in outcomes
+        .iter()
+        .filter(|outcome| outcome.settled == Some(Settled::Missing))
+    {
+        let Some(entry) = ctx.store.entry(&strategy.name, &outcome.order_id) else {
+            continue;
+        };
+        ctx.lifecycle.transition(OrderEvent {
+            order_id: entry.order_id.clone(),
+            price: entry.price.clone(),
+            qty: entry.qty.clone(),
+            ..OrderEvent::new(
+                &strategy.name,
+                strategy.category_of(&entry.symbol),
+                &entry.symbol,
+                &entry.order_link_id,
+                &entry.side,
+                OrderState::Lost,
+            )
+        });
+    }
+}
@@ -0,0 +1,332 @@
+use crate::storage;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{fmt, fs, path::Path};
+
+#[derive(Serialize)]
+#[serde(tag = "outcome", content = "detail", rename_all = "snake_case")]
+pub enum SymbolOutcome {
+    Placed(usize),
+    /// Nothing placed yet; entries go in once the condition is met.
+    Armed(String),
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Serialize)]
+pub struct SymbolReport {
+    pub symbol: String,
+    pub anchor: String,
+    pub outcome: SymbolOutcome,
+    /// Requested amount and sent qty per placed order.
+    pub orders: Vec<String>,
+    /// Quote value of the placed orders, against the ladder's budget.
+    pub notional: f64,
+    pub budget: f64,
+    /// Entries that filled during the cycle.
+    pub filled: Vec<String>,
+    /// How long after the placement time it placed, under a
+    /// `placement_stagger`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stagger_ms: Option<i64>,
+}
+
+/// Summary of one strategy's cycle, printed after the cancel sweep and
+/// sent to every notifier.
+#[derive(Serialize)]
+pub struct CycleReport {
+    pub strategy: String,
+    pub open: DateTime<Utc>,
+    pub symbols: Vec<SymbolReport>,
+    /// Re-anchors, fills and other things that happened during the hold.
+    pub events: Vec<String>,
+    /// Freeform notes attached to the cycle.
+    pub notes: Vec<String>,
+    pub cancelled: usize,
+    /// Fill rate per level over the last 7 days.
+    pub fill_stats: Vec<String>,
+    /// Open positions after the cancel sweep, unprotected ones flagged.
+    pub positions: Vec<String>,
+}
+
+impl CycleReport {
+    pub fn new(strategy: &str, open: DateTime<Utc>) -> CycleReport {
+        CycleReport {
+            strategy: strategy.to_string(),
+            open,
+            symbols: Vec::new(),
+            events: Vec::new(),
+            notes: Vec::new(),
+            cancelled: 0,
+            fill_stats: Vec::new(),
+            positions: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, symbol: &str, anchor: &str, outcome: SymbolOutcome) {
+        self.symbols.push(SymbolReport {
+            symbol: symbol.to_string(),
+            anchor: anchor.to_string(),
+            outcome,
+            orders: Vec::new(),
+            notional: 0.0,
+            budget: 0.0,
+            filled: Vec::new(),
+            stagger_ms: None,
+        });
+    }
+
+    pub fn add_placed(
+        &mut self,
+        symbol: &str,
+        anchor: &str,
+        orders: Vec<String>,
+        notional: f64,
+        budget: f64,
+    ) {
+        self.symbols.push(SymbolReport {
+            symbol: symbol.to_string(),
+            anchor: anchor.to_string(),
+            outcome: SymbolOutcome::Placed(orders.len()),
+            orders,
+            notional,
+            budget,
+            filled: Vec::new(),
+            stagger_ms: None,
+        });
+    }
+
+    pub fn note_stagger(&mut self, symbol: &str, stagger_ms: i64) {
+        for entry in self
+            .symbols
+            .iter_mut()
+            .filter(|entry| entry.symbol == symbol)
+        {
+            entry.stagger_ms = Some(stagger_ms);
+        }
+    }
+
+    pub fn note_fill(&mut self, symbol: &str, fill: String) {
+        if let Some(entry) = self.symbols.iter_mut().find(|entry| entry.symbol == symbol) {
+            entry.filled.push(fill);
+        }
+    }
+
+    /// The same table as `Display`, for HTML email bodies.
+    pub fn to_html(&self) -> String {
+        let mut html = format!(
+            "<h3>{} cycle opened {}</h3>\n",
+            escape(&self.strategy),
+            self.open
+        );
+        for note in &self.notes {
+            html.push_str(&format!("<p>note: {}</p>\n", escape(note)));
+        }
+        html.push_str("<table>\n<tr><th>symbol</th><th>open</th><th>outcome</th></tr>\n");
+        for entry in &self.symbols {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&entry.symbol),
+                escape(&entry.anchor),
+                escape(&entry.summary())
+            ));
+            for line in entry.details() {
+                html.push_str(&format!(
+                    "<tr><td></td><td></td><td>{}</td></tr>\n",
+                    escape(&line)
+                ));
+            }
+        }
+        html.push_str("</table>\n<ul>\n");
+        for event in &self.events {
+            html.push_str(&format!("<li>{}</li>\n", escape(event)));
+        }
+        html.push_str(&format!(
+            "</ul>\n<p>cancelled {} levels</p>\n",
+            self.cancelled
+        ));
+        if !self.fill_stats.is_empty() {
+            html.push_str(&format!(
+                "<p>7-day fills: {}</p>\n",
+                escape(&self.fill_stats.join(", "))
+            ));
+        }
+        if !self.positions.is_empty() {
+            html.push_str("<p>open positions:</p>\n<ul>\n");
+            for line in &self.positions {
+                html.push_str(&format!("<li>{}</li>\n", escape(line)));
+            }
+            html.push_str("</ul>\n");
+        }
+        html
+    }
+
+    /// The report as a markdown file for the reports directory.
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# {} cycle opened {}\n\n", self.strategy, self.open);
+        for note in &self.notes {
+            md.push_str(&format!("> note: {}\n\n", note));
+        }
+        md.push_str("| symbol | open | outcome |\n|---|---|---|\n");
+        for entry in &self.symbols {
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                entry.symbol,
+                entry.anchor,
+                entry.summary().replace('|', "\\|")
+            ));
+        }
+        for entry in self.symbols.iter().filter(|entry| !entry.orders.is_empty()) {
+            md.push_str(&format!("\n## {}\n\n", entry.symbol));
+            for line in entry.details() {
+                md.push_str(&format!("- {}\n", line));
+            }
+        }
+        if !self.events.is_empty() {
+            md.push_str("\n## Events\n\n");
+            for event in &self.events {
+                md.push_str(&format!("- {}\n", event));
+            }
+        }
+        md.push_str(&format!("\ncancelled {} levels\n", self.cancelled));
+        if !self.fill_stats.is_empty() {
+            md.push_str("\n## 7-day fills\n\n");
+            for line in &self.fill_stats {
+                md.push_str(&format!("- {}\n", line));
+            }
+        }
+        if !self.positions.is_empty() {
+            md.push_str("\n## Open positions\n\n");
+            for line in &self.positions {
+                md.push_str(&format!("- {}\n", line));
+            }
+        }
+        md
+    }
+}
+
+impl SymbolReport {
+    /// The outcome, with the notional deployed when something was placed
+    /// and how far into the stagger.
+    pub fn summary(&self) -> String {
+        let mut summary = match self.outcome {
+            SymbolOutcome::Placed(_) => format!(
+                "{}, {:.2} of {:.2} budget deployed",
+                self.outcome, self.notional, self.budget
+            ),
+            _ => self.outcome.to_string(),
+        };
+        if let Some(stagger_ms) = self.stagger_ms {
+            summary.push_str(&format!(
+                ", placed {:.1}s into the stagger",
+                stagger_ms as f64 / 1000.0
+            ));
+        }
+        summary
+    }
+
+    /// Orders placed, then the fills among them.
+    pub fn details(&self) -> Vec<String> {
+        self.orders
+            .iter()
+            .cloned()
+            .chain(self.filled.iter().map(|fill| format!("filled {}", fill)))
+            .collect()
+    }
+}
+
+/// Writes `report` as `<dir>/<open>-<strategy>.md`.
+pub fn write_markdown(dir: &str, report: &CycleReport) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    // each symbol's scheduled cycle reports on its own
+    let mut name = format!(
+        "{}-{}",
+        report.open.format("%Y-%m-%dT%H%M"),
+        report.strategy.replace('/', "-")
+    );
+    if let [only] = report.symbols.as_slice() {
+        name.push_str(&format!("-{}", only.symbol));
+    }
+    name.push_str(".md");
+    storage::write_atomic(&Path::new(dir).join(name), report.to_markdown().as_bytes())
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+impl fmt::Display for SymbolOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolOutcome::Placed(count) => write!(f, "placed {} levels", count),
+            SymbolOutcome::Armed(condition) => write!(f, "armed: {}", condition),
+            SymbolOutcome::Skipped(reason) => write!(f, "skipped: {}", reason),
+            SymbolOutcome::Failed(error) => write!(f, "failed: {}", error),
+        }
+    }
+}
+
+impl fmt::Display for CycleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "== {} cycle opened {} ==", self.strategy, self.open)?;
+        for note in &self.notes {
+            writeln!(f, "note: {}", note)?;
+        }
+        for entry in &self.symbols {
+            writeln!(
+                f,
+                "{:<12} open {:<12} {}",
+                entry.symbol,
+                entry.anchor,
+                entry.summary()
+            )?;
+            for line in entry.details() {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        for event in &self.events {
+            writeln!(f, "{}", event)?;
+        }
+        write!(f, "cancelled {} levels", self.cancelled)?;
+        if !self.fill_stats.is_empty() {
+            write!(f, "\n7-day fills: {}", self.fill_stats.join(", "))?;
+        }
+        for line in &self.positions {
+            write!(f, "\nposition {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a remote cancel did for one symbol.
+#[derive(Serialize, Default)]
+pub struct CancellationReport {
+    pub symbol: String,
+    pub scope: String,
+    /// orderLinkIds confirmed gone after the cancel.
+    pub cancelled: Vec<String>,
+    /// orderLinkIds still open when checked afterwards.
+    pub still_open: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl fmt::Display for CancellationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "remote cancel of {} ({}): {} cancelled",
+            self.symbol,
+            self.scope,
+            self.cancelled.len()
+        )?;
+        if !self.still_open.is_empty() {
+            write!(f, ", still open: {}", self.still_open.join(", "))?;
+        }
+        for error in &self.errors {
+            write!(f, ", {}", error)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+//! The envelope every Bybit v5 reply comes in. `result` is only parsed
+//! when retCode is 0: error replies carry `{}`, `null` or nothing there,
+//! and a typed parse of that would hide the retCode and retMsg.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiResponse<T> {
+    #[serde(rename = "retCode")]
+    pub ret_code: i32,
+    #[serde(rename = "retMsg", default)]
+    pub ret_msg: String,
+    /// `None` for any reply with a non-zero retCode.
+    #[serde(default)]
+    pub result: Option<T>,
+    #[serde(rename = "retExtInfo", default)]
+    pub ret_ext_info: HashMap<String, Value>,
+    #[serde(default)]
+    pub time: u64,
+}
+
+/// A reply that failed, or succeeded without a result.
+#[derive(Debug)]
+pub struct ApiError {
+    /// What was being called, e.g. "kline".
+    pub call: String,
+    pub code: i32,
+    pub msg: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed: {} {}", self.call, self.code, self.msg)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl<T> ApiResponse<T> {
+    /// The result of a successful reply, else its retCode and retMsg.
+    pub fn into_result(self, call: &str) -> Result<T, ApiError> {
+        let error = |code, msg| ApiError {
+            call: call.to_string(),
+            code,
+            msg,
+        };
+        if self.ret_code != 0 {
+            return Err(error(self.ret_code, self.ret_msg));
+        }
+        self.result
+            .ok_or_else(|| error(0, "reply has no result".to_string()))
+    }
+}
+
+/// Parses a reply body: the envelope first, then `result` as `T` only if
+/// retCode is 0.
+pub fn parse<T: DeserializeOwned>(body: Value) -> Result<ApiResponse<T>, serde_json::Error> {
+    let envelope: ApiResponse<Value> = serde_json::from_value(body)?;
+    let result = match envelope.result {
+        Some(result) if envelope.ret_code == 0 && !result.is_null() => {
+            Some(serde_json::from_value(result)?)
+        }
+        _ => None,
+    };
+    Ok(ApiResponse {
+        ret_code: envelope.ret_code,
+        ret_msg: envelope.ret_msg,
+        result,
+        ret_ext_info: envelope.ret_ext_info,
+        time: envelope.time,
+    })
+}
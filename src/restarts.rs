@@ -0,0 +1,88 @@
+//! When a failed subsystem is restarted, and how each is doing. A
+//! subsystem that keeps failing is left stopped rather than restarted in a
+//! tight loop.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Restarts in a row after which a subsystem is given up on.
+pub const MAX_RESTARTS: u32 = 5;
+
+/// Wait before the first restart, doubled for each one after.
+pub const FIRST_BACKOFF: Duration = Duration::from_secs(1);
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long a subsystem has to run before its restarts stop counting as
+/// in a row.
+pub const HEALTHY_AFTER: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    /// Failed and waiting out its backoff.
+    Restarting,
+    /// Returned by itself, e.g. the HTTP server with no `[http]` table.
+    Stopped,
+    /// Failed past `MAX_RESTARTS`, or a core task that isn't restarted.
+    Failed,
+}
+
+impl fmt::Display for TaskState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TaskState::Running => "running",
+            TaskState::Restarting => "restarting",
+            TaskState::Stopped => "stopped",
+            TaskState::Failed => "failed",
+        })
+    }
+}
+
+/// A subsystem's state, as shown by `status`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TaskHealth {
+    pub state: TaskState,
+    /// When it entered `state`.
+    pub since: DateTime<Utc>,
+    /// Restarts in a row so far.
+    pub restarts: u32,
+    /// What its latest failure was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl fmt::Display for TaskHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} since {}", self.state, self.since)?;
+        if self.restarts > 0 {
+            write!(f, ", {} restarts", self.restarts)?;
+        }
+        if let Some(error) = &self.last_error {
+            write!(f, ", last failure: {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Restarts in a row once a subsystem that had `restarts` failed after
+/// running for `ran`. A long enough run starts the count over.
+pub fn restarts_after(restarts: u32, ran: Duration) -> u32 {
+    if ran >= HEALTHY_AFTER {
+        1
+    } else {
+        restarts + 1
+    }
+}
+
+/// How long to wait before restart number `restart`, counted from 1.
+/// None past `MAX_RESTARTS`.
+pub fn backoff(restart: u32) -> Option<Duration> {
+    if restart == 0 || restart > MAX_RESTARTS {
+        return None;
+    }
+    let doubled = FIRST_BACKOFF.saturating_mul(1 << (restart - 1).min(16));
+    Some(doubled.min(MAX_BACKOFF))
+}
@@ -0,0 +1,163 @@
+//! When and how often a failed exchange call is sent again. Every call
+//! site runs through `with_retry` and only picks its policy.
+
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// retCode for going over the API rate limit.
+const RATE_LIMITED: i32 = 10006;
+
+/// Why a call failed, as far as sending it again is concerned.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// Never reached the exchange, e.g. a refused connection.
+    Transport,
+    /// Turned away by a rate limit (HTTP 429, retCode 10006).
+    RateLimited,
+    /// Sent, but the reply was lost or broken, so the exchange may have
+    /// acted on it.
+    Ambiguous,
+    /// Won't change on resending: a ban, maintenance, a refused key.
+    Rejected,
+}
+
+/// How often and how fast a kind of call is retried.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Tries in all, the first one included.
+    pub max_attempts: u32,
+    /// First retry delay, doubled on each further attempt.
+    #[serde(with = "humantime_serde")]
+    pub base_delay: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_delay: Duration,
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+            retry_on: vec![
+                ErrorClass::Transport,
+                ErrorClass::RateLimited,
+                ErrorClass::Ambiguous,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait after failed attempt `attempt`, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let doubled = 2u32
+            .checked_pow(attempt.saturating_sub(1))
+            .and_then(|factor| self.base_delay.checked_mul(factor))
+            .unwrap_or(self.max_delay);
+        doubled.min(self.max_delay)
+    }
+
+    pub fn retries(&self, class: ErrorClass) -> bool {
+        self.retry_on.contains(&class)
+    }
+}
+
+/// A failed try and its class.
+#[derive(Debug)]
+pub struct Failure {
+    pub class: ErrorClass,
+    pub error: Box<dyn Error + Send + Sync>,
+    /// Tries made before giving up.
+    pub attempts: u32,
+}
+
+impl Failure {
+    pub fn new(class: ErrorClass, error: impl Into<Box<dyn Error + Send + Sync>>) -> Failure {
+        Failure {
+            class,
+            error: error.into(),
+            attempts: 1,
+        }
+    }
+
+    /// The underlying error, so callers can still tell what it was.
+    pub fn into_error(self) -> Box<dyn Error + Send + Sync> {
+        self.error
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.attempts > 1 {
+            write!(f, "gave up after {} attempts: ", self.attempts)?;
+        }
+        write!(f, "{}", self.error)
+    }
+}
+
+/// A request that got no reply. A refused connection never reached the
+/// exchange; anything else, such as a timeout, may have.
+pub fn classify_send(connect: bool) -> ErrorClass {
+    if connect {
+        ErrorClass::Transport
+    } else {
+        ErrorClass::Ambiguous
+    }
+}
+
+/// An HTTP status that says the call didn't go through, `None` for one
+/// whose body is to be read.
+pub fn classify_status(status: u16) -> Option<ErrorClass> {
+    match status {
+        429 => Some(ErrorClass::RateLimited),
+        500..=599 => Some(ErrorClass::Ambiguous),
+        _ => None,
+    }
+}
+
+/// A retCode worth sending again for, `None` for an answer that stands,
+/// whether success or a refusal the caller reads itself.
+pub fn classify_code(ret_code: i32) -> Option<ErrorClass> {
+    match ret_code {
+        RATE_LIMITED => Some(ErrorClass::RateLimited),
+        _ => None,
+    }
+}
+
+/// Runs `op` until it succeeds, fails with a class `policy` doesn't retry
+/// or runs out of attempts, waiting out the backoff in between. `what`
+/// names the call in the log.
+pub async fn with_retry<T, F>(
+    policy: &RetryPolicy,
+    what: &str,
+    mut op: impl FnMut() -> F,
+) -> Result<T, Failure>
+where
+    F: Future<Output = Result<T, Failure>>,
+{
+    let mut attempt = 1;
+    loop {
+        let mut failure = match op().await {
+            Ok(value) => return Ok(value),
+            Err(failure) => failure,
+        };
+        failure.attempts = attempt;
+        if attempt >= policy.max_attempts || !policy.retries(failure.class) {
+            return Err(failure);
+        }
+        let delay = policy.delay(attempt);
+        println!(
+            "{} attempt {} failed ({}), retrying in {:?}",
+            what, attempt, failure.error, delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
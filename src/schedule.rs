@@ -0,0 +1,160 @@
+use crate::catch_up::{self, StartupAction};
+use crate::config::{Config, Interval, ScheduleConfig, StrategyConfig};
+use crate::stagger;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, DurationRound, Utc};
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal};
+use tokio::time::sleep;
+
+/// How long before its hold ends the ladder gets cancelled.
+const CANCEL_LEAD_SECS: i64 = 60;
+/// Longest single sleep, so suspends and clock changes are noticed on wake.
+const MAX_SLEEP_SECS: i64 = 60;
+
+/// Start of the candle containing `now`. Weekly candles open Monday 00:00 UTC.
+pub fn current_open(now: DateTime<Utc>, interval: Interval) -> DateTime<Utc> {
+    let day = now
+        .duration_trunc(ChronoDuration::days(1))
+        .expect("day truncation cannot overflow");
+    match interval {
+        Interval::Daily => day,
+        Interval::Weekly => day - ChronoDuration::days(day.weekday().num_days_from_monday() as i64),
+    }
+}
+
+pub fn next_open(now: DateTime<Utc>, interval: Interval) -> DateTime<Utc> {
+    current_open(now, interval) + interval.length()
+}
+
+/// The next time the UTC clock reads `hour`:00 after `now`.
+pub fn next_hour_of_day(now: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let today = current_open(now, Interval::Daily) + ChronoDuration::hours(hour as i64);
+    if today > now {
+        today
+    } else {
+        today + ChronoDuration::days(1)
+    }
+}
+
+/// When the ladder for the candle starting at `open` is placed: the open
+/// itself, or the first `place_at` after it.
+pub fn placement_at(schedule: &ScheduleConfig, open: DateTime<Utc>) -> DateTime<Utc> {
+    schedule
+        .place_at
+        .map_or(open, |place_at| place_at.first_at_or_after(open))
+}
+
+/// How long after the placement time `symbol` places for the candle
+/// starting at `open`, by its place in the `placement_stagger`.
+pub fn stagger_offset(config: &Config, symbol: &str, open: DateTime<Utc>) -> ChronoDuration {
+    let Some(window) = config.schedule.placement_stagger else {
+        return ChronoDuration::zero();
+    };
+    let symbols: Vec<(&str, u32)> = config
+        .symbols
+        .iter()
+        .map(|(symbol, settings)| (symbol.as_str(), settings.priority))
+        .collect();
+    let offsets = stagger::offsets(
+        &symbols,
+        window,
+        config.schedule.stagger,
+        open.timestamp() as u64,
+    );
+    offsets
+        .get(symbol)
+        .and_then(|offset| ChronoDuration::from_std(*offset).ok())
+        .unwrap_or_else(ChronoDuration::zero)
+}
+
+/// When `symbol`'s ladder for the candle starting at `open` is placed, its
+/// stagger offset after `placement_at`.
+pub fn symbol_placement_at(config: &Config, symbol: &str, open: DateTime<Utc>) -> DateTime<Utc> {
+    placement_at(&config.schedule, open) + stagger_offset(config, symbol, open)
+}
+
+/// `instant` in UTC, followed by the `place_at` zone's clock if one is set.
+pub fn describe(schedule: &ScheduleConfig, instant: DateTime<Utc>) -> String {
+    match schedule.place_at {
+        Some(place_at) => format!("{} ({})", instant, place_at.local(instant)),
+        None => instant.to_string(),
+    }
+}
+
+/// When a ladder placed at `placed` gets cancelled.
+pub fn cancel_at(placed: DateTime<Utc>, hold: ChronoDuration) -> DateTime<Utc> {
+    placed + hold - ChronoDuration::seconds(CANCEL_LEAD_SECS)
+}
+
+/// When each configured symbol's ladder for the candle starting at `open`
+/// gets cancelled, by its own hold from its own staggered placement. Unless
+/// overlap is allowed, a hold never runs into the next placement, which a
+/// DST change can bring an hour closer.
+pub fn cancel_times(
+    config: &Config,
+    strategy: &StrategyConfig,
+    open: DateTime<Utc>,
+) -> BTreeMap<String, DateTime<Utc>> {
+    let following = open + strategy.interval.length();
+    config
+        .symbols
+        .iter()
+        .map(|(symbol, settings)| {
+            let placed = symbol_placement_at(config, symbol, open);
+            let next = symbol_placement_at(config, symbol, following);
+            let mut hold = strategy.hold_for(Some(settings));
+            if !strategy.allow_overlap {
+                hold = hold.min(next - placed);
+            }
+            (symbol.clone(), cancel_at(placed, hold))
+        })
+        .collect()
+}
+
+/// The open following `open`, or the current open if the process was
+/// suspended for longer than an interval.
+pub fn following_open(
+    open: DateTime<Utc>,
+    interval: Interval,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    (open + interval.length()).max(current_open(now, interval))
+}
+
+/// Sleeps until the wall clock reaches `target`. Sleeps in bounded chunks
+/// and re-reads the clock after each wake, so time spent suspended or a
+/// clock step doesn't push the action late.
+pub async fn sleep_until(target: DateTime<Utc>) {
+    loop {
+        let remaining = target - Utc::now();
+        if remaining <= ChronoDuration::zero() {
+            return;
+        }
+        let chunk = remaining.min(ChronoDuration::seconds(MAX_SLEEP_SECS));
+        sleep(chunk.to_std().unwrap_or_default()).await;
+    }
+}
+
+pub fn startup_action(
+    schedule: &ScheduleConfig,
+    interval: Interval,
+    now: DateTime<Utc>,
+) -> StartupAction {
+    // with `place_at`, lateness counts from that rather than the open, and
+    // starting before it is on time
+    let placement = placement_at(schedule, current_open(now, interval));
+    let window = schedule.catch_up_window_hours;
+    let action = catch_up::startup_action(schedule.catch_up, window, placement, now, |late| {
+        let stdin = io::stdin();
+        catch_up::prompt_place_late(&mut stdin.lock(), stdin.is_terminal(), late)
+    });
+    let late = now - placement;
+    if late > ChronoDuration::hours(window as i64) {
+        info!(
+            "{}m past the placement, outside the {}h catch-up window",
+            late.num_minutes(),
+            window
+        );
+    }
+    action
+}
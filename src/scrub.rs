@@ -0,0 +1,82 @@
+//! Keeps credentials out of anything printed or sent, even when a panic
+//! or an error from a dependency embeds them.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::RwLock;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Recent request signatures kept for scrubbing. Older ones can no longer
+/// be replayed, as the exchange rejects them past the receive window.
+const SIGNATURES_KEPT: usize = 64;
+
+/// Values shorter than this are too likely to match ordinary text.
+const MIN_LEN: usize = 8;
+
+static SECRETS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+static SIGNATURES: RwLock<VecDeque<String>> = RwLock::new(VecDeque::new());
+
+/// Scrubs `secret`, e.g. the API key or secret, from now on.
+pub fn register(secret: &str) {
+    if secret.len() < MIN_LEN {
+        return;
+    }
+    let mut secrets = SECRETS.write().expect("scrub lock poisoned");
+    if !secrets.iter().any(|known| known == secret) {
+        secrets.push(secret.to_string());
+    }
+}
+
+/// Scrubs a request signature, keeping the last `SIGNATURES_KEPT`.
+pub fn register_signature(signature: &str) {
+    if signature.len() < MIN_LEN {
+        return;
+    }
+    let mut signatures = SIGNATURES.write().expect("scrub lock poisoned");
+    if signatures.len() == SIGNATURES_KEPT {
+        signatures.pop_front();
+    }
+    signatures.push_back(signature.to_string());
+}
+
+/// `text` with every registered value replaced by `REDACTED`.
+pub fn scrub(text: &str) -> String {
+    let secrets = SECRETS.read().expect("scrub lock poisoned");
+    let signatures = SIGNATURES.read().expect("scrub lock poisoned");
+    let mut text = text.to_string();
+    for value in secrets.iter().chain(signatures.iter()) {
+        if text.contains(value.as_str()) {
+            text = text.replace(value.as_str(), REDACTED);
+        }
+    }
+    text
+}
+
+/// Displays the wrapped value scrubbed, for printing errors.
+pub struct Scrubbed<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Scrubbed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&scrub(&self.0.to_string()))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Scrubbed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&scrub(&format!("{:?}", self.0)))
+    }
+}
+
+/// Prints panics scrubbed, in place of the default hook.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let thread = std::thread::current();
+        eprintln!(
+            "thread '{}' {}",
+            thread.name().unwrap_or("<unnamed>"),
+            scrub(&info.to_string())
+        );
+    }));
+}
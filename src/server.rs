@@ -0,0 +1,283 @@
+use crate::context::{CancelScope, Context};
+use crate::cycle::Cycle;
+use crate::ledger;
+use crate::notes::Note;
+use crate::notify::{self, Topic};
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use std::env;
+use std::sync::Arc;
+
+/// Header carrying the shared secret from `HTTP_TOKEN`.
+const TOKEN_HEADER: &str = "x-stink-bid-token";
+
+#[derive(Clone)]
+struct Server {
+    ctx: Arc<Context>,
+    /// `HTTP_TOKEN`'s tag, see `tag`.
+    token_tag: Vec<u8>,
+}
+
+/// Body of `POST /trigger/place`. Every field is optional.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct TriggerRequest {
+    /// Strategy whose ladder is placed, the first one when left out.
+    strategy: Option<String>,
+    /// Symbols to place, all tradable ones when empty.
+    symbols: Vec<String>,
+    /// Overrides the strategy's hold before the ladder is cancelled.
+    hold_minutes: Option<u64>,
+    /// Freeform note attached to the cycle.
+    note: Option<String>,
+}
+
+/// Body of `POST /notes`.
+#[derive(Deserialize)]
+struct NoteRequest {
+    /// An orderLinkId, or `<strategy>@<open>` for a cycle.
+    subject: String,
+    text: String,
+}
+
+/// Body of `POST /trigger/cancel`.
+#[derive(Deserialize)]
+struct CancelRequest {
+    symbol: String,
+    #[serde(default)]
+    scope: CancelScope,
+}
+
+/// Releases symbols claimed by a trigger once its cycle is over.
+struct Claim {
+    ctx: Arc<Context>,
+    symbols: Vec<String>,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let mut in_flight = self.ctx.triggered.lock().expect("trigger lock poisoned");
+        for symbol in &self.symbols {
+            in_flight.remove(symbol);
+        }
+    }
+}
+
+/// Serves the HTTP API on the `[http]` bind address until the process
+/// exits. Needs `HTTP_TOKEN` to be set.
+pub async fn serve(ctx: Arc<Context>) {
+    let Some(config) = &ctx.config.http else {
+        return;
+    };
+    let token = env::var("HTTP_TOKEN").unwrap_or_default();
+    if token.is_empty() {
+        println!("WARNING: HTTP_TOKEN is missing or empty, not starting the HTTP server");
+        return;
+    }
+    let listener = match tokio::net::TcpListener::bind(&config.bind).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            println!(
+                "WARNING: failed binding HTTP server to {}: {}",
+                config.bind, e
+            );
+            return;
+        }
+    };
+    info!("HTTP server listening on {}", config.bind);
+    let app = Router::new()
+        .route("/trigger/place", post(trigger_place))
+        .route("/trigger/cancel", post(trigger_cancel))
+        .route("/metrics", get(metrics))
+        .route("/pnl", get(pnl))
+        .route("/orders", get(orders))
+        .route("/notes", post(add_note))
+        .with_state(Server {
+            ctx: ctx.clone(),
+            token_tag: tag(&token).finalize().into_bytes().to_vec(),
+        });
+    if let Err(e) = axum::serve(listener, app).await {
+        println!("WARNING: HTTP server stopped: {}", e);
+    }
+}
+
+impl Server {
+    /// Whether `headers` carry the token. The tags are compared in
+    /// constant time, so a reply's timing doesn't give away how much of a
+    /// guess was right.
+    fn authorized(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get(TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| tag(value).verify_slice(&self.token_tag).is_ok())
+    }
+}
+
+/// An HMAC keyed with `token`, so tokens of any length are compared as
+/// digests of the same size.
+fn tag(token: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(token.as_bytes()).expect("HMAC can take key of any size");
+    mac.update(TOKEN_HEADER.as_bytes());
+    mac
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+/// Places a ladder now, outside the schedule, through the same pipeline
+/// as a scheduled cycle. Replies with the placement report and leaves the
+/// hold, cancel and fill handling running in the background.
+async fn trigger_place(State(server): State<Server>, headers: HeaderMap, body: Bytes) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "bad or missing token".to_string());
+    }
+    // an empty body means defaults; a malformed one must not place everything
+    let request: TriggerRequest = if body.is_empty() {
+        TriggerRequest::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        }
+    };
+    let ctx = server.ctx;
+
+    let strategy = match &request.strategy {
+        Some(name) => ctx.config.strategies.iter().find(|s| &s.name == name),
+        None => ctx.config.strategies.first(),
+    };
+    let Some(strategy) = strategy else {
+        return error(StatusCode::BAD_REQUEST, "unknown strategy".to_string());
+    };
+    // tracked under its own name so the scheduled cycle's sweep leaves it be
+    let mut strategy = strategy.clone();
+    strategy.name = format!("{}/trigger", strategy.name);
+
+    let tradable = ctx.tradable_symbols();
+    // every configured symbol, so the report lists the blocked ones as
+    // skipped
+    let symbols = if request.symbols.is_empty() {
+        ctx.config.symbols.keys().cloned().collect()
+    } else if let Some(symbol) = request.symbols.iter().find(|s| !tradable.contains(s)) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!("{} is not a tradable symbol", symbol),
+        );
+    } else {
+        request.symbols.clone()
+    };
+
+    let claim = {
+        let mut in_flight = ctx.triggered.lock().expect("trigger lock poisoned");
+        let busy: Vec<&String> = symbols.iter().filter(|s| in_flight.contains(*s)).collect();
+        if !busy.is_empty() {
+            return error(
+                StatusCode::CONFLICT,
+                format!("already triggered: {:?}", busy),
+            );
+        }
+        in_flight.extend(symbols.iter().cloned());
+        Claim {
+            ctx: ctx.clone(),
+            symbols: symbols.clone(),
+        }
+    };
+
+    let open = Utc::now();
+    let mut cycle = Cycle::new(&strategy.name, open);
+    if let Some(note) = &request.note {
+        cycle.note(&ctx.store, note);
+    }
+    if let Err(e) = crate::place_ladders(&ctx, &strategy, &symbols, true, &mut cycle).await {
+        return error(StatusCode::SERVICE_UNAVAILABLE, e.to_string());
+    }
+    let body = Json(json!(cycle.report)).into_response();
+
+    let cancel_at = symbols
+        .iter()
+        .map(|symbol| {
+            let hold = match request.hold_minutes {
+                Some(minutes) => ChronoDuration::minutes(minutes as i64),
+                None => strategy.hold_for(ctx.config.symbols.get(symbol)),
+            };
+            (symbol.clone(), open + hold)
+        })
+        .collect();
+    crate::start_twaps(&ctx, &strategy, &mut cycle, &cancel_at);
+    tokio::spawn(async move {
+        crate::finish_cycle(&ctx, &strategy, cycle, cancel_at).await;
+        drop(claim);
+    });
+    body
+}
+
+/// Cancels a symbol's orders and replies with what was confirmed gone.
+/// Safe to repeat: with nothing left it returns an empty report.
+async fn trigger_cancel(
+    State(server): State<Server>,
+    headers: HeaderMap,
+    Json(request): Json<CancelRequest>,
+) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "bad or missing token".to_string());
+    }
+    let report = server
+        .ctx
+        .cancel_symbol(&request.symbol, request.scope)
+        .await;
+    info!("{}", report);
+    if !report.cancelled.is_empty() || !report.still_open.is_empty() || !report.errors.is_empty() {
+        notify::notice(Topic::Cancel, &report.to_string());
+    }
+    Json(json!(report)).into_response()
+}
+
+/// Business counters and request latency for Prometheus. Not behind the
+/// token, since scrapers can't send it; keep `bind` on a private address.
+async fn metrics(State(server): State<Server>) -> String {
+    let ctx = server.ctx;
+    let latency = ctx.client.latency().summary();
+    ctx.store.read(|state| state.prometheus(&latency))
+}
+
+/// Ledger positions valued at the current mark, for dashboards.
+async fn pnl(State(server): State<Server>, headers: HeaderMap) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "bad or missing token".to_string());
+    }
+    let ledger = server.ctx.store.read(|state| state.ledger.clone());
+    Json(json!(ledger::pnl(&server.ctx.client, &ledger).await)).into_response()
+}
+
+/// The lifecycle state of every order sent since startup, by orderLinkId.
+async fn orders(State(server): State<Server>, headers: HeaderMap) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "bad or missing token".to_string());
+    }
+    Json(json!(server.ctx.lifecycle.states())).into_response()
+}
+
+/// Keeps a note on an order or cycle, for `stink-bid note add`.
+async fn add_note(
+    State(server): State<Server>,
+    headers: HeaderMap,
+    Json(request): Json<NoteRequest>,
+) -> Response {
+    if !server.authorized(&headers) {
+        return error(StatusCode::UNAUTHORIZED, "bad or missing token".to_string());
+    }
+    let note = Note::new(&request.subject, &request.text);
+    server.ctx.store.update(|state| state.notes.push(note));
+    Json(json!({ "noted": request.subject })).into_response()
+}
@@ -0,0 +1,291 @@
+//! Sizing a ladder's levels against a symbol's trading rules: the price
+//! and qty each order is sent with, or why the ladder can't be placed.
+
+use crate::decimal::{self, Rounding};
+use serde::Deserialize;
+use std::fmt;
+
+/// Relative slack before a level counts as over the maximum order, so
+/// float noise at exactly the maximum doesn't split or clamp it.
+const MAX_ORDER_SLACK: f64 = 1e-9;
+
+/// What's done with a level larger than the instrument's maximum order.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverMaxOrder {
+    /// Sent as child orders, as many as it takes to stay under it.
+    #[default]
+    Split,
+    /// Sent at the maximum, the rest of its budget left unplaced.
+    Clamp,
+}
+
+/// The parts of a symbol's trading rules a ladder is sized against.
+#[derive(Debug, Clone, Copy)]
+pub struct Rules<'a> {
+    pub quote_coin: &'a str,
+    /// Prices are sent as whole multiples of it, e.g. "0.05".
+    pub tick_size: &'a str,
+    /// Qtys are sent as whole multiples of it, e.g. "10".
+    pub qty_step: &'a str,
+    /// Smallest order value the exchange accepts, in the quote coin.
+    pub min_notional: f64,
+    pub min_qty: f64,
+    /// Largest limit order qty, when the exchange lists one.
+    pub max_qty: Option<f64>,
+    /// Largest order value in the quote coin, spot only.
+    pub max_notional: Option<f64>,
+}
+
+impl Rules<'_> {
+    fn step(&self) -> f64 {
+        self.qty_step.parse().unwrap_or(f64::NAN)
+    }
+
+    /// Largest order value the exchange takes at `price`, when it lists a
+    /// maximum.
+    pub fn max_order_notional(&self, price: f64) -> Option<f64> {
+        let by_qty = self.max_qty.map(|max| max * price);
+        match (by_qty, self.max_notional) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// One rung of a ladder: how far below the anchor and how much to spend.
+#[derive(Debug, Clone, Copy)]
+pub struct Level {
+    pub percent: f64,
+    pub notional: f64,
+    /// Splits the level into children of at most this much each.
+    pub max_child_notional: Option<f64>,
+    /// Depth of the band the children spread over, below the level price.
+    pub child_band_percent: f64,
+    /// What's done when an order would be over the instrument's maximum.
+    pub over_max: OverMaxOrder,
+}
+
+impl Level {
+    /// How many orders the level goes out as. Never so many that a child
+    /// falls under `min_notional`.
+    fn children(&self, min_notional: f64) -> usize {
+        let Some(max) = self.max_child_notional else {
+            return 1;
+        };
+        let wanted = (self.notional / max).ceil() as usize;
+        let fit = if min_notional > 0.0 {
+            (self.notional / min_notional).floor() as usize
+        } else {
+            wanted
+        };
+        wanted.min(fit).max(1)
+    }
+}
+
+/// How a level at `price` goes out once the maximum order is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    pub children: usize,
+    /// What the children spend between them.
+    pub notional: f64,
+    /// The maximum order the level was split or clamped under, if it was.
+    pub max: Option<f64>,
+}
+
+/// How `level`, priced at `price`, is split or clamped to stay under the
+/// maximum order.
+pub fn fit(level: &Level, price: f64, rules: &Rules) -> Fit {
+    let children = level.children(rules.min_notional);
+    // the deepest child buys the most qty for its budget
+    let deepest = price * (1.0 - level.child_band_percent / 100.0);
+    let over = rules
+        .max_order_notional(deepest)
+        .filter(|max| level.notional / children as f64 > max * (1.0 + MAX_ORDER_SLACK));
+    match (over, level.over_max) {
+        (None, _) => Fit {
+            children,
+            notional: level.notional,
+            max: None,
+        },
+        (Some(max), OverMaxOrder::Split) => Fit {
+            children: (level.notional / max * (1.0 - MAX_ORDER_SLACK)).ceil() as usize,
+            notional: level.notional,
+            max: Some(max),
+        },
+        (Some(max), OverMaxOrder::Clamp) => Fit {
+            children,
+            notional: max * children as f64,
+            max: Some(max),
+        },
+    }
+}
+
+/// A level, or one child of a split level, sized and formatted for a
+/// symbol, ready to become an order.
+#[derive(Debug)]
+pub struct PlannedOrder {
+    pub level: usize,
+    /// Index of the child when the level is split.
+    pub child: Option<usize>,
+    pub percent: f64,
+    pub price: String,
+    pub qty: String,
+    /// Quote amount this order was sized from.
+    pub notional: f64,
+}
+
+impl PlannedOrder {
+    /// Last orderLinkId segment: the level, with `c<child>` for a child.
+    pub fn link_suffix(&self) -> String {
+        match self.child {
+            Some(child) => format!("{}c{}", self.level, child),
+            None => self.level.to_string(),
+        }
+    }
+}
+
+/// Why a ladder couldn't be planned. Any of these means an input is off,
+/// e.g. a misparsed kline field, so nothing of the ladder is placed.
+#[derive(Debug)]
+pub enum LadderError {
+    /// The anchor price isn't a finite positive number.
+    BadAnchor(f64),
+    /// A level priced at or below zero, or not below the anchor.
+    BadPrice { level: usize, price: f64 },
+    /// A level qty that isn't finite, or falls below the instrument's
+    /// minimum.
+    BadQty { level: usize, qty: f64, min: f64 },
+    /// A level qty above the instrument's maximum.
+    QtyTooLarge { level: usize, qty: f64, max: f64 },
+    BelowMinNotional {
+        level: usize,
+        notional: f64,
+        min: f64,
+        coin: String,
+    },
+}
+
+impl fmt::Display for LadderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LadderError::BadAnchor(price) => write!(f, "anchor price {} is not usable", price),
+            LadderError::BadPrice { level, price } => {
+                write!(f, "level {} price {} is not below the anchor", level, price)
+            }
+            LadderError::BadQty { level, qty, min } => {
+                write!(
+                    f,
+                    "level {} qty {} is below the minimum {}",
+                    level, qty, min
+                )
+            }
+            LadderError::QtyTooLarge { level, qty, max } => {
+                write!(
+                    f,
+                    "level {} qty {} is above the maximum {}",
+                    level, qty, max
+                )
+            }
+            LadderError::BelowMinNotional {
+                level,
+                notional,
+                min,
+                coin,
+            } => write!(
+                f,
+                "level {} is {} {}, below the {} minimum",
+                level, notional, coin, min
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LadderError {}
+
+/// Sizes each level below `price`, a split level as children spread evenly
+/// from the level price down through its band. Fails on the first level
+/// whose price or qty, as they would be sent, the exchange couldn't take.
+pub fn size_levels(
+    price: f64,
+    rules: &Rules,
+    levels: &[Level],
+) -> Result<Vec<PlannedOrder>, LadderError> {
+    if !price.is_finite() || price <= 0.0 {
+        return Err(LadderError::BadAnchor(price));
+    }
+    let mut planned = Vec::new();
+    for (index, level) in levels.iter().enumerate() {
+        let level_price = price - (price * level.percent / 100.0);
+        let Fit {
+            children, notional, ..
+        } = fit(level, level_price, rules);
+        for child in 0..children {
+            let depth = match children {
+                1 => 0.0,
+                _ => level.child_band_percent * child as f64 / (children - 1) as f64,
+            };
+            let child_price = level_price * (1.0 - depth / 100.0);
+            let child_notional = notional / children as f64;
+            let formatted_price =
+                decimal::format_step(child_price, rules.tick_size, Rounding::Nearest);
+            // checked as sent, since rounding to the tick can reach zero
+            let sent_price: f64 = formatted_price.parse().unwrap_or(f64::NAN);
+            if !(sent_price > 0.0 && sent_price < price) {
+                return Err(LadderError::BadPrice {
+                    level: index,
+                    price: child_price,
+                });
+            }
+            let size = checked_qty(index, child_price, sent_price, child_notional, rules)?;
+            planned.push(PlannedOrder {
+                level: index,
+                child: (children > 1).then_some(child),
+                percent: level.percent,
+                price: formatted_price,
+                qty: decimal::format_step(size, rules.qty_step, Rounding::Nearest),
+                notional: child_notional,
+            });
+        }
+    }
+    Ok(planned)
+}
+
+/// Base qty buying `notional` at `price`, floored to a multiple of the qty
+/// step and checked against the instrument's limits at `sent_price`.
+pub fn checked_qty(
+    level: usize,
+    price: f64,
+    sent_price: f64,
+    notional: f64,
+    rules: &Rules,
+) -> Result<f64, LadderError> {
+    let min_qty = rules.min_qty.max(rules.step());
+    // never rounded up: spot rejects a qty past basePrecision, and either
+    // category would spend past the level's budget
+    let size = decimal::round_to_step(notional / price, rules.qty_step, Rounding::Down);
+    if !size.is_finite() || size < min_qty {
+        return Err(LadderError::BadQty {
+            level,
+            qty: size,
+            min: min_qty,
+        });
+    }
+    if let Some(max) = rules.max_qty.filter(|max| size > *max) {
+        return Err(LadderError::QtyTooLarge {
+            level,
+            qty: size,
+            max,
+        });
+    }
+    let notional = sent_price * size;
+    if notional < rules.min_notional {
+        return Err(LadderError::BelowMinNotional {
+            level,
+            notional,
+            min: rules.min_notional,
+            coin: rules.quote_coin.to_string(),
+        });
+    }
+    Ok(size)
+}
@@ -0,0 +1,182 @@
+use crate::bybit::BoxError;
+use crate::config::SlackConfig;
+use crate::notify::{Event, Notifier, RenderedReport};
+use futures::future::BoxFuture;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const GREEN: &str = "#2eb886";
+const GREY: &str = "#9e9e9e";
+const RED: &str = "#e01e5a";
+
+/// Posts events to a Slack incoming webhook (`SLACK_WEBHOOK_URL`). Events
+/// are held for a few seconds; a burst bigger than `coalesce_after` goes
+/// out as one message to stay inside Slack's one-per-second limit.
+pub struct SlackNotifier {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    client: Client,
+    webhook_url: String,
+    events: Vec<String>,
+    coalesce_after: usize,
+    window: Duration,
+    queue: Mutex<Vec<Event>>,
+}
+
+impl SlackNotifier {
+    pub fn new(config: &SlackConfig) -> Result<SlackNotifier, BoxError> {
+        let webhook_url =
+            env::var("SLACK_WEBHOOK_URL").map_err(|_| "SLACK_WEBHOOK_URL is missing")?;
+        Ok(SlackNotifier {
+            inner: Arc::new(Inner {
+                client: Client::new(),
+                webhook_url,
+                events: config.events.clone(),
+                coalesce_after: config.coalesce_after,
+                window: Duration::from_secs(config.coalesce_seconds),
+                queue: Mutex::new(Vec::new()),
+            }),
+        })
+    }
+}
+
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &'static str {
+        "slack"
+    }
+
+    fn wants(&self, event: &Event) -> bool {
+        self.inner.events.iter().any(|kind| kind == event.kind())
+    }
+
+    fn deliver<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, Result<(), BoxError>> {
+        Box::pin(async move {
+            let first = {
+                let mut queue = self.inner.queue.lock().expect("slack lock poisoned");
+                queue.push(event.clone());
+                queue.len() == 1
+            };
+            // the first event of a burst flushes everything queued behind it
+            if first {
+                let inner = self.inner.clone();
+                tokio::spawn(async move { inner.flush().await });
+            }
+            Ok(())
+        })
+    }
+
+    fn send_test<'a>(&'a self, message: &'a str) -> BoxFuture<'a, Result<(), BoxError>> {
+        Box::pin(async move { self.inner.post(&json!({ "text": message })).await })
+    }
+}
+
+impl Inner {
+    async fn flush(&self) {
+        tokio::time::sleep(self.window).await;
+        let events = std::mem::take(&mut *self.queue.lock().expect("slack lock poisoned"));
+        let messages = if events.len() > self.coalesce_after {
+            vec![coalesced(&events)]
+        } else {
+            events.iter().map(message).collect()
+        };
+        for payload in messages {
+            if let Err(e) = self.post(&payload).await {
+                println!("WARNING: slack notification failed: {}", e);
+            }
+        }
+    }
+
+    async fn post(&self, payload: &Value) -> Result<(), BoxError> {
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .json(payload)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(format!("{} {}", response.status(), response.text().await?).into());
+        }
+        Ok(())
+    }
+}
+
+fn message(event: &Event) -> Value {
+    match event {
+        Event::Report(report) => json!({
+            "text": report.title,
+            "attachments": report_attachments(report),
+        }),
+        Event::Critical(text) => json!({
+            "text": format!(":rotating_light: {}", text),
+            "attachments": [attachment(RED, text)],
+        }),
+        Event::Notice(_, text) | Event::Fill { message: text, .. } => json!({ "text": text }),
+    }
+}
+
+/// Folds a burst into one message: plain events as a list, then every
+/// report's attachments.
+fn coalesced(events: &[Event]) -> Value {
+    let lines: Vec<String> = events
+        .iter()
+        .filter(|event| !matches!(event, Event::Report(_)))
+        .map(|event| match event {
+            Event::Critical(text) => format!(":rotating_light: {}", text),
+            other => format!("• {}", other.summary()),
+        })
+        .collect();
+    let attachments: Vec<Value> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Report(report) => Some(report_attachments(report)),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+    json!({
+        "text": format!("{} events\n{}", events.len(), lines.join("\n")),
+        "attachments": attachments,
+    })
+}
+
+/// A section per symbol, green when placed, red when failed and grey when
+/// skipped, followed by the cycle's events.
+fn report_attachments(report: &RenderedReport) -> Vec<Value> {
+    let mut attachments: Vec<Value> = report
+        .symbols
+        .iter()
+        .map(|line| {
+            let color = if line.placed {
+                GREEN
+            } else if line.failed {
+                RED
+            } else {
+                GREY
+            };
+            let mut text = format!("*{}* open {}\n{}", line.symbol, line.anchor, line.outcome);
+            if !line.orders.is_empty() {
+                text.push_str(&format!("\n```{}```", line.orders.join("\n")));
+            }
+            attachment(color, &text)
+        })
+        .collect();
+    if !report.events.is_empty() {
+        attachments.push(attachment(GREY, &report.events.join("\n")));
+    }
+    attachments
+}
+
+fn attachment(color: &str, text: &str) -> Value {
+    json!({
+        "color": color,
+        "blocks": [{
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": text },
+        }],
+    })
+}
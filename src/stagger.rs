@@ -0,0 +1,60 @@
+//! Spreads the symbols' placements over a window after the open, so dozens
+//! of ladders don't go out in one burst that trips the rate limits, with
+//! every anchor read at the same congested moment.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// How the placements are laid out over the window.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Spread {
+    /// At equal steps from the start of the window.
+    #[default]
+    Even,
+    /// At random times, drawn again each open but the same for every run
+    /// given that open.
+    Random,
+}
+
+/// The order symbols place in: highest `priority` first, then by name.
+pub fn order<'a>(symbols: &[(&'a str, u32)]) -> Vec<&'a str> {
+    let mut ordered = symbols.to_vec();
+    ordered.sort_by(|(a, a_priority), (b, b_priority)| b_priority.cmp(a_priority).then(a.cmp(b)));
+    ordered.into_iter().map(|(symbol, _)| symbol).collect()
+}
+
+/// How long after the scheduled placement each of `symbols`, with its
+/// priority, places within `window`. The first in `order` always places
+/// first; with `Spread::Random` the draws depend only on `seed`.
+pub fn offsets(
+    symbols: &[(&str, u32)],
+    window: Duration,
+    spread: Spread,
+    seed: u64,
+) -> BTreeMap<String, Duration> {
+    let ordered = order(symbols);
+    let count = ordered.len() as u32;
+    let mut times: Vec<Duration> = match spread {
+        Spread::Even => (0..count).map(|i| window * i / count.max(1)).collect(),
+        Spread::Random => {
+            let mut state = seed;
+            let millis = window.as_millis().max(1) as u64;
+            (0..count)
+                .map(|_| Duration::from_millis(next(&mut state) % millis))
+                .collect()
+        }
+    };
+    times.sort();
+    ordered.into_iter().map(str::to_string).zip(times).collect()
+}
+
+/// splitmix64, so the same seed gives the same draws on every build.
+fn next(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,666 @@
+use crate::bybit::{BatchOrderResponse, BoxError, OrderRequest};
+use crate::clock::Measurement;
+use crate::config::StrategyConfig;
+use crate::cooldown::CooldownStatus;
+use crate::equity::EquitySnapshot;
+use crate::fill_times::{self, Distribution, FillTime};
+use crate::key_expiry;
+use crate::ladder;
+use crate::latency::EndpointStats;
+use crate::ledger::Ledger;
+use crate::notes::Note;
+use crate::notify;
+use crate::restarts::TaskHealth;
+use crate::storage;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Schema version of the state file. Files from before it was recorded
+/// read as 0, which has the same layout as 1.
+pub const STATE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct State {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub strategies: BTreeMap<String, StrategyState>,
+    /// The client's IP-ban cooldown as of its last change.
+    #[serde(default)]
+    pub cooldown: CooldownStatus,
+    /// Request latency per endpoint, refreshed every minute.
+    #[serde(default)]
+    pub latency: BTreeMap<String, EndpointStats>,
+    /// Positions and PnL built from entry and exit fills.
+    #[serde(default)]
+    pub ledger: Ledger,
+    /// Last time the ledger was checked against the exchange.
+    #[serde(default)]
+    pub reconciled_at: Option<DateTime<Utc>>,
+    /// Funding settlements already booked, by transaction id, with when
+    /// they settled. Pruned past the lookback of a poll.
+    #[serde(default)]
+    pub funding_seen: BTreeMap<String, DateTime<Utc>>,
+    /// Last time the funding log was read.
+    #[serde(default)]
+    pub funding_polled_at: Option<DateTime<Utc>>,
+    /// One snapshot per cycle, oldest first.
+    #[serde(default)]
+    pub equity: Vec<EquitySnapshot>,
+    /// Notes on cycles and orders, oldest first.
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// Last time the API key's info was read, at startup and daily.
+    #[serde(default)]
+    pub key_checked_at: Option<DateTime<Utc>>,
+    /// When the API key expires, None for a key that doesn't.
+    #[serde(default)]
+    pub key_expires_at: Option<DateTime<Utc>>,
+    /// The tightest days-before-expiry already warned about for that
+    /// expiry.
+    #[serde(default)]
+    pub key_expiry_warned: Option<i64>,
+    /// The latest measurement of our clock against the exchange's.
+    #[serde(default)]
+    pub clock: Option<Measurement>,
+    /// Each supervised task's health, by name.
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskHealth>,
+}
+
+/// Orders a strategy currently has on both sides of the book.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct StrategyState {
+    #[serde(default)]
+    pub entries: Vec<StateOrder>,
+    #[serde(default)]
+    pub exits: Vec<StateOrder>,
+    /// Trailing stops attached per symbol.
+    #[serde(default)]
+    pub stops: BTreeMap<String, StopState>,
+    #[serde(default)]
+    pub counters: Counters,
+    /// Cycle open each symbol's ladder was last placed for, so a restart
+    /// doesn't place it twice.
+    #[serde(default)]
+    pub placed_opens: BTreeMap<String, DateTime<Utc>>,
+    /// Entries placed and filled per level and day, for the last week.
+    #[serde(default)]
+    pub history: BTreeMap<NaiveDate, DayStats>,
+    /// When each level was placed and first filled, for the last quarter.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fill_times: Vec<FillTime>,
+    /// Entries whose first exit fill already moved the stop to break-even.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub break_even: BTreeSet<String>,
+}
+
+/// Days of history kept for the fill statistics.
+const HISTORY_DAYS: i64 = 7;
+
+/// Days of time-to-fill samples kept.
+const FILL_TIMES_DAYS: i64 = 90;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DayStats {
+    pub placed: BTreeMap<String, u64>,
+    pub filled: BTreeMap<String, u64>,
+}
+
+impl StrategyState {
+    /// Counts the levels placed now, each an orderLinkId without a child
+    /// suffix and its level (e.g. "5%").
+    pub fn record_placed(&mut self, levels: &[(&str, String)]) {
+        let now = Utc::now();
+        let today = self.today();
+        for (_, level) in levels {
+            *today.placed.entry(level.clone()).or_default() += 1;
+        }
+        let oldest = now - ChronoDuration::days(FILL_TIMES_DAYS);
+        self.fill_times.retain(|sample| sample.placed_at >= oldest);
+        self.fill_times
+            .extend(levels.iter().map(|(order_link_id, level)| FillTime {
+                level: level.clone(),
+                order_link_id: order_link_id.to_string(),
+                placed_at: now,
+                filled_at: None,
+            }));
+    }
+
+    /// Counts the first fill of the level placed as `order_link_id`.
+    pub fn record_fill(&mut self, level: &str, order_link_id: &str, filled_at: DateTime<Utc>) {
+        *self.today().filled.entry(level.to_string()).or_default() += 1;
+        if let Some(sample) = self
+            .fill_times
+            .iter_mut()
+            .find(|sample| sample.order_link_id == order_link_id && sample.filled_at.is_none())
+        {
+            sample.filled_at = Some(filled_at.max(sample.placed_at));
+        }
+    }
+
+    /// Time-to-fill per level over the kept samples.
+    pub fn time_to_fill(&self) -> BTreeMap<String, Distribution> {
+        fill_times::by_level(&self.fill_times, Utc::now())
+    }
+
+    fn today(&mut self) -> &mut DayStats {
+        let today = Utc::now().date_naive();
+        let oldest = today - ChronoDuration::days(HISTORY_DAYS - 1);
+        self.history.retain(|day, _| *day >= oldest);
+        self.history.entry(today).or_default()
+    }
+
+    /// Fills against placements per level over the kept history.
+    pub fn fill_stats(&self) -> Vec<String> {
+        let mut placed: BTreeMap<&str, u64> = BTreeMap::new();
+        let mut filled: BTreeMap<&str, u64> = BTreeMap::new();
+        for day in self.history.values() {
+            for (level, count) in &day.placed {
+                *placed.entry(level).or_default() += count;
+            }
+            for (level, count) in &day.filled {
+                *filled.entry(level).or_default() += count;
+            }
+        }
+        placed
+            .into_iter()
+            .map(|(level, placed)| {
+                let filled = filled.get(level).copied().unwrap_or(0);
+                format!(
+                    "{} {}/{} ({:.0}%)",
+                    level,
+                    filled,
+                    placed,
+                    filled as f64 * 100.0 / placed as f64
+                )
+            })
+            .collect()
+    }
+}
+
+/// Running totals kept across restarts.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Counters {
+    pub placed: u64,
+    /// Filled entries per ladder level, keyed by its percent below the open.
+    pub fills_by_level: BTreeMap<String, u64>,
+    pub cancelled: u64,
+    pub rejected_by_code: BTreeMap<i32, u64>,
+    /// Quote value of every entry placed.
+    pub notional_deployed: f64,
+    /// PnL realized by exits, keyed by the percent of the level whose fill
+    /// they sold.
+    #[serde(default)]
+    pub realized_by_level: BTreeMap<String, f64>,
+}
+
+impl fmt::Display for Counters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "placed {}, cancelled {}, notional deployed {:.2}",
+            self.placed, self.cancelled, self.notional_deployed
+        )?;
+        if !self.fills_by_level.is_empty() {
+            let fills: Vec<String> = self
+                .fills_by_level
+                .iter()
+                .map(|(level, count)| format!("{} x{}", level, count))
+                .collect();
+            write!(f, ", fills {}", fills.join(" "))?;
+        }
+        if !self.realized_by_level.is_empty() {
+            let realized: Vec<String> = self
+                .realized_by_level
+                .iter()
+                .map(|(level, pnl)| format!("{} {:.4}", level, pnl))
+                .collect();
+            write!(f, ", realized {}", realized.join(" "))?;
+        }
+        if !self.rejected_by_code.is_empty() {
+            let rejected: Vec<String> = self
+                .rejected_by_code
+                .iter()
+                .map(|(code, count)| format!("{} x{}", code, count))
+                .collect();
+            write!(f, ", rejected {}", rejected.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StopState {
+    pub trailing_stop: String,
+    pub attached_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StateOrder {
+    pub symbol: String,
+    pub order_id: String,
+    pub order_link_id: String,
+    pub side: String,
+    /// Price and qty as planned and sent.
+    pub price: String,
+    pub qty: String,
+    pub status: String,
+    /// Strategy that placed it, `<name>/trigger` for triggered cycles.
+    #[serde(default)]
+    pub strategy: String,
+    /// Ladder level and its percent below the open. For an exit, those of
+    /// the entry it sells.
+    #[serde(default)]
+    pub level: Option<usize>,
+    #[serde(default)]
+    pub level_percent: Option<f64>,
+    /// When it was placed. Orders from older state files get the time in
+    /// their orderLinkId, the cycle open for entries.
+    #[serde(default)]
+    pub placed_at: Option<DateTime<Utc>>,
+    /// Set on the entry whose fill was counted for its level, so the other
+    /// children of a split level don't count it again.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fill_counted: bool,
+    /// When an exit still resting is cancelled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// For an exit, the orderLinkId of the entry whose fill it sells.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+}
+
+/// The bot's state, shared by the strategy tasks and written to disk on
+/// every change so `status` can read it from another process.
+pub struct StateStore {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+impl StateOrder {
+    /// Pairs placed orders with the requests they came from.
+    pub fn placed(
+        strategy: &StrategyConfig,
+        requests: &[OrderRequest],
+        placed: &[BatchOrderResponse],
+    ) -> Vec<StateOrder> {
+        placed
+            .iter()
+            .filter_map(|response| {
+                let request = requests
+                    .iter()
+                    .find(|request| request.order_link_id == response.order_link_id)?;
+                let level = ladder::level_from_link_id(&response.order_link_id);
+                Some(StateOrder {
+                    symbol: response.symbol.clone(),
+                    order_id: response.order_id.clone(),
+                    order_link_id: response.order_link_id.clone(),
+                    side: request.side.clone(),
+                    price: request.price.clone(),
+                    qty: request.qty.clone(),
+                    status: "New".to_string(),
+                    strategy: strategy.name.clone(),
+                    level,
+                    level_percent: level.and_then(|level| strategy.levels.get(level).copied()),
+                    placed_at: Some(Utc::now()),
+                    fill_counted: false,
+                    expires_at: None,
+                    entry: None,
+                })
+            })
+            .collect()
+    }
+
+    /// How reports name the order: its level if it has one (e.g. "25%
+    /// level"), else its orderLinkId.
+    pub fn label(&self) -> String {
+        match (
+            self.level_percent,
+            ladder::child_from_link_id(&self.order_link_id),
+        ) {
+            (Some(percent), Some(child)) => format!("{}% level child {}", percent, child),
+            (Some(percent), None) => format!("{}% level", percent),
+            (None, _) => self.order_link_id.clone(),
+        }
+    }
+
+    /// Fills in what an order from an older state file lacks, from its
+    /// strategy and orderLinkId.
+    fn backfill(&mut self, strategy: &str, config: Option<&StrategyConfig>) {
+        if self.strategy.is_empty() {
+            self.strategy = strategy.to_string();
+        }
+        if self.level.is_none() {
+            self.level = ladder::level_from_link_id(&self.order_link_id);
+        }
+        if self.level_percent.is_none() {
+            self.level_percent = self
+                .level
+                .zip(config)
+                .and_then(|(level, config)| config.levels.get(level).copied());
+        }
+        if self.placed_at.is_none() {
+            self.placed_at = self
+                .order_link_id
+                .rsplit('-')
+                .nth(1)
+                .and_then(|millis| millis.parse().ok())
+                .and_then(DateTime::from_timestamp_millis);
+        }
+    }
+}
+
+impl fmt::Display for StateOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<12} {:<4} {:>14} @ {:<12} {:<16} {}",
+            self.symbol, self.side, self.qty, self.price, self.status, self.order_link_id
+        )?;
+        if let Some(percent) = self.level_percent {
+            write!(f, " {}%", percent)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(until) = self.cooldown.until {
+            writeln!(
+                f,
+                "API cooldown until {} after {} ({} calls suspended)",
+                until, self.cooldown.reason, self.cooldown.suspended
+            )?;
+        }
+        if self.cooldown.entered > 0 {
+            writeln!(f, "API cooldowns entered: {}", self.cooldown.entered)?;
+        }
+        if let Some(clock) = &self.clock {
+            writeln!(f, "{} (measured {})", clock, clock.at)?;
+        }
+        if !self.tasks.is_empty() {
+            writeln!(f, "tasks:")?;
+            for (name, health) in &self.tasks {
+                writeln!(f, "  {:<20} {}", name, health)?;
+            }
+        }
+        if let Some(checked) = self.key_checked_at {
+            let expiry = self.key_expires_at.map_or_else(
+                || "doesn't expire".to_string(),
+                |expires| key_expiry::describe(expires, Utc::now()),
+            );
+            writeln!(f, "API key {} (checked {})", expiry, checked)?;
+        }
+        if self.strategies.is_empty() {
+            return write!(f, "no tracked orders");
+        }
+        for (name, strategy) in &self.strategies {
+            writeln!(f, "== {} ==", name)?;
+            writeln!(f, "counters: {}", strategy.counters)?;
+            writeln!(f, "entries:")?;
+            for order in &strategy.entries {
+                writeln!(f, "  {}", order)?;
+            }
+            writeln!(f, "exits:")?;
+            for order in &strategy.exits {
+                writeln!(f, "  {}", order)?;
+            }
+            if !strategy.stops.is_empty() {
+                writeln!(f, "trailing stops:")?;
+                for (symbol, stop) in &strategy.stops {
+                    writeln!(
+                        f,
+                        "  {:<12} distance {} since {}",
+                        symbol, stop.trailing_stop, stop.attached_at
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl State {
+    /// Counters and the live `latency` window in the Prometheus text
+    /// format.
+    pub fn prometheus(&self, latency: &BTreeMap<String, EndpointStats>) -> String {
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>| {
+            out.push_str(&format!(
+                "# HELP {} {}\n# TYPE {} {}\n",
+                name, help, name, kind
+            ));
+            for (labels, value) in samples {
+                out.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+            }
+        };
+        let strategies = &self.strategies;
+        let per_strategy = |value: fn(&Counters) -> f64| {
+            strategies
+                .iter()
+                .map(|(name, state)| (format!("strategy=\"{}\"", name), value(&state.counters)))
+                .collect()
+        };
+        family(
+            "stinkbid_orders_placed_total",
+            "counter",
+            "Entry orders placed.",
+            per_strategy(|c| c.placed as f64),
+        );
+        family(
+            "stinkbid_orders_cancelled_total",
+            "counter",
+            "Entry orders cancelled.",
+            per_strategy(|c| c.cancelled as f64),
+        );
+        family(
+            "stinkbid_notional_deployed_total",
+            "counter",
+            "Quote value of entries placed.",
+            per_strategy(|c| c.notional_deployed),
+        );
+        family(
+            "stinkbid_fills_total",
+            "counter",
+            "Filled entries per ladder level.",
+            strategies
+                .iter()
+                .flat_map(|(name, state)| {
+                    state
+                        .counters
+                        .fills_by_level
+                        .iter()
+                        .map(move |(level, count)| {
+                            (
+                                format!("strategy=\"{}\",level=\"{}\"", name, level),
+                                *count as f64,
+                            )
+                        })
+                })
+                .collect(),
+        );
+        family(
+            "stinkbid_realized_pnl",
+            "gauge",
+            "PnL realized by exits per level of the entries they sold.",
+            strategies
+                .iter()
+                .flat_map(|(name, state)| {
+                    state
+                        .counters
+                        .realized_by_level
+                        .iter()
+                        .map(move |(level, pnl)| {
+                            (format!("strategy=\"{}\",level=\"{}\"", name, level), *pnl)
+                        })
+                })
+                .collect(),
+        );
+        family(
+            "stinkbid_rejections_total",
+            "counter",
+            "Rejected orders per retCode.",
+            strategies
+                .iter()
+                .flat_map(|(name, state)| {
+                    state
+                        .counters
+                        .rejected_by_code
+                        .iter()
+                        .map(move |(code, count)| {
+                            (
+                                format!("strategy=\"{}\",code=\"{}\"", name, code),
+                                *count as f64,
+                            )
+                        })
+                })
+                .collect(),
+        );
+        family(
+            "stinkbid_clock_offset_ms",
+            "gauge",
+            "Milliseconds the exchange's clock is ahead of ours, as last measured.",
+            self.clock
+                .iter()
+                .map(|clock| (String::new(), clock.offset_ms as f64))
+                .collect(),
+        );
+        family(
+            "stinkbid_request_latency_p95_ms",
+            "gauge",
+            "p95 request latency per endpoint over the recent window.",
+            latency
+                .iter()
+                .map(|(endpoint, stats)| {
+                    (format!("endpoint=\"{}\"", endpoint), stats.p95_ms as f64)
+                })
+                .collect(),
+        );
+        out
+    }
+
+    /// The `status --latency` view.
+    pub fn latency_table(&self) -> String {
+        if self.latency.is_empty() {
+            return "no requests recorded".to_string();
+        }
+        self.latency
+            .iter()
+            .map(|(endpoint, stats)| format!("{:<36} {}", endpoint, stats))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Time-to-fill per strategy and level, for `stats fills` and the
+    /// weekly summary. Only `strategy` when given.
+    pub fn time_to_fill(&self, strategy: Option<&str>) -> Vec<String> {
+        self.strategies
+            .iter()
+            .filter(|(name, _)| strategy.is_none_or(|strategy| strategy == name.as_str()))
+            .flat_map(|(name, state)| {
+                state
+                    .time_to_fill()
+                    .into_iter()
+                    .map(move |(level, distribution)| {
+                        format!("[{}] {:<8} {}", name, level, distribution)
+                    })
+            })
+            .collect()
+    }
+
+    /// Completes orders read from an older state file. A triggered cycle's
+    /// orders take their levels from the strategy it was triggered for.
+    pub fn backfill(&mut self, strategies: &[StrategyConfig]) {
+        for (name, state) in &mut self.strategies {
+            let base = name.strip_suffix("/trigger").unwrap_or(name);
+            let config = strategies.iter().find(|strategy| strategy.name == base);
+            for order in state.entries.iter_mut().chain(&mut state.exits) {
+                order.backfill(name, config);
+            }
+        }
+    }
+
+    /// Reads the state file, migrated to `STATE_VERSION`. One written by a
+    /// newer release is refused.
+    pub fn load(path: &str) -> Result<State, Box<dyn std::error::Error>> {
+        if !PathBuf::from(path).exists() {
+            return Ok(State::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        let mut state: State = serde_json::from_str(&contents)?;
+        storage::check_version(state.version, STATE_VERSION)?;
+        state.version = STATE_VERSION;
+        Ok(state)
+    }
+
+    /// Writes the state file atomically, at `STATE_VERSION`.
+    pub fn save(&mut self, path: &Path) -> Result<(), BoxError> {
+        self.version = STATE_VERSION;
+        let json = serde_json::to_string_pretty(self)?;
+        Ok(storage::write_atomic(path, json.as_bytes())?)
+    }
+}
+
+impl StateStore {
+    /// Opens the state file. One that doesn't parse is moved aside and the
+    /// bot starts fresh, with a critical alert, rather than crashing or
+    /// overwriting it; one it can't read at all, or from a newer release,
+    /// is an error.
+    pub fn open(path: &str) -> Result<StateStore, Box<dyn std::error::Error>> {
+        let state = match State::load(path) {
+            Ok(state) => state,
+            Err(e) if e.is::<serde_json::Error>() => {
+                let aside = storage::quarantine(Path::new(path), Utc::now())?;
+                notify::critical(&format!(
+                    "state file {} is corrupt ({}), moved it to {} and started with empty state. Open orders placed before are no longer tracked, check them on the exchange",
+                    path,
+                    e,
+                    aside.display()
+                ));
+                State::default()
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(StateStore {
+            path: PathBuf::from(path),
+            state: Mutex::new(state),
+        })
+    }
+
+    /// The tracked entry with `order_id`, as the strategy placed it.
+    pub fn entry(&self, strategy: &str, order_id: &str) -> Option<StateOrder> {
+        self.read(|state| {
+            state
+                .strategies
+                .get(strategy)?
+                .entries
+                .iter()
+                .find(|entry| entry.order_id == order_id)
+                .cloned()
+        })
+    }
+
+    pub fn read<R, F: FnOnce(&State) -> R>(&self, view: F) -> R {
+        view(&self.state.lock().expect("state lock poisoned"))
+    }
+
+    pub fn update<F: FnOnce(&mut State)>(&self, change: F) {
+        let mut state = self.state.lock().expect("state lock poisoned");
+        change(&mut state);
+        if let Err(e) = state.save(&self.path) {
+            println!("failed writing state {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn update_strategy<F: FnOnce(&mut StrategyState)>(&self, strategy: &str, change: F) {
+        self.update(|state| change(state.strategies.entry(strategy.to_string()).or_default()));
+    }
+}
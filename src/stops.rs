@@ -0,0 +1,85 @@
+use crate::bybit::{BoxError, TradingStopRequest};
+use crate::config::{StrategyConfig, TrailingStopConfig};
+use crate::context::Context;
+use crate::decimal::{self, Rounding};
+use crate::notify::{self, Topic};
+use crate::state::StopState;
+use chrono::Utc;
+
+/// Attaches the configured trailing stop to `symbol`'s position, returning
+/// the price distance that was set.
+pub async fn attach_trailing_stop(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    config: &TrailingStopConfig,
+    symbol: &str,
+    average: f64,
+) -> Result<String, BoxError> {
+    let instrument = ctx.instrument(strategy.category_of(symbol), symbol)?;
+    let distance = match (config.distance, config.percent) {
+        (Some(distance), _) => distance,
+        (None, Some(percent)) => average * percent / 100.0,
+        (None, None) => return Err("trailing stop has no distance".into()),
+    };
+    let distance = decimal::format_step(distance, &instrument.tick_size, Rounding::Up);
+
+    let request = TradingStopRequest {
+        category: "linear".to_string(),
+        symbol: symbol.to_string(),
+        tpsl_mode: "Full".to_string(),
+        trailing_stop: Some(distance.clone()),
+        stop_loss: None,
+        position_idx: 0,
+    };
+    ctx.client.set_trading_stop(&request).await?;
+    ctx.store.update_strategy(&strategy.name, |state| {
+        state.stops.insert(
+            symbol.to_string(),
+            StopState {
+                trailing_stop: distance.clone(),
+                attached_at: Utc::now(),
+            },
+        );
+    });
+    Ok(distance)
+}
+
+/// Moves the stop loss of `symbol`'s position to its average entry, once
+/// the first exit of an entry filled, so what's left can't turn into a
+/// loss. Returns the stop price, or `None` when no position is left.
+pub async fn move_to_break_even(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+) -> Result<Option<String>, BoxError> {
+    let instrument = ctx.instrument(strategy.category_of(symbol), symbol)?;
+    let Some(position) = ctx
+        .client
+        .get_positions(symbol)
+        .await?
+        .into_iter()
+        .find(|position| position.side == "Buy" && position.is_open())
+    else {
+        return Ok(None);
+    };
+    let average: f64 = position.avg_price.parse()?;
+    // rounded up, so the stop never sits below the entry
+    let price = decimal::format_step(average, &instrument.tick_size, Rounding::Up);
+    let request = TradingStopRequest {
+        category: "linear".to_string(),
+        symbol: symbol.to_string(),
+        tpsl_mode: "Full".to_string(),
+        trailing_stop: None,
+        stop_loss: Some(price.clone()),
+        position_idx: 0,
+    };
+    ctx.client.set_trading_stop(&request).await?;
+    notify::notice(
+        Topic::Placement,
+        &format!(
+            "[{}] {} stop moved to break-even at {}",
+            strategy.name, symbol, price
+        ),
+    );
+    Ok(Some(price))
+}
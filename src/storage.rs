@@ -0,0 +1,65 @@
+//! Where the bot keeps its files and how it writes them: atomically, so a
+//! crash mid-write can't leave half a file, and with a schema version, so
+//! a release never misreads a layout it doesn't know.
+
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// `$XDG_DATA_HOME/stink-bid`, else `~/.local/share/stink-bid`, else the
+/// working directory.
+pub fn default_data_dir(xdg_data_home: Option<&str>, home: Option<&str>) -> String {
+    let base = match (xdg_data_home, home) {
+        (Some(xdg), _) if !xdg.is_empty() => PathBuf::from(xdg),
+        (_, Some(home)) if !home.is_empty() => Path::new(home).join(".local/share"),
+        _ => return ".".to_string(),
+    };
+    base.join("stink-bid").to_string_lossy().into_owned()
+}
+
+/// `path` under `data_dir`, unless it's absolute.
+pub fn resolve(data_dir: &str, path: &str) -> String {
+    if Path::new(path).is_absolute() {
+        return path.to_string();
+    }
+    Path::new(data_dir)
+        .join(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Writes `contents` to a temporary file beside `path`, syncs it and
+/// renames it over `path`, so readers see the old file or the new one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+/// Whether a file at schema version `found` can be read by a release at
+/// `current`. Older ones are migrated on load; newer ones are refused
+/// rather than misread.
+pub fn check_version(found: u32, current: u32) -> Result<(), String> {
+    if found > current {
+        return Err(format!(
+            "schema version {} is newer than this release's {}, upgrade stink-bid or restore an older file",
+            found, current
+        ));
+    }
+    Ok(())
+}
+
+/// Moves an unreadable file aside as `<path>.corrupt-<time>`, kept for
+/// inspection, and returns where it went.
+pub fn quarantine(path: &Path, now: DateTime<Utc>) -> io::Result<PathBuf> {
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(format!(".corrupt-{}", now.format("%Y%m%dT%H%M%S")));
+    let aside = PathBuf::from(aside);
+    fs::rename(path, &aside)?;
+    Ok(aside)
+}
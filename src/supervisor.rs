@@ -0,0 +1,106 @@
+//! Runs each subsystem as its own task and watches it. A subsystem that
+//! panics is restarted with backoff; a core task that ends takes the bot
+//! down through the shutdown path, cancelling its entries.
+
+use crate::context::Context;
+use crate::notify;
+use crate::restarts::{self, TaskHealth, TaskState};
+use chrono::Utc;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::task::{JoinError, JoinHandle};
+
+/// What a task's panic said.
+fn panic_message(error: JoinError) -> String {
+    if error.is_cancelled() {
+        return "cancelled".to_string();
+    }
+    let payload = error.into_panic();
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panicked".to_string())
+}
+
+fn record(ctx: &Context, name: &str, state: TaskState, restarts: u32, error: Option<String>) {
+    let health = TaskHealth {
+        state,
+        since: Utc::now(),
+        restarts,
+        last_error: error,
+    };
+    ctx.store.update(|saved| {
+        saved.tasks.insert(name.to_string(), health);
+    });
+}
+
+/// Runs the subsystem `start` builds and restarts it after a panic, up to
+/// `MAX_RESTARTS` in a row. It returning means it's done, e.g. a server
+/// with nothing to serve, and it's left stopped.
+pub fn spawn<F, Fut>(ctx: &Arc<Context>, name: &str, start: F)
+where
+    F: Fn(Arc<Context>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (ctx, name) = (ctx.clone(), name.to_string());
+    tokio::spawn(async move {
+        let mut restarts = 0;
+        loop {
+            record(&ctx, &name, TaskState::Running, restarts, None);
+            let started = Instant::now();
+            let error = match tokio::spawn(start(ctx.clone())).await {
+                Ok(()) => {
+                    info!("{} stopped", name);
+                    record(&ctx, &name, TaskState::Stopped, restarts, None);
+                    return;
+                }
+                Err(e) => panic_message(e),
+            };
+            restarts = restarts::restarts_after(restarts, started.elapsed());
+            let Some(backoff) = restarts::backoff(restarts) else {
+                notify::critical(&format!(
+                    "{} failed {} times in a row, not restarting it: {}",
+                    name, restarts, error
+                ));
+                record(&ctx, &name, TaskState::Failed, restarts - 1, Some(error));
+                return;
+            };
+            notify::send(&format!(
+                "{} failed, restarting in {:?} ({} of {}): {}",
+                name,
+                backoff,
+                restarts,
+                restarts::MAX_RESTARTS,
+                error
+            ));
+            record(&ctx, &name, TaskState::Restarting, restarts, Some(error));
+            tokio::time::sleep(backoff).await;
+        }
+    });
+}
+
+/// Runs a core task, one the bot can't trade without. Whether it panics or
+/// returns, the entry tasks are stopped, cancelling their orders, and the
+/// process exits.
+pub fn spawn_core<Fut>(ctx: &Arc<Context>, name: &str, task: Fut) -> JoinHandle<()>
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (ctx, name) = (ctx.clone(), name.to_string());
+    tokio::spawn(async move {
+        record(&ctx, &name, TaskState::Running, 0, None);
+        let error = match tokio::spawn(task).await {
+            Ok(()) => "returned".to_string(),
+            Err(e) => panic_message(e),
+        };
+        notify::critical(&format!(
+            "{} stopped ({}), cancelling entries and shutting down",
+            name, error
+        ));
+        record(&ctx, &name, TaskState::Failed, 0, Some(error));
+        ctx.stop_entries().await;
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,141 @@
+//! Keeps a burst of notifications from one root cause down to a few
+//! messages: repeats are dropped, the rest of a burst is sent as one
+//! digest and each channel has a rate cap.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ThrottleConfig {
+    /// The same message again within this is dropped.
+    #[serde(with = "humantime_serde")]
+    pub dedupe_window: Duration,
+    /// After the first message of a burst, the rest are held this long
+    /// and sent as one digest.
+    #[serde(with = "humantime_serde")]
+    pub batch_window: Duration,
+    /// Most messages a channel is sent per minute, 0 for no cap.
+    pub per_channel_per_minute: usize,
+    /// Fills at this level (1 = nearest the open) or deeper skip batching
+    /// and the cap, like critical alerts.
+    pub urgent_from_level: usize,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        ThrottleConfig {
+            dedupe_window: Duration::from_secs(600),
+            batch_window: Duration::from_secs(120),
+            per_channel_per_minute: 20,
+            urgent_from_level: 3,
+        }
+    }
+}
+
+/// What became of an offered message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Admit {
+    /// First of a burst: send it now.
+    Now,
+    /// Held for the digest at the end of the window.
+    Held,
+    /// Sent already within the dedupe window.
+    Duplicate,
+}
+
+/// Dedupe and burst window for one kind of message.
+#[derive(Debug)]
+pub struct Batcher {
+    dedupe_window: ChronoDuration,
+    batch_window: ChronoDuration,
+    seen: BTreeMap<String, DateTime<Utc>>,
+    window_ends: Option<DateTime<Utc>>,
+    held: Vec<String>,
+}
+
+impl Batcher {
+    pub fn new(config: &ThrottleConfig) -> Batcher {
+        Batcher {
+            dedupe_window: ChronoDuration::from_std(config.dedupe_window).unwrap_or_default(),
+            batch_window: ChronoDuration::from_std(config.batch_window).unwrap_or_default(),
+            seen: BTreeMap::new(),
+            window_ends: None,
+            held: Vec::new(),
+        }
+    }
+
+    pub fn offer(&mut self, message: &str, now: DateTime<Utc>) -> Admit {
+        let oldest = now - self.dedupe_window;
+        self.seen.retain(|_, at| *at > oldest);
+        if self.seen.contains_key(message) {
+            return Admit::Duplicate;
+        }
+        self.seen.insert(message.to_string(), now);
+        // a window past its end still holds until its digest goes out
+        match self.window_ends {
+            Some(end) if now < end || !self.held.is_empty() => {
+                self.held.push(message.to_string());
+                Admit::Held
+            }
+            _ => {
+                self.window_ends = Some(now + self.batch_window);
+                Admit::Now
+            }
+        }
+    }
+
+    /// When the window opened by an `Admit::Now` closes.
+    pub fn window_ends(&self) -> Option<DateTime<Utc>> {
+        self.window_ends
+    }
+
+    /// Closes a window that is over, with one message for what it held:
+    /// the message itself when there was one, a digest when more. `kind`
+    /// names them in the digest, e.g. "notices".
+    pub fn flush(&mut self, kind: &str, now: DateTime<Utc>) -> Option<String> {
+        if self.window_ends.is_none_or(|end| now < end) {
+            return None;
+        }
+        self.window_ends = None;
+        let held = std::mem::take(&mut self.held);
+        match held.as_slice() {
+            [] => None,
+            [only] => Some(only.clone()),
+            [first, ..] => Some(format!(
+                "{} more {} in the last {}s, first: {}",
+                held.len(),
+                kind,
+                self.batch_window.num_seconds(),
+                first
+            )),
+        }
+    }
+}
+
+/// Messages a channel has been sent in the last minute.
+#[derive(Debug, Default)]
+pub struct RateCap {
+    sent: VecDeque<DateTime<Utc>>,
+}
+
+impl RateCap {
+    /// Whether one more message fits under `per_minute`, counting it if
+    /// so. A cap of 0 admits everything.
+    pub fn admit(&mut self, per_minute: usize, now: DateTime<Utc>) -> bool {
+        if per_minute == 0 {
+            return true;
+        }
+        let oldest = now - ChronoDuration::minutes(1);
+        while self.sent.front().is_some_and(|at| *at <= oldest) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= per_minute {
+            return false;
+        }
+        self.sent.push_back(now);
+        true
+    }
+}
@@ -0,0 +1,43 @@
+use crate::scrub;
+use reqwest::Request;
+use sha2::{Digest, Sha256};
+
+/// Headers that are never logged as-is.
+const REDACTED_HEADERS: [&str; 2] = ["x-bapi-api-key", "x-bapi-sign"];
+
+/// Logs an outgoing request. Credential headers are swapped for a short
+/// hash here, so no caller can log them by accident.
+pub fn request(request: &Request) {
+    let mut line = format!("TRACE -> {} {}", request.method(), request.url().path());
+    if let Some(query) = request.url().query() {
+        line.push_str(&format!("?{}", query));
+    }
+    for (name, value) in request.headers() {
+        let value = value.to_str().unwrap_or("<binary>");
+        if REDACTED_HEADERS.contains(&name.as_str()) {
+            line.push_str(&format!(" {}={}", name, redact(value)));
+        } else {
+            line.push_str(&format!(" {}={}", name, value));
+        }
+    }
+    if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+        line.push_str(&format!(" body={}", String::from_utf8_lossy(body)));
+    }
+    // a secret echoed in a body or query is still scrubbed
+    println!("{}", scrub::scrub(&line));
+}
+
+/// Logs the raw body of a reply.
+pub fn reply(endpoint: &str, ret_code: i32, body: &str) {
+    println!(
+        "TRACE <- {} retCode {} body={}",
+        endpoint,
+        ret_code,
+        scrub::scrub(body)
+    );
+}
+
+fn redact(value: &str) -> String {
+    let digest = hex::encode(Sha256::digest(value.as_bytes()));
+    format!("<redacted {}>", &digest[..8])
+}
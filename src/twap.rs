@@ -0,0 +1,223 @@
+use crate::bybit::{BoxError, CancelOrderData};
+use crate::config::{Category, StrategyConfig};
+use crate::context::Context;
+use crate::journal;
+use crate::ladder;
+use crate::lifecycle::OrderEvent;
+use crate::notify::{self, Topic};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// How often price is checked against the trigger.
+const TRIGGER_CHECK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Waits for `symbol` to trade `trigger_percent` below `anchor`, then buys
+/// its budget in slices until the budget is spent, the TWAP deadline or
+/// `until`, the hold's end, passes. A slice resting on shutdown is
+/// cancelled before the task ends.
+pub async fn run(
+    ctx: Arc<Context>,
+    strategy: StrategyConfig,
+    symbol: String,
+    open: DateTime<Utc>,
+    anchor: f64,
+    until: DateTime<Utc>,
+) {
+    let twap = strategy
+        .twap
+        .clone()
+        .expect("validated: twap symbols need twap settings");
+    let category = strategy.category_of(&symbol);
+    let mut shutdown = ctx.shutdown.subscribe();
+    let trigger = anchor * (1.0 - twap.trigger_percent / 100.0);
+    info!(
+        "[{}] {} twap armed below {} until {}",
+        strategy.name, symbol, trigger, until
+    );
+    let last = loop {
+        if Utc::now() >= until {
+            info!(
+                "[{}] {} twap never triggered below {}",
+                strategy.name, symbol, trigger
+            );
+            return;
+        }
+        match last_price(&ctx, category, &symbol).await {
+            Ok(last) if last <= trigger => break last,
+            Ok(_) => {}
+            Err(e) => println!(
+                "[{}] {} twap price check failed: {}",
+                strategy.name, symbol, e
+            ),
+        }
+        let next = (Utc::now() + ChronoDuration::from_std(TRIGGER_CHECK).unwrap()).min(until);
+        if !wait_until(next, &mut shutdown).await {
+            return;
+        }
+    };
+    let deadline = match twap.deadline {
+        Some(deadline) => until.min(Utc::now() + ChronoDuration::from_std(deadline).unwrap()),
+        None => until,
+    };
+    let budget: f64 = strategy.budgets.iter().sum();
+    journal::record(
+        "twap_triggered",
+        json!({
+            "strategy": strategy.name,
+            "symbol": symbol,
+            "last": last,
+            "trigger": trigger,
+            "budget": budget,
+            "deadline": deadline,
+        }),
+    );
+    notify::notice(
+        Topic::Placement,
+        &format!(
+            "[{}] {} traded {} below the {} twap trigger, buying {:.2} over {} slices until {}",
+            strategy.name, symbol, last, trigger, budget, twap.slices, deadline
+        ),
+    );
+    let every = ChronoDuration::from_std(twap.every).unwrap();
+    let mut spent = 0.0;
+    for index in 0..twap.slices {
+        if Utc::now() >= deadline {
+            break;
+        }
+        // each slice takes an even share of what's left
+        let notional = (budget - spent) / (twap.slices - index) as f64;
+        let placed = match place_slice(&ctx, &strategy, &symbol, open, index, notional).await {
+            Ok(placed) => placed,
+            Err(e) => {
+                info!(
+                    "[{}] {} twap slice {} not placed: {}",
+                    strategy.name, symbol, index, e
+                );
+                None
+            }
+        };
+        let running = wait_until((Utc::now() + every).min(deadline), &mut shutdown).await;
+        if let Some(order_id) = placed {
+            spent += settle(&ctx, &strategy, category, &symbol, &order_id).await;
+        }
+        if !running {
+            break;
+        }
+        let min_notional = ctx
+            .instrument(category, &symbol)
+            .map_or(0.0, |instrument| instrument.min_notional);
+        if budget - spent < min_notional.max(f64::EPSILON) {
+            break;
+        }
+    }
+    notify::notice(
+        Topic::Placement,
+        &format!(
+            "[{}] {} twap done, bought {:.2} of {:.2}",
+            strategy.name, symbol, spent, budget
+        ),
+    );
+}
+
+/// Places slice `index` at the last price. The order id when the exchange
+/// took it.
+async fn place_slice(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    symbol: &str,
+    open: DateTime<Utc>,
+    index: usize,
+    notional: f64,
+) -> Result<Option<String>, BoxError> {
+    let category = strategy.category_of(symbol);
+    let settings = &ctx.config.symbols[symbol];
+    let instrument = ctx.instrument_or_refresh(category, symbol).await?;
+    let price = last_price(ctx, category, symbol).await?;
+    let link_id = format!(
+        "{}-{}-{}-t{}",
+        strategy.link_id_prefix,
+        symbol,
+        open.timestamp_millis(),
+        index
+    );
+    let request = ladder::plan_slice(symbol, index, price, notional, &instrument, link_id)?
+        .leverage(category == Category::Spot && settings.spot_margin)
+        .build();
+    let orders = [request];
+    let placement = ctx.client.place_batch_order(category, &orders).await;
+    ctx.lifecycle
+        .placed(&strategy.name, category, &orders, &placement);
+    let placement = placement?;
+    ctx.note_rejections(strategy, &placement.rejected).await;
+    if let Some(rejection) = placement.rejected.first() {
+        return Err(rejection.msg.clone().into());
+    }
+    let [request] = orders;
+    info!(
+        "[{}] {} twap slice {}: {} @ {}",
+        strategy.name, symbol, index, request.qty, request.price
+    );
+    Ok(placement
+        .placed
+        .into_iter()
+        .next()
+        .map(|placed| placed.order_id))
+}
+
+/// Cancels what's left of a slice and returns the quote amount it filled.
+async fn settle(
+    ctx: &Context,
+    strategy: &StrategyConfig,
+    category: Category,
+    symbol: &str,
+    order_id: &str,
+) -> f64 {
+    let resting = [CancelOrderData {
+        symbol: symbol.to_string(),
+        order_id: order_id.to_string(),
+    }];
+    // a filled slice refuses the cancel, which the lookup below sorts out
+    if let Err(e) = ctx.client.cancel_batch_order(category, &resting).await {
+        println!(
+            "[{}] {} twap slice {} not cancelled: {}",
+            strategy.name, symbol, order_id, e
+        );
+    }
+    match ctx.client.get_order(category, symbol, order_id, None).await {
+        Ok(Some(order)) => {
+            if let Some(event) = OrderEvent::observed(&strategy.name, category, &order) {
+                ctx.lifecycle.transition(event);
+            }
+            let executed: f64 = order.cum_exec_qty.parse().unwrap_or(0.0);
+            let average: f64 = order.avg_price.parse().unwrap_or(0.0);
+            executed * average
+        }
+        Ok(None) => 0.0,
+        Err(e) => {
+            println!(
+                "[{}] {} twap slice {} lookup failed: {}",
+                strategy.name, symbol, order_id, e
+            );
+            0.0
+        }
+    }
+}
+
+async fn last_price(ctx: &Context, category: Category, symbol: &str) -> Result<f64, BoxError> {
+    let ticker = ctx.client.get_ticker(category, symbol).await?;
+    Ok(ticker.last_price.parse()?)
+}
+
+/// Sleeps until `at`. False when shutdown came first.
+async fn wait_until(at: DateTime<Utc>, shutdown: &mut watch::Receiver<bool>) -> bool {
+    if *shutdown.borrow() {
+        return false;
+    }
+    let wait = (at - Utc::now()).to_std().unwrap_or_default();
+    tokio::select! {
+        _ = tokio::time::sleep(wait) => true,
+        _ = shutdown.changed() => false,
+    }
+}
@@ -0,0 +1,332 @@
+//! Checks a config before it's deployed, without trading: what
+//! `Config::load` rejects, plus what only shows against the exchange's
+//! trading rules, the schedule and the notifier settings.
+
+use crate::bybit::{BoxError, BybitClient, InstrumentInfo, Ticker};
+use crate::catch_up::CatchUpPolicy;
+use crate::config::{Category, Config, CredentialSource, Sizing, VaultAuth};
+use crate::guards;
+use crate::instruments::{self, Instrument};
+use crate::preflight;
+use lettre::message::Mailbox;
+use reqwest::Url;
+use std::collections::BTreeMap;
+use std::{env, fmt, fs};
+
+/// Instruments listed per category, as fetched by `--online` and saved
+/// with `--save-instruments`.
+pub type Snapshot = BTreeMap<Category, Vec<InstrumentInfo>>;
+
+/// Each traded symbol's ticker, for the 24h volume `--online` holds the
+/// guards to.
+pub type Tickers = BTreeMap<(Category, String), Ticker>;
+
+/// Errors fail the check, warnings are printed alongside.
+#[derive(Debug, Default)]
+pub struct Findings {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Findings {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl fmt::Display for Findings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "error: {}", error)?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        write!(
+            f,
+            "{} error(s), {} warning(s)",
+            self.errors.len(),
+            self.warnings.len()
+        )
+    }
+}
+
+pub fn load_snapshot(path: &str) -> Result<Snapshot, BoxError> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// The instruments listed in every category `config` trades in.
+pub async fn fetch_snapshot(client: &BybitClient, config: &Config) -> Result<Snapshot, BoxError> {
+    let mut snapshot = Snapshot::new();
+    for category in config.traded_categories().into_keys() {
+        let listed = instruments::listed(client, category).await?;
+        snapshot.insert(category, listed.into_values().collect());
+    }
+    Ok(snapshot)
+}
+
+/// The ticker of every symbol `config` trades, in each category.
+pub async fn fetch_tickers(client: &BybitClient, config: &Config) -> Result<Tickers, BoxError> {
+    let mut tickers = Tickers::new();
+    for (category, traded) in config.traded_categories() {
+        for symbol in traded {
+            let ticker = client.get_ticker(category, &symbol).await?;
+            tickers.insert((category, symbol), ticker);
+        }
+    }
+    Ok(tickers)
+}
+
+/// What a loaded, so already validated, config would still run into.
+pub fn check(config: &Config, snapshot: Option<&Snapshot>, tickers: Option<&Tickers>) -> Findings {
+    let mut findings = Findings::default();
+    match snapshot {
+        Some(snapshot) => check_instruments(config, snapshot, &mut findings),
+        None => findings
+            .warnings
+            .push("minimum order values not checked, pass --instruments or --online".to_string()),
+    }
+    check_guards(config, tickers, &mut findings);
+    check_schedule(config, &mut findings);
+    check_notifiers(config, &mut findings);
+    check_credentials(config, &mut findings);
+    findings
+}
+
+/// Every traded symbol has to be listed and trading, quote in its budget
+/// currency and take each level's budget as an order.
+fn check_instruments(config: &Config, snapshot: &Snapshot, findings: &mut Findings) {
+    for (category, traded) in config.traded_categories() {
+        let listed: BTreeMap<&String, &InstrumentInfo> = snapshot
+            .get(&category)
+            .into_iter()
+            .flatten()
+            .map(|info| (&info.symbol, info))
+            .collect();
+        if listed.is_empty() {
+            findings.errors.push(format!(
+                "the instruments snapshot has no {} symbols",
+                category.as_param()
+            ));
+            continue;
+        }
+        for symbol in &traded {
+            let Some(info) = listed.get(symbol) else {
+                let close = preflight::suggestions(symbol, listed.keys().copied());
+                let mut error = format!("{}: not a known {} symbol", symbol, category.as_param());
+                if !close.is_empty() {
+                    let close: Vec<&str> = close.iter().map(|s| s.as_str()).collect();
+                    error.push_str(&format!(", did you mean {}?", close.join(" or ")));
+                }
+                findings.errors.push(error);
+                continue;
+            };
+            if info.status != "Trading" {
+                findings.warnings.push(format!(
+                    "{}: status is {}, it would be skipped",
+                    symbol, info.status
+                ));
+            }
+            let instrument = match Instrument::from_info((*info).clone()) {
+                Ok(instrument) => instrument,
+                Err(e) => {
+                    findings
+                        .errors
+                        .push(format!("{}: unreadable instrument info: {}", symbol, e));
+                    continue;
+                }
+            };
+            let currency = config.symbols[symbol]
+                .budget_currency(symbol)
+                .unwrap_or_default();
+            if instrument.quote_coin != currency {
+                findings.errors.push(format!(
+                    "{}: quotes in {} but budgets are in {}",
+                    symbol, instrument.quote_coin, currency
+                ));
+                continue;
+            }
+            for strategy in &config.strategies {
+                if strategy.category_of(symbol) != category {
+                    continue;
+                }
+                if strategy.sizing == Sizing::TargetPosition {
+                    findings.warnings.push(format!(
+                        "strategy {} sizes to a target position, its {} orders are only sized at placement",
+                        strategy.name, symbol
+                    ));
+                    continue;
+                }
+                for (index, level) in strategy.ladder().iter().enumerate() {
+                    if level.notional < instrument.min_notional {
+                        findings.errors.push(format!(
+                            "strategy {} level {} ({}%) budget {:.2} {} is below the {} minimum order value of {}",
+                            strategy.name,
+                            index + 1,
+                            level.percent,
+                            level.notional,
+                            currency,
+                            symbol,
+                            instrument.min_notional
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Every strategy's full ladder against `[guards]`, as if it were placed
+/// for every symbol. Volumes are only known `--online`, where the qty is
+/// sized from the last price.
+fn check_guards(config: &Config, tickers: Option<&Tickers>, findings: &mut Findings) {
+    let bounds = &config.guards;
+    if bounds.max_volume_multiple.is_some() && tickers.is_none() {
+        findings
+            .warnings
+            .push("guards.max_volume_multiple not checked, pass --online".to_string());
+    }
+    for strategy in &config.strategies {
+        if strategy.sizing == Sizing::TargetPosition {
+            continue;
+        }
+        let mut orders = Vec::new();
+        let mut volumes = BTreeMap::new();
+        for symbol in config.symbols.keys() {
+            let ticker = tickers.and_then(|tickers| {
+                tickers.get(&(strategy.category_of(symbol), symbol.to_string()))
+            });
+            let last = ticker.and_then(|ticker| ticker.last_price.parse::<f64>().ok());
+            if let Some(volume) = ticker.and_then(|ticker| ticker.volume_24h.parse().ok()) {
+                volumes.insert(symbol.as_str(), volume);
+            }
+            for level in strategy.ladder() {
+                // a split level goes out as children of at most this much
+                let notional = level
+                    .max_child_notional
+                    .map_or(level.notional, |child| child.min(level.notional));
+                let price = last.map(|last| last * (1.0 - level.percent / 100.0));
+                orders.push(guards::Order {
+                    symbol,
+                    qty: price.map_or(0.0, |price| notional / price),
+                    notional,
+                });
+            }
+        }
+        for breach in guards::check(bounds, &orders, &volumes, 0.0) {
+            findings
+                .errors
+                .push(format!("strategy {}: {}", strategy.name, breach));
+        }
+    }
+}
+
+fn check_schedule(config: &Config, findings: &mut Findings) {
+    let schedule = &config.schedule;
+    match schedule.catch_up {
+        CatchUpPolicy::PlaceLate if schedule.catch_up_window_hours == 0 => {
+            findings.warnings.push(
+                "schedule.catch_up = \"place_late\" with a catch_up_window_hours of 0 never places late"
+                    .to_string(),
+            )
+        }
+        CatchUpPolicy::PlaceLate => {
+            for strategy in &config.strategies {
+                let hours = strategy.interval.length().num_hours();
+                if schedule.catch_up_window_hours as i64 >= hours {
+                    findings.warnings.push(format!(
+                        "schedule.catch_up_window_hours of {} covers all of strategy {}'s {}h interval, a late start places against a stale open",
+                        schedule.catch_up_window_hours, strategy.name, hours
+                    ));
+                }
+            }
+        }
+        CatchUpPolicy::Prompt => findings.warnings.push(
+            "schedule.catch_up = \"prompt\" skips the open when not run from a terminal"
+                .to_string(),
+        ),
+        CatchUpPolicy::Skip => {}
+    }
+}
+
+/// The env vars the credentials provider reads, warned about like the
+/// notifiers', and a secrets store address that can't work.
+fn check_credentials(config: &Config, findings: &mut Findings) {
+    let credentials = &config.credentials;
+    let mut needed = Vec::new();
+    match (credentials.source, &credentials.vault) {
+        (CredentialSource::Env, _) => needed.extend(["API_KEY", "API_SECRET"]),
+        // without a key in the environment the default chain looks further
+        (CredentialSource::Aws, _) if env::var("AWS_ACCESS_KEY_ID").is_ok() => {
+            needed.push("AWS_SECRET_ACCESS_KEY")
+        }
+        (CredentialSource::Aws, _) => {}
+        (CredentialSource::Vault, Some(vault)) => {
+            if !Url::parse(&vault.address).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+            {
+                findings.errors.push(format!(
+                    "credentials.vault.address {:?} is not an http(s) URL",
+                    vault.address
+                ));
+            }
+            needed.push(match vault.auth {
+                VaultAuth::Token => "VAULT_TOKEN",
+                VaultAuth::Approle => "VAULT_SECRET_ID",
+            });
+        }
+        (CredentialSource::Vault, None) => {}
+    }
+    for var in needed.into_iter().filter(|var| env::var(var).is_err()) {
+        findings.warnings.push(format!(
+            "{} is not set, the credentials couldn't be fetched",
+            var
+        ));
+    }
+}
+
+/// Settings a notifier would fail on at startup. Missing credentials are
+/// only warned about, as they're often set where the bot runs.
+fn check_notifiers(config: &Config, findings: &mut Findings) {
+    let missing = |var: &str| env::var(var).is_err();
+    if let Some(email) = &config.email {
+        for (field, address) in [("from", &email.from), ("to", &email.to)] {
+            if let Err(e) = address.parse::<Mailbox>() {
+                findings
+                    .errors
+                    .push(format!("email.{} {:?}: {}", field, address, e));
+            }
+        }
+        for var in ["SMTP_USERNAME", "SMTP_PASSWORD"]
+            .into_iter()
+            .filter(|var| missing(var))
+        {
+            findings
+                .warnings
+                .push(format!("{} is not set, email would be disabled", var));
+        }
+    }
+    if config.slack.is_some() && missing("SLACK_WEBHOOK_URL") {
+        findings
+            .warnings
+            .push("SLACK_WEBHOOK_URL is not set, slack would be disabled".to_string());
+    }
+    if let Some(ntfy) = &config.ntfy {
+        if !Url::parse(&ntfy.server).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+            findings.errors.push(format!(
+                "ntfy.server {:?} is not an http(s) URL",
+                ntfy.server
+            ));
+        }
+        if ntfy.topic.is_empty() || ntfy.topic.contains('/') {
+            findings.errors.push(format!(
+                "ntfy.topic {:?} must be one path segment",
+                ntfy.topic
+            ));
+        }
+    }
+    if config.email.is_none() && config.slack.is_none() && config.ntfy.is_none() {
+        findings
+            .warnings
+            .push("no notifier configured, alerts only go to the log".to_string());
+    }
+}
@@ -0,0 +1,93 @@
+//! How much the bot logs: `-q`, `-v` and `-vv` on the command line, with
+//! `RUST_LOG` taking precedence for those used to its syntax.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+/// Crate name a `RUST_LOG` directive can target, as in `stink_bid=debug`.
+const TARGET: &str = "stink_bid";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Warnings and failures only.
+    Warn,
+    Info,
+    Debug,
+    /// Every request and failed reply, bodies included.
+    Trace,
+}
+
+impl Level {
+    fn parse(name: &str) -> Option<Level> {
+        match name.trim().to_lowercase().as_str() {
+            // nothing is quieter than warnings
+            "off" | "error" | "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        })
+    }
+}
+
+/// The level set by `RUST_LOG`: a bare level, or one for this crate.
+/// Directives for other targets are ignored, and the last that applies
+/// wins.
+pub fn from_rust_log(rust_log: &str) -> Option<Level> {
+    rust_log
+        .rsplit(',')
+        .find_map(|directive| match directive.split_once('=') {
+            Some((target, level)) if target.trim() == TARGET => Level::parse(level),
+            Some(_) => None,
+            None => Level::parse(directive),
+        })
+}
+
+/// The effective level and what set it, for the startup banner.
+pub fn resolve(verbose: u8, quiet: bool, rust_log: Option<&str>) -> (Level, &'static str) {
+    if let Some(level) = rust_log.and_then(from_rust_log) {
+        return (level, "RUST_LOG");
+    }
+    match (quiet, verbose) {
+        (true, _) => (Level::Warn, "-q"),
+        (false, 0) => (Level::Info, "default"),
+        (false, 1) => (Level::Debug, "-v"),
+        (false, _) => (Level::Trace, "-vv"),
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+static SOURCE: OnceLock<&'static str> = OnceLock::new();
+
+/// Sets the level for the rest of the process.
+pub fn set(level: Level, source: &'static str) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+    let _ = SOURCE.set(source);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+/// The effective level as the banner states it, e.g. "debug (-v)".
+pub fn describe() -> String {
+    let level = [Level::Warn, Level::Info, Level::Debug, Level::Trace]
+        .into_iter()
+        .rev()
+        .find(|level| enabled(*level))
+        .unwrap_or(Level::Warn);
+    format!("{} ({})", level, SOURCE.get().copied().unwrap_or("default"))
+}
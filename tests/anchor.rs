@@ -0,0 +1,123 @@
+//! Anchor candles picked and checked against the period being placed.
+
+#[path = "../src/anchor.rs"]
+mod anchor;
+
+use anchor::{check, reduce, select, start_of, Candle, Reducer, StaleCandle};
+use chrono::{DateTime, Duration, Utc};
+
+/// 2024-05-02 00:00 UTC, a daily open.
+fn open() -> DateTime<Utc> {
+    DateTime::from_timestamp(1_714_608_000, 0).unwrap()
+}
+
+fn millis(at: DateTime<Utc>) -> String {
+    at.timestamp_millis().to_string()
+}
+
+#[test]
+fn accepts_the_candle_of_the_period() {
+    assert_eq!(check("1714608000000", open()), Ok(()));
+    assert_eq!(start_of("1714608000000"), Ok(open()));
+}
+
+#[test]
+fn rejects_yesterdays_candle() {
+    let yesterday = open() - Duration::days(1);
+    assert_eq!(
+        check(&millis(yesterday), open()),
+        Err(StaleCandle::Old {
+            start: yesterday,
+            expected: open()
+        })
+    );
+    assert_eq!(
+        check(&millis(yesterday), open()).unwrap_err().to_string(),
+        "stale kline from 2024-05-01 00:00:00 UTC, expected the candle opening 2024-05-02 00:00:00 UTC"
+    );
+}
+
+#[test]
+fn rejects_a_candle_from_the_future() {
+    let tomorrow = open() + Duration::days(1);
+    assert_eq!(
+        check(&millis(tomorrow), open()),
+        Err(StaleCandle::Future {
+            start: tomorrow,
+            expected: open()
+        })
+    );
+    assert!(check(&millis(tomorrow), open())
+        .unwrap_err()
+        .to_string()
+        .ends_with("check the clock"));
+    // even a minute off isn't the candle
+    assert!(check(&millis(open() + Duration::minutes(1)), open()).is_err());
+}
+
+#[test]
+fn rejects_an_unreadable_start() {
+    assert_eq!(
+        check("2024-05-02", open()),
+        Err(StaleCandle::Unreadable("2024-05-02".to_string()))
+    );
+    assert_eq!(
+        start_of("").unwrap_err().to_string(),
+        "kline start time \"\" is unreadable"
+    );
+}
+
+#[test]
+fn selects_the_candle_of_the_period_from_the_newest_few() {
+    let day = Duration::days(1);
+    let (ahead, current, previous) = (millis(open() + day), millis(open()), millis(open() - day));
+    // tomorrow's, already forming, listed first
+    assert_eq!(select(&[&ahead, &current], open()), Ok(1));
+    assert_eq!(select(&[&current, &previous], open()), Ok(0));
+    // neither is today's, so the newest's mismatch is reported
+    assert_eq!(
+        select(&[&previous, &millis(open() - day * 2)], open()),
+        Err(StaleCandle::Old {
+            start: open() - day,
+            expected: open()
+        })
+    );
+    assert!(matches!(
+        select(&[&ahead], open()),
+        Err(StaleCandle::Future { .. })
+    ));
+}
+
+fn candle<'a>(open: &'a str, high: &'a str, low: &'a str) -> Candle<'a> {
+    Candle { open, high, low }
+}
+
+#[test]
+fn reduces_the_last_candles_to_one_anchor() {
+    // newest first; the middle day gapped down
+    let candles = [
+        candle("1.0000", "1.0500", "0.9800"),
+        candle("0.9000", "1.0100", "0.8800"),
+        candle("1.0200", "1.0800", "0.9900"),
+    ];
+    assert_eq!(reduce(Reducer::Open, &candles).as_deref(), Some("1.0000"));
+    assert_eq!(reduce(Reducer::MinLow, &candles).as_deref(), Some("0.8800"));
+    assert_eq!(
+        reduce(Reducer::MaxHigh, &candles).as_deref(),
+        Some("1.0800")
+    );
+    // 2.92 / 3, to the newest open's four decimals
+    assert_eq!(
+        reduce(Reducer::AvgOpen, &candles).as_deref(),
+        Some("0.9733")
+    );
+    assert_eq!(Reducer::AvgOpen.to_string(), "avg_open");
+}
+
+#[test]
+fn reduces_nothing_from_no_candles_or_unreadable_prices() {
+    assert_eq!(reduce(Reducer::MinLow, &[]), None);
+    let candles = [candle("1.0", "1.1", "0.9"), candle("1.0", "1.1", "n/a")];
+    assert_eq!(reduce(Reducer::MinLow, &candles), None);
+    assert_eq!(reduce(Reducer::AvgOpen, &candles).as_deref(), Some("1.0"));
+}
@@ -0,0 +1,164 @@
+//! Batch cancel replies turned into one outcome per requested order.
+
+#[path = "../src/response.rs"]
+mod response;
+
+#[path = "../src/cancels.rs"]
+mod cancels;
+
+use cancels::{CancelOrderData, CancelOutcome};
+
+fn requests(count: usize) -> Vec<CancelOrderData> {
+    (1..=count)
+        .map(|n| CancelOrderData {
+            symbol: "SEIUSDT".to_string(),
+            order_id: format!("mock-{}", n),
+        })
+        .collect()
+}
+
+fn outcomes(fixture: &str, requests: &[CancelOrderData]) -> Vec<CancelOutcome> {
+    let path = format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        fixture
+    );
+    let body = std::fs::read_to_string(&path).unwrap();
+    let reply = response::parse(serde_json::from_str(&body).unwrap()).unwrap();
+    cancels::parse_cancels(reply, requests).unwrap()
+}
+
+#[test]
+fn every_order_cancelled() {
+    let outcomes = outcomes("cancel_all_ok", &requests(2));
+    assert!(outcomes.iter().all(CancelOutcome::is_cancelled));
+    assert_eq!(outcomes[1].order_link_id, "daily-SEIUSDT-1700000000000-1");
+}
+
+#[test]
+fn too_late_is_gone_rather_than_failed() {
+    let outcomes = outcomes("cancel_too_late", &requests(2));
+    for outcome in &outcomes {
+        assert_eq!(outcome.code, cancels::NOT_EXISTS_OR_TOO_LATE);
+        assert!(outcome.is_gone());
+        assert!(!outcome.is_failed());
+        assert_eq!(outcome.msg, "order not exists or too late to cancel");
+    }
+    // the reply leaves the id blank, so it comes from the request
+    assert_eq!(outcomes[0].order_id, "mock-1");
+    assert_eq!(outcomes[1].order_id, "mock-2");
+}
+
+#[test]
+fn mixed_results_are_told_apart() {
+    let outcomes = outcomes("cancel_mixed", &requests(3));
+    assert!(outcomes[0].is_cancelled());
+    assert!(outcomes[1].is_gone());
+    assert!(outcomes[2].is_failed());
+    assert_eq!(outcomes[2].order_id, "mock-3");
+    assert_eq!(outcomes[2].msg, "Too many visits!");
+}
+
+#[test]
+fn an_order_missing_from_the_reply_failed() {
+    let outcomes = outcomes("cancel_all_ok", &requests(3));
+    assert!(outcomes[2].is_failed());
+    assert_eq!(outcomes[2].msg, "missing from reply");
+}
+
+#[test]
+fn a_failed_batch_keeps_its_code() {
+    let reply = response::parse(serde_json::json!({
+        "retCode": 10004,
+        "retMsg": "error sign!",
+        "result": {},
+    }))
+    .unwrap();
+    let error = cancels::parse_cancels(reply, &requests(1)).unwrap_err();
+    assert_eq!(error.to_string(), "batch cancel failed: 10004 error sign!");
+    assert!(CancelOutcome::cancelled(&requests(1)[0]).is_cancelled());
+    let gave_up = CancelOutcome::failed(&requests(1)[0], "gave up".to_string());
+    assert!(gave_up.is_failed());
+}
+
+#[test]
+fn each_terminal_status_settles() {
+    use cancels::Settled;
+    assert_eq!(Settled::from_status("Filled", 56.0), Settled::Filled);
+    assert_eq!(
+        Settled::from_status("PartiallyFilledCanceled", 20.0),
+        Settled::Filled
+    );
+    assert_eq!(Settled::from_status("Cancelled", 20.0), Settled::Filled);
+    for status in [
+        "Cancelled",
+        "PartiallyFilledCanceled",
+        "Deactivated",
+        "Rejected",
+    ] {
+        assert_eq!(
+            Settled::from_status(status, 0.0),
+            Settled::ClosedElsewhere,
+            "{}",
+            status
+        );
+    }
+    for status in ["New", "PartiallyFilled", "Untriggered", "Triggered"] {
+        assert_eq!(
+            Settled::from_status(status, 0.0),
+            Settled::Open,
+            "{}",
+            status
+        );
+    }
+}
+
+#[test]
+fn a_gone_order_that_is_still_open_gets_resent() {
+    let mut outcome = outcomes("cancel_too_late", &requests(1)).remove(0);
+    for (settled, gone) in [
+        (cancels::Settled::Filled, true),
+        (cancels::Settled::ClosedElsewhere, true),
+        (cancels::Settled::Missing, true),
+        (cancels::Settled::Open, false),
+    ] {
+        outcome.settled = Some(settled);
+        assert_eq!(outcome.is_gone(), gone, "{:?}", settled);
+        assert_eq!(outcome.is_failed(), !gone, "{:?}", settled);
+    }
+}
+
+#[test]
+fn recorded_cancel_replies_round_trip() {
+    for fixture in ["cancel_all_ok", "cancel_mixed", "cancel_too_late"] {
+        let path = format!(
+            "{}/tests/fixtures/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            fixture
+        );
+        let body: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let recorded = &body["result"];
+        let parsed: cancels::BatchCancelResult = serde_json::from_value(recorded.clone())
+            .unwrap_or_else(|e| panic!("{} didn't parse: {}", fixture, e));
+        let written = serde_json::to_value(&parsed).unwrap();
+        for (recorded, written) in recorded["list"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(written["list"].as_array().unwrap())
+        {
+            let mut kept = recorded.clone();
+            // the category and symbol come from the request instead
+            kept.as_object_mut().unwrap().remove("category");
+            kept.as_object_mut().unwrap().remove("symbol");
+            assert_eq!(&kept, written, "{}", fixture);
+        }
+    }
+    let blank: cancels::BatchCancelResponse =
+        serde_json::from_value(serde_json::json!({})).unwrap();
+    assert_eq!(
+        (blank.order_id.as_str(), blank.order_link_id.as_str()),
+        ("", "")
+    );
+}
@@ -0,0 +1,206 @@
+//! What a bot starting after the placement time does, at fixed clocks on
+//! either side of each boundary.
+
+#[path = "../src/catch_up.rs"]
+mod catch_up;
+// tests/localtime.rs covers it; only `place_at` placements are used here
+#[allow(dead_code)]
+#[path = "../src/localtime.rs"]
+mod localtime;
+
+use catch_up::{
+    prompt_place_late, startup_action, CatchUpPolicy, StartupAction, ON_TIME_GRACE_SECS,
+};
+use chrono::{DateTime, Duration, Utc};
+use localtime::LocalTime;
+use std::io::BufRead;
+
+const WINDOW_HOURS: u64 = 6;
+
+fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn open() -> DateTime<Utc> {
+    utc("2026-03-02T00:00:00Z")
+}
+
+fn never_asked(_: Duration) -> bool {
+    panic!("asked outside the prompt's range")
+}
+
+/// The action under `policy` for a start at `now`, a `prompt` answering yes.
+fn action(policy: CatchUpPolicy, placement: DateTime<Utc>, now: DateTime<Utc>) -> StartupAction {
+    startup_action(policy, WINDOW_HOURS, placement, now, |_| true)
+}
+
+#[test]
+fn starting_within_the_grace_is_on_time() {
+    let grace = Duration::seconds(ON_TIME_GRACE_SECS);
+    assert_eq!(grace, Duration::minutes(5));
+    for policy in [
+        CatchUpPolicy::Skip,
+        CatchUpPolicy::PlaceLate,
+        CatchUpPolicy::Prompt,
+    ] {
+        for now in [open() - Duration::seconds(1), open(), open() + grace] {
+            assert_eq!(
+                startup_action(policy, WINDOW_HOURS, open(), now, never_asked),
+                StartupAction::PlaceNow,
+                "{:?} at {}",
+                policy,
+                now
+            );
+        }
+    }
+    let past = open() + grace + Duration::seconds(1);
+    assert_eq!(
+        action(CatchUpPolicy::Skip, open(), past),
+        StartupAction::WaitForOpen
+    );
+    assert_eq!(
+        action(CatchUpPolicy::PlaceLate, open(), past),
+        StartupAction::PlaceLate
+    );
+}
+
+#[test]
+fn skip_waits_and_place_late_places_inside_the_window() {
+    let now = open() + Duration::hours(1);
+    assert_eq!(
+        action(CatchUpPolicy::Skip, open(), now),
+        StartupAction::WaitForOpen
+    );
+    assert_eq!(
+        action(CatchUpPolicy::PlaceLate, open(), now),
+        StartupAction::PlaceLate
+    );
+}
+
+#[test]
+fn the_window_ends_at_its_last_second() {
+    let edge = open() + Duration::hours(WINDOW_HOURS as i64);
+    assert_eq!(
+        action(CatchUpPolicy::PlaceLate, open(), edge),
+        StartupAction::PlaceLate
+    );
+    assert_eq!(
+        action(CatchUpPolicy::Prompt, open(), edge),
+        StartupAction::PlaceLate
+    );
+    let past = edge + Duration::seconds(1);
+    for policy in [
+        CatchUpPolicy::Skip,
+        CatchUpPolicy::PlaceLate,
+        CatchUpPolicy::Prompt,
+    ] {
+        assert_eq!(
+            startup_action(policy, WINDOW_HOURS, open(), past, never_asked),
+            StartupAction::WaitForOpen,
+            "{:?}",
+            policy
+        );
+    }
+    // a zero window leaves only the grace
+    let late = open() + Duration::seconds(ON_TIME_GRACE_SECS + 1);
+    assert_eq!(
+        startup_action(CatchUpPolicy::PlaceLate, 0, open(), late, never_asked),
+        StartupAction::WaitForOpen
+    );
+}
+
+#[test]
+fn lateness_counts_from_place_at() {
+    let at: LocalTime = "00:05 America/New_York".parse().unwrap();
+    let placement = at.first_at_or_after(open());
+    assert_eq!(placement, utc("2026-03-02T05:05:00Z"));
+
+    // hours past the open but before the placement is on time
+    let before = open() + Duration::hours(5);
+    assert_eq!(
+        startup_action(
+            CatchUpPolicy::Skip,
+            WINDOW_HOURS,
+            placement,
+            before,
+            never_asked
+        ),
+        StartupAction::PlaceNow
+    );
+    let grace = placement + Duration::seconds(ON_TIME_GRACE_SECS);
+    assert_eq!(
+        action(CatchUpPolicy::Skip, placement, grace),
+        StartupAction::PlaceNow
+    );
+    assert_eq!(
+        action(CatchUpPolicy::Skip, placement, grace + Duration::seconds(1)),
+        StartupAction::WaitForOpen
+    );
+    // 11h past the open, but still inside the window after the placement
+    let edge = placement + Duration::hours(WINDOW_HOURS as i64);
+    assert_eq!(
+        action(CatchUpPolicy::PlaceLate, placement, edge),
+        StartupAction::PlaceLate
+    );
+    assert_eq!(
+        action(
+            CatchUpPolicy::PlaceLate,
+            placement,
+            edge + Duration::seconds(1)
+        ),
+        StartupAction::WaitForOpen
+    );
+}
+
+#[test]
+fn prompt_is_asked_how_late_and_decides() {
+    let now = open() + Duration::minutes(90);
+    let mut asked = None;
+    let answered_no = startup_action(CatchUpPolicy::Prompt, WINDOW_HOURS, open(), now, |late| {
+        asked = Some(late);
+        false
+    });
+    assert_eq!(answered_no, StartupAction::WaitForOpen);
+    assert_eq!(asked, Some(Duration::minutes(90)));
+    assert_eq!(
+        action(CatchUpPolicy::Prompt, open(), now),
+        StartupAction::PlaceLate
+    );
+}
+
+#[test]
+fn prompt_skips_without_a_terminal() {
+    let now = open() + Duration::minutes(90);
+    let mut input: &[u8] = b"y\n";
+    let action = startup_action(CatchUpPolicy::Prompt, WINDOW_HOURS, open(), now, |late| {
+        prompt_place_late(&mut input, false, late)
+    });
+    assert_eq!(action, StartupAction::WaitForOpen);
+    // nothing was read from it
+    assert_eq!(input.fill_buf().unwrap(), b"y\n");
+}
+
+#[test]
+fn prompt_places_only_on_a_yes() {
+    let late = Duration::minutes(90);
+    for (answer, place) in [
+        ("y\n", true),
+        ("Y\n", true),
+        ("yes\n", true),
+        ("  y  \n", true),
+        ("n\n", false),
+        ("\n", false),
+        ("yep\n", false),
+        ("", false),
+    ] {
+        let mut input = answer.as_bytes();
+        assert_eq!(
+            prompt_place_late(&mut input, true, late),
+            place,
+            "{:?}",
+            answer
+        );
+    }
+}
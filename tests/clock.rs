@@ -0,0 +1,63 @@
+//! Clock offsets read from the middle of a round trip, and wall clock
+//! jumps.
+
+#[path = "../src/clock.rs"]
+mod clock;
+
+use chrono::{DateTime, Duration, Utc};
+use clock::{drift, Measurement};
+
+fn at(text: &str) -> DateTime<Utc> {
+    text.parse().unwrap()
+}
+
+#[test]
+fn offset_is_read_at_the_middle_of_the_round_trip() {
+    let sent = at("2026-10-15T00:00:00Z");
+    let received = sent + Duration::milliseconds(200);
+    let server = sent + Duration::milliseconds(1100);
+    let clock = Measurement::new(sent, received, server);
+    assert_eq!(
+        clock,
+        Measurement {
+            at: received,
+            offset_ms: 1000,
+            round_trip_ms: 200,
+        }
+    );
+    assert_eq!(
+        clock.to_string(),
+        "clock 1000ms behind the exchange, 200ms round trip"
+    );
+    let ahead = Measurement::new(sent, received, sent);
+    assert_eq!(
+        ahead.to_string(),
+        "clock 100ms ahead of the exchange, 200ms round trip"
+    );
+}
+
+#[test]
+fn over_half_the_recv_window_is_risky() {
+    let sent = at("2026-10-15T00:00:00Z");
+    let clock = |offset| Measurement::new(sent, sent, sent + Duration::milliseconds(offset));
+    assert!(!clock(5000).is_risky(10000));
+    assert!(clock(5001).is_risky(10000));
+    assert!(clock(-5001).is_risky(10000));
+}
+
+#[test]
+fn a_stepped_wall_clock_drifts_from_the_monotonic_one() {
+    let since = at("2026-10-15T00:00:00Z");
+    let hour = Duration::hours(1);
+    assert_eq!(drift(since, hour, since + hour), 0);
+    // NTP stepped the clock back 3s during the hour
+    assert_eq!(
+        drift(since, hour, since + hour - Duration::seconds(3)),
+        -3000
+    );
+    // a VM paused for a minute, its wall clock caught up on resume
+    assert_eq!(
+        drift(since, hour, since + hour + Duration::minutes(1)),
+        60000
+    );
+}
@@ -0,0 +1,2185 @@
+//! Runs the bot binary against the mock exchange and drives a triggered
+//! ladder through placement, a fill and the cancel sweep.
+
+mod mock_exchange;
+
+use chrono::Utc;
+use mock_exchange::{
+    MockExchange, Script, API_KEY, API_SECRET, AWS_ACCESS_KEY_ID, AWS_ROLE_ARN,
+    AWS_SECRET_ACCESS_KEY, AWS_SECRET_ID, AWS_SESSION_TOKEN, CONTAINER_TOKEN, SYMBOL, VAULT_TOKEN,
+    WEB_IDENTITY_TOKEN,
+};
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+const HTTP_TOKEN: &str = "e2e-token";
+const STARTUP: Duration = Duration::from_secs(30);
+
+/// The bot running in its own scratch directory, killed when dropped.
+struct Bot {
+    child: Child,
+    dir: PathBuf,
+    http: String,
+}
+
+impl Bot {
+    fn start(name: &str, exchange: &MockExchange, secret: &str) -> Bot {
+        Bot::start_with(
+            name,
+            exchange,
+            secret,
+            r#"
+levels = [10.0, 20.0, 30.0]
+budgets = [50.0, 50.0, 50.0]
+fill_check_minutes = 60
+[strategies.exits]
+levels = [5.0]
+"#,
+        )
+    }
+
+    /// Starts the bot with `strategy` as the body of its one strategy table.
+    fn start_with(name: &str, exchange: &MockExchange, secret: &str, strategy: &str) -> Bot {
+        Bot::start_configured(name, exchange, secret, "", strategy)
+    }
+
+    /// Starts the bot with `symbol` and `strategy` as the bodies of its one
+    /// symbol and strategy tables.
+    fn start_configured(
+        name: &str,
+        exchange: &MockExchange,
+        secret: &str,
+        symbol: &str,
+        strategy: &str,
+    ) -> Bot {
+        let dir = std::env::temp_dir().join(format!("stink-bid-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let http = format!("127.0.0.1:{}", port);
+        let config = format!(
+            r#"
+data_dir = "."
+state_path = "state.json"
+[http]
+bind = "{http}"
+
+[symbols.{SYMBOL}]
+{symbol}
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+{strategy}"#
+        );
+        fs::write(dir.join("config.toml"), config).unwrap();
+        let child = Bot::spawn(&dir, exchange, secret, &[]);
+        Bot { child, dir, http }
+    }
+
+    /// Runs the bot binary with `args` in `dir`, logging to its bot.log.
+    fn spawn(dir: &PathBuf, exchange: &MockExchange, secret: &str, args: &[&str]) -> Child {
+        let log = File::create(dir.join("bot.log")).unwrap();
+        Command::new(env!("CARGO_BIN_EXE_stink-bid"))
+            .args(args)
+            .current_dir(dir)
+            .env("CONFIG_PATH", "config.toml")
+            .env("API_KEY", API_KEY)
+            .env("API_SECRET", secret)
+            .env("VAULT_TOKEN", VAULT_TOKEN)
+            .env("AWS_ACCESS_KEY_ID", AWS_ACCESS_KEY_ID)
+            .env("AWS_SECRET_ACCESS_KEY", AWS_SECRET_ACCESS_KEY)
+            .env("HTTP_TOKEN", HTTP_TOKEN)
+            .env("BYBIT_BASE_URL", &exchange.url)
+            .env("KLINE_URL", format!("{}/v5/market/kline", exchange.url))
+            .env(
+                "BATCH_ORDER_URL",
+                format!("{}/v5/order/create-batch", exchange.url),
+            )
+            .env(
+                "BATCH_CANCEL_ORDER_URL",
+                format!("{}/v5/order/cancel-batch", exchange.url),
+            )
+            .stdout(log.try_clone().unwrap())
+            .stderr(log)
+            .stdin(Stdio::null())
+            .spawn()
+            .expect("failed starting the bot")
+    }
+
+    /// Kills the bot and starts it again with `args` in the same
+    /// directory, its state and caches kept.
+    fn restart(&mut self, exchange: &MockExchange, args: &[&str]) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        self.child = Bot::spawn(&self.dir, exchange, API_SECRET, args);
+    }
+
+    /// Waits for `text` in the log.
+    async fn wait_for(&self, text: &str) {
+        let started = Instant::now();
+        while !self.log().contains(text) {
+            assert!(started.elapsed() < STARTUP, "no {:?}\n{}", text, self.log());
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    fn log(&self) -> String {
+        fs::read_to_string(self.dir.join("bot.log")).unwrap_or_default()
+    }
+
+    /// POSTs to the bot's HTTP API, retrying while it's still starting up.
+    async fn post(&self, path: &str, body: Value) -> Value {
+        let client = reqwest::Client::new();
+        let started = Instant::now();
+        loop {
+            let sent = client
+                .post(format!("http://{}{}", self.http, path))
+                .header("x-stink-bid-token", HTTP_TOKEN)
+                .json(&body)
+                .send()
+                .await;
+            match sent {
+                Ok(response) => return response.json().await.unwrap(),
+                Err(e) if e.is_connect() && started.elapsed() < STARTUP => {
+                    tokio::time::sleep(Duration::from_millis(200)).await
+                }
+                Err(e) => panic!("{} failed: {}\n{}", path, e, self.log()),
+            }
+        }
+    }
+
+    /// Runs a subcommand of the binary against the bot's config and state.
+    fn cli(&self, args: &[&str]) -> String {
+        let output = Command::new(env!("CARGO_BIN_EXE_stink-bid"))
+            .current_dir(&self.dir)
+            .env("CONFIG_PATH", "config.toml")
+            .env("HTTP_TOKEN", HTTP_TOKEN)
+            .args(args)
+            .output()
+            .expect("failed running the cli");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    async fn get(&self, path: &str) -> Value {
+        reqwest::Client::new()
+            .get(format!("http://{}{}", self.http, path))
+            .header("x-stink-bid-token", HTTP_TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    /// The first cycle report written to the reports directory.
+    async fn report(&self) -> String {
+        let started = Instant::now();
+        loop {
+            let written = fs::read_dir(self.dir.join("reports"))
+                .into_iter()
+                .flatten()
+                .filter_map(Result::ok)
+                // not one still being written
+                .find(|entry| entry.path().extension().is_some_and(|ext| ext == "md"));
+            if let Some(entry) = written {
+                return fs::read_to_string(entry.path()).unwrap();
+            }
+            if started.elapsed() > STARTUP {
+                panic!("no cycle report written\n{}", self.log());
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+
+    async fn wait(&mut self) -> ExitStatus {
+        let started = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+            if started.elapsed() > STARTUP {
+                panic!("bot didn't exit\n{}", self.log());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[tokio::test]
+async fn places_fills_and_cancels_a_ladder() {
+    let exchange = MockExchange::start(Script {
+        fill_levels: vec![0],
+        reject_levels: vec![2],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("cycle", &exchange, API_SECRET);
+
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(
+        placed["symbols"][0]["outcome"]["outcome"],
+        "placed",
+        "{}\n{}",
+        placed,
+        bot.log()
+    );
+    let report = bot.report().await;
+    // a cycle of one symbol reports under its name
+    let written: Vec<String> = fs::read_dir(bot.dir.join("reports"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".md"))
+        .collect();
+    assert!(
+        written[0].ends_with(&format!("-{}.md", SYMBOL)),
+        "{:?}",
+        written
+    );
+
+    let orders = exchange.orders();
+    let status = |level: &str| {
+        orders
+            .iter()
+            .find(|order| order.order_link_id.ends_with(level))
+            .map(|order| order.status.clone())
+    };
+    // level 2 was refused, so only two entries made it to the book
+    assert_eq!(status("-0").as_deref(), Some("Filled"), "{:#?}", orders);
+    assert_eq!(status("-1").as_deref(), Some("Cancelled"), "{:#?}", orders);
+    assert_eq!(status("-2"), None, "{:#?}", orders);
+
+    let exit = orders
+        .iter()
+        .find(|order| order.order_link_id.ends_with("-x0"))
+        .unwrap_or_else(|| panic!("no exit placed\n{}", bot.log()));
+    assert_eq!(exit.side, "Sell");
+    assert!(exit.reduce_only);
+    assert_eq!(exit.status, "New");
+    // 50 USDT at 0.9 buys 55, floored to the qty step, sold 5% above the
+    // fill
+    assert_eq!(exit.qty, "55");
+    assert_eq!(exit.price, "0.9450");
+
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 2);
+    // the filled entry's 110001 is looked up, not resent or reported as a failure
+    assert_eq!(exchange.calls("/v5/order/cancel-batch"), 1);
+    assert!(!bot.log().contains("not cancelled"), "{}", bot.log());
+    assert_eq!(exchange.bad_signatures(), 0, "{}", bot.log());
+    assert!(report.contains(SYMBOL), "{}", report);
+    assert!(report.contains("insufficient balance"), "{}", report);
+    assert!(report.contains("10% level filled"), "{}", report);
+
+    let states = bot.get("/orders").await;
+    let state = |suffix: &str| {
+        states
+            .as_object()
+            .unwrap()
+            .iter()
+            .find(|(link_id, _)| link_id.ends_with(suffix))
+            .map(|(_, state)| state.clone())
+    };
+    assert_eq!(state("-0"), Some(json!("filled")), "{}", states);
+    assert_eq!(state("-1"), Some(json!("cancelled")), "{}", states);
+    assert_eq!(state("-2"), Some(json!("rejected")), "{}", states);
+    assert_eq!(state("-x0"), Some(json!("working")), "{}", states);
+}
+
+/// Recomputes each POST's signature over the body exactly as it arrived,
+/// so a body that's signed one way and sent another fails here.
+#[tokio::test]
+async fn signs_the_body_it_sends() {
+    let exchange = MockExchange::start(Script {
+        fill_levels: vec![0],
+        reject_levels: Vec::new(),
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("signing", &exchange, API_SECRET);
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    bot.report().await;
+
+    let posts = exchange.posts();
+    for path in ["/v5/order/create-batch", "/v5/order/cancel-batch"] {
+        assert!(posts.iter().any(|post| post.path == path), "{:#?}", posts);
+    }
+    for post in posts {
+        assert_eq!(post.content_type, "application/json", "{:?}", post);
+        assert!(
+            serde_json::from_str::<Value>(&post.body).is_ok_and(|body| body.is_object()),
+            "{:?}",
+            post
+        );
+        assert_eq!(
+            mock_exchange::signature(&post.timestamp, &post.recv_window, &post.body),
+            post.signature,
+            "{:?}",
+            post
+        );
+    }
+}
+
+#[tokio::test]
+async fn refuses_to_start_with_a_bad_signature() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let mut bot = Bot::start("badsig", &exchange, "not-the-secret");
+
+    let status = bot.wait().await;
+    assert!(!status.success(), "{}", bot.log());
+    assert!(exchange.bad_signatures() > 0);
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 0);
+}
+
+#[tokio::test]
+async fn refuses_to_start_with_a_key_that_cant_place_orders() {
+    let exchange = MockExchange::start(Script {
+        no_order_permission: true,
+        ..Script::default()
+    })
+    .await;
+    let mut bot = Bot::start("noorder", &exchange, API_SECRET);
+
+    let status = bot.wait().await;
+    let log = bot.log();
+    assert!(!status.success(), "{}", log);
+    assert!(
+        log.contains("the API key is missing ContractTrade: Order to trade SEIUSDT."),
+        "{}",
+        log
+    );
+    assert_eq!(exchange.calls("/v5/account/info"), 0);
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 0);
+}
+
+#[tokio::test]
+async fn warns_of_a_key_expiring_within_the_week() {
+    let exchange = MockExchange::start(Script {
+        key_expires_in_hours: Some(5 * 24 + 1),
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("keyexpiry", &exchange, API_SECRET);
+
+    let started = Instant::now();
+    while !bot.log().contains("NOTICE: API key expires") {
+        assert!(started.elapsed() < STARTUP, "{}", bot.log());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let log = bot.log();
+    assert!(log.contains("in 5 days"), "{}", log);
+    let status = bot.cli(&["status"]);
+    assert!(status.contains("API key expires"), "{}", status);
+}
+
+#[tokio::test]
+async fn skips_a_symbol_in_hedge_mode() {
+    let exchange = MockExchange::start(Script {
+        hedge_mode: true,
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("hedge", &exchange, API_SECRET);
+
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let log = bot.log();
+    assert!(
+        log.contains("error: SEIUSDT in hedge mode, but orders and stops are placed one-way")
+            && log.contains("fix: switch SEIUSDT to one-way mode"),
+        "{}",
+        log
+    );
+    assert!(
+        log.contains("SEIUSDT      skipped: in hedge mode"),
+        "{}",
+        log
+    );
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 0, "{}", placed);
+}
+
+#[tokio::test]
+async fn warns_of_a_clock_over_half_the_recv_window_behind() {
+    let exchange = MockExchange::start(Script {
+        clock_ahead_ms: 6000,
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("clock", &exchange, API_SECRET);
+
+    let started = Instant::now();
+    while !bot.log().contains("== stink-bid ==") {
+        assert!(started.elapsed() < STARTUP, "{}", bot.log());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let log = bot.log();
+    assert!(
+        log.contains("behind the exchange") && log.contains("over half the 10000ms recv window"),
+        "{}",
+        log
+    );
+    let status = bot.cli(&["status"]);
+    assert!(status.contains("ms behind the exchange"), "{}", status);
+    // every subsystem is supervised, each symbol's cycle as a core task
+    let strategy = format!("strategy daily {}", SYMBOL);
+    for task in ["ledger", "http server", strategy.as_str()] {
+        assert!(
+            status
+                .lines()
+                .any(|line| line.trim_start().starts_with(task) && line.contains("running since")),
+            "{}",
+            status
+        );
+    }
+    let metrics = reqwest::get(format!("http://{}/metrics", bot.http))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let offset: f64 = metrics
+        .lines()
+        .find_map(|line| line.strip_prefix("stinkbid_clock_offset_ms{} "))
+        .unwrap_or_else(|| panic!("{}", metrics))
+        .parse()
+        .unwrap();
+    assert!((5500.0..6500.0).contains(&offset), "{}", metrics);
+}
+
+#[tokio::test]
+async fn staggers_placements_after_the_priority_symbols() {
+    let exchange = MockExchange::start(Script {
+        also_listed: vec!["AAAUSDT"],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_configured(
+        "stagger",
+        &exchange,
+        API_SECRET,
+        r#"
+[symbols.AAAUSDT]
+priority = 1
+
+[schedule]
+catch_up = "place_late"
+catch_up_window_hours = 24
+placement_stagger = "90s"
+"#,
+        r#"
+levels = [10.0, 20.0]
+budgets = [50.0, 50.0]
+"#,
+    );
+
+    let started = Instant::now();
+    while exchange.calls("/v5/order/create-batch") < 2 {
+        assert!(started.elapsed() < STARTUP, "{}", bot.log());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let log = bot.log();
+    let placement = |symbol: &str| {
+        log.lines()
+            .find(|line| line.contains(&format!("{} next placement at", symbol)))
+            .unwrap_or_else(|| panic!("{}", log))
+            .to_string()
+    };
+    assert!(
+        placement("AAAUSDT").ends_with(", 0.0s into the stagger"),
+        "{}",
+        log
+    );
+    assert!(
+        placement(SYMBOL).ends_with(", 45.0s into the stagger"),
+        "{}",
+        log
+    );
+}
+
+#[tokio::test]
+async fn starts_on_cached_instruments_while_the_listing_is_down() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let mut bot = Bot::start("instruments", &exchange, API_SECRET);
+    bot.wait_for("== stink-bid ==").await;
+    let listing = "/v5/market/instruments-info";
+    assert_eq!(exchange.calls(listing), 1);
+    let cache = bot.dir.join("instruments.json");
+    assert!(cache.exists(), "{}", bot.log());
+
+    // fresh enough to start on without asking
+    bot.restart(&exchange, &[]);
+    bot.wait_for("== stink-bid ==").await;
+    assert!(
+        bot.log().contains("using cached instrument rules"),
+        "{}",
+        bot.log()
+    );
+    assert_eq!(exchange.calls(listing), 1);
+    bot.restart(&exchange, &["run", "--refresh-instruments"]);
+    bot.wait_for("== stink-bid ==").await;
+    assert_eq!(exchange.calls(listing), 2);
+
+    // four days old, and the listing down
+    let mut cached: Value = serde_json::from_str(&fs::read_to_string(&cache).unwrap()).unwrap();
+    cached["fetched_at"] = json!(Utc::now() - chrono::Duration::days(4));
+    fs::write(&cache, cached.to_string()).unwrap();
+    exchange.rescript(|script| script.instruments_down = true);
+    bot.restart(&exchange, &[]);
+    bot.wait_for("== stink-bid ==").await;
+    let log = bot.log();
+    assert!(
+        log.contains("warning: fetching instruments failed, using the cached rules fetched")
+            && log.contains("96h ago")
+            && log.contains("over the 72h instrument_cache_max_age_hours"),
+        "{}",
+        log
+    );
+}
+
+#[tokio::test]
+async fn moves_a_corrupt_state_file_aside_and_refuses_a_newer_one() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let mut bot = Bot::start("corrupt", &exchange, API_SECRET);
+    bot.wait_for("== stink-bid ==").await;
+    let state = bot.dir.join("state.json");
+    let saved: Value = serde_json::from_str(&fs::read_to_string(&state).unwrap()).unwrap();
+    assert_eq!(saved["version"], 1, "{}", saved);
+
+    fs::write(&state, "{\"strategies\": {").unwrap();
+    bot.restart(&exchange, &[]);
+    bot.wait_for("== stink-bid ==").await;
+    assert!(
+        bot.log()
+            .contains("CRITICAL: state file ./state.json is corrupt"),
+        "{}",
+        bot.log()
+    );
+    let aside: Vec<String> = fs::read_dir(&bot.dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("state.json.corrupt-"))
+        .collect();
+    assert_eq!(aside.len(), 1, "{:?}", aside);
+
+    // a newer release's state is left alone
+    fs::write(&state, "{\"version\": 99}").unwrap();
+    bot.restart(&exchange, &[]);
+    assert!(!bot.wait().await.success());
+    assert!(
+        bot.log()
+            .contains("schema version 99 is newer than this release's 1"),
+        "{}",
+        bot.log()
+    );
+    assert_eq!(fs::read_to_string(&state).unwrap(), "{\"version\": 99}");
+}
+
+#[tokio::test]
+async fn places_an_ad_hoc_ladder_from_the_command_line() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let config = format!(
+        r#"
+[symbols.{SYMBOL}]
+
+[guards]
+max_order_notional = 60.0
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+levels = [10.0]
+budgets = [50.0]
+"#
+    );
+    let place = |budget: &'static str| {
+        [
+            "place", "--symbol", "seiusdt", "--budget", budget, "--levels", "10,20", "--hold", "2s",
+        ]
+    };
+
+    let (passed, out) = run_cli("adhoc", &exchange, &config, API_SECRET, &place("100")).await;
+    assert!(passed, "{}", out);
+    assert!(out.contains("adhoc cycle opened"), "{}", out);
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 2, "{:#?}", orders);
+    assert!(
+        orders
+            .iter()
+            .all(|order| order.order_link_id.starts_with("adhoc") && order.status == "Cancelled"),
+        "{:#?}",
+        orders
+    );
+    // kept apart from a scheduled bot's state, under the default data_dir
+    let data_dir = std::env::temp_dir()
+        .join(format!("stink-bid-adhoc-{}", std::process::id()))
+        .join("stink-bid");
+    let state: Value =
+        serde_json::from_str(&fs::read_to_string(data_dir.join("adhoc.json")).unwrap()).unwrap();
+    assert!(state["strategies"]["adhoc"].is_object(), "{}", state);
+    assert!(!data_dir.join("state.json").exists());
+
+    // the guards still hold
+    let (_, out) = run_cli("adhoc", &exchange, &config, API_SECRET, &place("200")).await;
+    assert!(out.contains("held back by guards"), "{}", out);
+    assert_eq!(exchange.orders().len(), 2);
+}
+
+#[tokio::test]
+async fn a_profile_brings_its_own_credentials_exchange_and_data() {
+    let exchange = MockExchange::start(Script::default()).await;
+    // where the shell's env points, which the profile overrides
+    let decoy = MockExchange::start(Script::default()).await;
+    let config = format!(
+        r#"
+[symbols.{SYMBOL}]
+
+[journal]
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+levels = [10.0]
+budgets = [50.0]
+
+[profiles.testnet]
+base_url = "{}"
+link_id_prefix = "tn"
+"#,
+        exchange.url
+    );
+    let place = [
+        "place",
+        "--profile",
+        "testnet",
+        "--symbol",
+        "SEIUSDT",
+        "--budget",
+        "100",
+        "--levels",
+        "10,20",
+        "--hold",
+        "2s",
+    ];
+    let dir = std::env::temp_dir().join(format!("stink-bid-profile-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let (passed, out) = run_cli("profile", &decoy, &config, "wrong", &place).await;
+    assert!(!passed);
+    assert!(
+        out.contains("profile testnet takes its credentials from .env.testnet"),
+        "{}",
+        out
+    );
+
+    fs::write(
+        dir.join(".env.testnet"),
+        format!("API_SECRET={}\n", API_SECRET),
+    )
+    .unwrap();
+    let (passed, out) = run_cli("profile", &decoy, &config, "wrong", &place).await;
+    assert!(passed, "{}", out);
+    assert!(out.contains("profile: testnet"), "{}", out);
+    assert!(decoy.orders().is_empty());
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 2, "{:#?}", orders);
+    assert!(
+        orders
+            .iter()
+            .all(|order| order.order_link_id.starts_with("tnadhoc-")),
+        "{:#?}",
+        orders
+    );
+    let data_dir = dir.join("stink-bid").join("testnet");
+    assert!(data_dir.join("adhoc.json").exists());
+    let journal = fs::read_dir(data_dir.join("journal"))
+        .unwrap()
+        .map(|file| fs::read_to_string(file.unwrap().path()).unwrap())
+        .collect::<String>();
+    assert!(journal.contains("adhoc_run"), "{}", journal);
+    for line in journal.lines() {
+        let record: Value = serde_json::from_str(line).unwrap();
+        assert_eq!(record["profile"], "testnet", "{}", line);
+    }
+
+    fs::write(dir.join(".env.prod"), "").unwrap();
+    let (passed, out) = run_cli(
+        "profile",
+        &decoy,
+        &config,
+        API_SECRET,
+        &["validate", "--profile", "prod"],
+    )
+    .await;
+    assert!(!passed);
+    assert!(
+        out.contains("profile prod isn't defined: no config.prod.toml and no [profiles.prod]"),
+        "{}",
+        out
+    );
+
+    // the profile's prefix is checked on its own, then with the strategy's
+    for (prefix, refusal) in [
+        (
+            "t-n",
+            "profile testnet link_id_prefix must be at most 8 alphanumeric characters",
+        ),
+        (
+            "testnet1",
+            "strategy daily orderLinkIds for SEIUSDT could be longer than Bybit's 36 characters, \
+             shorten link_id_prefix and profile testnet's to 8 at most between them",
+        ),
+    ] {
+        let config = config.replace(
+            "link_id_prefix = \"tn\"",
+            &format!("link_id_prefix = \"{}\"", prefix),
+        );
+        let (passed, out) = run_cli(
+            "profile",
+            &decoy,
+            &config,
+            API_SECRET,
+            &["validate", "--profile", "testnet"],
+        )
+        .await;
+        assert!(!passed, "{}", out);
+        assert!(out.contains(refusal), "{}", out);
+    }
+}
+
+/// The key comes from Vault, not the (wrong) API_SECRET in the
+/// environment, and a rotated secret is signed with once refetched.
+#[tokio::test]
+async fn fetches_credentials_from_vault_and_picks_up_a_rotation() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_configured(
+        "vault",
+        &exchange,
+        "not-the-secret",
+        &format!(
+            r#"
+[credentials]
+source = "vault"
+refresh = "1s"
+[credentials.vault]
+address = "{}"
+path = "secret/data/stink-bid"
+"#,
+            exchange.url
+        ),
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+    bot.wait_for("== stink-bid ==").await;
+
+    exchange.rescript(|script| {
+        script.api_secret = Some("rotated-mock-secret");
+        script.stored_secret = Some("rotated-mock-secret");
+    });
+    bot.wait_for("credentials rotated, signing with the new ones from Vault")
+        .await;
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    bot.report().await;
+    assert_eq!(exchange.orders().len(), 1, "{}", bot.log());
+    assert!(!bot.log().contains("rotated-mock-secret"), "{}", bot.log());
+}
+
+#[tokio::test]
+async fn fetches_credentials_from_aws_secrets_manager() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let config = format!(
+        r#"
+[symbols.{SYMBOL}]
+
+[credentials]
+source = "aws"
+[credentials.aws]
+secret_id = "{AWS_SECRET_ID}"
+endpoint = "{}"
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+levels = [10.0]
+budgets = [50.0]
+"#,
+        exchange.url
+    );
+    let place = [
+        "place", "--symbol", "SEIUSDT", "--budget", "100", "--levels", "10,20", "--hold", "2s",
+    ];
+
+    let (passed, out) = run_cli("aws", &exchange, &config, "not-the-secret", &place).await;
+    assert!(passed, "{}", out);
+    assert_eq!(exchange.orders().len(), 2);
+    assert_eq!(exchange.bad_signatures(), 0);
+
+    // without a key in the environment the default chain looks further,
+    // each source in turn set up alone
+    let dir = std::env::temp_dir().join(format!("stink-bid-aws-keys-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let shared = dir.join("credentials");
+    fs::write(
+        &shared,
+        format!(
+            "[default]\naws_access_key_id = {}\naws_secret_access_key = {}\n",
+            AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY
+        ),
+    )
+    .unwrap();
+    let identity = dir.join("web-identity");
+    fs::write(&identity, WEB_IDENTITY_TOKEN).unwrap();
+    let missing = dir.join("no-credentials");
+    let none = [
+        ("AWS_ACCESS_KEY_ID", ""),
+        ("AWS_SECRET_ACCESS_KEY", ""),
+        ("AWS_SESSION_TOKEN", ""),
+        ("AWS_SHARED_CREDENTIALS_FILE", missing.to_str().unwrap()),
+        ("AWS_EC2_METADATA_DISABLED", "true"),
+    ];
+    let (sts, container) = (
+        format!("{}/sts", exchange.url),
+        format!("{}/ecs/credentials", exchange.url),
+    );
+    let sources: [(&str, Vec<(&str, &str)>); 4] = [
+        (
+            "/latest/api/token",
+            vec![
+                ("AWS_EC2_METADATA_DISABLED", "false"),
+                ("AWS_EC2_METADATA_SERVICE_ENDPOINT", &exchange.url),
+            ],
+        ),
+        (
+            "/ecs/credentials",
+            vec![
+                ("AWS_CONTAINER_CREDENTIALS_FULL_URI", &container),
+                ("AWS_CONTAINER_AUTHORIZATION_TOKEN", CONTAINER_TOKEN),
+            ],
+        ),
+        (
+            "/sts",
+            vec![
+                ("AWS_WEB_IDENTITY_TOKEN_FILE", identity.to_str().unwrap()),
+                ("AWS_ROLE_ARN", AWS_ROLE_ARN),
+                ("AWS_ENDPOINT_URL_STS", &sts),
+            ],
+        ),
+        (
+            "",
+            vec![("AWS_SHARED_CREDENTIALS_FILE", shared.to_str().unwrap())],
+        ),
+    ];
+    for (placed, (endpoint, source)) in sources.iter().enumerate() {
+        let env: Vec<(&str, &str)> = none.iter().chain(source).copied().collect();
+        let (passed, out) =
+            run_cli_with("aws", &exchange, &config, "not-the-secret", &place, &env).await;
+        assert!(passed, "{:?}\n{}", source, out);
+        assert_eq!(exchange.orders().len(), 4 + placed * 2, "{:?}", source);
+        if !endpoint.is_empty() {
+            assert_eq!(exchange.calls(endpoint), 1, "{:?}", source);
+        }
+        assert!(!out.contains(AWS_SESSION_TOKEN), "{}", out);
+    }
+    let (passed, out) = run_cli_with("aws", &exchange, &config, "x", &place, &none).await;
+    assert!(!passed);
+    assert!(
+        out.contains("no AWS credentials in the environment") && out.contains("disabled"),
+        "{}",
+        out
+    );
+    assert_eq!(exchange.orders().len(), 10);
+}
+
+/// Twelve levels don't fit one batch, so placing and cancelling both take
+/// two requests, and the mock refuses any batch over its limit. The levels
+/// start below the mock candle's 5% low.
+#[tokio::test]
+async fn chunks_a_ladder_larger_than_one_batch() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let levels: Vec<String> = (6..18).map(|level| format!("{}.0", level)).collect();
+    let strategy = format!(
+        "levels = [{}]\nbudgets = [{}]\n",
+        levels.join(", "),
+        ["10.0"; 12].join(", ")
+    );
+    let bot = Bot::start_with("chunks", &exchange, API_SECRET, &strategy);
+
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(
+        placed["symbols"][0]["outcome"]["outcome"], "placed",
+        "{}",
+        placed
+    );
+    bot.report().await;
+
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 12, "{:#?}\n{}", orders, bot.log());
+    assert!(
+        orders.iter().all(|order| order.status == "Cancelled"),
+        "{:#?}\n{}",
+        orders,
+        bot.log()
+    );
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 2);
+    assert_eq!(exchange.calls("/v5/order/cancel-batch"), 2);
+}
+
+#[tokio::test]
+async fn looks_up_a_batch_whose_reply_was_lost_instead_of_resending_it() {
+    let exchange = MockExchange::start(Script {
+        dropped_batches: vec![1],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("dropped", &exchange, API_SECRET);
+
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(
+        placed["symbols"][0]["outcome"]["outcome"],
+        "placed",
+        "{}\n{}",
+        placed,
+        bot.log()
+    );
+    bot.report().await;
+
+    // the batch went through once; the lost reply sent the bot looking
+    // its orders up, and it found and tracked all three
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 1);
+    assert!(exchange.calls("/v5/order/realtime") >= 3, "{}", bot.log());
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 3, "{:#?}", orders);
+    assert!(
+        orders.iter().all(|order| order.status == "Cancelled"),
+        "{:#?}\n{}",
+        orders,
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn adopts_an_order_answered_as_a_duplicate() {
+    let exchange = MockExchange::start(Script {
+        duplicate_levels: vec![1],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("duplicate", &exchange, API_SECRET);
+
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(
+        placed["symbols"][0]["outcome"]["outcome"],
+        "placed",
+        "{}\n{}",
+        placed,
+        bot.log()
+    );
+    bot.report().await;
+
+    // the order already carrying the id is tracked, so it's cancelled
+    // with the other two rather than left resting
+    assert!(bot.log().contains("already placed as"), "{}", bot.log());
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 1);
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 3, "{:#?}", orders);
+    assert!(
+        orders.iter().all(|order| order.status == "Cancelled"),
+        "{:#?}\n{}",
+        orders,
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn places_a_single_level_ladder() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_with(
+        "single",
+        &exchange,
+        API_SECRET,
+        "levels = [10.0]\nbudgets = [50.0]\n",
+    );
+
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(
+        placed["symbols"][0]["outcome"]["outcome"], "placed",
+        "{}",
+        placed
+    );
+    bot.report().await;
+
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 1, "{:#?}\n{}", orders, bot.log());
+    assert!(orders[0].order_link_id.ends_with("-0"), "{:#?}", orders);
+    assert_eq!(orders[0].price, "0.9000");
+    assert_eq!(orders[0].status, "Cancelled");
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 1);
+}
+
+#[tokio::test]
+async fn keeps_the_first_batch_when_the_second_is_refused() {
+    let exchange = MockExchange::start(Script {
+        refused_batches: vec![2],
+        ..Script::default()
+    })
+    .await;
+    let levels: Vec<String> = (6..18).map(|level| format!("{}.0", level)).collect();
+    let strategy = format!(
+        "levels = [{}]\nbudgets = [{}]\n",
+        levels.join(", "),
+        ["10.0"; 12].join(", ")
+    );
+    let bot = Bot::start_with("refused", &exchange, API_SECRET, &strategy);
+
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    // the first ten went out before the refusal and are still cancelled
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 10, "{:#?}\n{}", orders, bot.log());
+    assert!(
+        orders.iter().all(|order| order.status == "Cancelled"),
+        "{:#?}\n{}",
+        orders,
+        bot.log()
+    );
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 2);
+    assert!(report.contains("common banned"), "{}", report);
+}
+
+#[tokio::test]
+async fn cancel_all_sweeps_the_symbol() {
+    let exchange = MockExchange::start(Script {
+        fill_levels: vec![0],
+        reject_levels: Vec::new(),
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_with(
+        "cancelall",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0, 20.0, 30.0]
+budgets = [50.0, 50.0, 50.0]
+cancel_mode = "symbol_all"
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    let orders = exchange.orders();
+    let statuses: Vec<&str> = orders.iter().map(|order| order.status.as_str()).collect();
+    assert_eq!(
+        statuses,
+        ["Filled", "Cancelled", "Cancelled"],
+        "{:#?}",
+        orders
+    );
+    assert_eq!(exchange.calls("/v5/order/cancel-all"), 1);
+    assert_eq!(exchange.calls("/v5/order/cancel-batch"), 0);
+    assert!(
+        report.contains("cancel-all cancelled 2 orders"),
+        "{}",
+        report
+    );
+    // the filled entry was looked up rather than counted as a failure
+    assert!(!bot.log().contains("not cancelled"), "{}", bot.log());
+}
+
+#[tokio::test]
+async fn reconciles_open_orders_after_the_sweep() {
+    let exchange = MockExchange::start(Script {
+        stuck_levels: vec![1],
+        vanish_levels: vec![2],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_with(
+        "reconcile",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0, 20.0, 30.0]
+budgets = [50.0, 50.0, 50.0]
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    let orders = exchange.orders();
+    let statuses: Vec<&str> = orders.iter().map(|order| order.status.as_str()).collect();
+    assert_eq!(statuses, ["Cancelled", "New"], "{:#?}", orders);
+    assert!(
+        report.contains("cancel them by hand: SEIUSDT mock-2 20% level"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("30% level (mock-3) is unknown to the exchange"),
+        "{}",
+        report
+    );
+
+    let states = bot.get("/orders").await;
+    let lost = states
+        .as_object()
+        .unwrap()
+        .iter()
+        .find(|(link_id, _)| link_id.ends_with("-2"))
+        .map(|(_, state)| state.clone());
+    assert_eq!(lost, Some(json!("lost")), "{}", states);
+}
+
+#[tokio::test]
+async fn notes_attach_to_cycles_and_orders() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_with(
+        "notes",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+    let placed = bot
+        .post(
+            "/trigger/place",
+            json!({ "hold_minutes": 0, "note": "extra deep, CPI day" }),
+        )
+        .await;
+    assert_eq!(
+        placed["notes"],
+        json!(["extra deep, CPI day"]),
+        "{}",
+        placed
+    );
+    let report = bot.report().await;
+    assert!(report.contains("note: extra deep, CPI day"), "{}", report);
+
+    let order = exchange.orders().remove(0);
+    let added = bot.cli(&["note", "add", &order.order_link_id, "left it alone"]);
+    assert!(added.contains("noted"), "{}\n{}", added, bot.log());
+
+    let csv = bot.cli(&["report", "notes"]);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "time,subject,text", "{}", csv);
+    assert!(lines[1].contains(",daily/trigger@"), "{}", csv);
+    assert!(lines[1].ends_with(",\"extra deep, CPI day\""), "{}", csv);
+    assert!(
+        lines[2].ends_with(&format!(",{},left it alone", order.order_link_id)),
+        "{}",
+        csv
+    );
+}
+
+#[tokio::test]
+async fn anchors_on_the_mark_price_kline() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_configured(
+        "markanchor",
+        &exchange,
+        API_SECRET,
+        r#"anchor_price = "mark""#,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(
+        placed["symbols"][0]["anchor"], "1.0100 (mark)",
+        "{}",
+        placed
+    );
+    assert_eq!(exchange.calls("/v5/market/mark-price-kline"), 1);
+    assert_eq!(exchange.calls("/v5/market/kline"), 0);
+    // 10% under the mark open, not the traded one
+    assert_eq!(exchange.orders()[0].price, "0.9090");
+}
+
+#[tokio::test]
+async fn trades_a_symbol_in_its_own_category() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_configured(
+        "symbolcategory",
+        &exchange,
+        API_SECRET,
+        r#"category = "spot""#,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    bot.report().await;
+
+    assert!(
+        bot.log().contains(&format!("{:<12} ok, on spot", SYMBOL)),
+        "{}",
+        bot.log()
+    );
+    let posts = exchange.posts();
+    for path in ["/v5/order/create-batch", "/v5/order/cancel-batch"] {
+        let post = posts
+            .iter()
+            .find(|post| post.path == path)
+            .unwrap_or_else(|| panic!("no {} in {:#?}", path, posts));
+        let body: serde_json::Value = serde_json::from_str(&post.body).unwrap();
+        // the strategy is linear, the symbol overrides it
+        assert_eq!(body["category"], "spot", "{}", post.body);
+    }
+    // spot entries aren't reduce-only and have no position to look up
+    assert_eq!(exchange.calls("/v5/position/list"), 0);
+}
+
+#[tokio::test]
+async fn levels_override_their_order_type() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_with(
+        "levelorders",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0, 20.0, 30.0]
+budgets = [50.0, 50.0, 50.0]
+[[strategies.level_orders]]
+level = 0
+time_in_force = "PostOnly"
+[[strategies.level_orders]]
+level = 2
+order_type = "market"
+conditional = true
+trigger_by = "MarkPrice"
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+
+    let posts = exchange.posts();
+    let batch = posts
+        .iter()
+        .find(|post| post.path == "/v5/order/create-batch")
+        .unwrap_or_else(|| panic!("no batch in {:#?}", posts));
+    let body: serde_json::Value = serde_json::from_str(&batch.body).unwrap();
+    let orders = body["request"].as_array().unwrap();
+    assert_eq!(orders.len(), 3, "{}", batch.body);
+    assert_eq!(orders[0]["timeInForce"], "PostOnly");
+    assert_eq!(orders[1]["orderType"], "Limit");
+    assert!(orders[1].get("timeInForce").is_none(), "{}", orders[1]);
+    assert!(orders[1].get("triggerPrice").is_none(), "{}", orders[1]);
+    assert_eq!(orders[2]["orderType"], "Market");
+    assert_eq!(orders[2]["triggerPrice"], orders[2]["price"]);
+    assert_eq!(orders[2]["triggerBy"], "MarkPrice");
+    assert_eq!(orders[2]["triggerDirection"], 2);
+}
+
+#[tokio::test]
+async fn refuses_a_market_level_without_a_trigger() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let mut bot = Bot::start_with(
+        "marketlevel",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+[[strategies.level_orders]]
+level = 0
+order_type = "market"
+"#,
+    );
+
+    let status = bot.wait().await;
+    assert!(!status.success(), "{}", bot.log());
+    assert!(
+        bot.log()
+            .contains("level_orders level 0: a market order has to be conditional"),
+        "{}",
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn refuses_a_route_to_an_unconfigured_notifier() {
+    let exchange = MockExchange::start(Script::default()).await;
+    // tables after the symbol's keys land at the top level
+    let mut bot = Bot::start_configured(
+        "routes",
+        &exchange,
+        API_SECRET,
+        r#"
+[routes]
+default = ["slack"]
+critical = ["slack"]
+"#,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+
+    let status = bot.wait().await;
+    assert!(!status.success(), "{}", bot.log());
+    assert!(
+        bot.log()
+            .contains("routes: unknown notifier slack, configured are none"),
+        "{}",
+        bot.log()
+    );
+}
+
+/// A config parse error quotes the broken line, which here holds the API
+/// secret; it must print scrubbed, and exit 1 rather than panic.
+#[tokio::test]
+async fn scrubs_the_secret_from_a_config_error() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let mut bot = Bot::start_configured(
+        "scrub",
+        &exchange,
+        API_SECRET,
+        &format!("pasted = \"{}", API_SECRET),
+        "",
+    );
+
+    let status = bot.wait().await;
+    assert_eq!(status.code(), Some(1), "{}", bot.log());
+    assert!(bot.log().contains("failed loading config"), "{}", bot.log());
+    assert!(!bot.log().contains("panicked"), "{}", bot.log());
+    assert!(bot.log().contains("[REDACTED]"), "{}", bot.log());
+    assert!(!bot.log().contains(API_SECRET), "{}", bot.log());
+}
+
+#[tokio::test]
+async fn refuses_a_wrong_or_empty_token() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start("token", &exchange, API_SECRET);
+    bot.wait_for("HTTP server listening").await;
+
+    let client = reqwest::Client::new();
+    for token in ["", "e2e-toke", "e2e-token-and-more"] {
+        let status = client
+            .get(format!("http://{}/orders", bot.http))
+            .header("x-stink-bid-token", token)
+            .send()
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, reqwest::StatusCode::UNAUTHORIZED, "{:?}", token);
+    }
+    assert!(bot.get("/orders").await.is_object());
+}
+
+/// At `-vv` every request and reply is traced, yet neither the key, the
+/// secret nor a signature makes it into the log.
+#[tokio::test]
+async fn traces_requests_without_credentials() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let mut bot = Bot::start("trace", &exchange, API_SECRET);
+    bot.restart(&exchange, &["-vv"]);
+
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    bot.report().await;
+
+    let log = bot.log();
+    assert!(
+        log.contains("TRACE -> POST /v5/order/create-batch"),
+        "{}",
+        log
+    );
+    assert!(log.contains("x-bapi-sign=<redacted "), "{}", log);
+    assert!(!log.contains(API_KEY), "{}", log);
+    assert!(!log.contains(API_SECRET), "{}", log);
+    let posts = exchange.posts();
+    assert!(!posts.is_empty());
+    for post in posts {
+        assert!(!log.contains(&post.signature), "{}", log);
+    }
+}
+
+/// 50 USDT with children of at most 20 goes out as three children of about
+/// 16.67 each, from the level price down through a 0.5% band.
+#[tokio::test]
+async fn splits_a_level_into_children() {
+    let exchange = MockExchange::start(Script {
+        fill_levels: vec![1],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_with(
+        "children",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [6.0, 10.0]
+budgets = [10.0, 50.0]
+fill_check_minutes = 60
+[[strategies.level_orders]]
+level = 1
+max_child_notional = 20.0
+[strategies.exits]
+levels = [5.0]
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    let orders = exchange.orders();
+    let children: Vec<(&str, &str)> = orders
+        .iter()
+        .filter(|order| order.order_link_id.contains("-1c"))
+        .map(|order| (order.price.as_str(), order.qty.as_str()))
+        .collect();
+    assert_eq!(
+        children,
+        [("0.9000", "18"), ("0.8978", "18"), ("0.8955", "18")],
+        "{:#?}",
+        orders
+    );
+    // both levels are reported and counted as one each
+    assert!(report.contains("placed 2 levels"), "{}", report);
+    assert!(
+        report.contains("10% 50.00 USDT -> qty 18 @ 0.9000, qty 18 @ 0.8978, qty 18 @ 0.8955"),
+        "{}",
+        report
+    );
+    assert!(report.contains("10% 1/1 (100%)"), "{}", report);
+    assert!(report.contains("6% 0/1 (0%)"), "{}", report);
+    assert!(report.contains("cancelled 1 levels"), "{}", report);
+    assert!(report.contains("10% level child 2 filled"), "{}", report);
+}
+
+#[tokio::test]
+async fn sells_a_fill_back_at_the_anchor() {
+    let exchange = MockExchange::start(Script {
+        fill_levels: vec![1],
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_with(
+        "tpanchor",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [6.0, 10.0]
+budgets = [10.0, 50.0]
+[strategies.exits]
+mode = "tp_at_anchor"
+expiry = "3d"
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    let exits: Vec<(String, String, bool)> = exchange
+        .orders()
+        .into_iter()
+        .filter(|order| order.side == "Sell")
+        .map(|order| (order.price, order.qty, order.reduce_only))
+        .collect();
+    assert_eq!(
+        exits,
+        [("1.0000".to_string(), "55".to_string(), true)],
+        "{}",
+        report
+    );
+    // kept past the cycle, booked to the 10% level and due to expire
+    let state: Value =
+        serde_json::from_str(&fs::read_to_string(bot.dir.join("state.json")).unwrap()).unwrap();
+    let exit = &state["strategies"]["daily/trigger"]["exits"][0];
+    assert_eq!(exit["level_percent"], json!(10.0), "{}", state);
+    assert!(exit["expires_at"].is_string(), "{}", state);
+}
+
+#[tokio::test]
+async fn places_a_generated_grid() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_with(
+        "grid",
+        &exchange,
+        API_SECRET,
+        r#"
+mode = "grid"
+[strategies.grid]
+top_percent = 6.0
+bottom_percent = 17.0
+levels = 12
+budget = 120.0
+"#,
+    );
+    let report = bot
+        .post("/trigger/place", json!({ "hold_minutes": 1 }))
+        .await;
+    assert_eq!(
+        report["symbols"][0]["outcome"],
+        json!({ "outcome": "placed", "detail": 12 }),
+        "{}",
+        report
+    );
+
+    // more levels than one batch takes, sent in two
+    assert_eq!(exchange.calls("/v5/order/create-batch"), 2);
+    let mut orders = exchange.orders();
+    orders.sort_by_key(|order| {
+        let level = order.order_link_id.rsplit('-').next().unwrap();
+        level.parse::<usize>().unwrap()
+    });
+    let prices: Vec<&str> = orders.iter().map(|order| order.price.as_str()).collect();
+    assert_eq!(
+        prices,
+        [
+            "0.9400", "0.9300", "0.9200", "0.9100", "0.9000", "0.8900", "0.8800", "0.8700",
+            "0.8600", "0.8500", "0.8400", "0.8300",
+        ]
+    );
+    let qtys: Vec<&str> = orders.iter().map(|order| order.qty.as_str()).collect();
+    assert_eq!(
+        qtys,
+        ["10", "10", "10", "10", "11", "11", "11", "11", "11", "11", "11", "12"]
+    );
+}
+
+#[tokio::test]
+async fn sizes_the_ladder_to_the_target_position() {
+    let exchange = MockExchange::start(Script {
+        position_size: Some("150"),
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_with(
+        "target",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0, 20.0, 30.0]
+budgets = [50.0, 50.0, 50.0]
+sizing = "target_position"
+target_position_notional = 250.0
+"#,
+    );
+    let report = bot
+        .post("/trigger/place", json!({ "hold_minutes": 1 }))
+        .await;
+    assert_eq!(
+        report["symbols"][0]["outcome"],
+        json!({ "outcome": "placed", "detail": 3 }),
+        "{}",
+        report
+    );
+    let event = "position 150.00, target 250.00, headroom 100.00: ladder sized to the headroom";
+    assert!(
+        report["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|line| line.as_str().unwrap().ends_with(event)),
+        "{}",
+        report
+    );
+
+    // 100 of headroom over three even levels
+    let mut orders = exchange.orders();
+    orders.sort_by(|a, b| a.order_link_id.cmp(&b.order_link_id));
+    let qtys: Vec<&str> = orders.iter().map(|order| order.qty.as_str()).collect();
+    assert_eq!(qtys, ["37", "41", "47"]);
+}
+
+#[tokio::test]
+async fn downsizes_a_ladder_the_margin_cant_carry() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_with(
+        "margin",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0, 20.0, 30.0]
+budgets = [50.0, 50.0, 50.0]
+[strategies.margin_check]
+buffer_percent = 99.0
+"#,
+    );
+    let report = bot
+        .post("/trigger/place", json!({ "hold_minutes": 1 }))
+        .await;
+    let event =
+        "margin 150.00 / 1x = 150.00, available 10000.00 less 99% = 100.00: downsized to 66.7%";
+    assert!(
+        report["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|line| line.as_str().unwrap().ends_with(event)),
+        "{}",
+        report
+    );
+    let mut orders = exchange.orders();
+    orders.sort_by(|a, b| a.order_link_id.cmp(&b.order_link_id));
+    let qtys: Vec<&str> = orders.iter().map(|order| order.qty.as_str()).collect();
+    assert_eq!(qtys, ["37", "41", "47"]);
+}
+
+#[tokio::test]
+async fn enters_by_twap_once_triggered() {
+    let exchange = MockExchange::start(Script {
+        last_price: Some("0.9700"),
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_configured(
+        "twap",
+        &exchange,
+        API_SECRET,
+        r#"entry_mode = "twap""#,
+        r#"
+levels = [6.0, 10.0]
+budgets = [10.0, 20.0]
+[strategies.twap]
+trigger_percent = 2.0
+slices = 3
+every = "500ms"
+"#,
+    );
+    let report = bot
+        .post("/trigger/place", json!({ "hold_minutes": 1 }))
+        .await;
+    assert!(
+        report.to_string().contains("twap once below 0.98"),
+        "{}",
+        report
+    );
+
+    // slices at the last price, each cancelled when the next is due and its
+    // unfilled share carried into the ones left
+    let mut slices = Vec::new();
+    for _ in 0..50 {
+        slices = exchange
+            .orders()
+            .into_iter()
+            .filter(|order| order.order_link_id.contains("-t"))
+            .collect::<Vec<_>>();
+        if slices.len() == 3 && slices.iter().all(|order| order.status == "Cancelled") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let sent: Vec<(&str, &str, &str)> = slices
+        .iter()
+        .map(|order| {
+            (
+                order.price.as_str(),
+                order.qty.as_str(),
+                order.status.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        sent,
+        [
+            ("0.9700", "10", "Cancelled"),
+            ("0.9700", "15", "Cancelled"),
+            ("0.9700", "30", "Cancelled"),
+        ],
+        "{}",
+        bot.log()
+    );
+    // nothing of the ladder rests
+    assert!(
+        exchange
+            .orders()
+            .iter()
+            .all(|order| order.order_link_id.contains("-t")),
+        "{:#?}",
+        exchange.orders()
+    );
+}
+
+#[tokio::test]
+async fn downsizes_when_open_interest_jumped() {
+    let exchange = MockExchange::start(Script {
+        oi_growth: 0.5,
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_with(
+        "openinterest",
+        &exchange,
+        API_SECRET,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+[strategies.open_interest]
+max_change_percent = 30.0
+action = "downsize"
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    assert!(
+        report.contains("open interest +50.0% over 24h, threshold 30%: downsized to 50%"),
+        "{}",
+        report
+    );
+    // half the 55.6 that 50 USDT buys at 0.9, floored
+    assert_eq!(exchange.orders()[0].qty, "27");
+    assert_eq!(exchange.calls("/v5/market/open-interest"), 1);
+}
+
+/// Runs a one-shot subcommand of the binary with `config` in a scratch
+/// directory, returning whether it succeeded and what it printed.
+async fn run_cli(
+    name: &str,
+    exchange: &MockExchange,
+    config: &str,
+    secret: &str,
+    args: &[&str],
+) -> (bool, String) {
+    run_cli_with(name, exchange, config, secret, args, &[]).await
+}
+
+/// `run_cli` with `env` set over the test environment, an empty value
+/// counting as unset.
+async fn run_cli_with(
+    name: &str,
+    exchange: &MockExchange,
+    config: &str,
+    secret: &str,
+    args: &[&str],
+    env: &[(&str, &str)],
+) -> (bool, String) {
+    let dir = std::env::temp_dir().join(format!("stink-bid-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("config.toml"), config).unwrap();
+    let output = tokio::process::Command::new(env!("CARGO_BIN_EXE_stink-bid"))
+        .current_dir(&dir)
+        .env("CONFIG_PATH", "config.toml")
+        .env("XDG_DATA_HOME", &dir)
+        .env("API_KEY", API_KEY)
+        .env("API_SECRET", secret)
+        .env("VAULT_TOKEN", VAULT_TOKEN)
+        .env("AWS_ACCESS_KEY_ID", AWS_ACCESS_KEY_ID)
+        .env("AWS_SECRET_ACCESS_KEY", AWS_SECRET_ACCESS_KEY)
+        .env("BYBIT_BASE_URL", &exchange.url)
+        .env("KLINE_URL", format!("{}/v5/market/kline", exchange.url))
+        .env(
+            "BATCH_ORDER_URL",
+            format!("{}/v5/order/create-batch", exchange.url),
+        )
+        .env(
+            "BATCH_CANCEL_ORDER_URL",
+            format!("{}/v5/order/cancel-batch", exchange.url),
+        )
+        .env_remove("SLACK_WEBHOOK_URL")
+        .envs(env.iter().copied())
+        .args(args)
+        .output()
+        .await
+        .expect("failed running the cli");
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+/// Runs `validate` on `config` with `args`.
+async fn validate(
+    name: &str,
+    exchange: &MockExchange,
+    config: &str,
+    args: &[&str],
+) -> (bool, String) {
+    let mut all = vec!["validate", "--config", "config.toml"];
+    all.extend(args);
+    run_cli(name, exchange, config, API_SECRET, &all).await
+}
+
+#[tokio::test]
+async fn validates_budgets_against_the_minimum_order_value() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let config = |budgets: &str| {
+        format!(
+            r#"
+[symbols.{SYMBOL}]
+[slack]
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+levels = [10.0, 20.0]
+budgets = [{budgets}]
+"#
+        )
+    };
+
+    let (passed, out) = validate(
+        "validate",
+        &exchange,
+        &config("50.0, 3.0"),
+        &["--online", "--save-instruments", "instruments.json"],
+    )
+    .await;
+    assert!(!passed, "{}", out);
+    assert!(
+        out.contains(&format!(
+            "error: strategy daily level 2 (20%) budget 3.00 USDT is below the {} minimum order value of 5",
+            SYMBOL
+        )),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("warning: SLACK_WEBHOOK_URL is not set"),
+        "{}",
+        out
+    );
+    assert!(out.contains("1 error(s), 1 warning(s)"), "{}", out);
+
+    // the saved snapshot checks the fixed config offline
+    let calls = exchange.calls("/v5/market/instruments-info");
+    let (passed, out) = validate(
+        "validate",
+        &exchange,
+        &config("50.0, 50.0"),
+        &["--instruments", "instruments.json"],
+    )
+    .await;
+    assert!(passed, "{}", out);
+    assert!(out.contains("0 error(s), 1 warning(s)"), "{}", out);
+    assert_eq!(exchange.calls("/v5/market/instruments-info"), calls);
+
+    let (passed, out) = validate("validate", &exchange, &config("50.0"), &[]).await;
+    assert!(!passed, "{}", out);
+    assert!(
+        out.contains("strategy daily needs at least one level and one budget per level"),
+        "{}",
+        out
+    );
+}
+
+#[tokio::test]
+async fn doctor_checks_the_key_and_symbols() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let config = format!(
+        r#"
+[symbols.{SYMBOL}]
+[symbols.SIEUSDT]
+[slack]
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+levels = [10.0]
+budgets = [50.0]
+"#
+    );
+
+    let (passed, out) = run_cli("doctor", &exchange, &config, API_SECRET, &["doctor"]).await;
+    assert!(!passed, "{}", out);
+    for line in [
+        "[ ok ] base url: 127.0.0.1 resolves to 127.0.0.1",
+        "[ ok ] api key: authenticated, Unified account",
+        "[ ok ] key permissions: ContractTrade: Order, Position; Spot: SpotTrade; bound to 203.0.113.7",
+        "[ ok ] key expiry: doesn't expire",
+        &format!("[ ok ] linear {}: tick 0.0001, step 1, min order 5 USDT", SYMBOL),
+        &format!("[FAIL] linear SIEUSDT: not listed, did you mean {}?", SYMBOL),
+        "[warn] slack: disabled: SLACK_WEBHOOK_URL is missing",
+    ] {
+        assert!(out.contains(line), "{}", out);
+    }
+    assert!(out.contains("[ ok ] server time: clock "), "{}", out);
+
+    let config = config.replace("[symbols.SIEUSDT]\n", "");
+    let (passed, out) = run_cli("doctor", &exchange, &config, API_SECRET, &["doctor"]).await;
+    assert!(passed, "{}", out);
+    assert!(out.ends_with("7 passed, 1 warned, 0 failed\n"), "{}", out);
+
+    let (passed, out) = run_cli("doctor", &exchange, &config, "not-the-secret", &["doctor"]).await;
+    assert!(!passed, "{}", out);
+    assert!(out.contains("[FAIL] api key: "), "{}", out);
+    assert!(!out.contains("key permissions"), "{}", out);
+}
+
+#[tokio::test]
+async fn refuses_to_anchor_on_a_stale_candle() {
+    let exchange = MockExchange::start(Script {
+        stale_kline: true,
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start("stale", &exchange, API_SECRET);
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = bot.report().await;
+
+    assert!(report.contains("stale kline from "), "{}", report);
+    assert!(exchange.orders().is_empty(), "{:#?}", exchange.orders());
+    // refetched under the reads policy before giving up
+    assert_eq!(exchange.calls("/v5/market/kline"), 4);
+    assert!(
+        bot.log().contains("not placed: stale kline"),
+        "{}",
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn anchors_on_the_current_candle_not_the_next() {
+    let exchange = MockExchange::start(Script {
+        ahead_kline: true,
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_configured(
+        "aheadanchor",
+        &exchange,
+        API_SECRET,
+        "",
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    assert_eq!(placed["symbols"][0]["anchor"], "1.0000", "{}", placed);
+    // the forming 2.0000 candle listed first is passed over, not refetched
+    assert_eq!(exchange.calls("/v5/market/kline"), 1);
+    assert_eq!(exchange.orders()[0].price, "0.9000");
+    assert!(
+        bot.log().contains("anchored on the candle opening "),
+        "{}",
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn anchors_on_the_lowest_low_of_the_last_candles() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_configured(
+        "minlow",
+        &exchange,
+        API_SECRET,
+        r#"
+anchor_reducer = "min_low"
+anchor_candles = 3"#,
+        r#"
+levels = [10.0]
+budgets = [50.0]
+"#,
+    );
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    // the current candle's low is 0.9500, two days back it was 0.9300
+    assert_eq!(
+        placed["symbols"][0]["anchor"], "0.9300 (min_low of 3)",
+        "{}",
+        placed
+    );
+    assert_eq!(exchange.orders()[0].price, "0.8370");
+    assert!(
+        bot.log().contains("anchor min_low of 3 candles: 0.9300"),
+        "{}",
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn holds_the_cycle_back_when_an_order_breaches_a_guard() {
+    let exchange = MockExchange::start(Script::default()).await;
+    let bot = Bot::start_configured(
+        "guards",
+        &exchange,
+        API_SECRET,
+        r#"
+[guards]
+max_order_notional = 40.0
+"#,
+        r#"
+levels = [10.0, 20.0]
+budgets = [30.0, 50.0]
+"#,
+    );
+    let placed = bot
+        .post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let report = placed.to_string();
+    assert!(report.contains("held back by guards"), "{}", report);
+    assert!(
+        report.contains("order of 49.60 is over max_order_notional 40"),
+        "{}",
+        report
+    );
+    // the level within the bound isn't placed either
+    assert!(exchange.orders().is_empty(), "{:#?}", exchange.orders());
+    assert!(
+        bot.log().contains("cycle held back, nothing placed"),
+        "{}",
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn validates_the_guards_online() {
+    let exchange = MockExchange::start(Script::default()).await;
+    // 50 USDT at 10% under a last price of 1.02 is 54 coins, over 1% of
+    // the 1000 traded in 24h
+    let config = format!(
+        r#"
+[symbols.{SYMBOL}]
+[guards]
+max_volume_multiple = 0.01
+max_cycle_notional = 60.0
+
+[[strategies]]
+name = "daily"
+link_id_prefix = "e2e"
+levels = [10.0, 20.0]
+budgets = [50.0, 50.0]
+"#
+    );
+    let (passed, out) = validate("guards", &exchange, &config, &["--online"]).await;
+    assert!(!passed, "{}", out);
+    assert!(
+        out.contains("is over 0.01 times its 24h volume of 1000"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("error: strategy daily: cycle total of 100.00 is over max_cycle_notional 60"),
+        "{}",
+        out
+    );
+
+    // offline the volume can't be checked, the cycle total still is
+    let (passed, out) = validate("guards", &exchange, &config, &[]).await;
+    assert!(!passed, "{}", out);
+    assert!(
+        out.contains("warning: guards.max_volume_multiple not checked, pass --online"),
+        "{}",
+        out
+    );
+    assert!(!out.contains("24h volume"), "{}", out);
+}
+
+#[tokio::test]
+async fn splits_a_level_over_the_max_order_qty() {
+    let exchange = MockExchange::start(Script {
+        max_order_qty: Some("50"),
+        ..Script::default()
+    })
+    .await;
+    // 45 USDT at 0.9000 is exactly the 50 allowed, 80 at 0.8000 twice it
+    let bot = Bot::start_configured(
+        "maxqty",
+        &exchange,
+        API_SECRET,
+        "",
+        r#"
+levels = [10.0, 20.0]
+budgets = [45.0, 80.0]
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let orders: Vec<(String, String, String)> = exchange
+        .orders()
+        .into_iter()
+        .map(|order| {
+            let suffix = order.order_link_id.rsplit('-').next().unwrap().to_string();
+            (suffix, order.price, order.qty)
+        })
+        .collect();
+    let orders: Vec<(&str, &str, &str)> = orders
+        .iter()
+        .map(|(suffix, price, qty)| (suffix.as_str(), price.as_str(), qty.as_str()))
+        .collect();
+    assert_eq!(
+        orders,
+        [
+            ("0", "0.9000", "50"),
+            ("1c0", "0.8000", "50"),
+            ("1c1", "0.8000", "50"),
+        ]
+    );
+    assert!(
+        bot.log()
+            .contains("level 1 of 80.00 split into 2 orders under the maximum order of 40.00 USDT"),
+        "{}",
+        bot.log()
+    );
+}
+
+#[tokio::test]
+async fn clamps_a_level_over_the_max_order_qty() {
+    let exchange = MockExchange::start(Script {
+        max_order_qty: Some("50"),
+        ..Script::default()
+    })
+    .await;
+    let bot = Bot::start_configured(
+        "maxqtyclamp",
+        &exchange,
+        API_SECRET,
+        "",
+        r#"
+over_max_order = "clamp"
+levels = [20.0]
+budgets = [80.0]
+"#,
+    );
+    bot.post("/trigger/place", json!({ "hold_minutes": 0 }))
+        .await;
+    let orders = exchange.orders();
+    assert_eq!(orders.len(), 1, "{:#?}", orders);
+    assert_eq!(
+        (orders[0].price.as_str(), orders[0].qty.as_str()),
+        ("0.8000", "50")
+    );
+    assert!(
+        bot.log()
+            .contains("level 0 clamped from 80.00 to 40.00 USDT"),
+        "{}",
+        bot.log()
+    );
+}
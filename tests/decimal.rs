@@ -0,0 +1,175 @@
+//! Order payload numbers, checked against what Bybit accepts.
+
+#[path = "../src/decimal.rs"]
+mod decimal;
+
+use decimal::{format, format_step, places, round_to, round_to_step, Rounding, MAX_DECIMALS};
+
+/// Plain digits with one leading digit before the point and exactly
+/// `decimals` after it.
+fn assert_plain(text: &str, decimals: usize) {
+    assert!(!text.contains(['e', 'E', '+']), "{} has an exponent", text);
+    assert!(
+        !text.starts_with('.') && !text.starts_with("-0"),
+        "{}",
+        text
+    );
+    match text.split_once('.') {
+        Some((whole, fraction)) => {
+            assert!(!whole.is_empty(), "{}", text);
+            assert_eq!(fraction.len(), decimals, "{}", text);
+        }
+        None => assert_eq!(decimals, 0, "{}", text),
+    }
+    assert!(text
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '.' || c == '-'));
+}
+
+#[test]
+fn small_prices_keep_their_digits() {
+    assert_eq!(format(0.0000071, 7, Rounding::Nearest), "0.0000071");
+    assert_eq!(format(0.0000071, 8, Rounding::Nearest), "0.00000710");
+    assert_eq!(format(0.00000709, 7, Rounding::Up), "0.0000071");
+    assert_eq!(format(0.00000711, 7, Rounding::Down), "0.0000071");
+    assert_eq!(format(1e-12, 7, Rounding::Nearest), "0.0000000");
+    assert_eq!(format(71.0 / 10_000_000.0, 7, Rounding::Down), "0.0000071");
+}
+
+#[test]
+fn never_prints_an_exponent_or_negative_zero() {
+    assert_eq!(
+        format(1e21, 2, Rounding::Nearest),
+        "1000000000000000000000.00"
+    );
+    assert_eq!(format(-1e-12, 4, Rounding::Nearest), "0.0000");
+    assert_eq!(format(-0.0, 2, Rounding::Down), "0.00");
+    assert_eq!(format(0.5, 0, Rounding::Nearest), "1");
+    assert_eq!(format(0.5, 0, Rounding::Down), "0");
+}
+
+#[test]
+fn rounding_direction_holds_at_every_precision() {
+    let values = [
+        0.0000071, 0.00012345, 0.1, 0.15, 0.3, 1.005, 2.675, 33.333, 1234.5678, 98765.4321,
+    ];
+    for decimals in 0..=10 {
+        for value in values {
+            let down: f64 = format(value, decimals, Rounding::Down).parse().unwrap();
+            let up: f64 = format(value, decimals, Rounding::Up).parse().unwrap();
+            let nearest: f64 = format(value, decimals, Rounding::Nearest).parse().unwrap();
+            let step = 10f64.powi(-(decimals as i32));
+            assert!(
+                down <= value + 1e-9 && value - down < step + 1e-9,
+                "{} {}",
+                value,
+                down
+            );
+            assert!(
+                up >= value - 1e-9 && up - value < step + 1e-9,
+                "{} {}",
+                value,
+                up
+            );
+            assert!(
+                (nearest - value).abs() <= step / 2.0 + 1e-9,
+                "{} {}",
+                value,
+                nearest
+            );
+            for rounding in [Rounding::Down, Rounding::Up, Rounding::Nearest] {
+                assert_plain(&format(value, decimals, rounding), decimals);
+            }
+        }
+    }
+}
+
+#[test]
+fn float_noise_does_not_lose_a_step() {
+    assert_eq!(round_to(0.29 * 100.0, 0, Rounding::Down), 29.0);
+    assert_eq!(round_to(70.999_999_999_9, 0, Rounding::Down), 71.0);
+    assert_eq!(round_to(71.000_000_000_1, 0, Rounding::Up), 71.0);
+}
+
+#[test]
+fn places_come_from_the_step() {
+    assert_eq!(places("0.0001"), 4);
+    assert_eq!(places("0.00050"), 4);
+    assert_eq!(places("1"), 0);
+    assert_eq!(places("10"), 0);
+    assert_eq!(places("0.0000001"), 7);
+    assert_eq!(places("0.0000000000000000000001"), MAX_DECIMALS);
+    assert_plain(&format(1.0, 40, Rounding::Nearest), MAX_DECIMALS);
+}
+
+#[test]
+fn steps_that_are_not_powers_of_ten() {
+    assert_eq!(format_step(1.23, "0.05", Rounding::Nearest), "1.25");
+    assert_eq!(format_step(1.23, "0.05", Rounding::Down), "1.20");
+    assert_eq!(format_step(1.23, "0.05", Rounding::Up), "1.25");
+    assert_eq!(format_step(10.3, "0.5", Rounding::Nearest), "10.5");
+    assert_eq!(format_step(10.3, "0.5", Rounding::Down), "10.0");
+    assert_eq!(format_step(10.3, "0.5", Rounding::Up), "10.5");
+    assert_eq!(format_step(0.00123, "0.0005", Rounding::Down), "0.0010");
+    assert_eq!(format_step(0.00123, "0.0005", Rounding::Up), "0.0015");
+    assert_eq!(format_step(5555.5, "10", Rounding::Down), "5550");
+    assert_eq!(format_step(5555.5, "10", Rounding::Up), "5560");
+    assert_eq!(format_step(5555.5, "100", Rounding::Nearest), "5600");
+    assert_eq!(format_step(5555.5, "100", Rounding::Down), "5500");
+    assert_eq!(format_step(99.0, "100", Rounding::Down), "0");
+}
+
+#[test]
+fn step_multiples_shed_float_noise() {
+    // 3 * 0.05 is 0.15000000000000002 in f64
+    assert_eq!(round_to_step(3.0 * 0.05, "0.05", Rounding::Nearest), 0.15);
+    assert_eq!(format_step(3.0 * 0.05, "0.05", Rounding::Down), "0.15");
+    assert_eq!(
+        format_step(0.299_999_999_999, "0.05", Rounding::Down),
+        "0.30"
+    );
+    assert_eq!(format_step(0.300_000_000_001, "0.05", Rounding::Up), "0.30");
+    assert_eq!(format_step(1e-12, "0.5", Rounding::Up), "0.0");
+    assert_eq!(format_step(-0.01, "0.05", Rounding::Nearest), "0.00");
+    assert_eq!(format_step(-0.0, "10", Rounding::Down), "0");
+}
+
+#[test]
+fn an_unusable_step_only_rounds_to_its_places() {
+    assert_eq!(format_step(1.26, "0", Rounding::Down), "1");
+    assert_eq!(format_step(1.26, "0.00", Rounding::Nearest), "1");
+    assert_eq!(format_step(1.26, "nope", Rounding::Up), "2");
+}
+
+#[test]
+fn every_rounding_lands_on_a_multiple_of_the_step() {
+    let values = [
+        0.0000071, 0.00012345, 0.1, 0.15, 0.3, 1.005, 2.675, 33.333, 1234.5678, 98765.4321,
+    ];
+    for step in [
+        "0.5", "0.25", "0.05", "0.0005", "0.000025", "10", "25", "100",
+    ] {
+        let size: f64 = step.parse().unwrap();
+        for value in values {
+            for rounding in [Rounding::Down, Rounding::Up, Rounding::Nearest] {
+                let text = format_step(value, step, rounding);
+                assert_plain(&text, places(step));
+                let sent: f64 = text.parse().unwrap();
+                let steps = sent / size;
+                assert!(
+                    (steps - steps.round()).abs() < 1e-6,
+                    "{} on {}: {}",
+                    value,
+                    step,
+                    text
+                );
+                let within = match rounding {
+                    Rounding::Down => sent <= value + 1e-9 && value - sent < size + 1e-9,
+                    Rounding::Up => sent >= value - 1e-9 && sent - value < size + 1e-9,
+                    Rounding::Nearest => (sent - value).abs() <= size / 2.0 + 1e-9,
+                };
+                assert!(within, "{} on {} {:?}: {}", value, step, rounding, text);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Drawdown percentiles and suggested ladders from made-up daily candles.
+
+#[path = "../src/drawdowns.rs"]
+mod drawdowns;
+
+use drawdowns::{analyze, depth_reached, drawdown, frequency, Frequency, Percentile};
+
+/// Drawdowns of 1% to 100%, one day each.
+fn hundred_days() -> Vec<f64> {
+    (1..=100).map(f64::from).collect()
+}
+
+#[test]
+fn drawdown_is_the_low_below_the_open() {
+    assert_eq!(drawdown(100.0, 80.0), Some(20.0));
+    // a low above the open never happens, but rounds to no drawdown
+    assert_eq!(drawdown(100.0, 101.0), Some(0.0));
+    assert_eq!(drawdown(0.0, 1.0), None);
+}
+
+#[test]
+fn frequency_counts_days_at_or_past_the_depth() {
+    let days = hundred_days();
+    assert_eq!(frequency(&days, 20.0), 81.0);
+    assert_eq!(frequency(&days, 100.5), 0.0);
+    assert_eq!(frequency(&[], 5.0), 0.0);
+    assert_eq!(frequency(&[1.0, 2.0, 3.0], 2.0), 66.7);
+}
+
+#[test]
+fn depth_reached_floors_to_a_tenth() {
+    let days = [0.5, 3.27, 8.94, 12.01];
+    assert_eq!(depth_reached(&days, 25.0), Some(12.0));
+    assert_eq!(depth_reached(&days, 50.0), Some(8.9));
+    assert_eq!(depth_reached(&days, 0.0), None);
+}
+
+#[test]
+fn analysis_suggests_halving_frequencies() {
+    let analysis = analyze("BTCUSDT", &hundred_days(), 20.0, 3);
+    assert_eq!(analysis.days, 100);
+    assert_eq!(
+        analysis.percentiles[2],
+        Percentile {
+            percentile: 90.0,
+            depth: 90.0,
+        }
+    );
+    assert_eq!(
+        analysis.suggested,
+        vec![
+            Frequency {
+                depth: 81.0,
+                days_percent: 20.0,
+            },
+            Frequency {
+                depth: 91.0,
+                days_percent: 10.0,
+            },
+            Frequency {
+                depth: 96.0,
+                days_percent: 5.0,
+            },
+        ]
+    );
+    let text = analysis.to_string();
+    assert!(text.contains("a 20% intraday drawdown occurred on 81% of days"));
+    assert!(
+        text.ends_with("suggested levels: 81% (20% of days), 91% (10% of days), 96% (5% of days)")
+    );
+}
+
+#[test]
+fn short_histories_suggest_fewer_levels() {
+    let analysis = analyze("BTCUSDT", &[4.0, 6.0], 50.0, 3);
+    assert_eq!(analysis.suggested.len(), 1);
+    let empty = analyze("BTCUSDT", &[], 20.0, 3);
+    assert!(empty.percentiles.is_empty());
+    assert!(empty
+        .to_string()
+        .ends_with("too few days to suggest a ladder"));
+}
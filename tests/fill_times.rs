@@ -0,0 +1,68 @@
+//! Time-to-fill distributions against hand-worked samples.
+
+#[path = "../src/fill_times.rs"]
+mod fill_times;
+
+use chrono::{DateTime, Duration, Utc};
+use fill_times::{by_level, distribution, percentile, FillTime};
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn sample(level: &str, placed: i64, filled: Option<i64>) -> FillTime {
+    FillTime {
+        level: level.to_string(),
+        order_link_id: format!("sb-BTCUSDT-{}-0", placed),
+        placed_at: at(placed),
+        filled_at: filled.map(at),
+    }
+}
+
+#[test]
+fn percentile_takes_the_nearest_rank() {
+    let sorted: Vec<Duration> = (1..=10).map(Duration::minutes).collect();
+    assert_eq!(percentile(&sorted, 50.0), Some(Duration::minutes(5)));
+    assert_eq!(percentile(&sorted, 90.0), Some(Duration::minutes(9)));
+    assert_eq!(percentile(&sorted[..1], 90.0), Some(Duration::minutes(1)));
+    assert_eq!(percentile(&[], 50.0), None);
+}
+
+#[test]
+fn first_hour_counts_only_levels_placed_an_hour_ago() {
+    let samples = [
+        // filled after 30m and 3h, one never, one placed 10m ago
+        sample("5%", 0, Some(30)),
+        sample("5%", 0, Some(180)),
+        sample("5%", 0, None),
+        sample("5%", 230, None),
+    ];
+    let refs: Vec<&FillTime> = samples.iter().collect();
+    let stats = distribution(&refs, at(240));
+    assert_eq!(stats.placed, 4);
+    assert_eq!(stats.filled, 2);
+    assert_eq!(stats.median, Some(Duration::minutes(30)));
+    assert_eq!(stats.p90, Some(Duration::minutes(180)));
+    assert_eq!(stats.first_hour, Some(1.0 / 3.0));
+    assert_eq!(
+        stats.to_string(),
+        "filled 2/4, median 30m, p90 3h 0m, 33% in the first hour"
+    );
+}
+
+#[test]
+fn levels_are_kept_apart() {
+    let samples = [
+        sample("5%", 0, Some(10)),
+        sample("10%", 0, Some(2 * 24 * 60 + 60)),
+        sample("10%", 0, None),
+    ];
+    let levels = by_level(&samples, at(10));
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels["5%"].to_string(), "filled 1/1, median 10m, p90 10m");
+    assert_eq!(
+        levels["10%"].to_string(),
+        "filled 1/2, median 2d 1h, p90 2d 1h"
+    );
+    assert_eq!(samples[0].time_to_fill(), Some(Duration::minutes(10)));
+}
@@ -0,0 +1,48 @@
+//! Grid levels and allocations against hand-computed ladders.
+
+#[path = "../src/grid.rs"]
+mod grid;
+
+use grid::{allocations, levels, GridAllocation, GridSpacing};
+
+#[test]
+fn even_grid_steps_by_the_same_percent() {
+    assert_eq!(
+        levels(5.0, 10.0, 6, GridSpacing::Even),
+        [5.0, 6.0, 7.0, 8.0, 9.0, 10.0]
+    );
+    // rounded to hundredths, both ends included
+    assert_eq!(
+        levels(3.0, 4.0, 4, GridSpacing::Even),
+        [3.0, 3.33, 3.67, 4.0]
+    );
+}
+
+#[test]
+fn geometric_grid_steps_by_the_same_ratio() {
+    assert_eq!(
+        levels(2.0, 32.0, 5, GridSpacing::Geometric),
+        [2.0, 4.0, 8.0, 16.0, 32.0]
+    );
+    assert_eq!(
+        levels(10.0, 40.0, 3, GridSpacing::Geometric),
+        [10.0, 20.0, 40.0]
+    );
+}
+
+#[test]
+fn a_single_level_grid_sits_at_the_top() {
+    assert_eq!(levels(7.5, 20.0, 1, GridSpacing::Geometric), [7.5]);
+}
+
+#[test]
+fn budget_is_split_equally_or_growing_with_depth() {
+    assert_eq!(
+        allocations(90.0, 3, GridAllocation::Equal),
+        [30.0, 30.0, 30.0]
+    );
+    assert_eq!(
+        allocations(100.0, 4, GridAllocation::Linear),
+        [10.0, 20.0, 30.0, 40.0]
+    );
+}
@@ -0,0 +1,123 @@
+//! Planned orders held to the order, volume and cycle bounds.
+
+#[path = "../src/guards.rs"]
+mod guards;
+
+use guards::{check, Breach, Guards, Order};
+use std::collections::BTreeMap;
+
+fn order(symbol: &str, qty: f64, notional: f64) -> Order<'_> {
+    Order {
+        symbol,
+        qty,
+        notional,
+    }
+}
+
+#[test]
+fn no_guards_pass_anything() {
+    let guards = Guards::default();
+    assert!(guards.is_empty());
+    let orders = [order("BTCUSDT", 1e9, 1e12)];
+    assert_eq!(check(&guards, &orders, &BTreeMap::new(), 0.0), vec![]);
+}
+
+#[test]
+fn a_fat_fingered_budget_breaches_the_order_and_cycle_bounds() {
+    let guards = Guards {
+        max_order_notional: Some(1000.0),
+        max_cycle_notional: Some(5000.0),
+        ..Guards::default()
+    };
+    assert!(!guards.is_empty());
+    let orders = [
+        order("ALTUSDT", 10.0, 900.0),
+        order("ALTUSDT", 1e5, 100000.0),
+    ];
+    let breaches = check(&guards, &orders, &BTreeMap::new(), 0.0);
+    assert_eq!(
+        breaches,
+        vec![
+            Breach::Order {
+                symbol: "ALTUSDT".to_string(),
+                notional: 100000.0,
+                max: 1000.0
+            },
+            Breach::Cycle {
+                total: 100900.0,
+                max: 5000.0
+            },
+        ]
+    );
+    assert_eq!(
+        breaches[0].to_string(),
+        "ALTUSDT order of 100000.00 is over max_order_notional 1000"
+    );
+    assert_eq!(
+        breaches[1].to_string(),
+        "cycle total of 100900.00 is over max_cycle_notional 5000"
+    );
+}
+
+#[test]
+fn qty_is_held_to_a_multiple_of_the_24h_volume() {
+    let guards = Guards {
+        max_volume_multiple: Some(0.01),
+        ..Guards::default()
+    };
+    let volumes = BTreeMap::from([("ALTUSDT", 50000.0)]);
+    let orders = [
+        order("ALTUSDT", 500.0, 50.0),
+        order("ALTUSDT", 501.0, 50.1),
+        // no volume read, so not held to it
+        order("TAOUSDT", 1e9, 50.0),
+    ];
+    let breaches = check(&guards, &orders, &volumes, 0.0);
+    assert_eq!(
+        breaches,
+        vec![Breach::Volume {
+            symbol: "ALTUSDT".to_string(),
+            qty: 501.0,
+            volume: 50000.0,
+            multiple: 0.01
+        }]
+    );
+    assert_eq!(
+        breaches[0].to_string(),
+        "ALTUSDT order qty 501 is over 0.01 times its 24h volume of 50000"
+    );
+}
+
+#[test]
+fn a_misread_price_breaches_every_bound_it_meets() {
+    let guards = Guards {
+        max_order_notional: Some(1000.0),
+        max_volume_multiple: Some(0.01),
+        max_cycle_notional: Some(5000.0),
+    };
+    let volumes = BTreeMap::from([("ALTUSDT", 50000.0)]);
+    let breaches = check(
+        &guards,
+        &[order("ALTUSDT", f64::NAN, f64::NAN)],
+        &volumes,
+        0.0,
+    );
+    assert_eq!(breaches.len(), 3, "{:?}", breaches);
+}
+
+#[test]
+fn other_symbols_resting_count_toward_the_cycle() {
+    let guards = Guards {
+        max_cycle_notional: Some(500.0),
+        ..Guards::default()
+    };
+    let orders = [order("ALTUSDT", 10.0, 200.0)];
+    assert_eq!(check(&guards, &orders, &BTreeMap::new(), 300.0), vec![]);
+    assert_eq!(
+        check(&guards, &orders, &BTreeMap::new(), 300.5),
+        vec![Breach::Cycle {
+            total: 500.5,
+            max: 500.0
+        }]
+    );
+}
@@ -0,0 +1,64 @@
+//! Warnings ahead of an API key's expiry.
+
+#[path = "../src/key_expiry.rs"]
+mod key_expiry;
+
+use chrono::{DateTime, Duration, Utc};
+use key_expiry::{describe, due, parse, CRITICAL_DAYS, WARN_DAYS};
+
+fn at(text: &str) -> DateTime<Utc> {
+    text.parse().unwrap()
+}
+
+#[test]
+fn an_empty_expiry_never_expires() {
+    assert_eq!(parse(""), Ok(None));
+    assert_eq!(
+        parse("2026-10-29T00:00:00Z"),
+        Ok(Some(at("2026-10-29T00:00:00Z")))
+    );
+    assert!(parse("soon").is_err());
+}
+
+#[test]
+fn each_threshold_warns_once_as_the_expiry_nears() {
+    let expires = at("2026-10-29T00:00:00Z");
+    let mut warned = None;
+    let mut sent = Vec::new();
+    // a check a day, from three weeks out to past the expiry
+    for days_out in (-1..21).rev() {
+        let now = expires - Duration::days(days_out) + Duration::minutes(1);
+        if let Some(days) = due(expires, now, warned) {
+            warned = Some(days);
+            sent.push(days);
+        }
+    }
+    assert_eq!(sent, WARN_DAYS.to_vec());
+    assert!(sent.iter().filter(|days| **days <= CRITICAL_DAYS).count() == 2);
+}
+
+#[test]
+fn a_late_first_check_skips_to_the_tightest_threshold() {
+    let expires = at("2026-10-29T00:00:00Z");
+    let now = expires - Duration::days(2);
+    assert_eq!(due(expires, now, None), Some(3));
+    assert_eq!(due(expires, now, Some(3)), None);
+    assert_eq!(due(expires, expires - Duration::days(30), None), None);
+}
+
+#[test]
+fn describes_the_time_left() {
+    let expires = at("2026-10-29T00:00:00Z");
+    assert_eq!(
+        describe(expires, expires - Duration::hours(13 * 24 + 5)),
+        "expires 2026-10-29 00:00 UTC, in 13 days"
+    );
+    assert_eq!(
+        describe(expires, expires - Duration::hours(5)),
+        "expires 2026-10-29 00:00 UTC, in 5 hours"
+    );
+    assert_eq!(
+        describe(expires, expires + Duration::hours(1)),
+        "expired 2026-10-29 00:00 UTC"
+    );
+}
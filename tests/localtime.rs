@@ -0,0 +1,125 @@
+//! Placement times configured on a local clock, across the 2026 DST changes
+//! in New York: clocks jump 02:00 -> 03:00 on 8 March and 02:00 -> 01:00 on
+//! 1 November.
+
+#[path = "../src/localtime.rs"]
+mod localtime;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use localtime::LocalTime;
+use serde::Deserialize;
+
+fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+/// Every daily open from `first` for `days`, with where it's placed.
+fn placements(at: &LocalTime, first: &str, days: i64) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let first =
+        Utc.from_utc_datetime(&NaiveDate::parse_from_str(first, "%Y-%m-%d").unwrap().into());
+    (0..days)
+        .map(|day| first + Duration::days(day))
+        .map(|open| (open, at.first_at_or_after(open)))
+        .collect()
+}
+
+fn assert_one_per_day(placements: &[(DateTime<Utc>, DateTime<Utc>)]) {
+    for pair in placements.windows(2) {
+        let (open, placed) = pair[0];
+        let (next_open, next_placed) = pair[1];
+        assert!(placed >= open && placed < next_open, "{:?}", pair);
+        assert!(next_placed > placed, "{:?}", pair);
+        // a day either way would be a skipped or doubled cycle
+        let gap = next_placed - placed;
+        assert!(
+            gap >= Duration::hours(23) && gap <= Duration::hours(25),
+            "{:?}",
+            pair
+        );
+        assert!(next_placed < next_open + Duration::days(1), "{:?}", pair);
+    }
+}
+
+#[test]
+fn parses_time_and_zone() {
+    let at: LocalTime = "00:05 America/New_York".parse().unwrap();
+    assert_eq!(at.to_string(), "00:05 America/New_York");
+    assert_eq!(
+        "23:30".parse::<LocalTime>().unwrap().to_string(),
+        "23:30 UTC"
+    );
+    assert!("25:00 UTC".parse::<LocalTime>().is_err());
+    assert!("00:05 Mars/Olympus".parse::<LocalTime>().is_err());
+    assert!("00:05 UTC extra".parse::<LocalTime>().is_err());
+
+    #[derive(Deserialize)]
+    struct Schedule {
+        place_at: LocalTime,
+    }
+    let schedule: Schedule = toml::from_str(r#"place_at = "00:05 America/New_York""#).unwrap();
+    assert_eq!(schedule.place_at, at);
+    assert!(toml::from_str::<Schedule>(r#"place_at = "noon""#).is_err());
+}
+
+#[test]
+fn follows_the_local_clock_across_spring_forward() {
+    let at: LocalTime = "00:05 America/New_York".parse().unwrap();
+    let placed = placements(&at, "2026-03-05", 7);
+    assert_one_per_day(&placed);
+    // 8 March opens 00:00 UTC, still 7 March 19:00 in New York
+    let march_8 = placed[3].1;
+    assert_eq!(march_8, utc("2026-03-08T05:05:00Z"));
+    assert_eq!(at.local(march_8), "2026-03-08 00:05 EST");
+    assert_eq!(placed[4].1, utc("2026-03-09T04:05:00Z"));
+    assert_eq!(at.local(placed[4].1), "2026-03-09 00:05 EDT");
+}
+
+#[test]
+fn follows_the_local_clock_across_fall_back() {
+    let at: LocalTime = "00:05 America/New_York".parse().unwrap();
+    let placed = placements(&at, "2026-10-29", 7);
+    assert_one_per_day(&placed);
+    assert_eq!(placed[3].1, utc("2026-11-01T04:05:00Z"));
+    assert_eq!(placed[4].1, utc("2026-11-02T05:05:00Z"));
+    assert_eq!(at.local(placed[4].1), "2026-11-02 00:05 EST");
+}
+
+#[test]
+fn a_skipped_time_runs_once_when_the_clock_jumps() {
+    let at: LocalTime = "02:30 America/New_York".parse().unwrap();
+    let placed = placements(&at, "2026-03-05", 7);
+    assert_one_per_day(&placed);
+    // 02:30 doesn't exist on 8 March, so it runs at 03:00 EDT
+    let jumped = placed
+        .iter()
+        .find(|(_, placed)| at.local(*placed).starts_with("2026-03-08"))
+        .unwrap()
+        .1;
+    assert_eq!(jumped, utc("2026-03-08T07:00:00Z"));
+    assert_eq!(at.local(jumped), "2026-03-08 03:00 EDT");
+}
+
+#[test]
+fn a_repeated_time_runs_only_the_first_time() {
+    let at: LocalTime = "01:30 America/New_York".parse().unwrap();
+    let placed = placements(&at, "2026-10-29", 7);
+    assert_one_per_day(&placed);
+    let repeated: Vec<_> = placed
+        .iter()
+        .filter(|(_, placed)| at.local(*placed).starts_with("2026-11-01"))
+        .collect();
+    assert_eq!(repeated.len(), 1, "{:?}", placed);
+    assert_eq!(repeated[0].1, utc("2026-11-01T05:30:00Z"));
+    assert_eq!(at.local(repeated[0].1), "2026-11-01 01:30 EDT");
+}
+
+#[test]
+fn an_instant_on_the_time_is_its_own_placement() {
+    let at: LocalTime = "00:00 UTC".parse().unwrap();
+    let open = utc("2026-03-08T00:00:00Z");
+    assert_eq!(at.first_at_or_after(open), open);
+    let tokyo: LocalTime = "08:00 Asia/Tokyo".parse().unwrap();
+    assert_eq!(tokyo.first_at_or_after(open), utc("2026-03-08T23:00:00Z"));
+}
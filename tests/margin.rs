@@ -0,0 +1,47 @@
+//! Ladder margin against hand-worked balances.
+
+#[path = "../src/margin.rs"]
+mod margin;
+
+use margin::{effective_leverage, fit, MarginFit};
+
+#[test]
+fn ladder_within_the_balance_fits_whole() {
+    // 3000 at 5x ties up 600, under the 900 left of 1000 after a 10% buffer
+    assert_eq!(
+        fit(3000.0, 5.0, 1000.0, 10.0),
+        MarginFit {
+            required: 600.0,
+            usable: 900.0,
+            share: 1.0,
+        }
+    );
+}
+
+#[test]
+fn ladder_past_the_balance_fits_in_part() {
+    // 4000 at 2x ties up 2000, of which the 1500 left after 25% fits
+    assert_eq!(
+        fit(4000.0, 2.0, 2000.0, 25.0),
+        MarginFit {
+            required: 2000.0,
+            usable: 1500.0,
+            share: 0.75,
+        }
+    );
+}
+
+#[test]
+fn nothing_fits_an_empty_balance() {
+    let fitted = fit(150.0, 1.0, 0.0, 10.0);
+    assert_eq!(fitted.usable, 0.0);
+    assert_eq!(fitted.share, 0.0);
+}
+
+#[test]
+fn leverage_is_capped_by_the_instrument() {
+    assert_eq!(effective_leverage(None, Some(25.0)), 1.0);
+    assert_eq!(effective_leverage(Some(10.0), Some(25.0)), 10.0);
+    assert_eq!(effective_leverage(Some(50.0), Some(25.0)), 25.0);
+    assert_eq!(effective_leverage(Some(3.0), None), 3.0);
+}
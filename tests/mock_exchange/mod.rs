@@ -0,0 +1,847 @@
+//! An in-process stand-in for the handful of Bybit v5 endpoints the bot
+//! uses. Signed requests are checked against the test secret the same way
+//! Bybit does, and fills and rejections are scripted per ladder level.
+
+use axum::body::Bytes;
+use axum::extract::{RawQuery, State};
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use chrono::{Datelike, Duration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub const API_KEY: &str = "mock-key";
+pub const API_SECRET: &str = "mock-secret";
+pub const SYMBOL: &str = "SEIUSDT";
+/// What the mock Vault and Secrets Manager let in.
+pub const VAULT_TOKEN: &str = "mock-vault-token";
+pub const AWS_ACCESS_KEY_ID: &str = "AKIDMOCK";
+pub const AWS_SECRET_ACCESS_KEY: &str = "mock-aws-secret";
+/// The only secret the mock Secrets Manager serves, and its region.
+pub const AWS_SECRET_ID: &str = "arn:aws:secretsmanager:eu-west-1:123456789012:secret:stink-bid";
+const AWS_REGION: &str = "eu-west-1";
+/// What the mock STS, container endpoint and instance metadata service
+/// take before handing out a temporary key.
+pub const AWS_ROLE_ARN: &str = "arn:aws:iam::123456789012:role/stink-bid";
+pub const WEB_IDENTITY_TOKEN: &str = "mock-web-identity";
+pub const CONTAINER_TOKEN: &str = "mock-container-token";
+const IMDS_TOKEN: &str = "mock-imds-token";
+const IMDS_ROLE: &str = "stink-bid-role";
+/// The temporary key they hand out.
+const AWS_SESSION_KEY_ID: &str = "ASIAMOCK";
+const AWS_SESSION_SECRET: &str = "mock-session-secret";
+pub const AWS_SESSION_TOKEN: &str = "mock-session-token";
+
+/// retCode Bybit answers a bad signature with.
+const BAD_SIGNATURE: i64 = 10004;
+/// Per-order retCode for cancelling an order that already filled.
+const TOO_LATE_TO_CANCEL: i64 = 110001;
+/// Per-order retCode for a margin shortfall.
+const INSUFFICIENT_BALANCE: i64 = 110007;
+/// Per-order retCode for an orderLinkId that's already taken.
+const DUPLICATE_LINK_ID: i64 = 110072;
+const ACCOUNT_BANNED: i64 = 10008;
+/// Most orders a batch request may carry.
+const BATCH_LIMIT: usize = 10;
+
+/// What the exchange does with entries, by ladder level (0 = nearest the
+/// open). Exits always rest.
+#[derive(Default, Clone)]
+pub struct Script {
+    /// Filled in full at their limit price as soon as they're placed.
+    pub fill_levels: Vec<usize>,
+    /// Refused in the batch reply, while the rest of the batch goes through.
+    pub reject_levels: Vec<usize>,
+    /// Acknowledged by every cancel, yet left resting.
+    pub stuck_levels: Vec<usize>,
+    /// Accepted, then forgotten: no open order, no history.
+    pub vanish_levels: Vec<usize>,
+    /// Placed, yet answered as a taken orderLinkId, as when a resend
+    /// follows an attempt that went through.
+    pub duplicate_levels: Vec<usize>,
+    /// create-batch calls, counted from 1, refused whole with a retCode.
+    pub refused_batches: Vec<usize>,
+    /// create-batch calls, counted from 1, whose orders are placed but
+    /// whose reply is lost, as on a gateway timeout.
+    pub dropped_batches: Vec<usize>,
+    /// Open interest growth over the history served, e.g. 0.5 for +50%.
+    pub oi_growth: f64,
+    /// Ticker lastPrice in place of 1.0200, above the open.
+    pub last_price: Option<&'static str>,
+    /// Size of a long position already held, none when unset.
+    pub position_size: Option<&'static str>,
+    /// Serves the previous candle as the latest, as if the exchange hadn't
+    /// rolled over.
+    pub stale_kline: bool,
+    /// Lists the next period's candle, already forming, ahead of the
+    /// current one.
+    pub ahead_kline: bool,
+    /// The instrument's maxOrderQty, none listed when unset.
+    pub max_order_qty: Option<&'static str>,
+    /// The API key may only read positions, not place contract orders.
+    pub no_order_permission: bool,
+    /// Positions are held per side, the position list serving one entry
+    /// for each.
+    pub hedge_mode: bool,
+    /// Milliseconds the exchange's clock runs ahead of the real one.
+    pub clock_ahead_ms: u64,
+    /// Hours until the API key expires, never when unset.
+    pub key_expires_in_hours: Option<i64>,
+    /// Symbols listed besides `SYMBOL`, with the same rules.
+    pub also_listed: Vec<&'static str>,
+    /// instruments-info fails, as during an outage.
+    pub instruments_down: bool,
+    /// The secret signatures are checked against, `API_SECRET` when unset.
+    pub api_secret: Option<&'static str>,
+    /// The secret the mock Vault and Secrets Manager serve, `API_SECRET`
+    /// when unset.
+    pub stored_secret: Option<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MockOrder {
+    pub order_id: String,
+    pub order_link_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub price: String,
+    pub qty: String,
+    pub status: String,
+    pub reduce_only: bool,
+}
+
+impl MockOrder {
+    fn to_json(&self) -> Value {
+        let filled = self.status == "Filled";
+        json!({
+            "symbol": self.symbol,
+            "orderId": self.order_id,
+            "orderLinkId": self.order_link_id,
+            "orderStatus": self.status,
+            "side": self.side,
+            "price": self.price,
+            "qty": self.qty,
+            "avgPrice": if filled { self.price.as_str() } else { "" },
+            "cumExecQty": if filled { self.qty.as_str() } else { "0" },
+            "reduceOnly": self.reduce_only,
+        })
+    }
+
+    /// Ladder level of an entry, from the `-<level>` its orderLinkId ends
+    /// with. Exits end in `-x<n>` and have none.
+    fn level(&self) -> Option<usize> {
+        // a child `<level>c<child>` belongs to its level
+        let last = self.order_link_id.rsplit('-').next()?;
+        last.split('c').next()?.parse().ok()
+    }
+}
+
+/// A POST as it arrived, for checking its signature independently of the
+/// mock's own check.
+#[derive(Clone, Debug)]
+pub struct Captured {
+    pub path: String,
+    pub content_type: String,
+    pub timestamp: String,
+    pub recv_window: String,
+    pub signature: String,
+    pub body: String,
+}
+
+#[derive(Default)]
+struct Book {
+    script: Script,
+    orders: Vec<MockOrder>,
+    /// Method and path of every request, in order.
+    calls: Vec<String>,
+    posts: Vec<Captured>,
+    bad_signatures: usize,
+    /// Orders accepted under `vanish_levels`, still counted for ids.
+    vanished: usize,
+}
+
+#[derive(Clone)]
+pub struct MockExchange {
+    pub url: String,
+    book: Arc<Mutex<Book>>,
+}
+
+impl MockExchange {
+    /// Serves the mock on an ephemeral local port until the test ends.
+    pub async fn start(script: Script) -> MockExchange {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed binding mock exchange");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let book = Arc::new(Mutex::new(Book {
+            script,
+            ..Book::default()
+        }));
+        let app = Router::new().fallback(respond).with_state(book.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        MockExchange { url, book }
+    }
+
+    /// Changes the script mid-test, e.g. to take an endpoint down between
+    /// restarts.
+    pub fn rescript(&self, change: impl FnOnce(&mut Script)) {
+        change(&mut self.book.lock().unwrap().script);
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        self.book.lock().unwrap().orders.clone()
+    }
+
+    pub fn calls(&self, path: &str) -> usize {
+        let book = self.book.lock().unwrap();
+        book.calls
+            .iter()
+            .filter(|call| call.ends_with(path))
+            .count()
+    }
+
+    pub fn posts(&self) -> Vec<Captured> {
+        self.book.lock().unwrap().posts.clone()
+    }
+
+    pub fn bad_signatures(&self) -> usize {
+        self.book.lock().unwrap().bad_signatures
+    }
+}
+
+/// Most candles served per kline request, whatever the limit asked.
+const MAX_KLINES: usize = 10;
+
+/// Start in epoch milliseconds of the daily or weekly ("W") candle `back`
+/// periods before the current one, or after it when negative.
+fn candle_start(interval: &str, back: i64) -> String {
+    let today = Utc::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc();
+    let (open, length) = match interval {
+        "W" => (
+            today - Duration::days(today.weekday().num_days_from_monday() as i64),
+            Duration::weeks(1),
+        ),
+        _ => (today, Duration::days(1)),
+    };
+    let open = open - length * back as i32;
+    open.timestamp_millis().to_string()
+}
+
+fn ok(result: Value) -> Json<Value> {
+    ok_with(result, json!({}))
+}
+
+fn ok_with(result: Value, ext_info: Value) -> Json<Value> {
+    Json(json!({
+        "retCode": 0,
+        "retMsg": "OK",
+        "result": result,
+        "retExtInfo": ext_info,
+        "time": 0,
+    }))
+}
+
+fn fail(code: i64, msg: &str) -> Json<Value> {
+    Json(json!({
+        "retCode": code,
+        "retMsg": msg,
+        "result": {},
+        "retExtInfo": {},
+        "time": 0,
+    }))
+}
+
+/// Bybit's v5 signature: HMAC-SHA256 over timestamp, key, recv window and
+/// the query string (GET) or raw body (POST).
+pub fn signature(timestamp: &str, recv_window: &str, payload: &str) -> String {
+    signature_with(API_SECRET, timestamp, recv_window, payload)
+}
+
+fn signature_with(secret: &str, timestamp: &str, recv_window: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(timestamp.as_bytes());
+    mac.update(API_KEY.as_bytes());
+    mac.update(recv_window.as_bytes());
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Whether a GetSecretValue call is what Secrets Manager takes: a POST of
+/// just `{"SecretId": AWS_SECRET_ID}` as x-amz-json-1.1, SigV4-signed for
+/// the secret's region over its headers and body, with the mock's key or
+/// a temporary one sent with its session token.
+fn secrets_request_valid(method: &Method, path: &str, headers: &HeaderMap, body: &str) -> bool {
+    let authorization = header(headers, "Authorization");
+    let Some(fields) = authorization.strip_prefix("AWS4-HMAC-SHA256 ") else {
+        return false;
+    };
+    let fields: HashMap<&str, &str> = fields
+        .split(", ")
+        .filter_map(|field| field.split_once('='))
+        .collect();
+    let (Some(credential), Some(signed_headers), Some(signature)) = (
+        fields.get("Credential"),
+        fields.get("SignedHeaders"),
+        fields.get("Signature"),
+    ) else {
+        return false;
+    };
+    let scope: Vec<&str> = credential.split('/').collect();
+    let [key_id, date, region, "secretsmanager", "aws4_request"] = scope[..] else {
+        return false;
+    };
+    let secret = match key_id {
+        AWS_ACCESS_KEY_ID => AWS_SECRET_ACCESS_KEY,
+        AWS_SESSION_KEY_ID if header(headers, "X-Amz-Security-Token") == AWS_SESSION_TOKEN => {
+            AWS_SESSION_SECRET
+        }
+        _ => return false,
+    };
+    // every header that matters has to be signed, in canonical order
+    let signed: Vec<&str> = signed_headers.split(';').collect();
+    let mut wanted = vec!["content-type", "host", "x-amz-date", "x-amz-target"];
+    if headers.contains_key("X-Amz-Security-Token") {
+        wanted.push("x-amz-security-token");
+    }
+    if !signed.windows(2).all(|pair| pair[0] < pair[1])
+        || !wanted.iter().all(|name| signed.contains(name))
+    {
+        return false;
+    }
+    let canonical_headers: String = signed
+        .iter()
+        .map(|name| format!("{}:{}\n", name, header(headers, name).trim()))
+        .collect();
+    let canonical_request = format!(
+        "POST\n{}\n\n{}\n{}\n{}",
+        path,
+        canonical_headers,
+        signed_headers,
+        hex::encode(Sha256::digest(body.as_bytes()))
+    );
+    let amz_date = header(headers, "X-Amz-Date");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}/{}/secretsmanager/aws4_request\n{}",
+        amz_date,
+        date,
+        region,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = [date, region, "secretsmanager", "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{}", secret).into_bytes(), |key, part| {
+            hmac_sha256(&key, part)
+        });
+    let body: Value = serde_json::from_str(body).unwrap_or_default();
+    method == Method::POST
+        && header(headers, "Content-Type") == "application/x-amz-json-1.1"
+        && amz_date.starts_with(date)
+        && region == AWS_REGION
+        && body == json!({ "SecretId": AWS_SECRET_ID })
+        && hex::encode(hmac_sha256(&key, &string_to_sign)) == *signature
+}
+
+/// The mock STS, container endpoint and instance metadata service, each
+/// handing out the temporary key to the right token only. None for any
+/// other request.
+fn issue_aws_key(method: &Method, path: &str, headers: &HeaderMap, body: &str) -> Option<Response> {
+    let issued = json!({
+        "Code": "Success",
+        "AccessKeyId": AWS_SESSION_KEY_ID,
+        "SecretAccessKey": AWS_SESSION_SECRET,
+        "Token": AWS_SESSION_TOKEN,
+        "Expiration": "2030-01-01T00:00:00Z",
+    });
+    let imds_token = header(headers, "X-aws-ec2-metadata-token") == IMDS_TOKEN;
+    let roles = "/latest/meta-data/iam/security-credentials/";
+    let reply = match (method.clone(), path) {
+        (Method::POST, "/sts") => {
+            let form: HashMap<String, String> = Url::parse(&format!("http://mock/?{}", body))
+                .unwrap()
+                .query_pairs()
+                .into_owned()
+                .collect();
+            let field = |name: &str| form.get(name).map(String::as_str).unwrap_or_default();
+            if field("Action") != "AssumeRoleWithWebIdentity"
+                || field("RoleArn") != AWS_ROLE_ARN
+                || field("WebIdentityToken") != WEB_IDENTITY_TOKEN
+            {
+                return Some(StatusCode::FORBIDDEN.into_response());
+            }
+            let xml = format!(
+                "<AssumeRoleWithWebIdentityResponse>\
+                 <AssumeRoleWithWebIdentityResult><Credentials>\
+                 <AccessKeyId>{}</AccessKeyId>\
+                 <SecretAccessKey>{}</SecretAccessKey>\
+                 <SessionToken>{}</SessionToken>\
+                 <Expiration>2030-01-01T00:00:00Z</Expiration>\
+                 </Credentials></AssumeRoleWithWebIdentityResult>\
+                 </AssumeRoleWithWebIdentityResponse>",
+                AWS_SESSION_KEY_ID, AWS_SESSION_SECRET, AWS_SESSION_TOKEN
+            );
+            ([("Content-Type", "text/xml")], xml).into_response()
+        }
+        (Method::GET, "/ecs/credentials")
+            if header(headers, "Authorization") == CONTAINER_TOKEN =>
+        {
+            Json(issued).into_response()
+        }
+        (Method::GET, "/ecs/credentials") => StatusCode::FORBIDDEN.into_response(),
+        (Method::PUT, "/latest/api/token")
+            if headers.contains_key("X-aws-ec2-metadata-token-ttl-seconds") =>
+        {
+            IMDS_TOKEN.into_response()
+        }
+        (Method::GET, path) if path == roles && imds_token => {
+            format!("{}\n", IMDS_ROLE).into_response()
+        }
+        (Method::GET, path) if path == format!("{}{}", roles, IMDS_ROLE) && imds_token => {
+            Json(issued).into_response()
+        }
+        (_, path) if path.starts_with("/latest/") => StatusCode::UNAUTHORIZED.into_response(),
+        _ => return None,
+    };
+    Some(reply)
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+fn signed(headers: &HeaderMap, secret: &str, payload: &str) -> bool {
+    header(headers, "X-BAPI-API-KEY") == API_KEY
+        && signature_with(
+            secret,
+            header(headers, "X-BAPI-TIMESTAMP"),
+            header(headers, "X-BAPI-RECV-WINDOW"),
+            payload,
+        ) == header(headers, "X-BAPI-SIGN")
+}
+
+/// `handle`'s reply, unless it answers a batch `Script::dropped_batches`
+/// names, or is for a temporary AWS key.
+async fn respond(
+    State(book): State<Arc<Mutex<Book>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    query: RawQuery,
+    body: Bytes,
+) -> Response {
+    if let Some(reply) = issue_aws_key(
+        &method,
+        uri.path(),
+        &headers,
+        &String::from_utf8_lossy(&body),
+    ) {
+        let call = format!("{} {}", method, uri.path());
+        book.lock().unwrap().calls.push(call);
+        return reply;
+    }
+    let reply = handle(
+        State(book.clone()),
+        method,
+        uri.clone(),
+        headers,
+        query,
+        body,
+    )
+    .await;
+    let book = book.lock().unwrap();
+    let batch = book
+        .calls
+        .iter()
+        .filter(|call| call.ends_with("/v5/order/create-batch"))
+        .count();
+    if uri.path() == "/v5/order/create-batch" && book.script.dropped_batches.contains(&batch) {
+        return StatusCode::GATEWAY_TIMEOUT.into_response();
+    }
+    reply.into_response()
+}
+
+async fn handle(
+    State(book): State<Arc<Mutex<Book>>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    RawQuery(query): RawQuery,
+    body: Bytes,
+) -> Json<Value> {
+    let path = uri.path();
+    let query = query.unwrap_or_default();
+    let body = String::from_utf8_lossy(&body).to_string();
+    let mut book = book.lock().unwrap();
+    book.calls.push(format!("{} {}", method, path));
+    let stored = json!({
+        "api_key": API_KEY,
+        "api_secret": book.script.stored_secret.unwrap_or(API_SECRET),
+    });
+    // the secrets stores, answering only with the right token or key
+    if path.starts_with("/v1/") {
+        if header(&headers, "X-Vault-Token") != VAULT_TOKEN {
+            return Json(json!({ "errors": ["permission denied"] }));
+        }
+        return Json(json!({ "data": { "data": stored, "metadata": { "version": 1 } } }));
+    }
+    if header(&headers, "X-Amz-Target") == "secretsmanager.GetSecretValue" {
+        if !secrets_request_valid(&method, path, &headers, &body) {
+            return Json(json!({ "__type": "InvalidSignatureException" }));
+        }
+        return Json(json!({ "Name": "stink-bid", "SecretString": stored.to_string() }));
+    }
+    if method == Method::POST {
+        book.posts.push(Captured {
+            path: path.to_string(),
+            content_type: header(&headers, "Content-Type").to_string(),
+            timestamp: header(&headers, "X-BAPI-TIMESTAMP").to_string(),
+            recv_window: header(&headers, "X-BAPI-RECV-WINDOW").to_string(),
+            signature: header(&headers, "X-BAPI-SIGN").to_string(),
+            body: body.clone(),
+        });
+    }
+
+    let public = path.starts_with("/v5/market/");
+    let payload = if method == Method::POST {
+        &body
+    } else {
+        &query
+    };
+    let secret = book.script.api_secret.unwrap_or(API_SECRET);
+    if !public && !signed(&headers, secret, payload) {
+        book.bad_signatures += 1;
+        return fail(BAD_SIGNATURE, "error sign!");
+    }
+    let params: HashMap<String, String> = Url::parse(&format!("http://mock/?{}", query))
+        .unwrap()
+        .query_pairs()
+        .into_owned()
+        .collect();
+    let body: Value = serde_json::from_str(&body).unwrap_or_default();
+
+    // newest first, like Bybit's
+    let interval = params.get("interval").map_or("D", String::as_str);
+    let newest = book.script.stale_kline as i64 - book.script.ahead_kline as i64;
+    let limit = params
+        .get("limit")
+        .and_then(|limit| limit.parse().ok())
+        .unwrap_or(1usize)
+        .min(MAX_KLINES);
+    // each candle back opens a cent higher and dips a cent lower; the next
+    // period's, when listed, opens at 2.0000
+    let candles: Vec<(String, f64, f64)> = (0..limit as i64)
+        .map(|i| {
+            let back = newest + i;
+            let (open, low) = match back {
+                ..=-1 => (2.0, 1.95),
+                back => (1.0 + 0.01 * back as f64, 0.95 - 0.01 * back as f64),
+            };
+            (candle_start(interval, back), open, low)
+        })
+        .collect();
+    match path {
+        "/v5/market/kline" => ok(json!({
+            "symbol": params.get("symbol"),
+            "category": "linear",
+            "list": candles
+                .iter()
+                .map(|(start, open, low)| {
+                    json!([start, format!("{:.4}", open), "1.0500", format!("{:.4}", low), "1.0200", "1000", "1000"])
+                })
+                .collect::<Vec<_>>(),
+        })),
+        // no volume or turnover, like Bybit's
+        "/v5/market/mark-price-kline" | "/v5/market/index-price-kline" => ok(json!({
+            "symbol": params.get("symbol"),
+            "category": "linear",
+            "list": candles
+                .iter()
+                .map(|(start, open, low)| {
+                    json!([start, format!("{:.4}", open + 0.01), "1.0500", format!("{:.4}", low + 0.01), "1.0200"])
+                })
+                .collect::<Vec<_>>(),
+        })),
+        // newest first, like Bybit's
+        "/v5/market/open-interest" => ok(json!({
+            "symbol": params.get("symbol"),
+            "category": "linear",
+            "list": [
+                {
+                    "openInterest": format!("{}", 1000.0 * (1.0 + book.script.oi_growth)),
+                    "timestamp": "1700086400000",
+                },
+                { "openInterest": "1000", "timestamp": "1700000000000" },
+            ],
+            "nextPageCursor": "",
+        })),
+        "/v5/market/instruments-info" if book.script.instruments_down => {
+            fail(10016, "service unavailable")
+        }
+        "/v5/market/instruments-info" => {
+            let list: Vec<Value> = std::iter::once(SYMBOL)
+                .chain(book.script.also_listed.iter().copied())
+                .map(|symbol| {
+                    json!({
+                        "symbol": symbol,
+                        "status": "Trading",
+                        "quoteCoin": "USDT",
+                        "priceFilter": { "tickSize": "0.0001" },
+                        "lotSizeFilter": {
+                            "qtyStep": "1",
+                            "minNotionalValue": "5",
+                            "maxOrderQty": book.script.max_order_qty,
+                        },
+                    })
+                })
+                .collect();
+            ok(json!({ "list": list, "nextPageCursor": "" }))
+        }
+        "/v5/market/tickers" => ok(json!({
+            "list": [{
+                "symbol": SYMBOL,
+                "lastPrice": book.script.last_price.unwrap_or("1.0200"),
+                "markPrice": "1.0200",
+                "prevPrice24h": "1.0000",
+                "highPrice24h": "1.0500",
+                "lowPrice24h": "0.9500",
+                "volume24h": "1000",
+                "turnover24h": "1000",
+            }],
+        })),
+        "/v5/market/time" => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                + std::time::Duration::from_millis(book.script.clock_ahead_ms);
+            ok(json!({
+                "timeSecond": now.as_secs().to_string(),
+                "timeNano": now.as_nanos().to_string(),
+            }))
+        }
+        "/v5/account/info" => ok(json!({
+            "unifiedMarginStatus": 5,
+            "marginMode": "REGULAR_MARGIN",
+        })),
+        "/v5/user/query-api" => ok(json!({
+            "readOnly": 0,
+            "permissions": {
+                "ContractTrade": if book.script.no_order_permission {
+                    json!(["Position"])
+                } else {
+                    json!(["Order", "Position"])
+                },
+                "Spot": ["SpotTrade"],
+                "Wallet": [],
+            },
+            "ips": ["203.0.113.7"],
+            "expiredAt": book.script.key_expires_in_hours.map_or(String::new(), |hours| {
+                (Utc::now() + Duration::hours(hours)).to_rfc3339()
+            }),
+            "uta": 1,
+        })),
+        "/v5/account/wallet-balance" => ok(json!({
+            "list": [{
+                "accountType": "UNIFIED",
+                "totalAvailableBalance": "10000",
+                "totalEquity": "10000",
+                "coin": [{ "coin": "USDT", "availableToWithdraw": "10000", "usdValue": "10000" }],
+            }],
+        })),
+        "/v5/account/transaction-log" => ok(json!({ "list": [], "nextPageCursor": "" })),
+        "/v5/position/list" => {
+            let mut list: Vec<Value> = book
+                .script
+                .position_size
+                .iter()
+                .map(|size| {
+                    json!({
+                        "symbol": SYMBOL,
+                        "side": "Buy",
+                        "size": size,
+                        "avgPrice": "1.0000",
+                        "positionIdx": if book.script.hedge_mode { 1 } else { 0 },
+                    })
+                })
+                .collect();
+            if book.script.hedge_mode && list.is_empty() {
+                list = [(1, "Buy"), (2, "Sell")]
+                    .into_iter()
+                    .map(|(index, side)| {
+                        json!({
+                            "symbol": SYMBOL,
+                            "side": side,
+                            "size": "0",
+                            "avgPrice": "0",
+                            "positionIdx": index,
+                        })
+                    })
+                    .collect();
+            }
+            ok(json!({ "list": list }))
+        }
+        "/v5/order/realtime" | "/v5/order/history" => {
+            let realtime = path == "/v5/order/realtime";
+            let list: Vec<Value> = book
+                .orders
+                .iter()
+                .filter(|order| realtime == (order.status == "New"))
+                .filter(|order| {
+                    [
+                        ("symbol", &order.symbol),
+                        ("orderId", &order.order_id),
+                        ("orderLinkId", &order.order_link_id),
+                    ]
+                    .iter()
+                    .all(|(key, value)| params.get(*key).is_none_or(|wanted| wanted == *value))
+                })
+                .map(MockOrder::to_json)
+                .collect();
+            ok(json!({ "list": list }))
+        }
+        "/v5/order/create-batch" | "/v5/order/cancel-batch"
+            if body["request"]
+                .as_array()
+                .is_some_and(|request| request.len() > BATCH_LIMIT) =>
+        {
+            fail(10001, "too many orders in one batch")
+        }
+        "/v5/order/create-batch"
+            if book.script.refused_batches.contains(
+                &book
+                    .calls
+                    .iter()
+                    .filter(|call| call.ends_with(path))
+                    .count(),
+            ) =>
+        {
+            fail(ACCOUNT_BANNED, "common banned")
+        }
+        "/v5/order/create-batch" => create_batch(&mut book, &body),
+        "/v5/order/cancel-batch" => cancel_batch(&mut book, &body),
+        "/v5/order/cancel-all" => {
+            let symbol = body["symbol"].as_str().unwrap_or_default();
+            let list: Vec<Value> = book
+                .orders
+                .iter_mut()
+                .filter(|order| order.symbol == symbol && order.status == "New")
+                .map(|order| {
+                    order.status = "Cancelled".to_string();
+                    json!({ "orderId": order.order_id, "orderLinkId": order.order_link_id })
+                })
+                .collect();
+            ok(json!({ "list": list, "success": "1" }))
+        }
+        _ => fail(10001, &format!("mock doesn't serve {}", path)),
+    }
+}
+
+fn create_batch(book: &mut Book, body: &Value) -> Json<Value> {
+    let mut list = Vec::new();
+    let mut codes = Vec::new();
+    for request in body["request"].as_array().into_iter().flatten() {
+        let text = |key: &str| request[key].as_str().unwrap_or_default().to_string();
+        let mut order = MockOrder {
+            order_id: format!("mock-{}", book.orders.len() + book.vanished + 1),
+            order_link_id: text("orderLinkId"),
+            symbol: text("symbol"),
+            side: text("side"),
+            price: text("price"),
+            qty: text("qty"),
+            status: "New".to_string(),
+            reduce_only: request["reduceOnly"] == true,
+        };
+        let level = order.level();
+        if level.is_some_and(|level| book.script.reject_levels.contains(&level)) {
+            list.push(json!({
+                "category": "linear",
+                "symbol": order.symbol,
+                "orderId": "",
+                "orderLinkId": order.order_link_id,
+                "createAt": "",
+            }));
+            codes.push(json!({ "code": INSUFFICIENT_BALANCE, "msg": "insufficient balance" }));
+            continue;
+        }
+        if level.is_some_and(|level| book.script.fill_levels.contains(&level)) {
+            order.status = "Filled".to_string();
+        }
+        if level.is_some_and(|level| book.script.duplicate_levels.contains(&level)) {
+            list.push(json!({
+                "category": "linear",
+                "symbol": order.symbol,
+                "orderId": "",
+                "orderLinkId": order.order_link_id,
+                "createAt": "",
+            }));
+            codes.push(json!({ "code": DUPLICATE_LINK_ID, "msg": "OrderLinkedID is duplicate" }));
+            book.orders.push(order);
+            continue;
+        }
+        list.push(json!({
+            "category": "linear",
+            "symbol": order.symbol,
+            "orderId": order.order_id,
+            "orderLinkId": order.order_link_id,
+            "createAt": "1700000000000",
+        }));
+        codes.push(json!({ "code": 0, "msg": "OK" }));
+        if level.is_some_and(|level| book.script.vanish_levels.contains(&level)) {
+            book.vanished += 1;
+            continue;
+        }
+        book.orders.push(order);
+    }
+    ok_with(json!({ "list": list }), json!({ "list": codes }))
+}
+
+fn cancel_batch(book: &mut Book, body: &Value) -> Json<Value> {
+    let mut list = Vec::new();
+    let mut codes = Vec::new();
+    let stuck = book.script.stuck_levels.clone();
+    for request in body["request"].as_array().into_iter().flatten() {
+        let order_id = request["orderId"].as_str().unwrap_or_default();
+        let order = book
+            .orders
+            .iter_mut()
+            .find(|order| order.order_id == order_id);
+        let (link_id, code) = match order {
+            Some(order) if order.level().is_some_and(|level| stuck.contains(&level)) => {
+                (order.order_link_id.clone(), 0)
+            }
+            Some(order) if order.status == "New" => {
+                order.status = "Cancelled".to_string();
+                (order.order_link_id.clone(), 0)
+            }
+            Some(order) => (order.order_link_id.clone(), TOO_LATE_TO_CANCEL),
+            None => (String::new(), TOO_LATE_TO_CANCEL),
+        };
+        list.push(json!({
+            "category": "linear",
+            "symbol": request["symbol"],
+            "orderId": order_id,
+            "orderLinkId": link_id,
+        }));
+        codes.push(json!({
+            "code": code,
+            "msg": if code == 0 { "OK" } else { "order not exists or too late to cancel" },
+        }));
+    }
+    ok_with(json!({ "list": list }), json!({ "list": codes }))
+}
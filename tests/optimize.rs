@@ -0,0 +1,70 @@
+//! The naive ladder replay against hand-worked candles.
+
+#[path = "../src/optimize.rs"]
+mod optimize;
+
+use optimize::{header, parse_levels, rank, simulate_day, snippet, Day, DayResult, LABEL};
+
+fn day(open: f64, low: f64, close: f64) -> Day {
+    Day { open, low, close }
+}
+
+#[test]
+fn levels_parse_from_percents() {
+    assert_eq!(parse_levels("5, 10,15"), Ok(vec![5.0, 10.0, 15.0]));
+    assert!(parse_levels("5,abc").is_err());
+    assert!(parse_levels("5,100").is_err());
+}
+
+#[test]
+fn filled_levels_exit_at_the_bounce_or_the_close() {
+    // 10% fills at 90 and 20% at 80; the close of 95 clears both targets,
+    // 93.6 and 83.2, for 4 each. 30% at 70 never fills
+    let result = simulate_day(&day(100.0, 79.0, 95.0), &[10.0, 20.0, 30.0], 300.0, 4.0);
+    assert_eq!(result.fills, 2);
+    assert_eq!(result.deployed, 200.0);
+    assert!((result.pnl - 8.0).abs() < 1e-9);
+    // a close under the target sells there: 90 to 88 loses 100/90*2
+    let result = simulate_day(&day(100.0, 90.0, 88.0), &[10.0], 100.0, 4.0);
+    assert!((result.pnl + 100.0 / 90.0 * 2.0).abs() < 1e-9);
+    assert_eq!(
+        simulate_day(&day(100.0, 95.0, 99.0), &[10.0], 100.0, 4.0),
+        DayResult::default()
+    );
+}
+
+#[test]
+fn candidates_rank_by_return_with_a_repeatable_range() {
+    let days = vec![day(100.0, 89.0, 100.0), day(100.0, 97.0, 100.0)];
+    let candidates = vec![vec![20.0], vec![10.0]];
+    let rankings = rank(&days, &candidates, 100.0, 5.0, 200, 7);
+    assert_eq!(rankings[0].levels, vec![10.0]);
+    assert_eq!(rankings[0].fills, 1);
+    assert!((rankings[0].return_percent - 5.0).abs() < 1e-9);
+    assert!((rankings[0].efficiency_percent - 5.0).abs() < 1e-9);
+    assert_eq!(rankings[1].deployed, 0.0);
+    assert_eq!(rankings[1].pnl, 0.0);
+    // each resample holds the winning day 0, 1 or 2 times
+    assert!(rankings[0].low_percent >= 0.0 && rankings[0].high_percent <= 10.0);
+    assert!(rankings[0].low_percent <= rankings[0].high_percent);
+    assert_eq!(rank(&days, &candidates, 100.0, 5.0, 200, 7), rankings);
+    assert!(rankings[0].to_string().starts_with("10 "));
+    assert!(header().starts_with("levels"));
+}
+
+#[test]
+fn snippet_is_labelled_and_ready_to_paste() {
+    let rankings = rank(
+        &[day(100.0, 80.0, 100.0)],
+        &[vec![5.0, 15.0]],
+        1000.0,
+        2.0,
+        10,
+        1,
+    );
+    let snippet = snippet(&rankings[0], 1000.0, "BTCUSDT", 1);
+    assert!(snippet.starts_with("# best of a naive simulation on 1 days of BTCUSDT"));
+    assert!(snippet.contains("no fees or slippage"));
+    assert!(snippet.ends_with("levels = [5.0, 15.0]\nbudgets = [500.0, 500.0]\n"));
+    assert!(LABEL.starts_with("naive simulation"));
+}
@@ -0,0 +1,89 @@
+//! Order payloads built with `OrderRequest::builder` against the JSON the
+//! bot sent before the builder existed.
+
+#[path = "../src/order_request.rs"]
+mod order_request;
+
+use order_request::{OrderRequest, TimeInForce};
+
+fn entry() -> order_request::OrderRequestBuilder {
+    OrderRequest::builder(
+        "SEIUSDT",
+        "Buy",
+        "Limit",
+        "56",
+        "0.9000",
+        "daily-SEIUSDT-1700000000000-0",
+    )
+}
+
+#[test]
+fn a_plain_order_sends_only_the_basics() {
+    let json = serde_json::to_string(&entry().build()).unwrap();
+    assert_eq!(
+        json,
+        r#"{"symbol":"SEIUSDT","side":"Buy","orderType":"Limit","qty":"56","price":"0.9000","orderLinkId":"daily-SEIUSDT-1700000000000-0"}"#
+    );
+}
+
+#[test]
+fn off_flags_are_left_out() {
+    let json = serde_json::to_string(&entry().reduce_only(false).leverage(false).build()).unwrap();
+    assert_eq!(json, serde_json::to_string(&entry().build()).unwrap());
+}
+
+#[test]
+fn an_exit_matches_the_old_reduce_only_payload() {
+    let exit = OrderRequest::builder(
+        "SEIUSDT",
+        "Sell",
+        "Limit",
+        "56",
+        "0.9450",
+        "daily-SEIUSDT-1700000000000-x0",
+    )
+    .reduce_only(true)
+    .build();
+    assert_eq!(
+        serde_json::to_string(&exit).unwrap(),
+        r#"{"symbol":"SEIUSDT","side":"Sell","orderType":"Limit","qty":"56","price":"0.9450","orderLinkId":"daily-SEIUSDT-1700000000000-x0","reduceOnly":true}"#
+    );
+}
+
+#[test]
+fn set_fields_use_bybit_names() {
+    let order = entry()
+        .trigger("1.0000", 2)
+        .trigger_by("MarkPrice")
+        .order_filter("StopOrder")
+        .market_unit("quoteCoin")
+        .leverage(true)
+        .time_in_force(TimeInForce::PostOnly)
+        .position_idx(1)
+        .take_profit("1.2000")
+        .stop_loss("0.8000")
+        .build();
+    let json: serde_json::Value = serde_json::to_value(&order).unwrap();
+    assert_eq!(json["triggerPrice"], "1.0000");
+    assert_eq!(json["triggerDirection"], 2);
+    assert_eq!(json["triggerBy"], "MarkPrice");
+    assert_eq!(json["orderFilter"], "StopOrder");
+    assert_eq!(json["marketUnit"], "quoteCoin");
+    assert_eq!(json["isLeverage"], 1);
+    assert_eq!(json["timeInForce"], "PostOnly");
+    assert_eq!(json["positionIdx"], 1);
+    assert_eq!(json["takeProfit"], "1.2000");
+    assert_eq!(json["stopLoss"], "0.8000");
+    assert_eq!(
+        serde_json::to_value(TimeInForce::GoodTillCancel).unwrap(),
+        "GTC"
+    );
+    assert_eq!(
+        serde_json::to_value(TimeInForce::ImmediateOrCancel).unwrap(),
+        "IOC"
+    );
+    assert_eq!(
+        serde_json::to_value(TimeInForce::FillOrKill).unwrap(),
+        "FOK"
+    );
+}
@@ -0,0 +1,97 @@
+//! Batch order replies recorded in `tests/fixtures`, parsed and written
+//! back, so a renamed or added field shows up here as a diff rather than
+//! as a parse error at the open.
+
+#[path = "../src/response.rs"]
+mod response;
+
+#[path = "../src/placements.rs"]
+mod placements;
+
+use placements::{BatchOrderResponse, BatchOrderResult};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+fn fixture(name: &str) -> Value {
+    let path = format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// The fields of each `result.list` entry of `name` that a round trip
+/// through `BatchOrderResponse` doesn't write back. Those it does have to
+/// come back as recorded.
+fn dropped(name: &str) -> Vec<BTreeSet<String>> {
+    let body = fixture(name);
+    let mut dropped = Vec::new();
+    for entry in body["result"]["list"].as_array().unwrap() {
+        let parsed: BatchOrderResponse = serde_json::from_value(entry.clone())
+            .unwrap_or_else(|e| panic!("{} entry {} didn't parse: {}", name, entry, e));
+        let written = serde_json::to_value(&parsed).unwrap();
+        let recorded = entry.as_object().unwrap();
+        for (field, value) in written.as_object().unwrap() {
+            if let Some(recorded) = recorded.get(field) {
+                assert_eq!(recorded, value, "{} field {} changed", name, field);
+            }
+        }
+        dropped.push(
+            recorded
+                .keys()
+                .filter(|field| written.get(field.as_str()).is_none())
+                .cloned()
+                .collect(),
+        );
+    }
+    dropped
+}
+
+fn fields(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn a_placed_batch_round_trips_whole() {
+    assert_eq!(dropped("batch_placed"), [fields(&[])]);
+    let reply = response::parse::<BatchOrderResult>(fixture("batch_placed")).unwrap();
+    let placed = reply.into_result("batch order").unwrap();
+    assert_eq!(placed.list[0].created_at, "1700000000000");
+}
+
+#[test]
+fn a_rejected_order_blanks_every_field() {
+    assert_eq!(dropped("batch_mixed"), [fields(&[]), fields(&[])]);
+    let reply = response::parse::<BatchOrderResult>(fixture("batch_mixed")).unwrap();
+    let list = reply.into_result("batch order").unwrap().list;
+    assert_eq!(list[0].order_id, "mock-1");
+    assert_eq!(list[1].order_id, "");
+    assert_eq!(list[1].symbol, "");
+}
+
+#[test]
+fn renamed_and_added_fields_still_parse() {
+    // both creation times are read, then written back as createAt
+    assert_eq!(
+        dropped("batch_renamed"),
+        [
+            fields(&["createdAt", "createType"]),
+            fields(&["createTime"])
+        ]
+    );
+    let reply = response::parse::<BatchOrderResult>(fixture("batch_renamed")).unwrap();
+    let list = reply.into_result("batch order").unwrap().list;
+    assert_eq!(list[0].created_at, "1700000000000");
+    assert_eq!(list[1].created_at, "1700000000001");
+}
+
+#[test]
+fn an_entry_missing_fields_parses_blank() {
+    let entry = serde_json::json!({ "orderId": "mock-1" });
+    let parsed: BatchOrderResponse = serde_json::from_value(entry).unwrap();
+    assert_eq!(parsed.order_id, "mock-1");
+    assert_eq!(parsed.order_link_id, "");
+    assert_eq!(parsed.created_at, "");
+    assert_eq!(parsed.category, "");
+}
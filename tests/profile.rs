@@ -0,0 +1,82 @@
+//! Profile names, the files a profile reads and the exchange it points at.
+
+#[path = "../src/profile.rs"]
+mod profile;
+
+use profile::{
+    base_url, check_name, config_file, env_file, is_own_file, load_env, rebase, ENV_VAR,
+};
+use std::{env, fs};
+
+#[test]
+fn names_are_safe_in_file_names() {
+    assert_eq!(check_name("prod"), Ok(()));
+    assert_eq!(check_name("testnet-2_b"), Ok(()));
+    for name in ["", "../prod", "prod net", "a".repeat(33).as_str()] {
+        assert!(check_name(name).is_err(), "{:?}", name);
+    }
+}
+
+#[test]
+fn a_profile_has_its_own_files_beside_the_shared_ones() {
+    assert_eq!(config_file("config.toml", "prod"), "config.prod.toml");
+    assert_eq!(
+        config_file("/etc/stink-bid/bot.toml", "testnet"),
+        "/etc/stink-bid/bot.testnet.toml"
+    );
+    assert_eq!(config_file("config", "prod"), "config.prod");
+    assert!(is_own_file("/etc/stink-bid/config.prod.toml", "prod"));
+    assert!(!is_own_file("config.toml", "prod"));
+    assert!(!is_own_file("config.testnet.toml", "prod"));
+    assert_eq!(env_file("testnet"), ".env.testnet");
+}
+
+#[test]
+fn endpoints_follow_the_base_url() {
+    assert_eq!(base_url("mainnet"), "https://api.bybit.com");
+    assert_eq!(base_url("testnet"), "https://api-testnet.bybit.com");
+    assert_eq!(base_url("http://127.0.0.1:9000/"), "http://127.0.0.1:9000");
+    assert_eq!(
+        rebase(
+            "https://api.bybit.com/v5/market/kline?category=linear",
+            "https://api-testnet.bybit.com"
+        ),
+        Ok("https://api-testnet.bybit.com/v5/market/kline?category=linear".to_string())
+    );
+    assert_eq!(
+        rebase(
+            "https://api.bybit.com/v5/order/create-batch",
+            "http://127.0.0.1:9000"
+        ),
+        Ok("http://127.0.0.1:9000/v5/order/create-batch".to_string())
+    );
+    assert!(rebase("api.bybit.com", "https://api-testnet.bybit.com").is_err());
+}
+
+#[test]
+fn the_env_file_wins_over_the_environment() {
+    let path = env::temp_dir().join(format!("stink-bid-profile-{}.env", std::process::id()));
+    fs::write(
+        &path,
+        format!(
+            "# testnet keys\nexport {}=testnet\nPROFILE_TEST_KEY = \"from file\"\n\nPROFILE_TEST_URL='https://api-testnet.bybit.com'\n",
+            ENV_VAR
+        ),
+    )
+    .unwrap();
+    env::set_var("PROFILE_TEST_KEY", "from shell");
+    let names = load_env(path.to_str().unwrap()).unwrap();
+    assert_eq!(names, [ENV_VAR, "PROFILE_TEST_KEY", "PROFILE_TEST_URL"]);
+    assert_eq!(env::var("PROFILE_TEST_KEY").unwrap(), "from file");
+    assert_eq!(
+        env::var("PROFILE_TEST_URL").unwrap(),
+        "https://api-testnet.bybit.com"
+    );
+
+    fs::write(&path, "API_KEY\n").unwrap();
+    assert_eq!(
+        load_env(path.to_str().unwrap()),
+        Err("line 1 isn't NAME=value".to_string())
+    );
+    assert!(load_env("/nonexistent/.env.prod").is_err());
+}
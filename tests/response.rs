@@ -0,0 +1,81 @@
+//! Error replies with an empty, null or missing `result` still surface
+//! their retCode and retMsg instead of a parse error.
+
+#[path = "../src/response.rs"]
+mod response;
+
+use response::ApiResponse;
+use serde::Deserialize;
+
+/// Shaped like a batch order result, so `{}` alone can't parse as one.
+#[derive(Deserialize, Debug)]
+struct Placed {
+    list: Vec<Order>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Order {
+    #[serde(rename = "orderId")]
+    order_id: String,
+}
+
+fn fixture(name: &str) -> ApiResponse<Placed> {
+    let path = format!(
+        "{}/tests/fixtures/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let body = std::fs::read_to_string(&path).unwrap();
+    response::parse(serde_json::from_str(&body).unwrap())
+        .unwrap_or_else(|e| panic!("{} didn't parse: {}", path, e))
+}
+
+#[test]
+fn an_auth_failure_keeps_its_code() {
+    let reply = fixture("auth_failure");
+    assert_eq!(reply.ret_code, 10003);
+    assert!(reply.result.is_none());
+    assert_eq!(
+        reply.into_result("batch order").unwrap_err().to_string(),
+        "batch order failed: 10003 API key is invalid."
+    );
+}
+
+#[test]
+fn a_param_error_without_a_result_keeps_its_message() {
+    let error = fixture("param_error").into_result("kline").unwrap_err();
+    assert_eq!(error.code, 10001);
+    assert_eq!(error.msg, "params error: symbol invalid");
+}
+
+#[test]
+fn a_maintenance_reply_with_a_null_result_parses() {
+    let reply = fixture("maintenance");
+    assert_eq!(reply.ret_code, 10016);
+    assert_eq!(reply.ret_msg, "System is under maintenance");
+    assert!(reply.ret_ext_info.is_empty());
+    assert_eq!(reply.time, 0);
+}
+
+#[test]
+fn a_success_parses_its_result() {
+    let reply = fixture("batch_placed");
+    assert_eq!(reply.ret_ext_info["list"][0]["code"], 0);
+    let placed = reply.into_result("batch order").unwrap();
+    assert_eq!(placed.list[0].order_id, "mock-1");
+}
+
+#[test]
+fn a_success_without_a_result_is_an_error() {
+    let body = serde_json::json!({ "retCode": 0, "retMsg": "OK" });
+    let reply: ApiResponse<Placed> = response::parse(body).unwrap();
+    let error = reply.into_result("tickers").unwrap_err();
+    assert_eq!(error.call, "tickers");
+    assert_eq!(error.code, 0);
+}
+
+#[test]
+fn a_malformed_success_is_still_a_parse_error() {
+    let body = serde_json::json!({ "retCode": 0, "retMsg": "OK", "result": {} });
+    assert!(response::parse::<Placed>(body).is_err());
+}
@@ -0,0 +1,68 @@
+//! Restart backoff for failed subsystems, and how their health reads.
+
+#[path = "../src/restarts.rs"]
+mod restarts;
+
+use chrono::{DateTime, Utc};
+use restarts::{
+    backoff, restarts_after, TaskHealth, TaskState, FIRST_BACKOFF, HEALTHY_AFTER, MAX_BACKOFF,
+    MAX_RESTARTS,
+};
+use std::time::Duration;
+
+#[test]
+fn backoff_doubles_until_the_restarts_run_out() {
+    let waits: Vec<Option<Duration>> = (1..=MAX_RESTARTS + 1).map(backoff).collect();
+    assert_eq!(
+        waits,
+        vec![
+            Some(FIRST_BACKOFF),
+            Some(Duration::from_secs(2)),
+            Some(Duration::from_secs(4)),
+            Some(Duration::from_secs(8)),
+            Some(Duration::from_secs(16)),
+            None,
+        ]
+    );
+    assert_eq!(backoff(0), None);
+    assert!(FIRST_BACKOFF * 16 <= MAX_BACKOFF);
+}
+
+#[test]
+fn a_healthy_run_starts_the_count_over() {
+    let quick = Duration::from_secs(3);
+    assert_eq!(restarts_after(0, quick), 1);
+    assert_eq!(restarts_after(4, quick), 5);
+    assert_eq!(restarts_after(4, HEALTHY_AFTER), 1);
+}
+
+#[test]
+fn health_reads_with_its_restarts_and_failure() {
+    let since: DateTime<Utc> = "2026-10-15T08:00:00Z".parse().unwrap();
+    let running = TaskHealth {
+        state: TaskState::Running,
+        since,
+        restarts: 0,
+        last_error: None,
+    };
+    assert_eq!(running.to_string(), "running since 2026-10-15 08:00:00 UTC");
+    let restarting = TaskHealth {
+        state: TaskState::Restarting,
+        restarts: 2,
+        last_error: Some("index out of bounds".to_string()),
+        ..running.clone()
+    };
+    assert_eq!(
+        restarting.to_string(),
+        "restarting since 2026-10-15 08:00:00 UTC, 2 restarts, last failure: index out of bounds"
+    );
+    for state in [TaskState::Stopped, TaskState::Failed] {
+        let json = serde_json::to_string(&TaskHealth {
+            state,
+            ..running.clone()
+        })
+        .unwrap();
+        let back: TaskHealth = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.state, state);
+    }
+}
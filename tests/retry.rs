@@ -0,0 +1,109 @@
+//! Retry classification and backoff, and the combinator every exchange
+//! call goes through.
+
+#[path = "../src/retry.rs"]
+mod retry;
+
+use retry::{
+    classify_code, classify_send, classify_status, with_retry, ErrorClass, Failure, RetryPolicy,
+};
+use std::cell::Cell;
+use std::time::Duration;
+
+fn quick(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(2),
+        ..RetryPolicy::default()
+    }
+}
+
+#[test]
+fn refused_connections_never_reached_the_exchange() {
+    assert_eq!(classify_send(true), ErrorClass::Transport);
+    // a timeout may have been acted on
+    assert_eq!(classify_send(false), ErrorClass::Ambiguous);
+}
+
+#[test]
+fn statuses_and_codes_are_classified() {
+    assert_eq!(classify_status(429), Some(ErrorClass::RateLimited));
+    assert_eq!(classify_status(502), Some(ErrorClass::Ambiguous));
+    assert_eq!(classify_status(503), Some(ErrorClass::Ambiguous));
+    assert_eq!(classify_status(200), None);
+    // a ban is read from the body like any refusal
+    assert_eq!(classify_status(403), None);
+    assert_eq!(classify_code(10006), Some(ErrorClass::RateLimited));
+    assert_eq!(classify_code(0), None);
+    assert_eq!(classify_code(10001), None);
+    assert_eq!(classify_code(110072), None);
+}
+
+#[test]
+fn delay_doubles_up_to_the_cap() {
+    let policy = RetryPolicy::default();
+    let delays: Vec<Duration> = (1..=6).map(|attempt| policy.delay(attempt)).collect();
+    assert_eq!(
+        delays,
+        [250, 500, 1000, 2000, 4000, 5000].map(Duration::from_millis)
+    );
+    assert_eq!(policy.delay(60), Duration::from_secs(5));
+}
+
+#[test]
+fn default_policy_leaves_rejections_alone() {
+    let policy = RetryPolicy::default();
+    assert!(policy.retries(ErrorClass::Transport));
+    assert!(policy.retries(ErrorClass::RateLimited));
+    assert!(policy.retries(ErrorClass::Ambiguous));
+    assert!(!policy.retries(ErrorClass::Rejected));
+}
+
+#[tokio::test]
+async fn retries_until_the_call_goes_through() {
+    let tries = Cell::new(0);
+    let result = with_retry(&quick(4), "test", || async {
+        tries.set(tries.get() + 1);
+        if tries.get() < 3 {
+            return Err(Failure::new(ErrorClass::RateLimited, "10006 too many"));
+        }
+        Ok(tries.get())
+    })
+    .await;
+    assert_eq!(result.unwrap(), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let tries = Cell::new(0);
+    let result: Result<(), Failure> = with_retry(&quick(3), "test", || async {
+        tries.set(tries.get() + 1);
+        Err(Failure::new(ErrorClass::Transport, "connection refused"))
+    })
+    .await;
+    let failure = result.unwrap_err();
+    assert_eq!(tries.get(), 3);
+    assert_eq!(failure.attempts, 3);
+    assert_eq!(
+        failure.to_string(),
+        "gave up after 3 attempts: connection refused"
+    );
+}
+
+#[tokio::test]
+async fn stops_on_a_class_the_policy_does_not_retry() {
+    let tries = Cell::new(0);
+    let policy = RetryPolicy {
+        retry_on: vec![ErrorClass::Transport],
+        ..quick(4)
+    };
+    let result: Result<(), Failure> = with_retry(&policy, "test", || async {
+        tries.set(tries.get() + 1);
+        Err(Failure::new(ErrorClass::Ambiguous, "timed out"))
+    })
+    .await;
+    assert_eq!(tries.get(), 1);
+    // handed back as the error the call failed with
+    assert_eq!(result.unwrap_err().into_error().to_string(), "timed out");
+}
@@ -0,0 +1,61 @@
+//! Credentials scrubbed from errors, debug output and panics.
+
+#[path = "../src/scrub.rs"]
+mod scrub;
+
+use scrub::{install_panic_hook, register, register_signature, scrub, Scrubbed, REDACTED};
+use std::fmt;
+
+#[derive(Debug)]
+struct RequestError {
+    header: String,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request failed, X-BAPI-API-KEY: {}", self.header)
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+#[test]
+fn errors_render_without_the_secret() {
+    register("test-api-secret-0123");
+    let error: Box<dyn std::error::Error> = Box::new(RequestError {
+        header: "test-api-secret-0123".to_string(),
+    });
+    let rendered = format!("{}", Scrubbed(&error));
+    assert_eq!(
+        rendered,
+        format!("request failed, X-BAPI-API-KEY: {}", REDACTED)
+    );
+    let debugged = format!("{:?}", Scrubbed(&error));
+    assert!(!debugged.contains("test-api-secret-0123"), "{}", debugged);
+    assert!(debugged.contains(REDACTED));
+}
+
+#[test]
+fn short_values_are_left_alone() {
+    register("abc");
+    assert_eq!(scrub("abc is not a secret"), "abc is not a secret");
+}
+
+#[test]
+fn only_recent_signatures_are_kept() {
+    let signatures: Vec<String> = (0..65).map(|n| format!("{:064x}", n + 1_000)).collect();
+    for signature in &signatures {
+        register_signature(signature);
+    }
+    assert_eq!(scrub(&signatures[64]), REDACTED);
+    assert_eq!(scrub(&signatures[1]), REDACTED);
+    assert_eq!(scrub(&signatures[0]), signatures[0]);
+}
+
+#[test]
+fn panics_still_unwind_with_the_hook_installed() {
+    register("panic-secret-4567");
+    install_panic_hook();
+    let caught = std::panic::catch_unwind(|| panic!("leaked panic-secret-4567"));
+    assert!(caught.is_err());
+}
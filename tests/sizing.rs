@@ -0,0 +1,374 @@
+//! Ladders sized against hand-picked trading rules.
+
+// tests/decimal.rs covers it; only what sizing formats with is used here
+#[allow(dead_code)]
+#[path = "../src/decimal.rs"]
+mod decimal;
+
+#[path = "../src/sizing.rs"]
+mod sizing;
+
+use sizing::{checked_qty, fit, size_levels, Fit, LadderError, Level, OverMaxOrder, Rules};
+
+fn rules(tick: &'static str, qty_step: &'static str) -> Rules<'static> {
+    Rules {
+        quote_coin: "USDT",
+        tick_size: tick,
+        qty_step,
+        min_notional: 5.0,
+        min_qty: 0.0,
+        max_qty: None,
+        max_notional: None,
+    }
+}
+
+fn level(percent: f64, notional: f64) -> Level {
+    Level {
+        percent,
+        notional,
+        max_child_notional: None,
+        child_band_percent: 0.0,
+        over_max: OverMaxOrder::Split,
+    }
+}
+
+/// Whether `value` is a whole multiple of `step`, both as sent. Worked
+/// out in integers, since a float quotient drifts at large qtys.
+fn on_step(value: &str, step: &str) -> bool {
+    let places = decimal::places(value).max(decimal::places(step));
+    let scaled = |text: &str| {
+        let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let fraction = fraction.trim_end_matches('0');
+        format!("{}{:0<width$}", whole, fraction, width = places)
+            .parse::<i128>()
+            .unwrap()
+    };
+    scaled(value) % scaled(step) == 0
+}
+
+/// Price and qty of the single order `notional` buys `percent` below
+/// `anchor`.
+fn sized(anchor: f64, percent: f64, notional: f64, rules: &Rules) -> (String, String) {
+    let planned = size_levels(anchor, rules, &[level(percent, notional)]).unwrap();
+    assert_eq!(planned.len(), 1);
+    (planned[0].price.clone(), planned[0].qty.clone())
+}
+
+#[test]
+fn formerly_whitelisted_symbols_keep_their_strings() {
+    // the decimals ALT, MANTA and TAO were hardcoded with: (4, 0), (4, 1)
+    // and (2, 3)
+    let alt = rules("0.0001", "1");
+    let manta = rules("0.0001", "0.1");
+    let tao = rules("0.01", "0.001");
+    assert_eq!(
+        sized(0.125, 20.0, 50.0, &alt),
+        ("0.1000".to_string(), "500".to_string())
+    );
+    assert_eq!(
+        sized(2.0, 20.0, 80.0, &manta),
+        ("1.6000".to_string(), "50.0".to_string())
+    );
+    assert_eq!(
+        sized(500.0, 20.0, 800.0, &tao),
+        ("400.00".to_string(), "2.000".to_string())
+    );
+    // where rounding and flooring part: linear qtys used to be rounded to
+    // whole units, sending 555.5 ALT as 556 and 55.58 MANTA as 56.0
+    assert_eq!(
+        sized(0.125, 20.0, 55.55, &alt),
+        ("0.1000".to_string(), "555".to_string())
+    );
+    assert_eq!(
+        sized(2.0, 20.0, 88.93, &manta),
+        ("1.6000".to_string(), "55.5".to_string())
+    );
+}
+
+#[test]
+fn prices_and_qtys_go_out_as_multiples_of_their_steps() {
+    // 8.8 is sent as the nearest multiple of 0.5, 1.107 of 0.05
+    let half = rules("0.5", "0.001");
+    assert_eq!(
+        sized(10.0, 12.0, 50.0, &half),
+        ("9.0".to_string(), "5.681".to_string())
+    );
+    let nickel = rules("0.05", "0.1");
+    assert_eq!(
+        sized(1.23, 10.0, 50.0, &nickel),
+        ("1.10".to_string(), "45.1".to_string())
+    );
+    // 1000-prefixed symbols trade in tens and hundreds
+    let tens = rules("0.000001", "10");
+    assert_eq!(
+        sized(0.01, 10.0, 50.0, &tens),
+        ("0.009000".to_string(), "5550".to_string())
+    );
+    let hundreds = rules("0.000001", "100");
+    assert_eq!(
+        sized(0.01, 10.0, 50.0, &hundreds),
+        ("0.009000".to_string(), "5500".to_string())
+    );
+    // less than one step of 100 is refused rather than sent as a single unit
+    let sized = size_levels(0.01, &hundreds, &[level(10.0, 0.5)]);
+    assert!(
+        matches!(sized, Err(LadderError::BadQty { qty, min, .. }) if qty == 0.0 && min == 100.0),
+        "{:?}",
+        sized
+    );
+}
+
+#[test]
+fn a_linear_qty_is_floored_to_its_step() {
+    let tao = rules("0.01", "0.001");
+    // a quarter TAO, which rounding to a whole coin sent as 0
+    assert_eq!(
+        sized(500.0, 20.0, 100.0, &tao),
+        ("400.00".to_string(), "0.250".to_string())
+    );
+    let btc = rules("0.10", "0.001");
+    assert_eq!(
+        sized(60_000.0, 10.0, 100.0, &btc),
+        ("54000.0".to_string(), "0.001".to_string())
+    );
+    // 55.56 ALT at 0.9 is floored, so the level never spends past 50
+    let alt = rules("0.0001", "1");
+    assert_eq!(
+        sized(1.0, 10.0, 50.0, &alt),
+        ("0.9000".to_string(), "55".to_string())
+    );
+}
+
+#[test]
+fn a_level_over_the_maximum_is_split_or_clamped() {
+    let capped = Rules {
+        max_qty: Some(100.0),
+        ..rules("0.0001", "1")
+    };
+    // 100 qty at 0.9 is a 90 USDT maximum
+    let split = level(10.0, 200.0);
+    assert_eq!(
+        fit(&split, 0.9, &capped),
+        Fit {
+            children: 3,
+            notional: 200.0,
+            max: Some(90.0),
+        }
+    );
+    let planned = size_levels(1.0, &capped, &[split]).unwrap();
+    let suffixes: Vec<String> = planned.iter().map(|order| order.link_suffix()).collect();
+    assert_eq!(suffixes, ["0c0", "0c1", "0c2"]);
+    assert!(planned.iter().all(|order| order.percent == 10.0));
+
+    let clamp = Level {
+        over_max: OverMaxOrder::Clamp,
+        ..split
+    };
+    assert_eq!(fit(&clamp, 0.9, &capped).notional, 90.0);
+    let planned = size_levels(1.0, &capped, &[clamp]).unwrap();
+    assert_eq!(planned.len(), 1);
+    assert_eq!(planned[0].notional, 90.0);
+    assert_eq!(planned[0].qty, "100");
+}
+
+#[test]
+fn an_unusable_anchor_is_refused() {
+    let rules = rules("0.0001", "1");
+    for anchor in [f64::NAN, 0.0, -0.0, -1.0, f64::INFINITY, f64::NEG_INFINITY] {
+        let sized = size_levels(anchor, &rules, &[level(10.0, 50.0)]);
+        assert!(
+            matches!(sized, Err(LadderError::BadAnchor(_))),
+            "{}: {:?}",
+            anchor,
+            sized
+        );
+    }
+}
+
+#[test]
+fn a_level_not_priced_below_the_anchor_is_refused() {
+    let rules = rules("0.0001", "1");
+    // at or past 100% the price reaches zero, below 0% it's over the anchor
+    for percent in [100.0, 150.0, -5.0, 0.0, f64::NAN] {
+        let sized = size_levels(1.0, &rules, &[level(percent, 50.0)]);
+        assert!(
+            matches!(sized, Err(LadderError::BadPrice { level: 0, .. })),
+            "{}: {:?}",
+            percent,
+            sized
+        );
+    }
+    // a price under half a tick rounds to zero as sent
+    let sized = size_levels(0.00004, &rules, &[level(10.0, 50.0)]);
+    assert!(
+        matches!(sized, Err(LadderError::BadPrice { level: 0, .. })),
+        "{:?}",
+        sized
+    );
+}
+
+#[test]
+fn a_qty_outside_the_instrument_bounds_is_refused() {
+    // a huge anchor leaves less than one qty step for the budget
+    let sized = size_levels(1e300, &rules("0.01", "0.001"), &[level(10.0, 50.0)]);
+    assert!(
+        matches!(sized, Err(LadderError::BadQty { level: 0, qty, .. }) if qty == 0.0),
+        "{:?}",
+        sized
+    );
+    let sized = size_levels(1.0, &rules("0.0001", "1"), &[level(10.0, f64::INFINITY)]);
+    assert!(
+        matches!(sized, Err(LadderError::BadQty { level: 0, .. })),
+        "{:?}",
+        sized
+    );
+
+    // 55 qty at 0.9 is under a minimum of 100
+    let min = Rules {
+        min_qty: 100.0,
+        ..rules("0.0001", "1")
+    };
+    let sized = size_levels(1.0, &min, &[level(10.0, 50.0)]);
+    assert!(
+        matches!(sized, Err(LadderError::BadQty { level: 0, qty, min }) if qty == 55.0 && min == 100.0),
+        "{:?}",
+        sized
+    );
+    // exactly at the minimum goes through
+    assert!(size_levels(1.0, &min, &[level(10.0, 90.0)]).is_ok());
+
+    // size_levels splits under the maximum first, so only a qty checked
+    // on its own can be over it
+    let max = Rules {
+        max_qty: Some(100.0),
+        ..rules("0.0001", "1")
+    };
+    let qty = checked_qty(3, 0.9, 0.9, 200.0, &max);
+    assert!(
+        matches!(qty, Err(LadderError::QtyTooLarge { level: 3, qty, max }) if qty == 222.0 && max == 100.0),
+        "{:?}",
+        qty
+    );
+    assert_eq!(checked_qty(3, 0.9, 0.9, 90.0, &max).unwrap(), 100.0);
+}
+
+#[test]
+fn a_level_below_the_minimum_order_value_is_refused() {
+    let sized = size_levels(1.0, &rules("0.0001", "1"), &[level(10.0, 4.0)]);
+    assert!(
+        matches!(
+            &sized,
+            Err(LadderError::BelowMinNotional { level: 0, min, coin, .. })
+                if *min == 5.0 && coin == "USDT"
+        ),
+        "{:?}",
+        sized
+    );
+    // the second level is the one reported
+    let sized = size_levels(
+        1.0,
+        &rules("0.0001", "1"),
+        &[level(10.0, 50.0), level(20.0, 4.0)],
+    );
+    assert!(
+        matches!(sized, Err(LadderError::BelowMinNotional { level: 1, .. })),
+        "{:?}",
+        sized
+    );
+}
+
+#[test]
+fn a_sized_order_stays_inside_its_level() {
+    // anchors from 1e-6 to 1e6, each against ticks and steps from fine to
+    // coarse: whatever is planned is on its steps, below the anchor, above
+    // zero and never spends past its level or under the minimum
+    let steps = [
+        "0.00000001",
+        "0.0005",
+        "0.0001",
+        "0.05",
+        "0.01",
+        "0.5",
+        "1",
+        "10",
+        "100",
+    ];
+    let mut checked = 0;
+    for exponent in -6..=6 {
+        for mantissa in [1.0, 1.7, 3.3, 9.99] {
+            let anchor = mantissa * 10f64.powi(exponent);
+            for tick in steps {
+                for qty_step in steps {
+                    let rules = rules(tick, qty_step);
+                    let levels = [level(5.0, 25.0), level(37.5, 1_000.0)];
+                    let Ok(planned) = size_levels(anchor, &rules, &levels) else {
+                        continue;
+                    };
+                    checked += planned.len();
+                    for order in &planned {
+                        let price: f64 = order.price.parse().unwrap();
+                        let qty: f64 = order.qty.parse().unwrap();
+                        let context = format!("{} {} {}: {:?}", anchor, tick, qty_step, order);
+                        assert!(price > 0.0 && price < anchor, "{}", context);
+                        assert!(qty > 0.0, "{}", context);
+                        assert!(on_step(&order.price, tick), "{}", context);
+                        assert!(on_step(&order.qty, qty_step), "{}", context);
+                        assert!(price * qty >= rules.min_notional, "{}", context);
+                        let at_level = anchor * (1.0 - order.percent / 100.0);
+                        assert!(
+                            qty * at_level <= order.notional * (1.0 + 1e-9),
+                            "{}",
+                            context
+                        );
+                    }
+                }
+            }
+        }
+    }
+    assert!(checked > 100, "{}", checked);
+}
+
+#[test]
+fn errors_read_as_the_level_and_the_bound() {
+    let messages = [
+        (
+            LadderError::BadAnchor(f64::NAN),
+            "anchor price NaN is not usable",
+        ),
+        (
+            LadderError::BadPrice {
+                level: 2,
+                price: -0.5,
+            },
+            "level 2 price -0.5 is not below the anchor",
+        ),
+        (
+            LadderError::BadQty {
+                level: 0,
+                qty: 0.0,
+                min: 0.001,
+            },
+            "level 0 qty 0 is below the minimum 0.001",
+        ),
+        (
+            LadderError::QtyTooLarge {
+                level: 1,
+                qty: 222.0,
+                max: 100.0,
+            },
+            "level 1 qty 222 is above the maximum 100",
+        ),
+        (
+            LadderError::BelowMinNotional {
+                level: 3,
+                notional: 4.5,
+                min: 5.0,
+                coin: "USDT".to_string(),
+            },
+            "level 3 is 4.5 USDT, below the 5 minimum",
+        ),
+    ];
+    for (error, message) in messages {
+        assert_eq!(error.to_string(), message);
+    }
+}
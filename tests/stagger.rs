@@ -0,0 +1,59 @@
+//! How placements are spread over the stagger window.
+
+#[path = "../src/stagger.rs"]
+mod stagger;
+
+use stagger::{offsets, order, Spread};
+use std::time::Duration;
+
+const SYMBOLS: [(&str, u32); 4] = [
+    ("BTCUSDT", 0),
+    ("ETHUSDT", 0),
+    ("SEIUSDT", 2),
+    ("SOLUSDT", 1),
+];
+
+#[test]
+fn priority_symbols_go_first_then_by_name() {
+    assert_eq!(
+        order(&SYMBOLS),
+        vec!["SEIUSDT", "SOLUSDT", "BTCUSDT", "ETHUSDT"]
+    );
+}
+
+#[test]
+fn even_spread_steps_across_the_window() {
+    let spread = offsets(&SYMBOLS, Duration::from_secs(90), Spread::Even, 0);
+    let secs = |symbol: &str| spread[symbol].as_millis();
+    assert_eq!(secs("SEIUSDT"), 0);
+    assert_eq!(secs("SOLUSDT"), 22_500);
+    assert_eq!(secs("BTCUSDT"), 45_000);
+    assert_eq!(secs("ETHUSDT"), 67_500);
+}
+
+#[test]
+fn random_spread_is_the_same_for_a_seed_and_keeps_the_order() {
+    let window = Duration::from_secs(90);
+    let first = offsets(&SYMBOLS, window, Spread::Random, 1_714_521_600);
+    assert_eq!(
+        first,
+        offsets(&SYMBOLS, window, Spread::Random, 1_714_521_600)
+    );
+    assert_ne!(
+        first,
+        offsets(&SYMBOLS, window, Spread::Random, 1_714_608_000)
+    );
+    let times: Vec<Duration> = order(&SYMBOLS).iter().map(|s| first[*s]).collect();
+    assert!(
+        times.windows(2).all(|pair| pair[0] <= pair[1]),
+        "{:?}",
+        times
+    );
+    assert!(times.iter().all(|time| *time < window), "{:?}", times);
+}
+
+#[test]
+fn a_lone_symbol_places_at_the_start() {
+    let spread = offsets(&[("SEIUSDT", 0)], Duration::from_secs(90), Spread::Even, 0);
+    assert_eq!(spread["SEIUSDT"], Duration::ZERO);
+}
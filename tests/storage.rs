@@ -0,0 +1,76 @@
+//! Where data files go, and how they're written, versioned and moved
+//! aside.
+
+#[path = "../src/storage.rs"]
+mod storage;
+
+use chrono::{TimeZone, Utc};
+use std::fs;
+use std::path::PathBuf;
+use storage::{check_version, default_data_dir, quarantine, resolve, write_atomic};
+
+fn scratch(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("stink-bid-storage-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn data_dir_follows_xdg_then_home() {
+    assert_eq!(
+        default_data_dir(Some("/data"), Some("/home/bot")),
+        "/data/stink-bid"
+    );
+    assert_eq!(
+        default_data_dir(Some(""), Some("/home/bot")),
+        "/home/bot/.local/share/stink-bid"
+    );
+    assert_eq!(default_data_dir(None, None), ".");
+}
+
+#[test]
+fn relative_paths_go_under_the_data_dir() {
+    assert_eq!(
+        resolve("/data/stink-bid", "state.json"),
+        "/data/stink-bid/state.json"
+    );
+    assert_eq!(
+        resolve("/data/stink-bid", "/var/state.json"),
+        "/var/state.json"
+    );
+    assert_eq!(resolve(".", "reports"), "./reports");
+}
+
+#[test]
+fn writes_replace_the_file_whole() {
+    let dir = scratch("atomic");
+    let path = dir.join("state.json");
+    write_atomic(&path, b"{\"version\": 1}").unwrap();
+    write_atomic(&path, b"{}").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+    // the temporary file is renamed, not left behind
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    assert_eq!(check_version(0, 1), Ok(()));
+    assert_eq!(check_version(1, 1), Ok(()));
+    assert!(check_version(2, 1)
+        .unwrap_err()
+        .contains("newer than this release's 1"));
+}
+
+#[test]
+fn a_corrupt_file_is_moved_aside() {
+    let dir = scratch("quarantine");
+    let path = dir.join("state.json");
+    fs::write(&path, "{").unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 10, 15, 9, 30, 0).unwrap();
+    let aside = quarantine(&path, now).unwrap();
+    assert_eq!(aside, dir.join("state.json.corrupt-20261015T093000"));
+    assert!(!path.exists());
+    assert_eq!(fs::read_to_string(aside).unwrap(), "{");
+}
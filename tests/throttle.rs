@@ -0,0 +1,70 @@
+//! Dedupe, digests and rate caps on a made-up clock.
+
+#[path = "../src/throttle.rs"]
+mod throttle;
+
+use chrono::{DateTime, Duration, Utc};
+use throttle::{Admit, Batcher, RateCap, ThrottleConfig};
+
+fn at(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000, 0).unwrap() + Duration::seconds(seconds)
+}
+
+#[test]
+fn a_burst_sends_its_first_message_then_one_digest() {
+    let mut batcher = Batcher::new(&ThrottleConfig::default());
+    assert_eq!(
+        batcher.offer("BTCUSDT failed: key expired", at(0)),
+        Admit::Now
+    );
+    assert_eq!(batcher.window_ends(), Some(at(120)));
+    for symbol in ["ETHUSDT", "SOLUSDT", "XRPUSDT"] {
+        let message = format!("{} failed: key expired", symbol);
+        assert_eq!(batcher.offer(&message, at(30)), Admit::Held);
+    }
+    assert_eq!(batcher.flush("notices", at(60)), None);
+    assert_eq!(
+        batcher.flush("notices", at(120)).as_deref(),
+        Some("3 more notices in the last 120s, first: ETHUSDT failed: key expired")
+    );
+    // the next message opens a new window
+    assert_eq!(
+        batcher.offer("ADAUSDT failed: key expired", at(130)),
+        Admit::Now
+    );
+    assert_eq!(batcher.flush("notices", at(250)), None);
+}
+
+#[test]
+fn a_lone_held_message_goes_out_as_it_is() {
+    let mut batcher = Batcher::new(&ThrottleConfig::default());
+    batcher.offer("first", at(0));
+    batcher.offer("second", at(10));
+    // a late message still joins the window whose digest is pending
+    assert_eq!(batcher.offer("third", at(125)), Admit::Held);
+    assert_eq!(
+        batcher.flush("fills", at(126)).as_deref(),
+        Some("2 more fills in the last 120s, first: second")
+    );
+    batcher.offer("fourth", at(200));
+    batcher.offer("fifth", at(210));
+    assert_eq!(batcher.flush("fills", at(320)).as_deref(), Some("fifth"));
+}
+
+#[test]
+fn repeats_are_dropped_within_the_dedupe_window() {
+    let mut batcher = Batcher::new(&ThrottleConfig::default());
+    assert_eq!(batcher.offer("cooldown", at(0)), Admit::Now);
+    assert_eq!(batcher.offer("cooldown", at(599)), Admit::Duplicate);
+    assert_eq!(batcher.offer("cooldown", at(600)), Admit::Now);
+}
+
+#[test]
+fn the_rate_cap_counts_the_last_minute() {
+    let mut cap = RateCap::default();
+    assert!(cap.admit(2, at(0)));
+    assert!(cap.admit(2, at(10)));
+    assert!(!cap.admit(2, at(59)));
+    assert!(cap.admit(2, at(60)));
+    assert!(cap.admit(0, at(60)));
+}
@@ -0,0 +1,56 @@
+//! How -q, -v, -vv and RUST_LOG settle on one log level.
+
+#[path = "../src/verbosity.rs"]
+mod verbosity;
+
+use verbosity::{describe, enabled, from_rust_log, resolve, set, Level};
+
+#[test]
+fn flags_pick_the_level_without_rust_log() {
+    assert_eq!(resolve(0, false, None), (Level::Info, "default"));
+    assert_eq!(resolve(0, true, None), (Level::Warn, "-q"));
+    assert_eq!(resolve(1, false, None), (Level::Debug, "-v"));
+    assert_eq!(resolve(2, false, None), (Level::Trace, "-vv"));
+    assert_eq!(resolve(5, false, None), (Level::Trace, "-vv"));
+}
+
+#[test]
+fn rust_log_overrides_the_flags() {
+    assert_eq!(resolve(0, true, Some("debug")), (Level::Debug, "RUST_LOG"));
+    assert_eq!(resolve(2, false, Some("warn")), (Level::Warn, "RUST_LOG"));
+    // nothing in it for this crate, so the flags still apply
+    assert_eq!(resolve(1, false, Some("hyper=trace")), (Level::Debug, "-v"));
+    assert_eq!(resolve(0, false, Some("")), (Level::Info, "default"));
+}
+
+#[test]
+fn rust_log_directives_for_this_crate_and_bare_levels() {
+    assert_eq!(from_rust_log("TRACE"), Some(Level::Trace));
+    assert_eq!(from_rust_log("stink_bid=debug"), Some(Level::Debug));
+    assert_eq!(
+        from_rust_log("reqwest=trace,stink_bid=info"),
+        Some(Level::Info)
+    );
+    assert_eq!(from_rust_log("info, stink_bid = trace"), Some(Level::Trace));
+    // the last that applies wins
+    assert_eq!(from_rust_log("stink_bid=trace,warn"), Some(Level::Warn));
+    assert_eq!(from_rust_log("off"), Some(Level::Warn));
+    assert_eq!(from_rust_log("error"), Some(Level::Warn));
+    assert_eq!(from_rust_log("loud"), None);
+    assert_eq!(from_rust_log("tokio=debug"), None);
+}
+
+#[test]
+fn the_set_level_gates_lower_levels_and_is_described() {
+    assert_eq!(describe(), "info (default)");
+    set(Level::Debug, "-v");
+    assert!(enabled(Level::Warn));
+    assert!(enabled(Level::Info));
+    assert!(enabled(Level::Debug));
+    assert!(!enabled(Level::Trace));
+    assert_eq!(describe(), "debug (-v)");
+    set(Level::Warn, "-v");
+    assert!(!enabled(Level::Info));
+    assert_eq!(describe(), "warn (-v)");
+    assert_eq!(Level::Trace.to_string(), "trace");
+}